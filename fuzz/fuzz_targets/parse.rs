@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use thermal_parser::context::Context;
+
+//Parses with every command set and applies the commands to a context
+fuzz_target!(|data: &[u8]| {
+    for commands in [
+        thermal_parser::parse_esc_pos(data),
+        thermal_parser::parse_esc_p(data),
+        thermal_parser::parse_star(data),
+        thermal_parser::parse_star_raster(data),
+        thermal_parser::parse_tspl(data),
+    ] {
+        let mut context = Context::new();
+
+        for command in &commands {
+            command.handler.apply_context(command, &mut context);
+            command.handler.get_graphics(command, &context);
+            command.handler.get_text(command, &context);
+            command.handler.get_device_command(command, &context);
+            command.handler.debug(command, &context);
+        }
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use thermal_renderer::html_renderer::HtmlRenderer;
+use thermal_renderer::image_renderer::ImageRenderer;
+use thermal_renderer::json_renderer::JsonRenderer;
+
+//Renders with every output renderer, bad bytes should only produce errors
+fuzz_target!(|data: &[u8]| {
+    ImageRenderer::render(data, None);
+    HtmlRenderer::render(data, None);
+    JsonRenderer::render(data, None);
+});
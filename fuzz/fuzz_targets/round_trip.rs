@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use thermal_parser::command::Command;
+use thermal_parser::round_trip::check_round_trip;
+
+//Commands come back the same after they are serialized and parsed
+fuzz_target!(|commands: Vec<Command>| {
+    let mut parser = thermal_parser::new_esc_pos_parser();
+
+    if let Err(mismatch) = check_round_trip(&mut parser, &commands) {
+        panic!("{}", mismatch);
+    }
+});
@@ -0,0 +1,100 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::path::PathBuf;
+use thermal_parser::graphics::{GraphicsCommand, ImageFlow, RGBA};
+use thermal_parser::thermal_file::parse_str;
+use thermal_parser::utils::corpus::CorpusGenerator;
+
+fn sample(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("sample_files")
+        .join("in")
+        .join(name);
+    let bytes = std::fs::read(&path).unwrap();
+
+    match name.ends_with(".thermal") {
+        true => parse_str(&String::from_utf8(bytes).unwrap()),
+        false => bytes,
+    }
+}
+
+fn parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    for name in ["discount.bin", "retail.bin", "gs_images_raster.thermal"] {
+        let bytes = sample(name);
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| thermal_parser::parse_esc_pos(black_box(&bytes)))
+        });
+    }
+
+    let jobs: Vec<u8> = CorpusGenerator::new(1).take(100).flatten().collect();
+    group.throughput(Throughput::Bytes(jobs.len() as u64));
+    group.bench_function("corpus", |b| {
+        b.iter(|| thermal_parser::parse_esc_pos(black_box(&jobs)))
+    });
+
+    group.finish();
+}
+
+fn image_decoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    let color = RGBA {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+
+    //A full width 80mm image
+    let (width, height) = (576, 400);
+    let bits: Vec<u8> = (0..width / 8 * height).map(|i| (i * 7) as u8).collect();
+
+    group.bench_function("raster", |b| {
+        b.iter(|| {
+            GraphicsCommand::image_from_raster_bytes_single_color(
+                width as u32,
+                height as u32,
+                (1, 1),
+                &color,
+                ImageFlow::Block,
+                black_box(&bits),
+                true,
+            )
+        })
+    });
+
+    group.bench_function("raster_stretched", |b| {
+        b.iter(|| {
+            GraphicsCommand::image_from_raster_bytes_single_color(
+                width as u32,
+                height as u32,
+                (2, 2),
+                &color,
+                ImageFlow::Block,
+                black_box(&bits),
+                true,
+            )
+        })
+    });
+
+    group.bench_function("column", |b| {
+        b.iter(|| {
+            GraphicsCommand::image_from_column_bytes_single_color(
+                width as u32,
+                height as u32,
+                (1, 1),
+                &color,
+                ImageFlow::Block,
+                black_box(&bits),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parsing, image_decoding);
+criterion_main!(benches);
@@ -0,0 +1,161 @@
+//! Parse Audit
+//!
+//! Reports the places in a parse where the framing of commands may
+//! have gone wrong. When a command reads one byte too many or too few
+//! the rest of the stream desyncs, data is parsed as commands and
+//! commands end up inside data or text.
+//!
+//! A finding is not always an error, image data can contain the bytes
+//! of a command by chance. Findings close to where a parse went wrong
+//! are the place to start looking.
+//!
+//! ```
+//! use thermal_parser::audit_esc_pos;
+//!
+//! let bytes = [0x1B, 0x2A, 0x00, 0x02, 0x00, 0x1B, 0x40, b'H', b'I'];
+//!
+//! for finding in audit_esc_pos(&bytes) {
+//!     println!("{}", finding);
+//! }
+//! ```
+
+use crate::command::{Command, CommandType};
+use crate::command_sets::CommandSet;
+use crate::parser::fixed_data_len;
+use crate::prelude::*;
+use core::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditKind {
+    /// Bytes inside the data of the command are the bytes of another
+    /// command, with a different data length they are parsed as it
+    CommandInData { command: String },
+    /// A real-time command was executed from inside the data
+    RealtimeInData,
+    /// Bytes that start like a command but match none, the bytes up
+    /// to the next command were taken as its data
+    UnknownCommand { swallowed: usize },
+    /// Text has control bytes, which is usually data that was
+    /// parsed as text after a command ended too early
+    BinaryText { control_bytes: usize },
+    /// The stream ended before the command had all of its data
+    Truncated { expected: usize, actual: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditFinding {
+    /// Position of the suspicious bytes in the parsed bytes
+    pub offset: usize,
+    /// Name of the command the bytes belong to
+    pub command: String,
+    pub kind: AuditKind,
+}
+
+impl fmt::Display for AuditFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06X} {}: ", self.offset, self.command)?;
+
+        match &self.kind {
+            AuditKind::CommandInData { command } => {
+                write!(f, "the data contains the bytes of {}", command)
+            }
+            AuditKind::RealtimeInData => write!(f, "executed from inside the data of a command"),
+            AuditKind::UnknownCommand { swallowed } => {
+                write!(f, "unknown command took {} bytes of data", swallowed)
+            }
+            AuditKind::BinaryText { control_bytes } => {
+                write!(f, "text has {} control bytes", control_bytes)
+            }
+            AuditKind::Truncated { expected, actual } => {
+                write!(f, "expected {} bytes of data, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+/// Audits commands that were parsed with raw bytes retained
+pub fn audit_commands(commands: &[Command], cmd_set: &CommandSet) -> Vec<AuditFinding> {
+    //Only commands that start like a command can take over data
+    let patterns: Vec<&Command> = cmd_set
+        .commands
+        .iter()
+        .filter(|cmd| cmd.commands.len() >= 2)
+        .filter(|cmd| cmd_set.unknown.commands.contains(&cmd.commands[0]))
+        .collect();
+
+    let mut findings = vec![];
+    let mut executed = vec![];
+    let mut raw_start = 0;
+
+    for command in commands {
+        let finding = |offset: usize, kind: AuditKind| AuditFinding {
+            offset,
+            command: command.name.to_string(),
+            kind,
+        };
+
+        if command.embedded {
+            findings.push(finding(command.offset, AuditKind::RealtimeInData));
+            executed.push(command.offset);
+            continue;
+        }
+
+        let raw_end = raw_start + command.raw.len();
+        let data_start = (command.offset + command.commands.len()).clamp(raw_start, raw_end);
+        let data = &command.raw[data_start - raw_start..];
+        raw_start = raw_end;
+
+        match command.kind {
+            CommandType::Unknown => {
+                if !command.data.is_empty() {
+                    let swallowed = command.data.len().saturating_sub(1);
+                    findings.push(finding(
+                        command.offset,
+                        AuditKind::UnknownCommand { swallowed },
+                    ));
+                }
+            }
+            CommandType::Text => {
+                let control_bytes = data.iter().filter(|byte| **byte < 0x20).count();
+                if control_bytes > 0 {
+                    findings.push(finding(
+                        command.offset,
+                        AuditKind::BinaryText { control_bytes },
+                    ));
+                }
+            }
+            _ => {
+                if let Some(expected) = fixed_data_len(&command.data_kind) {
+                    if command.data.len() < expected {
+                        findings.push(finding(
+                            command.offset,
+                            AuditKind::Truncated {
+                                expected,
+                                actual: command.data.len(),
+                            },
+                        ));
+                    }
+                }
+
+                for index in 0..data.len() {
+                    //Executed real-time commands were already reported
+                    if executed.contains(&(data_start + index)) {
+                        continue;
+                    }
+
+                    let rest = &data[index..];
+                    if let Some(pattern) = patterns.iter().find(|p| rest.starts_with(&p.commands)) {
+                        findings.push(finding(
+                            data_start + index,
+                            AuditKind::CommandInData {
+                                command: pattern.name.to_string(),
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    findings
+}
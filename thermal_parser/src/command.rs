@@ -1,7 +1,10 @@
 use crate::context::{Context, TextJustify};
 use crate::graphics::GraphicsCommand;
+use crate::prelude::*;
+use crate::reference::{self, CommandInfo};
 use crate::text::TextSpan;
-use std::rc::Rc;
+use alloc::rc::Rc;
+use core::fmt;
 
 #[derive(Clone, PartialEq)]
 pub enum DeviceCommand {
@@ -25,7 +28,24 @@ pub enum DeviceCommand {
     ChangeTabs(u8, u8),
     Transmit(Vec<u8>),
     MoveX(u16),
+    MoveY(u16),
     ClearBufferGraphics,
+    EnablePanelButtons(bool),
+    /// ESC =, bit 0 selects the printer and bit 1 the customer display
+    SelectPeripheral(u8),
+    SelectPaperEndSensors(u8),
+    SelectPrintStopSensors(u8),
+    FeedToNextLabel,
+    /// GS ( P, the paper width or length changed
+    ChangePaperLayout,
+    /// GS I, the id type that the job asked for
+    TransmitPrinterId(u8),
+    /// DLE EOT, the status type that the job asked for
+    TransmitStatus(u8),
+    /// DLE DC4 2, the printer saves its state and waits to be turned off
+    PowerOff,
+    /// DLE DC4 8, the data that was received and not printed is discarded
+    ClearBuffers,
 }
 
 impl DeviceCommand {
@@ -48,10 +68,21 @@ impl DeviceCommand {
             Self::ChangeTabs(_num, _at) => "Tabs Changed".to_string(),
             Self::Transmit(_b) => "Transmit Data Back".to_string(),
             Self::MoveX(_n) => "Move Horizontally".to_string(),
+            Self::MoveY(_n) => "Move Vertically".to_string(),
             Self::ClearBufferGraphics => "Clear Buffer Graphics".to_string(),
             Self::Justify(n) => format!("Justify {:?}", n),
             Self::SetTextWidth(_) => "Scale Text Width".to_string(),
             Self::SetTextHeight(_) => "Scale Text Height".to_string(),
+            Self::EnablePanelButtons(enabled) => format!("Panel Buttons Enabled {}", enabled),
+            Self::SelectPeripheral(n) => format!("Peripheral Devices {:08b}", n),
+            Self::SelectPaperEndSensors(n) => format!("Paper End Sensors {:08b}", n),
+            Self::SelectPrintStopSensors(n) => format!("Print Stop Sensors {:08b}", n),
+            Self::FeedToNextLabel => "Feed To Next Label".to_string(),
+            Self::ChangePaperLayout => "Change Paper Layout".to_string(),
+            Self::TransmitPrinterId(n) => format!("Transmit Printer Id {}", n),
+            Self::TransmitStatus(n) => format!("Transmit Status {}", n),
+            Self::PowerOff => "Power Off".to_string(),
+            Self::ClearBuffers => "Clear Buffers".to_string(),
         }
     }
 }
@@ -90,6 +121,31 @@ pub struct Command {
     pub kind: CommandType,
     pub data_kind: DataType,
     pub handler: Box<dyn CommandHandler>,
+    /// Position of the first byte of the command in the parsed bytes
+    pub offset: usize,
+    /// The original bytes of the command, only kept when the parser
+    /// retains raw bytes (`Parser::retain_raw`)
+    pub raw: Vec<u8>,
+    /// A real-time command found inside the data of another command,
+    /// its bytes belong to that command and are not serialized twice
+    pub embedded: bool,
+    /// The command reference of the command set the command belongs
+    /// to, see `Command::info`
+    pub reference: Option<&'static [CommandInfo]>,
+}
+
+//Handlers are not Debug, the command is shown by what was parsed
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .field("commands", &self.commands)
+            .field("data", &self.data)
+            .field("offset", &self.offset)
+            .field("embedded", &self.embedded)
+            .field("reference", &self.info().map(|info| info.reference))
+            .finish_non_exhaustive()
+    }
 }
 
 impl Command {
@@ -109,7 +165,56 @@ impl Command {
             kind,
             data_kind,
             handler,
+            offset: 0,
+            raw: vec![],
+            embedded: false,
+            reference: None,
+        }
+    }
+
+    /// The metadata of the command in the command reference of its
+    /// command set, `None` when the command is not in the reference
+    pub fn info(&self) -> Option<&'static CommandInfo> {
+        reference::find(self.reference?, &self.opcode())
+    }
+
+    /// The bytes that identify the command. Subcommands are identified by
+    /// the command and their function bytes without the data length,
+    /// GS ( k Aztec print for example is GS ( k 53 81
+    pub fn opcode(&self) -> Vec<u8> {
+        let len = self.commands.len();
+
+        //Parsed subcommands have the parameter bytes after the 3 command bytes
+        if self.data_kind == DataType::Subcommand && len > 5 {
+            let mut opcode = self.commands[..3].to_vec();
+            opcode.extend(&self.commands[len - 2..]);
+            return opcode;
         }
+
+        self.commands.to_vec()
+    }
+
+    /// Replaces the data, the command is serialized from its
+    /// command bytes and the new data from now on
+    pub fn set_data(&mut self, data: Vec<u8>) {
+        self.data = data;
+        self.raw.clear();
+    }
+
+    /// The bytes of the command, the original bytes when they were
+    /// retained, otherwise rebuilt by the handler
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.embedded {
+            return vec![];
+        }
+
+        if !self.raw.is_empty() {
+            return self.raw.clone();
+        }
+
+        let (mut bytes, mut data) = self.handler.get_command_bytes(self);
+        bytes.append(&mut data);
+        bytes
     }
 
     // returns true if the byte was consumed or false if it was rejected
@@ -209,6 +314,17 @@ pub trait CommandHandler: CloneCommandHandler {
         return false;
     }
 
+    //Real-time commands are executed as soon as they arrive,
+    //even inside the data of another command
+    fn is_realtime(&self) -> bool {
+        false
+    }
+
+    //Commands that turn real-time processing on or off return the new state
+    fn realtime_commands(&self, _command: &Command) -> Option<bool> {
+        None
+    }
+
     //Returns the subcommand for a command, see subcommand module
     fn get_subcommand(&mut self) -> Option<Command> {
         None
@@ -0,0 +1,47 @@
+use crate::commands::esc_p;
+use crate::prelude::*;
+use crate::{command_sets::CommandSet, commands::*};
+
+//ESC/P subset for impact (dot matrix) printers like the TM-U220
+//Commands that behave the same as ESC/POS are shared
+//These should always be in alphabetical order
+pub fn new() -> CommandSet {
+    let commands = vec![
+        carriage_return::new(),
+        default_line_spacing::new(),
+        esc_p::bit_image::new(),
+        esc_p::cancel_emphasis::new(),
+        esc_p::cancel_italic::new(),
+        esc_p::nine_pin_bit_image::new(),
+        esc_p::print_and_feed::new(),
+        esc_p::return_home::new(),
+        esc_p::set_eighth_inch_line_spacing::new(),
+        esc_p::set_emphasis::new(),
+        esc_p::set_italic::new(),
+        esc_p::set_line_spacing::new(),
+        esc_p::set_print_mode::new(),
+        esc_p::set_unidirectional::new(),
+        feed_and_cut::new(),
+        formfeed::new(),
+        horizontal_tab::new(),
+        initialize::new(),
+        linefeed::new(),
+        print_and_feed_lines::new(),
+        pulse::new(),
+        set_alt_color::new(),
+        set_code_table::new(),
+        set_horizontal_pos::new(),
+        set_international_charset::new(),
+        set_justification::new(),
+        set_tab_len::new(),
+        set_underline::new(),
+    ];
+
+    CommandSet {
+        default: text::new(),
+        unknown: unknown::new(),
+        begin_parsing: begin_print::new(),
+        end_parsing: end_print::new(),
+        commands: Box::from(commands),
+    }
+}
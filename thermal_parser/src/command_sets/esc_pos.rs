@@ -1,12 +1,14 @@
-use crate::{command_sets::CommandSet, commands::*};
+use crate::prelude::*;
+use crate::{command_sets::CommandSet, commands::*, reference};
 
 //These should always be in alphabetical order
 pub fn new() -> CommandSet {
-    let commands = vec![
+    let mut commands = vec![
         barcode::new(),
         bit_image::new(),
         cancel::new(),
         carriage_return::new(),
+        clear_buffers::new(),
         code_2d::new(),
         default_line_spacing::new(),
         feed_and_cut::new(),
@@ -14,20 +16,24 @@ pub fn new() -> CommandSet {
         graphics::new(),
         horizontal_tab::new(),
         initialize::new(),
+        label_formfeed::new(),
         large_graphics::new(),
         linefeed::new(),
         paper_end_sensor::new(),
+        power_off::new(),
         print_and_feed_lines::new(),
         print_and_feed::new(),
         print_and_reverse_feed_lines::new(),
         print_stop_sensor::new(),
         pulse::new(),
         raster_bit_image::new(),
+        realtime_pulse::new(),
         request_response_transmission::new(),
         set_horizontal_pos::new(),
         set_alt_color::new(),
         set_barcode_height::new(),
         set_barcode_width::new(),
+        set_black_mark_adjustment::new(),
         set_black_white_invert::new(),
         set_character_size::new(),
         set_code_table::new(),
@@ -42,14 +48,19 @@ pub fn new() -> CommandSet {
         set_justification::new(),
         set_line_spacing::new(),
         set_panel_buttons::new(),
+        set_paper_layout::new(),
         set_peripheral_device::new(),
+        set_print_control::new(),
         set_print_mode::new(),
+        set_realtime_commands::new(),
+        offset_horizontal_pos::new(),
         offset_vertical_pos::new(),
         set_smoothing::new(),
         set_tab_len::new(),
         set_underline::new(),
         set_upside_down::new(),
         transmit_printer_id::new(),
+        transmit_realtime_status::new(),
         set_page_mode::new(),
         set_vertical_pos::new(),
         page_mode_print_area::new(),
@@ -60,6 +71,10 @@ pub fn new() -> CommandSet {
         unknown_gs_g::new(),
     ];
 
+    for command in commands.iter_mut() {
+        command.reference = Some(reference::ESC_POS);
+    }
+
     CommandSet {
         default: text::new(),
         unknown: unknown::new(),
@@ -1,4 +1,6 @@
 use crate::command::*;
+use crate::prelude::*;
+use core::mem;
 
 pub struct CommandSet {
     //list of supported commands
@@ -13,4 +15,31 @@ pub struct CommandSet {
     pub end_parsing: Command,
 }
 
+impl CommandSet {
+    /// Adds a command, for example a vendor specific extension.
+    /// A command with the same bytes is replaced and returned.
+    ///
+    /// The bytes should not start with the bytes of a different
+    /// command (or the other way around), the parser only matches
+    /// a command once it is the only candidate left.
+    pub fn add_command(&mut self, command: Command) -> Option<Command> {
+        let existing = self
+            .commands
+            .iter()
+            .position(|c| c.commands == command.commands);
+
+        match existing {
+            Some(index) => Some(mem::replace(&mut self.commands[index], command)),
+            None => {
+                self.commands.push(command);
+                None
+            }
+        }
+    }
+}
+
+pub mod esc_p;
 pub mod esc_pos;
+pub mod star;
+pub mod star_raster;
+pub mod tspl;
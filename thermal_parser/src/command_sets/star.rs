@@ -0,0 +1,37 @@
+use crate::commands::star;
+use crate::prelude::*;
+use crate::{command_sets::CommandSet, commands::*};
+
+//Star Line Mode
+//These should always be in alphabetical order
+pub fn new() -> CommandSet {
+    let commands = vec![
+        carriage_return::new(),
+        formfeed::new(),
+        horizontal_tab::new(),
+        initialize::new(),
+        linefeed::new(),
+        set_underline::new(),
+        star::barcode::new(),
+        star::cancel_emphasis::new(),
+        star::cancel_highlight::new(),
+        star::cut::new(),
+        star::feed_lines::new(),
+        star::open_drawer::new(),
+        star::raster_image::new(),
+        star::set_character_expansion::new(),
+        star::set_code_table::new(),
+        star::set_drawer_pulse::new(),
+        star::set_emphasis::new(),
+        star::set_highlight::new(),
+        star::set_justification::new(),
+    ];
+
+    CommandSet {
+        default: text::new(),
+        unknown: unknown::new(),
+        begin_parsing: begin_print::new(),
+        end_parsing: end_print::new(),
+        commands: Box::from(commands),
+    }
+}
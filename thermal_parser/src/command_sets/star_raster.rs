@@ -0,0 +1,29 @@
+use crate::commands::star_raster;
+use crate::prelude::*;
+use crate::{command_sets::CommandSet, commands::*};
+
+//Star Raster Mode (graphics only printers like the TSP100)
+//These should always be in alphabetical order
+pub fn new() -> CommandSet {
+    let mut commands = vec![
+        initialize::new(),
+        star_raster::clear_raster_data::new(),
+        star_raster::end_of_document::new(),
+        star_raster::enter_raster_mode::new(),
+        star_raster::form_feed::new(),
+        star_raster::initialize_raster_mode::new(),
+        star_raster::move_vertical::new(),
+        star_raster::quit_raster_mode::new(),
+        star_raster::transfer_raster_data::new(),
+    ];
+
+    commands.extend(star_raster::set_option::all());
+
+    CommandSet {
+        default: text::new(),
+        unknown: unknown::new(),
+        begin_parsing: begin_print::new(),
+        end_parsing: end_print::new(),
+        commands: Box::from(commands),
+    }
+}
@@ -0,0 +1,30 @@
+use crate::command_sets::CommandSet;
+use crate::commands::tspl;
+use crate::prelude::*;
+
+//TSPL / TSPL2 for TSC label printers
+//Keywords include the trailing space so that no keyword is a prefix of another (BAR / BARCODE)
+//These should always be in alphabetical order
+pub fn new() -> CommandSet {
+    let mut commands = vec![
+        tspl::bar::new(),
+        tspl::barcode::new(),
+        tspl::bitmap::new(),
+        tspl::clear_buffer::new(),
+        tspl::draw_box::new(),
+        tspl::print_label::new(),
+        tspl::qrcode::new(),
+        tspl::set_size::new(),
+        tspl::text::new(),
+    ];
+
+    commands.extend(tspl::setting::all());
+
+    CommandSet {
+        default: tspl::unknown::new(),
+        unknown: tspl::unknown::new(),
+        begin_parsing: tspl::begin_job::new(),
+        end_parsing: tspl::end_job::new(),
+        commands: Box::from(commands),
+    }
+}
@@ -1,7 +1,8 @@
 extern crate barcoders;
 
-use std::cmp::PartialEq;
-use std::str::from_utf8;
+use crate::prelude::*;
+use core::cmp::PartialEq;
+use core::str::from_utf8;
 
 use barcoders::sym::codabar::Codabar;
 use barcoders::sym::code128::Code128;
@@ -16,8 +17,8 @@ use crate::text::TextSpan;
 use crate::utils::barcodes::upce::UPCE;
 use crate::{command::*, constants::*, context::*, graphics::*};
 
-#[derive(Clone)]
-enum BarcodeType {
+#[derive(Clone, Debug, PartialEq)]
+pub enum BarcodeType {
     UpcA,
     UpcE,
     Ean13,
@@ -36,6 +37,29 @@ enum BarcodeType {
     Unknown,
 }
 
+impl BarcodeType {
+    /// The name of the symbology
+    pub fn name(&self) -> &'static str {
+        match self {
+            BarcodeType::UpcA => "UPC-A",
+            BarcodeType::UpcE => "UPC-E",
+            BarcodeType::Ean13 => "EAN13",
+            BarcodeType::Ean8 => "EAN8",
+            BarcodeType::Code39 => "CODE39",
+            BarcodeType::Itf => "ITF",
+            BarcodeType::Nw7Codabar => "CODABAR",
+            BarcodeType::Code93 => "CODE93",
+            BarcodeType::Code128 | BarcodeType::Code128Auto => "CODE128",
+            BarcodeType::Gs1128 => "GS1-128",
+            BarcodeType::Gs1DatabarOmni => "GS1 DataBar Omnidirectional",
+            BarcodeType::Gs1DatabarTruncated => "GS1 DataBar Truncated",
+            BarcodeType::Gs1DatabarLimited => "GS1 DataBar Limited",
+            BarcodeType::Gs1DatabarExpanded => "GS1 DataBar Expanded",
+            BarcodeType::Unknown => "Barcode",
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 enum EncodingFunction {
     NulTerminated,
@@ -51,6 +75,8 @@ struct BarcodeHandler {
     capacity: u8,
     has_capacity: bool,
     accept_data: bool,
+    //The NUL of NUL terminated data arrived
+    terminated: bool,
     raw_params: Vec<u8>,
 }
 
@@ -111,145 +137,105 @@ impl BarcodeHandler {
     }
 }
 
+/// Encodes barcode data into points (bars) and the text that
+/// should be used for the human readable interface.
+///
+/// This is shared by command sets that have their own barcode
+/// command framing but use the same symbologies.
+pub(crate) fn encode_barcode(kind: &BarcodeType, data: &str) -> Result<(Vec<u8>, String), String> {
+    match kind {
+        BarcodeType::Code128 => {
+            //all code128 data has two bytes that set the type, we are converting this to the barcoders format
+            let adjusted_data = data
+                .replace("{A", "À")
+                .replace("{B", "Ɓ")
+                .replace("{C", "Ć");
+
+            let hri_data: String = data.replace("{A", "").replace("{B", "").replace("{C", "");
+
+            Code128::new(adjusted_data)
+                .map(|barcode| (barcode.encode(), hri_data))
+                .map_err(|error| error.to_string())
+        }
+        BarcodeType::Gs1128 => {
+            //Application identifiers are shown in parentheses but are not encoded,
+            //the symbol starts with FNC1 to mark the data as GS1
+            let hri_data = data.to_string();
+            let digits: String = data.chars().filter(|c| *c != '(' && *c != ')').collect();
+            let numeric = digits.chars().all(|c| c.is_ascii_digit());
+            let start = if numeric && digits.len().is_multiple_of(2) {
+                "Ć"
+            } else {
+                "Ɓ"
+            };
+
+            Code128::new(format!("{}Ź{}", start, digits))
+                .map(|barcode| (barcode.encode(), hri_data))
+                .map_err(|error| error.to_string())
+        }
+        BarcodeType::Nw7Codabar => Codabar::new(data.to_string())
+            .map(|barcode| (barcode.encode(), data.to_string()))
+            .map_err(|error| error.to_string()),
+        BarcodeType::Code39 => {
+            //Data can be surrounded with * or not
+            //The data that was provided should be shown as
+            //it was provided.
+            //Code39 doesn't want asterisks in the data
+            let text = data.to_string();
+            let data = text.replace('*', "");
+
+            Code39::new(data)
+                .map(|barcode| (barcode.encode(), text))
+                .map_err(|error| error.to_string())
+        }
+        BarcodeType::Code93 => Code93::new(data.to_string())
+            .map(|barcode| (barcode.encode(), data.to_string()))
+            .map_err(|error| error.to_string()),
+        BarcodeType::Ean13 => {
+            //The check digit is calculated, a 13th digit is only shown
+            let data_sp = data
+                .get(..12)
+                .ok_or_else(|| "Invalid data length".to_string())?;
+            EAN13::new(data_sp.to_string())
+                .map(|barcode| (barcode.encode(), data.to_string()))
+                .map_err(|error| error.to_string())
+        }
+        BarcodeType::UpcA => UPCA::new(data.to_string())
+            .map(|barcode| (barcode.encode(), data.to_string()))
+            .map_err(|error| error.to_string()),
+        BarcodeType::UpcE => UPCE::new(data.to_string())
+            .map(|barcode| (barcode.encode(), data.to_string()))
+            .map_err(|error| error.to_string()),
+        BarcodeType::Ean8 => EAN8::new(data.to_string())
+            .map(|barcode| (barcode.encode(), data.to_string()))
+            .map_err(|error| error.to_string()),
+        BarcodeType::Itf => TF::interleaved(data.to_string())
+            .map(|barcode| (barcode.encode(), data.to_string()))
+            .map_err(|error| error.to_string()),
+        _ => Err("Unknown barcode type".to_string()),
+    }
+}
+
 impl CommandHandler for BarcodeHandler {
     fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
         let raw_data = &command.data.clone() as &[u8];
         let data = from_utf8(raw_data).unwrap_or("");
-        let point_width = context.barcode.width;
-        let point_height = context.barcode.height;
-        let hri = context.barcode.human_readable.clone();
 
         //Invalid data length
         if !self.validate_data_length(data.len()) {
             return self.decorate_error("Invalid data length".to_string(), command);
         }
 
-        match self.kind {
-            BarcodeType::Code128 => {
-                //all code128 data has two bytes that set the type, we are converting this to the barcoders format
-                let adjusted_data = data
-                    .replace("{A", "À")
-                    .replace("{B", "Ɓ")
-                    .replace("{C", "Ć");
-
-                let hri_data: String = data.replace("{A", "").replace("{B", "").replace("{C", "");
-
-                return match Code128::new(adjusted_data.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(hri_data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::Nw7Codabar => {
-                return match Codabar::new(data.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::Code39 => {
-                //Data can be surrounded with * or not
-                //The data that was provided should be shown as
-                //it was provided.
-                //Code39 doesn't want asterisks in the data
-                let text = data.to_string();
-                let data = text.replace("*", "");
-
-                return match Code39::new(data) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(text, context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::Code93 => {
-                return match Code93::new(data.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::Ean13 => {
-                let data_sp = &data[..12];
-                return match EAN13::new(data_sp.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::UpcA => {
-                return match UPCA::new(data.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::UpcE => {
-                return match UPCE::new(data.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::Ean8 => {
-                return match EAN8::new(data.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            BarcodeType::Itf => {
-                return match TF::interleaved(data.to_string()) {
-                    Ok(barcode) => Some(GraphicsCommand::Barcode(Barcode {
-                        points: barcode.encode(),
-                        text: TextSpan::new_for_barcode(data.to_string(), context),
-                        point_width,
-                        point_height,
-                        hri,
-                    })),
-                    Err(error) => self.decorate_error(error.to_string(), command),
-                };
-            }
-            _ => return self.decorate_error("Unknown barcode type".to_string(), command),
+        match encode_barcode(&self.kind, data) {
+            Ok((points, text)) => Some(GraphicsCommand::Barcode(Barcode {
+                kind: self.kind.clone(),
+                points,
+                text: TextSpan::new_for_barcode(text, context),
+                point_width: context.barcode.width,
+                point_height: context.barcode.height,
+                hri: context.barcode.human_readable.clone(),
+            })),
+            Err(error) => self.decorate_error(error, command),
         }
     }
 
@@ -331,10 +317,15 @@ impl CommandHandler for BarcodeHandler {
 
         return match self.encoding {
             EncodingFunction::NulTerminated => {
-                if *data.last().unwrap_or(&0x01) == NUL {
-                    data.pop();
+                //The NUL is part of the command but not of the data,
+                //so the data is right even when the stream ends after it
+                if self.terminated {
                     return false;
                 }
+                if byte == NUL {
+                    self.terminated = true;
+                    return true;
+                }
                 data.push(byte);
                 true
             }
@@ -368,6 +359,7 @@ pub fn new() -> Command {
             capacity: 0,
             has_capacity: false,
             accept_data: false,
+            terminated: false,
             raw_params: vec![],
         }),
     )
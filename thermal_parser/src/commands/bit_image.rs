@@ -1,3 +1,5 @@
+use crate::prelude::*;
+use crate::util::reserve_data;
 use crate::{command::*, constants::*, context::*, graphics::*};
 
 #[derive(Clone)]
@@ -8,19 +10,21 @@ struct Handler {
     col_encoded: bool,
     size: u32,
     accept_data: bool,
-    stretch: (u8, u8),
+    //Normal density dots per image dot
+    scale: (u8, u8),
     params: Vec<u8>,
 }
 
 impl CommandHandler for Handler {
     fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
         let color = context.graphics.render_colors.color_1;
+        let stretch = context.profile.image_stretch(self.scale);
 
         if self.col_encoded {
             Some(GraphicsCommand::image_from_column_bytes_single_color(
                 self.width,
                 self.height,
-                self.stretch,
+                stretch,
                 &color,
                 ImageFlow::Inline,
                 &command.data,
@@ -29,7 +33,7 @@ impl CommandHandler for Handler {
             Some(GraphicsCommand::image_from_raster_bytes_single_color(
                 self.width,
                 self.height,
-                self.stretch,
+                stretch,
                 &color,
                 ImageFlow::Inline,
                 &command.data,
@@ -57,7 +61,7 @@ impl CommandHandler for Handler {
             return true;
         }
 
-        let m = *data.get(0).unwrap() as u32;
+        let m = *data.first().unwrap() as u32;
         let p1 = *data.get(1).unwrap() as u32;
         let p2 = byte as u32;
 
@@ -65,26 +69,22 @@ impl CommandHandler for Handler {
 
         self.width = p1 + p2 * 256;
 
-        if m == 32 || m == 33 {
-            //24 dot mode (m = 32, 33)
-            self.height = 24;
-            self.capacity = (self.width * self.height) / 8;
-            self.col_encoded = true;
-        } else {
-            //8 dot mode (m = 0, 1)
-            self.height = 8;
-            self.capacity = (self.width * self.height) / 8;
-        }
+        //Every column is 8 dots (m = 0, 1) or 24 dots (m = 32, 33) high
+        self.height = if m == 32 || m == 33 { 24 } else { 8 };
+        self.capacity = (self.width * self.height) / 8;
+        self.col_encoded = true;
 
-        //Image is single density, which oddly enough needs to
-        //have its pixels stretched by 2 on the w and 3 on the h
-        if m == 0 || m == 32 {
-            self.stretch = (2, 3);
-        }
+        //8 dot images have a third of the vertical density, so each
+        //dot is 3 dots high. Single density (m = 0, 32) has half
+        //the horizontal density, so each dot is 2 dots wide.
+        let scale_w = if m == 0 || m == 32 { 2 } else { 1 };
+        let scale_h = if m == 0 || m == 1 { 3 } else { 1 };
+        self.scale = (scale_w, scale_h);
 
         //After this, we accept data until the capacity is met
         self.accept_data = true;
         data.clear();
+        reserve_data(data, self.capacity as usize);
         true
     }
 
@@ -110,7 +110,7 @@ pub fn new() -> Command {
             col_encoded: true,
             size: 0,
             accept_data: false,
-            stretch: (1, 1),
+            scale: (1, 1),
             params: vec![],
         }),
     )
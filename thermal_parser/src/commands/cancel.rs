@@ -1,9 +1,11 @@
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
 impl CommandHandler for Handler {
+    //Deletes the print data in the page mode print area, ignored in standard mode
     fn get_device_command(
         &self,
         _command: &Command,
@@ -16,7 +18,7 @@ impl CommandHandler for Handler {
 pub fn new() -> Command {
     Command::new(
         "Cancel",
-        vec![CAN, '@' as u8],
+        vec![CAN],
         CommandType::Control,
         DataType::Empty,
         Box::new(Handler {}),
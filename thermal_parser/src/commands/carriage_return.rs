@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::text::TextSpan;
 use crate::{command::*, constants::*, context::*};
 
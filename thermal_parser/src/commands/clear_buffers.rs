@@ -0,0 +1,30 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        Some(vec![DeviceCommand::ClearBuffers])
+    }
+
+    fn is_realtime(&self) -> bool {
+        true
+    }
+}
+
+//The parameters are fixed, d1...d7 = 1 3 20 1 6 2 8
+pub fn new() -> Command {
+    Command::new(
+        "Clear Buffers",
+        vec![DLE, DC4, 8, 1, 3, 20, 1, 6, 2, 8],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
@@ -1,4 +1,5 @@
 //See: https://reference.epson-biz.com/modules/ref_escpos/index.php?content_id=130
+use crate::prelude::*;
 use crate::{command::*, constants::*, subcommands};
 
 pub fn new() -> Command {
@@ -1,5 +1,6 @@
 use crate::command::*;
 use crate::context::Context;
+use crate::prelude::*;
 
 #[derive(Clone)]
 struct Handler;
@@ -0,0 +1,100 @@
+//! ESC/P 8 dot bit image
+//!
+//! ESC * m nL nH d1...dk
+//!
+//! Each byte is one column of 8 pins, the horizontal density
+//! depends on m. Images print in the selected ribbon color.
+use crate::commands::esc_p::{density_stretch, PINS_PER_INCH};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    width: u32,
+    capacity: u32,
+    accept_data: bool,
+    params: Vec<u8>,
+}
+
+impl Handler {
+    //Horizontal dots per inch for each mode
+    fn density(m: u8) -> u32 {
+        match m {
+            0 => 60,
+            1 | 2 => 120,
+            3 => 240,
+            4 => 80,
+            5 => 72,
+            6 => 90,
+            7 => 144,
+            _ => 60,
+        }
+    }
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        if self.width == 0 {
+            return None;
+        }
+
+        let m = *self.params.first().unwrap_or(&0u8);
+        let dpi = context.graphics.dots_per_inch;
+        let stretch = (
+            density_stretch(Handler::density(m), dpi),
+            density_stretch(PINS_PER_INCH, dpi),
+        );
+
+        Some(GraphicsCommand::image_from_column_bytes_single_color(
+            self.width,
+            8,
+            stretch,
+            &context.text.color,
+            ImageFlow::Inline,
+            &command.data,
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if self.accept_data {
+            if data.len() >= self.capacity as usize {
+                return false;
+            }
+            data.push(byte);
+            return true;
+        }
+
+        self.params.push(byte);
+
+        if self.params.len() < 3 {
+            return true;
+        }
+
+        self.width = self.params[1] as u32 + self.params[2] as u32 * 256;
+        self.capacity = self.width;
+        self.accept_data = true;
+        true
+    }
+
+    //Used when converting commands back into other formats i.e. Thermal format
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut data = self.params.clone();
+        data.extend(command.data.clone());
+        (command.commands.to_vec(), data)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Bit Image",
+        vec![ESC, b'*'],
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler {
+            width: 0,
+            capacity: 0,
+            accept_data: false,
+            params: vec![],
+        }),
+    )
+}
@@ -0,0 +1,35 @@
+//! ESC/P dot matrix commands
+//!
+//! Impact printers (kitchen printers like the TM-U220) share most
+//! of ESC/POS, but a handful of commands take different parameters
+//! or use inch based motion units instead of dots.
+//!
+//! Vertical motion is in 1/216 inch units and bit images are
+//! printed by a 9 pin head with a pin pitch of 1/72 inch.
+
+pub mod bit_image;
+pub mod cancel_emphasis;
+pub mod cancel_italic;
+pub mod nine_pin_bit_image;
+pub mod print_and_feed;
+pub mod return_home;
+pub mod set_eighth_inch_line_spacing;
+pub mod set_emphasis;
+pub mod set_italic;
+pub mod set_line_spacing;
+pub mod set_print_mode;
+pub mod set_unidirectional;
+
+/// Vertical pin pitch of a 9 pin print head
+pub const PINS_PER_INCH: u32 = 72;
+
+/// Converts a value in 1/units_per_inch inch into dots
+pub fn inches_to_dots(n: u32, units_per_inch: u32, dots_per_inch: u16) -> u32 {
+    (n * dots_per_inch as u32 + units_per_inch / 2) / units_per_inch
+}
+
+/// How many times a dot printed at the given density
+/// needs to be repeated to cover the same area
+pub fn density_stretch(density: u32, dots_per_inch: u16) -> u8 {
+    inches_to_dots(1, density, dots_per_inch).clamp(1, u8::MAX as u32) as u8
+}
@@ -0,0 +1,86 @@
+//! ESC/P 9 pin bit image
+//!
+//! ESC ^ m nL nH d1...dk
+//!
+//! Each column is two bytes, the first byte holds pins 1-8 and
+//! the most significant bit of the second byte is pin 9.
+//! m = 0 is 60 dpi and m = 1 is 120 dpi.
+use crate::commands::esc_p::{density_stretch, PINS_PER_INCH};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    width: u32,
+    capacity: u32,
+    accept_data: bool,
+    params: Vec<u8>,
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        if self.width == 0 {
+            return None;
+        }
+
+        let m = *self.params.first().unwrap_or(&0u8);
+        let dpi = context.graphics.dots_per_inch;
+        let density = if m == 1 { 120 } else { 60 };
+        let stretch = (
+            density_stretch(density, dpi),
+            density_stretch(PINS_PER_INCH, dpi),
+        );
+
+        Some(GraphicsCommand::image_from_column_bytes_single_color(
+            self.width,
+            9,
+            stretch,
+            &context.text.color,
+            ImageFlow::Inline,
+            &command.data,
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if self.accept_data {
+            if data.len() >= self.capacity as usize {
+                return false;
+            }
+            data.push(byte);
+            return true;
+        }
+
+        self.params.push(byte);
+
+        if self.params.len() < 3 {
+            return true;
+        }
+
+        self.width = self.params[1] as u32 + self.params[2] as u32 * 256;
+        self.capacity = self.width * 2;
+        self.accept_data = true;
+        true
+    }
+
+    //Used when converting commands back into other formats i.e. Thermal format
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut data = self.params.clone();
+        data.extend(command.data.clone());
+        (command.commands.to_vec(), data)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P 9 Pin Bit Image",
+        vec![ESC, b'^'],
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler {
+            width: 0,
+            capacity: 0,
+            accept_data: false,
+            params: vec![],
+        }),
+    )
+}
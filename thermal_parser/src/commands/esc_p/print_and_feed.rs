@@ -0,0 +1,29 @@
+use crate::commands::esc_p::inches_to_dots;
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    //Feeds n/216 inch
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.first().unwrap_or(&0u8);
+        let dots = inches_to_dots(n as u32, 216, context.graphics.dots_per_inch);
+        Some(vec![DeviceCommand::Feed(dots as i16)])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Print and Feed",
+        vec![ESC, b'J'],
+        CommandType::Control,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
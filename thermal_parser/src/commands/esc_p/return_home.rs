@@ -0,0 +1,19 @@
+//! Moves the print head to the home position, this only
+//! affects print quality so there is nothing to render
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Return Home",
+        vec![ESC, b'<'],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
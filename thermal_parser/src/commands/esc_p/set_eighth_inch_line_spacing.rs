@@ -0,0 +1,23 @@
+use crate::commands::esc_p::inches_to_dots;
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        let dots = inches_to_dots(1, 8, context.graphics.dots_per_inch);
+        context.text.line_spacing = dots.min(u8::MAX as u32) as u8;
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Set 1/8 Inch Line Spacing",
+        vec![ESC, b'0'],
+        CommandType::Context,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
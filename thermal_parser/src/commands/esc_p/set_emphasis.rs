@@ -0,0 +1,21 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.text.bold = true;
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Select Emphasis",
+        vec![ESC, b'E'],
+        CommandType::TextStyle,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
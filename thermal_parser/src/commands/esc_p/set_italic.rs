@@ -0,0 +1,21 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.text.italic = true;
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Select Italic",
+        vec![ESC, b'4'],
+        CommandType::TextStyle,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,25 @@
+use crate::commands::esc_p::inches_to_dots;
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    //Line spacing is n/216 inch
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let n = *command.data.first().unwrap_or(&0u8);
+        let dots = inches_to_dots(n as u32, 216, context.graphics.dots_per_inch);
+        context.text.line_spacing = dots.min(u8::MAX as u32) as u8;
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Set Line Spacing",
+        vec![ESC, b'3'],
+        CommandType::Context,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
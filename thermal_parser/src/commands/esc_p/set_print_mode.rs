@@ -0,0 +1,75 @@
+//! ESC/P master select
+//!
+//! Unlike ESC/POS there is no double height bit, instead
+//! condensed, double strike and italic can be selected.
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*, util::bitflags_lsb};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let (elite, _, condensed, emphasized, double_strike, wide, italic, underline) =
+            bitflags_lsb(command.data.first().unwrap_or(&0u8));
+
+        context.set_font(if elite || condensed { Font::B } else { Font::A });
+        context.text.bold = emphasized || double_strike;
+        context.text.italic = italic;
+        context.text.height_mult = 1;
+        context.text.width_mult = if wide { 2 } else { 1 };
+        context.text.underline = if underline {
+            TextUnderline::On
+        } else {
+            TextUnderline::Off
+        };
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        let mut changes = vec![];
+
+        let (elite, _, condensed, emphasized, double_strike, wide, italic, underline) =
+            bitflags_lsb(command.data.first().unwrap_or(&0u8));
+
+        changes.push(if elite {
+            "Elite"
+        } else if condensed {
+            "Condensed"
+        } else {
+            "Pica"
+        });
+        changes.push(if emphasized {
+            "Emphasized"
+        } else {
+            "Not Emphasized"
+        });
+        changes.push(if double_strike {
+            "Double Strike"
+        } else {
+            "Single Strike"
+        });
+        changes.push(if wide {
+            "Double Width"
+        } else {
+            "Standard Width"
+        });
+        changes.push(if italic { "Italic" } else { "Not Italic" });
+        changes.push(if underline {
+            "Underline"
+        } else {
+            "No Underline"
+        });
+
+        format!("{}: {:?}", command.name, changes)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Master Select",
+        vec![ESC, b'!'],
+        CommandType::TextStyle,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
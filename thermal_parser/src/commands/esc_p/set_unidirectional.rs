@@ -0,0 +1,19 @@
+//! Turns unidirectional printing on or off, this only
+//! affects print quality so there is nothing to render
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "ESC/P Set Unidirectional Printing",
+        vec![ESC, b'U'],
+        CommandType::Control,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
@@ -1,9 +1,11 @@
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
 impl CommandHandler for Handler {
+    //Functions B to D feed n vertical motion units before the cut
     fn get_device_command(
         &self,
         command: &Command,
@@ -11,15 +13,13 @@ impl CommandHandler for Handler {
     ) -> Option<Vec<DeviceCommand>> {
         let m = *command.data.get(0).unwrap_or(&0u8);
         let n = *command.data.get(1).unwrap_or(&0u8);
+        let feed = DeviceCommand::Feed(n as i16);
 
         return match m {
             0 | 48 => Some(vec![DeviceCommand::FullCut]),
             1 | 49 => Some(vec![DeviceCommand::PartialCut]),
-            65 | 97 | 103 => Some(vec![DeviceCommand::FullCut, DeviceCommand::Feed(n as i16)]),
-            66 | 98 | 104 => Some(vec![
-                DeviceCommand::PartialCut,
-                DeviceCommand::Feed(n as i16),
-            ]),
+            65 | 97 | 103 => Some(vec![feed, DeviceCommand::FullCut]),
+            66 | 98 | 104 => Some(vec![feed, DeviceCommand::PartialCut]),
             _ => None,
         };
     }
@@ -1,11 +1,13 @@
-use crate::command::DeviceCommand::{EndPageMode, EndPrint, PrintPageMode};
+use crate::command::DeviceCommand::{EndPageMode, EndPrint, FeedToNextLabel, PrintPageMode};
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
 impl CommandHandler for Handler {
-    //! Ends page mode if it is enabled. Otherwise, ends the print job.
+    //Ends page mode if it is enabled. Otherwise, ends the print job
+    //or feeds to the next label when printing on labels.
     fn get_device_command(
         &self,
         _command: &Command,
@@ -14,6 +16,9 @@ impl CommandHandler for Handler {
         if context.page_mode.enabled {
             return Some(vec![EndPageMode, PrintPageMode]);
         }
+        if context.label.length.is_some() {
+            return Some(vec![FeedToNextLabel]);
+        }
         Some(vec![EndPrint])
     }
 }
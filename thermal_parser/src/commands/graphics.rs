@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, constants::*, subcommands};
 
 pub fn new() -> Command {
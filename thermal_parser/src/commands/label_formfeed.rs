@@ -0,0 +1,29 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    //Feeds to the top of the next label, only used with label paper
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        if context.label.length.is_some() {
+            return Some(vec![DeviceCommand::FeedToNextLabel]);
+        }
+        None
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Print And Feed To Label Top",
+        vec![GS, FF],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
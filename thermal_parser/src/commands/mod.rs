@@ -3,27 +3,33 @@ pub mod begin_print;
 pub mod bit_image;
 pub mod cancel;
 pub mod carriage_return;
+pub mod clear_buffers;
 pub mod code_2d;
 pub mod default_line_spacing;
 pub mod end_print;
+pub mod esc_p;
 pub mod feed_and_cut;
 pub mod formfeed;
 pub mod graphics;
 pub mod horizontal_tab;
 pub mod initialize;
+pub mod label_formfeed;
 pub mod large_graphics;
 pub mod linefeed;
+pub mod offset_horizontal_pos;
 pub mod offset_vertical_pos;
 pub mod page_mode_print_area;
 pub mod page_mode_print_data;
 pub mod page_mode_print_direction;
 pub mod paper_end_sensor;
+pub mod power_off;
 pub mod print_and_feed;
 pub mod print_and_feed_lines;
 pub mod print_and_reverse_feed_lines;
 pub mod print_stop_sensor;
 pub mod pulse;
 pub mod raster_bit_image;
+pub mod realtime_pulse;
 pub mod request_response_transmission;
 pub mod select_standard_mode;
 pub mod set_alt_color;
@@ -31,6 +37,7 @@ pub mod set_barcode_font;
 pub mod set_barcode_height;
 pub mod set_barcode_hri;
 pub mod set_barcode_width;
+pub mod set_black_mark_adjustment;
 pub mod set_black_white_invert;
 pub mod set_character_effects;
 pub mod set_character_size;
@@ -46,14 +53,21 @@ pub mod set_line_spacing;
 pub mod set_motion_units;
 pub mod set_page_mode;
 pub mod set_panel_buttons;
+pub mod set_paper_layout;
 pub mod set_peripheral_device;
+pub mod set_print_control;
 pub mod set_print_mode;
+pub mod set_realtime_commands;
 pub mod set_smoothing;
 pub mod set_tab_len;
 pub mod set_underline;
 pub mod set_upside_down;
 pub mod set_vertical_pos;
+pub mod star;
+pub mod star_raster;
 pub mod text;
 pub mod transmit_printer_id;
+pub mod transmit_realtime_status;
+pub mod tspl;
 pub mod unknown;
 pub mod unknown_gs_g;
@@ -0,0 +1,39 @@
+/// Moves the horizontal print position by a signed number
+/// of horizontal motion units from the current position.
+///
+/// In Standard mode a negative offset that would move before
+/// the start of the line stops at the start of the line, in
+/// Page mode it stops at the start of the print area.
+use crate::context::Context;
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        context.offset_x_relative(get_pos(&command.data));
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!("{} --> {}", &command.name, get_pos(&command.data))
+    }
+}
+
+fn get_pos(data: &[u8]) -> i16 {
+    let nl = data.first().unwrap_or(&0u8);
+    let nh = data.get(1).unwrap_or(&0u8);
+
+    (*nl as u16 + (*nh as u16 * 256)) as i16
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set Relative Horizontal Position",
+        vec![ESC, b'\\'],
+        CommandType::Context,
+        DataType::Double,
+        Box::new(Handler {}),
+    )
+}
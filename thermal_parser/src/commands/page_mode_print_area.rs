@@ -30,11 +30,17 @@
 //! the print direction that is set.
 //!
 //! Dimension that go beyond the physical print area
-//! (max receipt width) will be clipped to the max receipt width.
+//! (max receipt width) will be clipped to the max receipt width,
+//! and heights are clipped to MAX_PAGE_HEIGHT_MM.
 //!
+use crate::prelude::*;
 use crate::util::parse_u16;
 use crate::{command::*, constants::*, context::*};
 
+//Printers only buffer a few hundred millimeters of page,
+//this leaves headroom while keeping the canvas allocatable
+const MAX_PAGE_HEIGHT_MM: f32 = 1000.0;
+
 #[derive(Clone)]
 struct Handler;
 
@@ -58,7 +64,15 @@ impl CommandHandler for Handler {
             //Only logical elements should be set here.
             // All other area fields in the page_mode struct
             // are reserved For the rendering context
-            context.set_page_area(calculate_page_area(command))
+            context.set_page_area(calculate_page_area(command));
+
+            let max_w = context.graphics.paper_area.w;
+            let max_h = context.profile.mm_to_dots(MAX_PAGE_HEIGHT_MM);
+            let area = &mut context.page_mode.logical_area;
+            area.x = area.x.min(max_w);
+            area.w = area.w.min(max_w - area.x);
+            area.y = area.y.min(max_h);
+            area.h = area.h.min(max_h - area.y);
         }
     }
 
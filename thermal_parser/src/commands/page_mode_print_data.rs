@@ -1,8 +1,10 @@
 //!
-//! This command ends Page Mode and prints the contents
-//! of the Page Mode buffer.
+//! This command prints the contents of the Page Mode buffer
+//! without leaving Page Mode. The buffer, print area and
+//! print position are kept so the page can be printed again.
 //!
 
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
@@ -55,8 +55,9 @@
 //!        ← x
 //! ```
 
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
-use std::mem;
+use core::mem;
 
 #[derive(Clone)]
 struct Handler;
@@ -0,0 +1,30 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        Some(vec![DeviceCommand::PowerOff])
+    }
+
+    fn is_realtime(&self) -> bool {
+        true
+    }
+}
+
+//The parameters are fixed, a = 1 and b = 8
+pub fn new() -> Command {
+    Command::new(
+        "Execute Power-Off Sequence",
+        vec![DLE, DC4, 2, 1, 8],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
@@ -1,9 +1,11 @@
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
 impl CommandHandler for Handler {
+    //n is in vertical motion units
     fn get_device_command(
         &self,
         command: &Command,
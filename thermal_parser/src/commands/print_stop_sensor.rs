@@ -1,9 +1,19 @@
-use crate::{command::*, constants::*};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
-impl CommandHandler for Handler {}
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.first().unwrap_or(&0);
+        Some(vec![DeviceCommand::SelectPrintStopSensors(n)])
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
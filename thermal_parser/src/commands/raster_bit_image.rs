@@ -1,3 +1,5 @@
+use crate::prelude::*;
+use crate::util::reserve_data;
 use crate::{command::*, constants::*, context::*, graphics::*};
 
 #[derive(Clone)]
@@ -12,18 +14,19 @@ struct Handler {
 
 impl CommandHandler for Handler {
     fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
-        let stretch = match self.scaling {
-            0 | 48 => (1, 1),
-            1 | 49 => (2, 1),
-            2 | 50 => (1, 2),
-            3 | 52 => (2, 2),
+        //Every dot of the image is 1 or 2 normal density dots wide and high
+        let scale = match self.scaling {
+            0 | 48 => (1, 1), //Normal
+            1 | 49 => (2, 1), //Double width
+            2 | 50 => (1, 2), //Double height
+            3 | 51 => (2, 2), //Quadruple
             _ => (1, 1),
         };
 
         Some(GraphicsCommand::image_from_raster_bytes_single_color(
             self.width,
             self.height,
-            stretch,
+            context.profile.image_stretch(scale),
             context.graphics.render_colors.color_for_number(1),
             ImageFlow::Block,
             &command.data,
@@ -52,6 +55,7 @@ impl CommandHandler for Handler {
             self.params = vec![self.scaling, xl as u8, xh as u8, yl as u8, yh as u8];
 
             data.clear();
+            reserve_data(data, self.capacity as usize);
 
             self.accept_data = true;
             return true;
@@ -0,0 +1,37 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    //m is the connector pin (0 or 1) and t the pulse time in 100ms
+    //(1 to 8), the printer ignores the command for other values
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let m = *command.data.first()?;
+        let t = *command.data.get(1)?;
+
+        if m > 1 || !(1..=8).contains(&t) {
+            return None;
+        }
+        Some(vec![DeviceCommand::Pulse])
+    }
+
+    fn is_realtime(&self) -> bool {
+        true
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Generate Pulse In Real-Time",
+        vec![DLE, DC4, 1],
+        CommandType::Control,
+        DataType::Double,
+        Box::new(Handler {}),
+    )
+}
@@ -5,6 +5,7 @@
 //!
 
 use crate::context::Context;
+use crate::prelude::*;
 use crate::{command::*, constants::*};
 
 #[derive(Clone)]
@@ -4,6 +4,7 @@
 //! Sets the barcode line and space between width. Minimum is 2 and max is 6.
 //! Values that fall outside of this range will be brought into range.
 //!
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
@@ -0,0 +1,68 @@
+use crate::context::Context;
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler {
+    capacity: u32,
+}
+
+impl CommandHandler for Handler {
+    fn push(&mut self, command: &mut Vec<u8>, byte: u8) -> bool {
+        if command.len() < 2 {
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() == 2 {
+            let pl = *command.first().unwrap();
+            let ph = *command.get(1).unwrap();
+            self.capacity = (pl as u32 + ph as u32 * 256) + 2;
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() < self.capacity as usize {
+            command.push(byte);
+            return true;
+        }
+
+        false
+    }
+
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        if command.data.len() < 6 {
+            return;
+        }
+
+        let a = command.data[2];
+        let m = command.data[3];
+        let n = command.data[4] as i32 + command.data[5] as i32 * 256;
+
+        //Adjustments are in vertical motion units
+        let mut dots = context.v_units_to_dots(n);
+
+        //m = 1 moves in the reverse direction
+        if m == 1 || m == 49 {
+            dots = -dots;
+        }
+
+        match a {
+            //Print start position
+            1 => context.label.start_adjustment = dots,
+            //Cut position
+            2 => context.label.cut_adjustment = dots,
+            _ => {}
+        }
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set Black Mark Adjustment",
+        vec![GS, b'(', b'F'],
+        CommandType::Context,
+        DataType::Custom,
+        Box::new(Handler { capacity: 2 }),
+    )
+}
@@ -1,4 +1,5 @@
 use crate::context::Context;
+use crate::prelude::*;
 use crate::{command::*, constants::*};
 
 #[derive(Clone)]
@@ -1,4 +1,5 @@
 use crate::command::DeviceCommand::{SetTextHeight, SetTextWidth};
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
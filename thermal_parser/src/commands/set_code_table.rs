@@ -69,6 +69,7 @@
 //! Page 254
 //! Page 255 We are using this for unicode
 
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
@@ -5,6 +5,7 @@
 ///
 /// In page mode, the x/y may need to be swapped based on the orientation.
 ///
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
@@ -36,6 +36,7 @@
 //! 75 India (Punjabi)
 //! 82 India (Marathi)
 
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
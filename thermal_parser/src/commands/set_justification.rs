@@ -1,5 +1,6 @@
-use crate::{command::*, constants::*, context::*};
 use crate::command::DeviceCommand::Justify;
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
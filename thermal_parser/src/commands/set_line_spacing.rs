@@ -1,16 +1,19 @@
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
 impl CommandHandler for Handler {
+    //n is in vertical motion units, changing the units with GS P
+    //later doesn't change the spacing that was already set
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        let n = *command
-            .data
-            .get(0)
-            .unwrap_or(&context.default.as_ref().unwrap().text.line_spacing);
+        let dots = match command.data.first() {
+            Some(n) => context.v_units_to_dots(*n as i32).max(0) as u32,
+            None => context.default.as_ref().unwrap().text.line_spacing as u32,
+        };
 
-        context.text.line_spacing = n;
+        context.text.line_spacing = dots.min(u8::MAX as u32) as u8;
     }
 }
 
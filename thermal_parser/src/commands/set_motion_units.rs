@@ -1,10 +1,15 @@
-/// Motion units in this library are divisors
-/// If the dots_per_inch is 203
+/// Sets the horizontal (x) and vertical (y) motion units
+/// to 1/x and 1/y inch.
 ///
-/// dpi   val  =  mu
-/// 203 / 203  =  1 <-- motion units are divided by 1
-/// 203 / 101 =   2 <-- motion units are divided by 2
+/// Positions and offsets given in motion units are converted
+/// to dots using the dots_per_inch of the printer, so on a 203
+/// dpi printer a motion unit of 1/406 inch is half a dot.
 ///
+/// When x or y is 0 that unit is set back to the default of
+/// the printer profile, which is one dot per unit unless the
+/// profile sets it (1/180 inch for a lot of Epson printers).
+///
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
@@ -12,38 +17,32 @@ struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        let x = *command.data.get(0).unwrap_or(&0u8);
+        let x = *command.data.first().unwrap_or(&0u8);
         let y = *command.data.get(1).unwrap_or(&0u8);
 
-        if x == 0 || y == 0 {
-            return;
-        }
-
-        let h_motion_unit = context.graphics.dots_per_inch / x as u16;
-        let v_motion_unit = context.graphics.dots_per_inch / y as u16;
+        let dots_per_inch = context.graphics.dots_per_inch;
+        let (default_h, default_v) = context
+            .profile
+            .motion_units
+            .unwrap_or((dots_per_inch, dots_per_inch));
 
-        if x > 0 {
-            context.graphics.h_motion_unit = h_motion_unit as u8;
-        } else {
-            if let Some(default_context) = &context.default {
-                context.graphics.h_motion_unit = default_context.graphics.h_motion_unit;
-            }
-        }
+        context.graphics.h_motion_units = if x > 0 { x as u16 } else { default_h };
+        context.graphics.v_motion_units = if y > 0 { y as u16 } else { default_v };
+        context.graphics.h_motion_carry = 0.0;
+        context.graphics.v_motion_carry = 0.0;
+    }
 
-        if y > 0 {
-            context.graphics.v_motion_unit = v_motion_unit as u8;
-        } else {
-            if let Some(default_context) = &context.default {
-                context.graphics.v_motion_unit = default_context.graphics.v_motion_unit;
-            }
-        }
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        let x = command.data.first().unwrap_or(&0u8);
+        let y = command.data.get(1).unwrap_or(&0u8);
+        format!("{} --> 1/{} x 1/{} inch", &command.name, x, y)
     }
 }
 
 pub fn new() -> Command {
     Command::new(
         "Set Vertical and Horizontal Motion Units",
-        vec![GS, 'P' as u8],
+        vec![GS, b'P'],
         CommandType::Context,
         DataType::Double,
         Box::new(Handler {}),
@@ -1,5 +1,6 @@
 use crate::command::DeviceCommand::BeginPageMode;
 use crate::context::Context;
+use crate::prelude::*;
 use crate::{command::*, constants::*};
 
 #[derive(Clone)]
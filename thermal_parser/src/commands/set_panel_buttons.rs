@@ -1,9 +1,20 @@
-use crate::{command::*, constants::*};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
-impl CommandHandler for Handler {}
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.first().unwrap_or(&0);
+        //The buttons are disabled when the lowest bit is set
+        Some(vec![DeviceCommand::EnablePanelButtons(n & 1 == 0)])
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
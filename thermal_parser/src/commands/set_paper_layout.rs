@@ -0,0 +1,141 @@
+//! GS ( P
+//!
+//! Paper layout of label and linerless printers. The layout is
+//! stored in the printer like the black mark adjustments, so it is
+//! not reset by initialize.
+//!
+//! fn 48 sets the paper width, the left origin and the printable
+//! width in horizontal motion units, a printable width of 0 prints
+//! up to the right edge of the paper.
+//!
+//! fn 49 sets the paper length in vertical motion units, the length
+//! of a label or of the piece a linerless label is cut to. A length
+//! of 0 is continuous paper.
+use crate::context::{Context, RenderArea};
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler {
+    capacity: u32,
+}
+
+//Paper width, origin and printable width in dots
+fn paper_width(command: &Command, context: &Context) -> Option<(u32, u32, u32)> {
+    if command.data.len() < 9 || command.data[2] != 48 {
+        return None;
+    }
+
+    let value = |i: usize| command.data[i] as i32 + command.data[i + 1] as i32 * 256;
+    let width = context.h_units_to_dots(value(3)) as u32;
+    let origin = context.h_units_to_dots(value(5)) as u32;
+    let printable = context.h_units_to_dots(value(7)) as u32;
+
+    if width == 0 || origin >= width {
+        return None;
+    }
+
+    let printable = match printable {
+        0 => width - origin,
+        _ => printable.min(width - origin),
+    };
+
+    Some((width, origin, printable))
+}
+
+//Paper length in dots, none for continuous paper
+fn paper_length(command: &Command, context: &Context) -> Option<Option<u32>> {
+    if command.data.len() < 5 || command.data[2] != 49 {
+        return None;
+    }
+
+    let length = command.data[3] as i32 + command.data[4] as i32 * 256;
+    let dots = context.v_units_to_dots(length) as u32;
+    Some((dots > 0).then_some(dots))
+}
+
+impl CommandHandler for Handler {
+    fn push(&mut self, command: &mut Vec<u8>, byte: u8) -> bool {
+        if command.len() < 2 {
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() == 2 {
+            let pl = *command.first().unwrap();
+            let ph = *command.get(1).unwrap();
+            self.capacity = (pl as u32 + ph as u32 * 256) + 2;
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() < self.capacity as usize {
+            command.push(byte);
+            return true;
+        }
+
+        false
+    }
+
+    fn debug(&self, command: &Command, context: &Context) -> String {
+        if let Some((width, origin, printable)) = paper_width(command, context) {
+            return format!(
+                "{} Width: {} dots, origin {} and printable width {}",
+                command.name, width, origin, printable
+            );
+        }
+
+        match paper_length(command, context) {
+            Some(Some(length)) => format!("{} Length: {} dots", command.name, length),
+            Some(None) => format!("{} Length: continuous", command.name),
+            None => format!("{} Unknown Function", command.name),
+        }
+    }
+
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        if let Some((width, origin, printable)) = paper_width(command, context) {
+            let paper_area = RenderArea {
+                x: origin,
+                y: width - origin - printable,
+                w: width,
+                h: 0,
+            };
+
+            //Initialize restores the default graphics context
+            let mut graphics = vec![&mut context.graphics];
+            if let Some(default) = &mut context.default {
+                graphics.push(&mut default.graphics);
+            }
+
+            for graphics in graphics {
+                graphics.paper_area = paper_area.clone();
+                graphics.render_area.w = printable;
+            }
+        }
+
+        if let Some(length) = paper_length(command, context) {
+            context.label.length = length;
+        }
+    }
+
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        if paper_width(command, context).is_some() || paper_length(command, context).is_some() {
+            return Some(vec![DeviceCommand::ChangePaperLayout]);
+        }
+        None
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set Paper Layout",
+        vec![GS, b'(', b'P'],
+        CommandType::ContextControl,
+        DataType::Custom,
+        Box::new(Handler { capacity: 2 }),
+    )
+}
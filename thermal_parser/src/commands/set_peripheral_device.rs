@@ -1,9 +1,21 @@
-use crate::{command::*, constants::*};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
-impl CommandHandler for Handler {}
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.first().unwrap_or(&1);
+        //The printer is enabled when the lowest bit is set,
+        //the customer display when the second bit is set
+        Some(vec![DeviceCommand::SelectPeripheral(n & 3)])
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
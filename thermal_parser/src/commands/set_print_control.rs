@@ -0,0 +1,83 @@
+use crate::context::Context;
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler {
+    capacity: u32,
+}
+
+impl CommandHandler for Handler {
+    fn push(&mut self, command: &mut Vec<u8>, byte: u8) -> bool {
+        if command.len() < 2 {
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() == 2 {
+            let pl = *command.first().unwrap();
+            let ph = *command.get(1).unwrap();
+            self.capacity = (pl as u32 + ph as u32 * 256) + 2;
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() < self.capacity as usize {
+            command.push(byte);
+            return true;
+        }
+
+        false
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        let fnc = command.data.get(2).unwrap_or(&0u8);
+        let m = command.data.get(3).unwrap_or(&0u8);
+
+        let name = match fnc {
+            48 => "Print Control Method",
+            49 => "Print Density",
+            50 => "Print Speed",
+            97 => "Thermal Head Divisions",
+            _ => "Unknown Function",
+        };
+
+        format!("{} {}: {}", command.name, name, m)
+    }
+
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        if command.data.len() < 4 {
+            return;
+        }
+
+        let fnc = command.data.get(2).unwrap();
+        let m = *command.data.get(3).unwrap();
+
+        match fnc {
+            //Select print control method {m}
+            48 => context.graphics.print_control_method = m,
+            //Select print density, 250-255 is -6 to -1, 0-8 is 0 to +8
+            49 => {
+                context.graphics.print_density = match m {
+                    0..=8 | 250..=255 => m as i8,
+                    _ => context.graphics.print_density,
+                }
+            }
+            //Select print speed level {m}, 0 is the printer default
+            50 => context.graphics.print_speed = m,
+            //Select number of parts for the thermal head energizing {m}
+            97 => context.graphics.head_divisions = m,
+            _ => {}
+        }
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set Print Control",
+        vec![GS, b'(', b'K'],
+        CommandType::Context,
+        DataType::Custom,
+        Box::new(Handler { capacity: 2 }),
+    )
+}
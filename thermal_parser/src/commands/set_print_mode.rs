@@ -1,28 +1,45 @@
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*, util::bitflags_lsb};
 
 #[derive(Clone)]
 struct Handler;
 
+//Every mode is set, modes with a 0 bit are turned off. The modes are
+//shared with ESC M, ESC E, GS ! and ESC -, the last command received wins
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        let (font_b, _, _, bold, tall, wide, underline, _) =
+        let (font_b, _, _, bold, tall, wide, _, underline) =
             bitflags_lsb(command.data.get(0).unwrap_or(&0u8));
 
         context.set_font(if font_b { Font::B } else { Font::A });
         context.text.bold = bold;
         context.text.height_mult = if tall { 2 } else { 1 };
         context.text.width_mult = if wide { 2 } else { 1 };
-        context.text.underline = if underline {
-            TextUnderline::On
-        } else {
-            TextUnderline::Off
+        context.text.underline = match (underline, context.text.underline_thickness) {
+            (false, _) => TextUnderline::Off,
+            (true, 2) => TextUnderline::Double,
+            (true, _) => TextUnderline::On,
         };
     }
 
+    //The size is sent the same way as GS ! so renderers see every size change
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let (_, _, _, _, tall, wide, _, _) = bitflags_lsb(command.data.first().unwrap_or(&0u8));
+
+        Some(vec![
+            DeviceCommand::SetTextWidth(if wide { 2 } else { 1 }),
+            DeviceCommand::SetTextHeight(if tall { 2 } else { 1 }),
+        ])
+    }
+
     fn debug(&self, command: &Command, _context: &Context) -> String {
         let mut changes = vec![];
 
-        let (font_b, _, _, bold, tall, wide, underline, _) =
+        let (font_b, _, _, bold, tall, wide, _, underline) =
             bitflags_lsb(command.data.get(0).unwrap_or(&0u8));
 
         changes.push(if font_b { "Font B" } else { "Font A" });
@@ -0,0 +1,66 @@
+use crate::context::Context;
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler {
+    capacity: u32,
+}
+
+impl CommandHandler for Handler {
+    fn push(&mut self, command: &mut Vec<u8>, byte: u8) -> bool {
+        if command.len() < 2 {
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() == 2 {
+            let pl = *command.first().unwrap();
+            let ph = *command.get(1).unwrap();
+            self.capacity = (pl as u32 + ph as u32 * 256) + 2;
+            command.push(byte);
+            return true;
+        }
+
+        if command.len() < self.capacity as usize {
+            command.push(byte);
+            return true;
+        }
+
+        false
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        match self.realtime_commands(command) {
+            Some(true) => format!("{}: Enabled", command.name),
+            Some(false) => format!("{}: Disabled", command.name),
+            None => format!("{} {:02X?}", command.name, command.data),
+        }
+    }
+
+    //Data is m (20) followed by pairs of a command (a) and
+    //whether it is enabled (b). Real-time processing stays on
+    //while any of the listed commands is enabled
+    fn realtime_commands(&self, command: &Command) -> Option<bool> {
+        if command.data.get(2) != Some(&20) {
+            return None;
+        }
+
+        let pairs = command.data.get(3..)?.chunks_exact(2);
+        if pairs.len() == 0 {
+            return None;
+        }
+
+        Some(pairs.into_iter().any(|pair| pair[1] & 1 == 1))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Enable Real-Time Commands",
+        vec![GS, b'(', b'D'],
+        CommandType::Control,
+        DataType::Custom,
+        Box::new(Handler { capacity: 2 }),
+    )
+}
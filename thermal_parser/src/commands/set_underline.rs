@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
@@ -7,9 +8,15 @@ impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
         let n = *command.data.get(0).unwrap_or(&0u8);
         match n {
-            0 | 48 => context.text.underline = TextUnderline::Off,
-            1 | 49 => context.text.underline = TextUnderline::On,
-            2 | 50 => context.text.underline = TextUnderline::Double,
+            1 | 49 => {
+                context.text.underline = TextUnderline::On;
+                context.text.underline_thickness = 1;
+            }
+            2 | 50 => {
+                context.text.underline = TextUnderline::Double;
+                context.text.underline_thickness = 2;
+            }
+            //Turning the underline off keeps the thickness for ESC !
             _ => context.text.underline = TextUnderline::Off,
         }
     }
@@ -12,6 +12,7 @@
 use crate::command::{Command, CommandHandler, CommandType, DataType};
 use crate::constants::GS;
 use crate::context::Context;
+use crate::prelude::*;
 
 #[derive(Clone)]
 struct Handler;
@@ -0,0 +1,129 @@
+//! Star barcode command
+//!
+//! ESC b n1 n2 n3 n4 d1...dk RS
+//!
+//! n1 barcode type, n2 HRI, n3 module width mode and n4 height in dots.
+//! Data is terminated by RS.
+use crate::prelude::*;
+use core::str::from_utf8;
+
+use crate::commands::barcode::{encode_barcode, BarcodeType};
+use crate::text::TextSpan;
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    params: Vec<u8>,
+    finished: bool,
+}
+
+impl Handler {
+    fn kind(&self) -> BarcodeType {
+        match self.params.first().unwrap_or(&255u8) {
+            0 | 48 => BarcodeType::UpcE,
+            1 | 49 => BarcodeType::UpcA,
+            2 | 50 => BarcodeType::Ean8,
+            3 | 51 => BarcodeType::Ean13,
+            4 | 52 => BarcodeType::Code39,
+            5 | 53 => BarcodeType::Itf,
+            6 | 54 => BarcodeType::Code128,
+            7 | 55 => BarcodeType::Code93,
+            8 | 56 => BarcodeType::Nw7Codabar,
+            _ => BarcodeType::Unknown,
+        }
+    }
+
+    fn hri(&self) -> HumanReadableInterface {
+        match self.params.get(1).unwrap_or(&1u8) {
+            2 | 50 | 4 | 52 => HumanReadableInterface::Below,
+            _ => HumanReadableInterface::None,
+        }
+    }
+
+    //Mode 1, 2 and 3 are 2, 3 and 4 dot modules
+    fn point_width(&self) -> u8 {
+        match self.params.get(2).unwrap_or(&2u8) {
+            1 | 49 => 2,
+            3 | 51 => 4,
+            _ => 3,
+        }
+    }
+
+    fn point_height(&self, context: &Context) -> u8 {
+        match self.params.get(3) {
+            Some(0) | None => context.barcode.height,
+            Some(h) => *h,
+        }
+    }
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let data = from_utf8(&command.data).unwrap_or("");
+
+        match encode_barcode(&self.kind(), data) {
+            Ok((points, text)) => Some(GraphicsCommand::Barcode(Barcode {
+                kind: self.kind(),
+                points,
+                text: TextSpan::new_for_barcode(text, context),
+                point_width: self.point_width(),
+                point_height: self.point_height(context),
+                hri: self.hri(),
+            })),
+            Err(error) => Some(GraphicsCommand::Error(format!(
+                "Star Barcode {} --> {}",
+                error, data
+            ))),
+        }
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} with params {:?} and data {}",
+            command.name,
+            self.params,
+            from_utf8(&command.data).unwrap_or("[No Data]")
+        )
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        if self.params.len() < 4 {
+            self.params.push(byte);
+            return true;
+        }
+
+        //RS terminates the data, it is consumed but not kept
+        if byte == RS {
+            self.finished = true;
+            return true;
+        }
+
+        data.push(byte);
+        true
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut commands = command.commands.to_vec();
+        commands.extend(self.params.clone());
+        let mut data = command.data.clone();
+        data.push(RS);
+        (commands, data)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Barcode",
+        vec![ESC, b'b'],
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler {
+            params: vec![],
+            finished: false,
+        }),
+    )
+}
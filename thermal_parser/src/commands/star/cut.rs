@@ -0,0 +1,34 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.first().unwrap_or(&0u8);
+
+        //2 and 3 feed to the cutter position before cutting
+        match n {
+            0 | 48 => Some(vec![DeviceCommand::FullCut]),
+            1 | 49 => Some(vec![DeviceCommand::PartialCut]),
+            2 | 50 => Some(vec![DeviceCommand::FeedLine(1), DeviceCommand::FullCut]),
+            3 | 51 => Some(vec![DeviceCommand::FeedLine(1), DeviceCommand::PartialCut]),
+            _ => None,
+        }
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Cut",
+        vec![ESC, b'd'],
+        CommandType::Control,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,23 @@
+//! Star Line Mode commands
+//!
+//! Star Micronics printers (TSP100, TSP650 etc.) speak their own
+//! line mode dialect. A handful of commands are shared with ESC/POS
+//! (ESC @, LF, ESC -) and are reused from the parent commands module,
+//! the commands in here are the ones that are Star specific or that
+//! have a different meaning than their ESC/POS counterpart.
+//!
+//! See: https://star-m.jp/products/s_print/sdk/StarPRNT/manual/en/index.html
+
+pub mod barcode;
+pub mod cancel_emphasis;
+pub mod cancel_highlight;
+pub mod cut;
+pub mod feed_lines;
+pub mod open_drawer;
+pub mod raster_image;
+pub mod set_character_expansion;
+pub mod set_code_table;
+pub mod set_drawer_pulse;
+pub mod set_emphasis;
+pub mod set_highlight;
+pub mod set_justification;
@@ -0,0 +1,25 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        Some(vec![DeviceCommand::Pulse])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Open Drawer",
+        vec![BEL],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
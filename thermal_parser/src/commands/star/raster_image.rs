@@ -0,0 +1,97 @@
+//! Star raster image command
+//!
+//! ESC GS S m xL xH yL yH n d1...dk
+//!
+//! m is always 1, x is the width in bytes, y is the height in dots
+//! and n is the tone, which is always 0 (monochrome).
+use crate::prelude::*;
+use crate::util::reserve_data;
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    width: u32,
+    height: u32,
+    capacity: u32,
+    accept_data: bool,
+    params: Vec<u8>,
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        Some(GraphicsCommand::image_from_raster_bytes_single_color(
+            self.width,
+            self.height,
+            (1, 1),
+            context.graphics.render_colors.color_for_number(1),
+            ImageFlow::Block,
+            &command.data,
+            true,
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if !self.accept_data {
+            data.push(byte);
+
+            if data.len() < 6 {
+                return true;
+            }
+
+            let xl = data[1] as u32;
+            let xh = data[2] as u32;
+            let yl = data[3] as u32;
+            let yh = data[4] as u32;
+
+            self.width = (xl + xh * 256) * 8;
+            self.height = yl + yh * 256;
+            self.capacity = (xl + xh * 256) * self.height;
+            self.params = data.clone();
+
+            data.clear();
+            reserve_data(data, self.capacity as usize);
+            self.accept_data = true;
+            return true;
+        }
+
+        if data.len() >= self.capacity as usize {
+            return false;
+        }
+        data.push(byte);
+        true
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} w{} h{} with {} bytes",
+            command.name,
+            self.width,
+            self.height,
+            command.data.len()
+        )
+    }
+
+    //Used when converting commands back into other formats i.e. Thermal format
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut data = self.params.clone();
+        let commands = command.commands.to_vec();
+        data.extend(command.data.clone());
+        (commands, data)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Raster Image",
+        vec![ESC, GS, b'S'],
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler {
+            width: 0,
+            height: 0,
+            capacity: 0,
+            accept_data: false,
+            params: vec![],
+        }),
+    )
+}
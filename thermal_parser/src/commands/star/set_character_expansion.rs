@@ -0,0 +1,45 @@
+use crate::command::DeviceCommand::{SetTextHeight, SetTextWidth};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+//n1 is the height expansion and n2 is the width expansion
+//both range from 0 - 5 which is 1x - 6x
+fn parse_expansion(data: &[u8]) -> (u8, u8) {
+    let h = *data.first().unwrap_or(&0u8);
+    let w = *data.get(1).unwrap_or(&0u8);
+
+    //ASCII digits are also accepted by the printer
+    let h = if h >= b'0' { h - b'0' } else { h };
+    let w = if w >= b'0' { w - b'0' } else { w };
+
+    (w.min(5) + 1, h.min(5) + 1)
+}
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let (w, h) = parse_expansion(&command.data);
+        Some(vec![SetTextWidth(w), SetTextHeight(h)])
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        let (w, h) = parse_expansion(&command.data);
+        format!("{} Stretch w{} h{}", command.name, w, h)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Set Character Expansion",
+        vec![ESC, b'i'],
+        CommandType::TextStyle,
+        DataType::Double,
+        Box::new(Handler {}),
+    )
+}
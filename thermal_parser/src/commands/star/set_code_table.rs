@@ -0,0 +1,53 @@
+//! Sets the code table using the Star numbering.
+//!
+//! Star numbers its code pages differently than Epson,
+//! so the Star number is mapped to the matching ESC/POS
+//! code table before updating the decoder. Code pages
+//! we don't have a table for fall back to PC437.
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+fn star_to_esc_pos_table(n: u8) -> u8 {
+    match n {
+        2 => 1,   //Katakana
+        4 => 19,  //PC858
+        6 => 3,   //PC860
+        8 => 4,   //PC863
+        9 => 5,   //PC865
+        12 => 13, //PC857
+        16 => 11, //PC851
+        255 => 255,
+        _ => 0, //PC437
+    }
+}
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let n = *command.data.first().unwrap_or(&0u8);
+        context.text.code_table = star_to_esc_pos_table(n);
+        context.update_decoder();
+    }
+
+    fn debug(&self, command: &Command, context: &Context) -> String {
+        format!(
+            "{} to {}. {} ({})",
+            command.name,
+            context.text.code_table,
+            context.text.decoder.name,
+            context.text.decoder.language
+        )
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Set Code Table",
+        vec![ESC, GS, b't'],
+        CommandType::TextStyle,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
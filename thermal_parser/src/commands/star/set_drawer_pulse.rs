@@ -0,0 +1,17 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Set Drawer Pulse Width",
+        vec![ESC, BEL],
+        CommandType::Control,
+        DataType::Double,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,23 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.text.background_color = context.graphics.render_colors.color_1;
+        context.text.color = context.graphics.render_colors.paper_color;
+        context.text.invert = true;
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Select Highlight",
+        vec![ESC, b'4'],
+        CommandType::TextStyle,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,32 @@
+use crate::command::DeviceCommand::Justify;
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.first().unwrap_or(&0u8);
+
+        Some(vec![Justify(match n {
+            1 | 49 => TextJustify::Center,
+            2 | 50 => TextJustify::Right,
+            _ => TextJustify::Left,
+        })])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Set Text Justification",
+        vec![ESC, GS, b'a'],
+        CommandType::Control,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,17 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Clear Raster Data",
+        vec![ESC, b'*', b'r', b'C'],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
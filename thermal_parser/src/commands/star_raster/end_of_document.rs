@@ -0,0 +1,26 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    //The default EOT mode feeds to the cutter and cuts
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        Some(vec![DeviceCommand::FullCut])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Execute EOT Mode",
+        vec![ESC, FF, 0x04],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
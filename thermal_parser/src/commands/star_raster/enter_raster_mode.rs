@@ -0,0 +1,17 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Enter Raster Mode",
+        vec![ESC, b'*', b'r', b'A'],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
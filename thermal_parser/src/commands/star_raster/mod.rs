@@ -0,0 +1,50 @@
+//! Star Raster Mode commands
+//!
+//! Graphics only printers like the TSP100 don't understand
+//! line mode at all. Jobs are sent as raster lines wrapped
+//! in ESC * r A (enter raster mode) and ESC * r B (quit raster mode).
+//!
+//! Most settings use an ascii decimal parameter that is terminated
+//! by NUL, for example ESC * r Y "24" NUL feeds 24 dots.
+
+use crate::prelude::*;
+
+pub mod clear_raster_data;
+pub mod end_of_document;
+pub mod enter_raster_mode;
+pub mod form_feed;
+pub mod initialize_raster_mode;
+pub mod move_vertical;
+pub mod quit_raster_mode;
+pub mod set_option;
+pub mod transfer_raster_data;
+
+/// Parses an ascii decimal parameter like "24" into a number.
+/// Non digit bytes are ignored.
+pub fn parse_ascii_number(data: &[u8]) -> u32 {
+    let mut n: u32 = 0;
+
+    for byte in data {
+        if byte.is_ascii_digit() {
+            n = n.saturating_mul(10).saturating_add((byte - b'0') as u32);
+        }
+    }
+
+    n
+}
+
+/// Collects bytes until NUL. The NUL is consumed but not kept.
+/// Returns false once the parameter is complete.
+pub fn push_nul_terminated(data: &mut Vec<u8>, finished: &mut bool, byte: u8) -> bool {
+    if *finished {
+        return false;
+    }
+
+    if byte == crate::constants::NUL {
+        *finished = true;
+        return true;
+    }
+
+    data.push(byte);
+    true
+}
@@ -0,0 +1,47 @@
+use crate::commands::star_raster::{parse_ascii_number, push_nul_terminated};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = parse_ascii_number(&command.data).min(i16::MAX as u32);
+        Some(vec![DeviceCommand::Feed(n as i16)])
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} {} dots",
+            command.name,
+            parse_ascii_number(&command.data)
+        )
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_nul_terminated(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut data = command.data.clone();
+        data.push(NUL);
+        (command.commands.to_vec(), data)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Move Vertical Direction",
+        vec![ESC, b'*', b'r', b'Y'],
+        CommandType::Control,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
@@ -0,0 +1,17 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Quit Raster Mode",
+        vec![ESC, b'*', b'r', b'B'],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,59 @@
+//! Raster mode settings that take a NUL terminated ascii parameter
+//! and don't affect how the job is rendered.
+//!
+//! ESC * r E n NUL  Set EOT mode
+//! ESC * r P n NUL  Set page length
+//! ESC * r Q n NUL  Set print quality
+//! ESC * r T n NUL  Set top margin
+//! ESC * r F n NUL  Set FF mode
+//! ESC * r m l n NUL  Set left margin
+//! ESC * r m r n NUL  Set right margin
+use crate::commands::star_raster::{parse_ascii_number, push_nul_terminated};
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!("{} {}", command.name, parse_ascii_number(&command.data))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_nul_terminated(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut data = command.data.clone();
+        data.push(NUL);
+        (command.commands.to_vec(), data)
+    }
+}
+
+fn new_option(name: &str, option: &[u8]) -> Command {
+    let mut commands = vec![ESC, b'*', b'r'];
+    commands.extend_from_slice(option);
+
+    Command::new(
+        name,
+        commands,
+        CommandType::Control,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
+
+pub fn all() -> Vec<Command> {
+    vec![
+        new_option("Star Set EOT Mode", b"E"),
+        new_option("Star Set FF Mode", b"F"),
+        new_option("Star Set Page Length", b"P"),
+        new_option("Star Set Print Quality", b"Q"),
+        new_option("Star Set Top Margin", b"T"),
+        new_option("Star Set Left Margin", b"ml"),
+        new_option("Star Set Right Margin", b"mr"),
+    ]
+}
@@ -0,0 +1,78 @@
+//! Transfers a single line of raster data
+//!
+//! b n1 n2 d1...dk
+//!
+//! k = n1 + n2 * 256 and each bit is one dot.
+//! Every line is rendered as a one dot tall image.
+use crate::prelude::*;
+use crate::{command::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    capacity: u32,
+    accept_data: bool,
+    params: Vec<u8>,
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        Some(GraphicsCommand::image_from_raster_bytes_single_color(
+            self.capacity * 8,
+            1,
+            (1, 1),
+            context.graphics.render_colors.color_for_number(1),
+            ImageFlow::Block,
+            &command.data,
+            true,
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if !self.accept_data {
+            self.params.push(byte);
+
+            if self.params.len() < 2 {
+                return true;
+            }
+
+            self.capacity = self.params[0] as u32 + self.params[1] as u32 * 256;
+            self.accept_data = true;
+            return true;
+        }
+
+        if data.len() >= self.capacity as usize {
+            return false;
+        }
+        data.push(byte);
+        true
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!("{} {} bytes", command.name, command.data.len())
+    }
+
+    //Used when converting commands back into other formats i.e. Thermal format
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut data = self.params.clone();
+        data.extend(command.data.clone());
+        (command.commands.to_vec(), data)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Star Transfer Raster Data",
+        vec![b'b'],
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler {
+            capacity: 0,
+            accept_data: false,
+            params: vec![],
+        }),
+    )
+}
@@ -0,0 +1,30 @@
+use crate::prelude::*;
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.first()?;
+        Some(vec![DeviceCommand::TransmitStatus(n)])
+    }
+
+    fn is_realtime(&self) -> bool {
+        true
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Transmit Real-Time Status",
+        vec![DLE, EOT],
+        CommandType::Control,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,50 @@
+//! BAR x,y,width,height
+//!
+//! Draws a solid bar
+use crate::commands::tspl::{line_bytes, param_u32, parse_params, positioned_image, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let params = parse_params(&command.data);
+        let w = param_u32(&params, 2);
+        let h = param_u32(&params, 3);
+
+        if w == 0 || h == 0 {
+            return None;
+        }
+
+        Some(positioned_image(
+            param_u32(&params, 0),
+            param_u32(&params, 1),
+            w,
+            h,
+            context.graphics.render_colors.color_for_number(1),
+            &vec![255u8; (w * h) as usize],
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Bar",
+        b"BAR ".to_vec(),
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
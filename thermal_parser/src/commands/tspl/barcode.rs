@@ -0,0 +1,94 @@
+//! BARCODE x,y,"code type",height,human readable,rotation,narrow,wide,[alignment,]"content"
+//!
+//! The narrow bar width is used as the module width, wide bars
+//! are determined by the symbology.
+use crate::commands::barcode::{encode_barcode, BarcodeType};
+use crate::commands::tspl::{line_bytes, param_u32, parse_params, positioned_image, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl Handler {
+    fn barcode_type(code: &[u8]) -> BarcodeType {
+        match code {
+            b"128" | b"128M" => BarcodeType::Code128,
+            b"EAN128" => BarcodeType::Gs1128,
+            b"25" | b"25C" | b"ITF14" => BarcodeType::Itf,
+            b"39" | b"39C" | b"39S" => BarcodeType::Code39,
+            b"93" => BarcodeType::Code93,
+            b"EAN13" => BarcodeType::Ean13,
+            b"EAN8" => BarcodeType::Ean8,
+            b"CODA" => BarcodeType::Nw7Codabar,
+            b"UPCA" => BarcodeType::UpcA,
+            b"UPCE" => BarcodeType::UpcE,
+            _ => BarcodeType::Unknown,
+        }
+    }
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let params = parse_params(&command.data);
+
+        if params.len() < 9 {
+            return Some(GraphicsCommand::Error(
+                "TSPL barcode is missing parameters".to_string(),
+            ));
+        }
+
+        let kind = Handler::barcode_type(&params[2]);
+        let content = String::from_utf8_lossy(params.last().unwrap()).to_string();
+
+        //Code 128 needs a code set, TSPL selects it automatically
+        let content = match kind {
+            BarcodeType::Code128 if !content.starts_with('{') => format!("{{B{}", content),
+            _ => content,
+        };
+
+        let points = match encode_barcode(&kind, &content) {
+            Ok((points, _hri)) => points,
+            Err(error) => return Some(GraphicsCommand::Error(error)),
+        };
+
+        let narrow = param_u32(&params, 6).max(1);
+        let height = param_u32(&params, 3).max(1);
+        let width = points.len() as u32 * narrow;
+
+        let mut row = Vec::with_capacity(width as usize);
+        for point in points {
+            let value = if point > 0 { 255u8 } else { 0u8 };
+            row.extend(vec![value; narrow as usize]);
+        }
+
+        Some(positioned_image(
+            param_u32(&params, 0),
+            param_u32(&params, 1),
+            width,
+            height,
+            context.graphics.render_colors.color_for_number(1),
+            &row.repeat(height as usize),
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Barcode",
+        b"BARCODE ".to_vec(),
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
@@ -0,0 +1,19 @@
+//! Labels begin printing when CLS is sent, so nothing
+//! happens at the beginning of a job
+use crate::command::*;
+use crate::prelude::*;
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Begin Job",
+        vec![],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,120 @@
+//! BITMAP x,y,width,height,mode,data
+//!
+//! Width is in bytes and height is in dots, the data is
+//! width * height bytes of raw binary. A 0 bit prints a dot.
+//! All modes (overwrite, or, xor) are drawn as overwrite.
+use crate::commands::tspl::{param_u32, parse_params, positioned_image};
+use crate::constants::{CR, LF};
+use crate::prelude::*;
+use crate::util::reserve_data;
+use crate::{command::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    params: Vec<u8>,
+    capacity: u32,
+    accept_data: bool,
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let params = parse_params(&self.params);
+        let width_bytes = param_u32(&params, 2);
+        let height = param_u32(&params, 3);
+
+        if width_bytes == 0 || height == 0 {
+            return None;
+        }
+
+        if command.data.len() < self.capacity as usize {
+            return Some(GraphicsCommand::Error(
+                "Not enough data to parse TSPL bitmap".to_string(),
+            ));
+        }
+
+        let width = width_bytes * 8;
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+
+        for byte in &command.data {
+            for bit in 0..8 {
+                pixels.push(if byte & (1 << (7 - bit)) == 0 { 255 } else { 0 });
+            }
+        }
+
+        Some(positioned_image(
+            param_u32(&params, 0),
+            param_u32(&params, 1),
+            width,
+            height,
+            context.graphics.render_colors.color_for_number(1),
+            &pixels,
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        if !self.accept_data {
+            self.params.push(byte);
+
+            //Data begins after the 5th comma
+            if self.params.iter().filter(|b| **b == b',').count() == 5 {
+                self.params.pop();
+                let params = parse_params(&self.params);
+                self.capacity = param_u32(&params, 2) * param_u32(&params, 3);
+                reserve_data(data, self.capacity as usize);
+                self.accept_data = true;
+            }
+            return true;
+        }
+
+        if data.len() < self.capacity as usize {
+            data.push(byte);
+            return true;
+        }
+
+        //Consume the line ending after the data
+        if byte == LF {
+            self.finished = true;
+        }
+
+        byte == CR || byte == LF
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} {} ({} bytes)",
+            command.name,
+            String::from_utf8_lossy(&self.params),
+            command.data.len()
+        )
+    }
+
+    //Used when converting commands back into other formats i.e. Thermal format
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        let mut data = self.params.clone();
+        data.push(b',');
+        data.extend(command.data.clone());
+        data.push(CR);
+        data.push(LF);
+        (command.commands.to_vec(), data)
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Bitmap",
+        b"BITMAP ".to_vec(),
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler {
+            params: vec![],
+            capacity: 0,
+            accept_data: false,
+            finished: false,
+        }),
+    )
+}
@@ -0,0 +1,57 @@
+//! CLS
+//!
+//! Clears the image buffer and starts a new label
+use crate::commands::tspl::{line_bytes, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        let empty = RenderArea {
+            x: 0,
+            y: 0,
+            w: 0,
+            h: 0,
+        };
+
+        context.page_mode.page_area = empty.clone();
+        context.page_mode.render_area = empty;
+    }
+
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        Some(vec![
+            DeviceCommand::BeginPrint,
+            DeviceCommand::MoveX(0),
+            DeviceCommand::MoveY(0),
+            DeviceCommand::BeginPageMode,
+            DeviceCommand::ChangePageArea,
+        ])
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Clear Buffer",
+        b"CLS".to_vec(),
+        CommandType::ContextControl,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
@@ -0,0 +1,67 @@
+//! BOX x,y,x_end,y_end,line thickness[,radius]
+//!
+//! Draws a box outline, rounded corners are drawn square
+use crate::commands::tspl::{line_bytes, param_u32, parse_params, positioned_image, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let params = parse_params(&command.data);
+        let x = param_u32(&params, 0);
+        let y = param_u32(&params, 1);
+        let w = param_u32(&params, 2).saturating_sub(x);
+        let h = param_u32(&params, 3).saturating_sub(y);
+        let thickness = param_u32(&params, 4).max(1);
+
+        if w == 0 || h == 0 {
+            return None;
+        }
+
+        let mut pixels = vec![0u8; (w * h) as usize];
+
+        for py in 0..h {
+            for px in 0..w {
+                if px < thickness
+                    || py < thickness
+                    || px >= w.saturating_sub(thickness)
+                    || py >= h.saturating_sub(thickness)
+                {
+                    pixels[(py * w + px) as usize] = 255;
+                }
+            }
+        }
+
+        Some(positioned_image(
+            x,
+            y,
+            w,
+            h,
+            context.graphics.render_colors.color_for_number(1),
+            &pixels,
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Box",
+        b"BOX ".to_vec(),
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
@@ -0,0 +1,19 @@
+//! Labels end printing when PRINT is sent, so nothing
+//! happens at the end of a job
+use crate::command::*;
+use crate::prelude::*;
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL End Job",
+        vec![],
+        CommandType::Control,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,142 @@
+//! TSPL / TSPL2 label commands
+//!
+//! TSC label printers take one ascii command per line, for example
+//!
+//! ```text
+//! SIZE 2,1
+//! CLS
+//! TEXT 10,10,"3",0,1,1,"Hello"
+//! PRINT 1
+//! ```
+//!
+//! Every label (CLS ... PRINT) is rendered as its own page, sized by SIZE.
+//! Elements are placed at their x and y in dots from the top left of the label.
+//!
+//! Rotation is not supported yet and human readable text for barcodes is not rendered.
+
+pub mod bar;
+pub mod barcode;
+pub mod begin_job;
+pub mod bitmap;
+pub mod clear_buffer;
+pub mod draw_box;
+pub mod end_job;
+pub mod print_label;
+pub mod qrcode;
+pub mod set_size;
+pub mod setting;
+pub mod text;
+pub mod unknown;
+
+use crate::constants::{CR, LF};
+use crate::graphics::{GraphicsCommand, ImageFlow, RGBA};
+use crate::prelude::*;
+
+/// Collects a command line, CR is dropped and LF ends the line.
+/// Returns false once the line is complete.
+pub fn push_line(data: &mut Vec<u8>, finished: &mut bool, byte: u8) -> bool {
+    if *finished {
+        return false;
+    }
+
+    if byte == LF {
+        *finished = true;
+    } else if byte != CR {
+        data.push(byte);
+    }
+
+    true
+}
+
+/// Line data with the line ending added back, used when
+/// converting commands back into other formats i.e. Thermal format
+pub fn line_bytes(data: &[u8]) -> Vec<u8> {
+    let mut bytes = data.to_vec();
+    bytes.push(CR);
+    bytes.push(LF);
+    bytes
+}
+
+/// Splits comma separated parameters. Quotes are removed from quoted
+/// parameters and commas inside of quotes are kept.
+pub fn parse_params(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut params = vec![];
+    let mut current = vec![];
+    let mut quoted = false;
+
+    for byte in data {
+        match byte {
+            b'"' => quoted = !quoted,
+            b',' if !quoted => {
+                params.push(current.trim_ascii().to_vec());
+                current.clear();
+            }
+            _ => current.push(*byte),
+        }
+    }
+
+    params.push(current.trim_ascii().to_vec());
+    params
+}
+
+/// Parses a numeric parameter, missing or invalid parameters are 0
+pub fn param_u32(params: &[Vec<u8>], index: usize) -> u32 {
+    params.get(index).map(|p| param_f32(p) as u32).unwrap_or(0)
+}
+
+fn param_f32(param: &[u8]) -> f32 {
+    String::from_utf8_lossy(param)
+        .trim()
+        .parse::<f32>()
+        .unwrap_or(0.0)
+}
+
+/// Parses a distance like "2.5" (inches), "60 mm" or "400 dot" into dots
+pub fn parse_distance(param: &[u8], dots_per_inch: u16) -> u32 {
+    let text = String::from_utf8_lossy(param).to_lowercase();
+    let dpi = dots_per_inch as f32;
+
+    if let Some(mm) = text.strip_suffix("mm") {
+        return (param_f32(mm.as_bytes()) * dpi / 25.4) as u32;
+    }
+
+    if let Some(dots) = text.strip_suffix("dot") {
+        return param_f32(dots.as_bytes()) as u32;
+    }
+
+    (param_f32(text.as_bytes()) * dpi) as u32
+}
+
+/// Creates an image from unpacked pixels (0 or 255) that is placed at x and y on the label
+pub fn positioned_image(
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color: &RGBA,
+    pixels: &[u8],
+) -> GraphicsCommand {
+    let image = GraphicsCommand::image_from_raster_bytes_single_color(
+        width,
+        height,
+        (1, 1),
+        color,
+        ImageFlow::Absolute,
+        pixels,
+        false,
+    );
+
+    match image {
+        GraphicsCommand::Image(mut image) => {
+            image.x = x;
+            image.y = y;
+            GraphicsCommand::Image(image)
+        }
+        GraphicsCommand::PartialImage(mut image, error) => {
+            image.x = x;
+            image.y = y;
+            GraphicsCommand::PartialImage(image, error)
+        }
+        other => other,
+    }
+}
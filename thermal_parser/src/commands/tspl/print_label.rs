@@ -0,0 +1,55 @@
+//! PRINT m[,n]
+//!
+//! Prints the label m times, only one copy is rendered
+use crate::commands::tspl::{line_bytes, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        if !context.page_mode.enabled {
+            return None;
+        }
+
+        Some(vec![
+            DeviceCommand::PrintPageMode,
+            DeviceCommand::EndPageMode,
+            DeviceCommand::EndPrint,
+        ])
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} {}",
+            command.name,
+            String::from_utf8_lossy(&command.data).trim()
+        )
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Print Label",
+        b"PRINT ".to_vec(),
+        CommandType::Control,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
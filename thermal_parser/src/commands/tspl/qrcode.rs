@@ -0,0 +1,88 @@
+//! QRCODE x,y,ECC level,cell width,mode,rotation,[model,mask,]"content"
+use crate::commands::tspl::{line_bytes, param_u32, parse_params, positioned_image, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*, graphics::*};
+#[cfg(feature = "std")]
+use qr_code::{EcLevel, QrCode};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let params = parse_params(&command.data);
+
+        if params.len() < 7 {
+            return Some(GraphicsCommand::Error(
+                "TSPL qr code is missing parameters".to_string(),
+            ));
+        }
+
+        let (points, modules) = match encode(params[2].as_slice(), params.last()?) {
+            Ok(qr) => qr,
+            Err(e) => return Some(GraphicsCommand::Error(format!("QR ERROR {}", e))),
+        };
+
+        let cell = param_u32(&params, 3).max(1);
+        let size = modules * cell;
+        let mut pixels = vec![0u8; (size * size) as usize];
+
+        for y in 0..size {
+            for x in 0..size {
+                if points[((y / cell) * modules + x / cell) as usize] {
+                    pixels[(y * size + x) as usize] = 255;
+                }
+            }
+        }
+
+        Some(positioned_image(
+            param_u32(&params, 0),
+            param_u32(&params, 1),
+            size,
+            size,
+            context.graphics.render_colors.color_for_number(1),
+            &pixels,
+        ))
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+//Returns the modules (true is dark) and the symbol width
+#[cfg(feature = "std")]
+fn encode(level: &[u8], content: &[u8]) -> Result<(Vec<bool>, u32), String> {
+    let error_correction = match level {
+        b"M" => EcLevel::M,
+        b"Q" => EcLevel::Q,
+        b"H" => EcLevel::H,
+        _ => EcLevel::L,
+    };
+
+    let qr = QrCode::with_error_correction_level(content, error_correction)
+        .map_err(|e| e.to_string())?;
+
+    Ok((qr.to_vec(), qr.width() as u32))
+}
+
+#[cfg(not(feature = "std"))]
+fn encode(_level: &[u8], _content: &[u8]) -> Result<(Vec<bool>, u32), String> {
+    Err("QR codes are only encoded with the std feature".to_string())
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL QR Code",
+        b"QRCODE ".to_vec(),
+        CommandType::Graphics,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
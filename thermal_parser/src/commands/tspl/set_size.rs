@@ -0,0 +1,61 @@
+//! SIZE w,h
+//!
+//! Label width and height in inches, "mm" or "dot" can
+//! be added to use millimeters or dots instead.
+use crate::commands::tspl::{line_bytes, parse_distance, parse_params, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let params = parse_params(&command.data);
+        let dpi = context.graphics.dots_per_inch;
+        let w = parse_distance(params.first().map_or(&[][..], |p| p), dpi);
+        let h = parse_distance(params.get(1).map_or(&[][..], |p| p), dpi);
+
+        if w == 0 {
+            return;
+        }
+
+        //Labels don't have margins, the whole label is printable
+        context.graphics.paper_area = RenderArea { x: 0, y: 0, w, h };
+        context.graphics.render_area = RenderArea {
+            x: 0,
+            y: 0,
+            w,
+            h: 0,
+        };
+        context.page_mode.logical_area = RenderArea { x: 0, y: 0, w, h };
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} {}",
+            command.name,
+            String::from_utf8_lossy(&command.data).trim()
+        )
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Set Label Size",
+        b"SIZE ".to_vec(),
+        CommandType::Context,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
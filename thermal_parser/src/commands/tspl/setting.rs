@@ -0,0 +1,54 @@
+//! Settings that don't affect how the label is rendered
+//!
+//! GAP m,n  BLINE m,n  OFFSET m  SPEED n  DENSITY n
+//! DIRECTION n  REFERENCE x,y  CODEPAGE n  SET ...
+use crate::commands::tspl::{line_bytes, push_line};
+use crate::prelude::*;
+use crate::{command::*, context::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl CommandHandler for Handler {
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} {}",
+            command.name,
+            String::from_utf8_lossy(&command.data).trim()
+        )
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+fn new_setting(name: &str, keyword: &[u8]) -> Command {
+    Command::new(
+        name,
+        keyword.to_vec(),
+        CommandType::Control,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
+
+pub fn all() -> Vec<Command> {
+    vec![
+        new_setting("TSPL Set Black Mark", b"BLINE "),
+        new_setting("TSPL Set Code Page", b"CODEPAGE "),
+        new_setting("TSPL Set Density", b"DENSITY "),
+        new_setting("TSPL Set Direction", b"DIRECTION "),
+        new_setting("TSPL Set Gap", b"GAP "),
+        new_setting("TSPL Set Offset", b"OFFSET "),
+        new_setting("TSPL Set Reference", b"REFERENCE "),
+        new_setting("TSPL Set", b"SET "),
+        new_setting("TSPL Set Speed", b"SPEED "),
+    ]
+}
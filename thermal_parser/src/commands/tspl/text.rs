@@ -0,0 +1,100 @@
+//! TEXT x,y,"font",rotation,x-multiplication,y-multiplication,[alignment,]"content"
+//!
+//! Fonts "1" to "8" are bitmap fonts with a fixed size in dots,
+//! any other font is treated as a true type font where the
+//! multiplication parameters are the size in points.
+use crate::commands::tspl::{line_bytes, param_u32, parse_params, push_line};
+use crate::prelude::*;
+use crate::text::TextSpan;
+use crate::{command::*, context::*};
+
+#[derive(Clone)]
+struct Handler {
+    finished: bool,
+}
+
+impl Handler {
+    fn font_size(font: &[u8]) -> Option<(u32, u32)> {
+        match font {
+            b"1" => Some((8, 12)),
+            b"2" => Some((12, 20)),
+            b"3" => Some((16, 24)),
+            b"4" => Some((24, 32)),
+            b"5" => Some((32, 48)),
+            b"6" => Some((14, 19)),
+            b"7" => Some((21, 27)),
+            b"8" => Some((14, 25)),
+            _ => None,
+        }
+    }
+}
+
+impl CommandHandler for Handler {
+    fn get_text(&self, command: &Command, context: &Context) -> Option<TextSpan> {
+        let params = parse_params(&command.data);
+
+        if params.len() < 7 {
+            return None;
+        }
+
+        let content = params.last().unwrap();
+        let x_mul = param_u32(&params, 4).max(1);
+        let y_mul = param_u32(&params, 5).max(1);
+
+        let (w, h, stretch_w, stretch_h) = match Handler::font_size(&params[2]) {
+            Some((w, h)) => (w, h, x_mul, y_mul),
+            None => {
+                let h = y_mul * context.graphics.dots_per_inch as u32 / 72;
+                (h / 2, h, 1, 1)
+            }
+        };
+
+        let mut span = TextSpan::new(context.text.decoder.decode_utf8(content), context);
+        span.justify = TextJustify::Left;
+        span.base_character_width = w;
+        span.base_character_height = h;
+        span.character_width = w * stretch_w;
+        span.character_height = h * stretch_h;
+        span.stretch_width = stretch_w as f32;
+        span.stretch_height = stretch_h as f32;
+        Some(span)
+    }
+
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let params = parse_params(&command.data);
+        let x = param_u32(&params, 0).min(u16::MAX as u32) as u16;
+        let y = param_u32(&params, 1).min(u16::MAX as u32) as u16;
+
+        Some(vec![DeviceCommand::MoveX(x), DeviceCommand::MoveY(y)])
+    }
+
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} {}",
+            command.name,
+            String::from_utf8_lossy(&command.data).trim()
+        )
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        push_line(data, &mut self.finished, byte)
+    }
+
+    fn get_command_bytes(&self, command: &Command) -> (Vec<u8>, Vec<u8>) {
+        (command.commands.to_vec(), line_bytes(&command.data))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Text",
+        b"TEXT ".to_vec(),
+        CommandType::Text,
+        DataType::Custom,
+        Box::new(Handler { finished: false }),
+    )
+}
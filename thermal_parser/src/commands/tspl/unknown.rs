@@ -0,0 +1,27 @@
+//! Lines that don't match any TSPL command are collected
+//! until the next known command
+use crate::prelude::*;
+use crate::{command::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn debug(&self, command: &Command, _context: &Context) -> String {
+        format!(
+            "{} {:?}",
+            command.name,
+            String::from_utf8_lossy(&command.data).trim()
+        )
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "TSPL Unknown Command",
+        vec![],
+        CommandType::Unknown,
+        DataType::Unknown,
+        Box::new(Handler {}),
+    )
+}
@@ -1,4 +1,6 @@
 pub static NUL: u8 = 0x00;
+pub static EOT: u8 = 0x04;
+pub static BEL: u8 = 0x07;
 pub static ESC: u8 = 0x1B;
 pub static HT: u8 = 0x09;
 pub static LF: u8 = 0x0A;
@@ -7,5 +9,7 @@ pub static CR: u8 = 0x0D;
 pub static GS: u8 = 0x1D;
 pub static FS: u8 = 0x1C;
 pub static DLE: u8 = 0x10;
+pub static DC4: u8 = 0x14;
 pub static CAN: u8 = 0x18;
+pub static RS: u8 = 0x1E;
 pub static US: u8 = 0x1F;
@@ -1,9 +1,12 @@
+use crate::commands::barcode::BarcodeType;
 use crate::decoder::{get_codepage, Codepage};
 use crate::graphics;
-use crate::graphics::{GraphicsCommand, ImageRef, RGBA};
+use crate::graphics::{GraphicsCommand, ImageRef, ImageRefStorage, RGBA};
+use crate::prelude::*;
+use crate::profile::PrinterProfile;
 use crate::text::TextSpan;
-use std::collections::HashMap;
-use std::mem;
+use alloc::collections::BTreeMap;
+use core::mem;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum TextJustify {
@@ -19,7 +22,7 @@ pub enum TextStrikethrough {
     Double,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum TextUnderline {
     Off,
     On,
@@ -50,13 +53,51 @@ impl Font {
             _ => Font::A,
         }
     }
-    //Currently the rest of the fonts default to font b
-    //We don't have enough information on C D E or the special fonts
+    /// Size of the font with the default metrics, printers with
+    /// other fonts are described with `FontMetrics` on the profile
     pub fn to_size(&self) -> (u8, u8) {
-        if self == &Font::A {
-            (12, 24)
-        } else {
-            (9, 17)
+        FontMetrics::default().size(self)
+    }
+}
+
+/// Character cell width and height in dots for each font
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontMetrics {
+    pub a: (u8, u8),
+    pub b: (u8, u8),
+    pub c: (u8, u8),
+    pub d: (u8, u8),
+    pub e: (u8, u8),
+    pub special_a: (u8, u8),
+    pub special_b: (u8, u8),
+}
+
+impl Default for FontMetrics {
+    //The special fonts are the size of A and B, C is
+    //the size of B unless the printer says otherwise
+    fn default() -> Self {
+        FontMetrics {
+            a: (12, 24),
+            b: (9, 17),
+            c: (9, 17),
+            d: (10, 24),
+            e: (8, 16),
+            special_a: (12, 24),
+            special_b: (9, 17),
+        }
+    }
+}
+
+impl FontMetrics {
+    pub fn size(&self, font: &Font) -> (u8, u8) {
+        match font {
+            Font::A => self.a,
+            Font::B => self.b,
+            Font::C => self.c,
+            Font::D => self.d,
+            Font::E => self.e,
+            Font::SpecialA => self.special_a,
+            Font::SpecialB => self.special_b,
         }
     }
 }
@@ -72,11 +113,13 @@ pub enum HumanReadableInterface {
 #[derive(Clone)]
 pub struct Context {
     pub default: Option<Box<Context>>,
+    pub profile: PrinterProfile,
     pub text: TextContext,
     pub barcode: BarcodeContext,
     pub code2d: Code2DContext,
     pub graphics: GraphicsContext,
     pub page_mode: PageModeContext,
+    pub label: LabelContext,
 }
 
 #[derive(Clone)]
@@ -92,18 +135,25 @@ pub struct TextContext {
     pub bold: bool,
     pub italic: bool,
     pub underline: TextUnderline,
+    //Underline thickness in dots that ESC - selected last, ESC ! turns
+    //the underline on with this thickness
+    pub underline_thickness: u8,
     pub strikethrough: TextStrikethrough,
     pub invert: bool,
     pub width_mult: u8,
     pub height_mult: u8,
     pub upside_down: bool,
     pub line_spacing: u8,
+    //Height of what is already printed on the current line,
+    //text laid out before a graphic and inline images
+    pub current_line_height: u32,
     pub color: RGBA,
     pub background_color: RGBA,
     pub shadow_color: RGBA,
     pub shadow: bool,
     pub smoothing: bool,
     pub tabs: Vec<u8>,
+    pub font_metrics: FontMetrics,
 }
 
 #[derive(Clone, Debug)]
@@ -138,11 +188,28 @@ pub struct GraphicsContext {
     pub paper_area: RenderArea,
 
     pub dots_per_inch: u16,
-    pub v_motion_unit: u8,
-    pub h_motion_unit: u8,
+    //Motion units per inch (GS P), one unit is 1/x inch
+    pub v_motion_units: u16,
+    pub h_motion_units: u16,
+    //Fraction of a dot that relative moves could not make yet,
+    //carried so repeated moves don't drift from the printer
+    pub h_motion_carry: f32,
+    pub v_motion_carry: f32,
     pub graphics_count: u16,
-    pub stored_graphics: HashMap<ImageRef, GraphicsCommand>,
-    pub buffer_graphics: Vec<GraphicsCommand>,
+    pub stored_graphics: BTreeMap<ImageRef, GraphicsCommand>,
+    //Replacements for stored graphics, used instead of the
+    //stored graphic even if it was never defined in the job
+    pub substitute_graphics: BTreeMap<ImageRef, GraphicsCommand>,
+    //Planes in the print buffer (GS ( L <Function 112/113>) by their
+    //color number, a plane stored again replaces the one before
+    pub buffer_graphics: BTreeMap<u8, GraphicsCommand>,
+
+    //Print control (GS ( K)
+    //Density steps are 5%, -6 (70%) to +8 (140%)
+    pub print_density: i8,
+    pub print_speed: u8,
+    pub print_control_method: u8,
+    pub head_divisions: u8,
 }
 
 #[derive(Clone)]
@@ -168,6 +235,10 @@ pub enum QrErrorCorrection {
     H,
 }
 
+/// Symbology, encoded bars and hri text of the linear component of a
+/// composite symbol, or why it couldn't be encoded
+pub type LinearComponent = Result<(BarcodeType, Vec<u8>, String), String>;
+
 #[derive(Clone)]
 pub struct Code2DContext {
     pub symbol_storage: Option<graphics::Code2D>,
@@ -191,6 +262,10 @@ pub struct Code2DContext {
     pub composite_width: u8,
     pub composite_max_width: u32,
     pub composite_font: Font,
+    pub composite_hri: HumanReadableInterface,
+    pub composite_linear: Option<LinearComponent>,
+    //Data of the 2D component, stacked above the linear component
+    pub composite_2d: Option<Vec<u8>>,
 
     pub aztec_mode: u8,
     pub aztec_layers: u8,
@@ -241,6 +316,18 @@ pub struct PageModeContext {
     pub previous_direction: PrintDirection,
 }
 
+//Label and black mark paper, the adjustments are stored
+//in the printer so they are not reset by initialize
+#[derive(Clone)]
+pub struct LabelContext {
+    //Distance in dots between label tops, none for continuous paper
+    pub length: Option<u32>,
+
+    //Offsets in dots from the label top (or black mark)
+    pub start_adjustment: i32,
+    pub cut_adjustment: i32,
+}
+
 #[derive(Debug)]
 pub enum Rotation {
     R0,
@@ -250,6 +337,16 @@ pub enum Rotation {
 }
 
 impl PageModeContext {
+    /// The current print area (ESC W) in page coordinates
+    pub fn print_area(&self) -> RenderArea {
+        RenderArea {
+            x: self.page_area.x,
+            y: self.page_area.y,
+            w: self.render_area.w,
+            h: self.render_area.h,
+        }
+    }
+
     pub fn apply_logical_area(&mut self) -> (Rotation, u32, u32) {
         let rotation =
             self.calculate_directional_rotation(&self.previous_direction, &self.direction);
@@ -328,20 +425,18 @@ impl PageModeContext {
         self.render_area.y += y;
     }
 
-    pub fn offset_x_relative(&mut self, x: i16) {
-        let mut new_x = self.render_area.x as i32 + x as i32;
-        if new_x < 0 {
-            new_x = 0;
-        }
-        self.render_area.x = new_x as u32;
+    //Relative positions can't move before the start of the page area,
+    //returns true when the move was clamped
+    pub fn offset_x_relative(&mut self, x: i32) -> bool {
+        let new_x = self.render_area.x as i32 + x;
+        self.render_area.x = new_x.max(self.page_area.x as i32) as u32;
+        new_x < self.page_area.x as i32
     }
 
-    pub fn offset_y_relative(&mut self, y: i16) {
-        let mut new_y = self.render_area.y as i32 + y as i32;
-        if new_y < 0 {
-            new_y = 0;
-        }
-        self.render_area.y = new_y as u32;
+    pub fn offset_y_relative(&mut self, y: i32) -> bool {
+        let new_y = self.render_area.y as i32 + y;
+        self.render_area.y = new_y.max(self.page_area.y as i32) as u32;
+        new_y < self.page_area.y as i32
     }
 
     fn should_dimension_swap(direction: &PrintDirection) -> bool {
@@ -427,13 +522,41 @@ impl PageModeContext {
     }
 }
 
+impl GraphicsContext {
+    /// Gets a stored graphic, substitutes take precedence
+    pub fn get_stored_graphic(&self, image_ref: &ImageRef) -> Option<GraphicsCommand> {
+        self.substitute_graphics
+            .get(image_ref)
+            .or_else(|| self.stored_graphics.get(image_ref))
+            .cloned()
+    }
+
+    /// Print density as a percentage of the standard density
+    pub fn print_density_percent(&self) -> u32 {
+        (100 + self.print_density as i32 * 5) as u32
+    }
+}
+
 impl Context {
-    fn default() -> Context {
-        let dots_per_inch = 203;
+    fn default(profile: &PrinterProfile) -> Context {
+        let dots_per_inch = profile.dots_per_inch;
         let paper_left_margin = (dots_per_inch as f32 * 0.1f32) as u32;
         let paper_right_margin = (dots_per_inch as f32 * 0.1f32) as u32;
         let paper_width = (dots_per_inch as f32 * 3.2f32) as u32;
         let render_width = paper_width - (paper_left_margin + paper_right_margin);
+        //Without a profile default there is one motion unit per dot
+        let (h_motion_units, v_motion_units) = profile
+            .motion_units
+            .unwrap_or((dots_per_inch, dots_per_inch));
+        let label_length = profile
+            .label_length_mm
+            .map(|length| profile.mm_to_dots(length));
+        //Labels start printing at the top of the label
+        let render_top = if label_length.is_some() {
+            0
+        } else {
+            paper_left_margin * 3
+        };
         let render_colors = RenderColors {
             paper_color: RGBA {
                 r: 255,
@@ -461,32 +584,36 @@ impl Context {
             }, //Blue
         };
 
-        Context {
+        let mut context = Context {
             default: None,
+            profile: profile.clone(),
             text: TextContext {
-                character_width: 12,
-                character_height: 24,
-                character_set: 0,
-                code_table: 0,
-                decoder: get_codepage(0, 0),
+                character_width: profile.fonts.a.0,
+                character_height: profile.fonts.a.1,
+                character_set: profile.character_set,
+                code_table: profile.code_table,
+                decoder: get_codepage(profile.code_table, profile.character_set),
                 font_size: 10,
                 justify: TextJustify::Left,
                 font: Font::A,
                 bold: false,
                 italic: false,
                 underline: TextUnderline::Off,
+                underline_thickness: 1,
                 strikethrough: TextStrikethrough::Off,
                 invert: false,
                 width_mult: 1,
                 height_mult: 1,
                 upside_down: false,
-                line_spacing: 24, //pixels
+                line_spacing: profile.line_spacing, //pixels
+                current_line_height: 0,
                 color: render_colors.color_1,
                 background_color: render_colors.paper_color,
                 shadow: false,
                 shadow_color: render_colors.color_1,
                 smoothing: false,
                 tabs: vec![8; 32], //Every 8 character widths is a tab stop
+                font_metrics: profile.fonts.clone(),
             },
             barcode: BarcodeContext {
                 human_readable: HumanReadableInterface::None,
@@ -508,9 +635,12 @@ impl Context {
                 maxicode_mode: 0,
                 gs1_databar_width: 0,
                 gs1_databar_max_width: 0,
-                composite_width: 0,
+                composite_width: 2,
                 composite_max_width: 0,
                 composite_font: Font::A,
+                composite_hri: HumanReadableInterface::None,
+                composite_linear: None,
+                composite_2d: None,
                 aztec_mode: 0,
                 aztec_layers: 0,
                 aztec_size: 0,
@@ -525,7 +655,7 @@ impl Context {
 
                 render_area: RenderArea {
                     x: 0,
-                    y: paper_left_margin * 3,
+                    y: render_top,
                     w: render_width,
                     h: 0,
                 },
@@ -539,11 +669,18 @@ impl Context {
                 //Both of these motion units are used for
                 //Various positioning commands in standard mode
                 //and in page mode.
-                v_motion_unit: 1, //Pixels per unit
-                h_motion_unit: 1, //Pixels per unit
+                v_motion_units,
+                h_motion_units,
+                h_motion_carry: 0.0,
+                v_motion_carry: 0.0,
                 graphics_count: 0,
-                stored_graphics: HashMap::<ImageRef, GraphicsCommand>::new(),
-                buffer_graphics: vec![],
+                stored_graphics: BTreeMap::<ImageRef, GraphicsCommand>::new(),
+                substitute_graphics: BTreeMap::<ImageRef, GraphicsCommand>::new(),
+                buffer_graphics: BTreeMap::<u8, GraphicsCommand>::new(),
+                print_density: 0,
+                print_speed: 0,
+                print_control_method: 0,
+                head_divisions: 0,
             },
             page_mode: PageModeContext {
                 enabled: false,
@@ -568,22 +705,47 @@ impl Context {
                 direction: PrintDirection::TopLeft2Right,
                 previous_direction: PrintDirection::TopLeft2Right,
             },
-        }
+            label: LabelContext {
+                length: label_length,
+                start_adjustment: 0,
+                cut_adjustment: 0,
+            },
+        };
+
+        //The power on code table can be the utf8 table
+        context.update_decoder();
+        context
     }
 
     pub fn new() -> Context {
-        let default_context = Context::default();
+        Self::new_with_profile(&PrinterProfile::default())
+    }
+
+    /// Creates a context with defaults taken from the printer profile
+    pub fn new_with_profile(profile: &PrinterProfile) -> Context {
+        let default_context = Context::default(profile);
         let mut new_context = default_context.clone();
         new_context.default = Some(Box::from(default_context));
         new_context
     }
 
+    //Restores the defaults like ESC @ does, the paper does not move
+    //and graphics stored in NV memory survive, graphics downloaded to
+    //RAM and page mode are discarded
     pub fn reset(&mut self) {
         if let Some(default) = &self.default {
+            let y = self.graphics.render_area.y;
+            let mut stored_graphics = mem::take(&mut self.graphics.stored_graphics);
+            stored_graphics.retain(|k, _| k.storage == ImageRefStorage::Disc);
+
             self.text = default.text.clone();
             self.barcode = default.barcode.clone();
             self.code2d = default.code2d.clone();
             self.graphics = default.graphics.clone();
+            self.page_mode = default.page_mode.clone();
+
+            self.graphics.render_area.y = y;
+            self.graphics.stored_graphics = stored_graphics;
         }
     }
 
@@ -607,6 +769,8 @@ impl Context {
     //Reset the x to the base value
     //which is the furthest left
     pub fn reset_x(&mut self) {
+        self.graphics.h_motion_carry = 0.0;
+        self.text.current_line_height = 0;
         if self.page_mode.enabled {
             self.page_mode.render_area.x = self.get_base_x();
         } else {
@@ -672,60 +836,137 @@ impl Context {
         }
     }
 
-    //Uses motion units
-    pub fn offset_x_relative(&mut self, x: i16) {
-        let adj_x = x.saturating_div(self.graphics.h_motion_unit as i16);
+    /// Converts horizontal motion units to dots
+    pub fn h_units_to_dots(&self, units: i32) -> i32 {
+        Self::units_to_dots(
+            units,
+            self.graphics.h_motion_units,
+            self.graphics.dots_per_inch,
+        )
+    }
 
-        if self.page_mode.enabled {
-            self.page_mode.offset_x_relative(adj_x);
+    /// Converts vertical motion units to dots
+    pub fn v_units_to_dots(&self, units: i32) -> i32 {
+        Self::units_to_dots(
+            units,
+            self.graphics.v_motion_units,
+            self.graphics.dots_per_inch,
+        )
+    }
+
+    fn units_to_dots(units: i32, units_per_inch: u16, dots_per_inch: u16) -> i32 {
+        Self::units_to_exact_dots(units, units_per_inch, dots_per_inch).round() as i32
+    }
+
+    fn units_to_exact_dots(units: i32, units_per_inch: u16, dots_per_inch: u16) -> f32 {
+        if units_per_inch == 0 {
+            return units as f32;
+        }
+        units as f32 * dots_per_inch as f32 / units_per_inch as f32
+    }
+
+    //Rounds a move to whole dots, returns the dots and the fraction left over
+    fn carry_dots(exact: f32) -> (i32, f32) {
+        let dots = exact.round();
+        (dots as i32, exact - dots)
+    }
+
+    //Uses motion units, moving before the start of the line stops at the start
+    pub fn offset_x_relative(&mut self, x: i16) {
+        let exact = Self::units_to_exact_dots(
+            x as i32,
+            self.graphics.h_motion_units,
+            self.graphics.dots_per_inch,
+        );
+        let (adj_x, carry) = Self::carry_dots(exact + self.graphics.h_motion_carry);
+        self.graphics.h_motion_carry = carry;
+
+        let clamped = if self.page_mode.enabled {
+            self.page_mode.offset_x_relative(adj_x)
         } else {
-            let mut new_x = self.graphics.render_area.x as i32 + adj_x as i32;
-            if new_x < 0 {
-                new_x = 0;
-            }
-            self.graphics.render_area.x = new_x as u32;
+            let new_x = self.graphics.render_area.x as i32 + adj_x;
+            self.graphics.render_area.x = new_x.max(0) as u32;
+            new_x < 0
+        };
+
+        if clamped {
+            self.graphics.h_motion_carry = 0.0;
         }
     }
 
-    //Uses motion units
+    //Uses motion units, moving before the top of the area stops at the top
     pub fn offset_y_relative(&mut self, y: i16) {
-        let adj_y = y.saturating_div(self.graphics.v_motion_unit as i16);
-
-        if self.page_mode.enabled {
-            self.page_mode.offset_y_relative(adj_y);
+        let exact = Self::units_to_exact_dots(
+            y as i32,
+            self.graphics.v_motion_units,
+            self.graphics.dots_per_inch,
+        );
+        let (adj_y, carry) = Self::carry_dots(exact + self.graphics.v_motion_carry);
+        self.graphics.v_motion_carry = carry;
+
+        let clamped = if self.page_mode.enabled {
+            self.page_mode.offset_y_relative(adj_y)
         } else {
-            let mut new_y = self.graphics.render_area.y as i32 + adj_y as i32;
-            if new_y < 0 {
-                new_y = 0;
-            }
-            self.graphics.render_area.y = new_y as u32;
+            let new_y = self.graphics.render_area.y as i32 + adj_y;
+            self.graphics.render_area.y = new_y.max(0) as u32;
+            new_y < 0
+        };
+
+        if clamped {
+            self.graphics.v_motion_carry = 0.0;
         }
     }
 
-    pub fn feed(&mut self, motion_units: u32) {
-        self.offset_y(motion_units);
-        self.reset_x();
+    /// A line feed moves by the line spacing, unless something on the
+    /// line is taller than the spacing, then it moves by the tallest
+    pub fn line_feed_height(&self, tallest: u32) -> u32 {
+        (self.text.line_spacing as u32)
+            .max(self.text.current_line_height)
+            .max(tallest)
     }
 
     pub fn newline(&mut self, count: u32) {
         let line_height = self.text.line_spacing as u32;
+
+        //The first line is at least as tall as what is printed on it
+        let height = match count {
+            0 => 0,
+            _ => self.line_feed_height(0) + line_height * (count - 1),
+        };
+
         self.reset_x();
-        self.offset_y(line_height * count);
+        self.offset_y(height);
     }
 
-    pub fn newline_for_spans(&mut self, spans: &Vec<TextSpan>) {
-        let mut line_height = self.text.line_spacing as u32;
+    //Moving before the top of the area stops at the top
+    pub fn reverse_newline(&mut self, count: u32) {
+        let height = (self.text.line_spacing as u32).saturating_mul(count);
 
-        for span in spans {
-            line_height = line_height.max(span.character_height);
+        self.reset_x();
+        if self.page_mode.enabled {
+            self.page_mode
+                .offset_y_relative(-(height.min(i32::MAX as u32) as i32));
+        } else {
+            let y = &mut self.graphics.render_area.y;
+            *y = y.saturating_sub(height);
         }
+    }
+
+    pub fn newline_for_spans(&mut self, spans: &[TextSpan]) {
+        let tallest = spans.iter().map(|span| span.character_height).max();
+        let line_height = self.line_feed_height(tallest.unwrap_or(0));
 
         self.reset_x();
         self.offset_y(line_height);
     }
 
+    /// Makes sure the current line is at least this tall
+    pub fn extend_line_height(&mut self, height: u32) {
+        self.text.current_line_height = self.text.current_line_height.max(height);
+    }
+
     pub fn set_font(&mut self, font: Font) {
-        let size = font.to_size();
+        let size = self.text.font_metrics.size(&font);
         self.text.font = font;
         self.text.character_width = size.0;
         self.text.character_height = size.1;
@@ -749,7 +990,14 @@ impl Context {
 
     //Uses motion units
     pub fn set_x_absolute(&mut self, x: u32) {
-        let adj_x = x.saturating_div(self.graphics.h_motion_unit as u32);
+        let exact = Self::units_to_exact_dots(
+            x as i32,
+            self.graphics.h_motion_units,
+            self.graphics.dots_per_inch,
+        );
+        let (adj_x, carry) = Self::carry_dots(exact);
+        let adj_x = adj_x as u32;
+        self.graphics.h_motion_carry = carry;
         if self.page_mode.enabled {
             self.page_mode.set_x_absolute(adj_x);
         } else {
@@ -759,7 +1007,14 @@ impl Context {
 
     //Uses motion units
     pub fn set_y_absolute(&mut self, y: u32) {
-        let adj_y = y.saturating_div(self.graphics.v_motion_unit as u32);
+        let exact = Self::units_to_exact_dots(
+            y as i32,
+            self.graphics.v_motion_units,
+            self.graphics.dots_per_inch,
+        );
+        let (adj_y, carry) = Self::carry_dots(exact);
+        let adj_y = adj_y as u32;
+        self.graphics.v_motion_carry = carry;
         if self.page_mode.enabled {
             self.page_mode.set_y_absolute(adj_y);
         } else {
@@ -768,21 +1023,13 @@ impl Context {
     }
 
     pub fn set_page_area(&mut self, area: RenderArea) {
-        let mut adj_area = area.clone();
-
         //Area needs to be adjusted based on motion units
-        adj_area.x = adj_area
-            .x
-            .saturating_div(self.graphics.h_motion_unit as u32);
-        adj_area.y = adj_area
-            .y
-            .saturating_div(self.graphics.v_motion_unit as u32);
-        adj_area.w = adj_area
-            .w
-            .saturating_div(self.graphics.h_motion_unit as u32);
-        adj_area.h = adj_area
-            .h
-            .saturating_div(self.graphics.v_motion_unit as u32);
+        let adj_area = RenderArea {
+            x: self.h_units_to_dots(area.x as i32) as u32,
+            y: self.v_units_to_dots(area.y as i32) as u32,
+            w: self.h_units_to_dots(area.w as i32) as u32,
+            h: self.v_units_to_dots(area.h as i32) as u32,
+        };
 
         self.page_mode.logical_area = adj_area;
     }
@@ -1,7 +1,10 @@
+use crate::prelude::*;
+
 mod codepage_0_437;
 mod codepage_11_pc851;
 mod codepage_12_pc853;
 mod codepage_13_pc857;
+mod codepage_19_pc858;
 mod codepage_1_katakana;
 mod codepage_2_pc850;
 mod codepage_3_pc860;
@@ -35,6 +38,14 @@ impl Codepage {
 
         decoded.join("")
     }
+
+    /// The byte of a character in the code page, none for characters
+    /// it doesn't have
+    pub fn encode(&self, c: char) -> Option<u8> {
+        let mut buffer = [0u8; 4];
+        let c = &*c.encode_utf8(&mut buffer);
+        self.table.iter().position(|s| *s == c).map(|i| i as u8)
+    }
 }
 
 pub fn get_codepage(codepage_index: u8, language_index: u8) -> Codepage {
@@ -80,6 +91,7 @@ fn get_codepage_table(codepage_index: u8) -> (&'static str, &'static [&'static s
         11 => codepage_11_pc851::TABLE,
         12 => codepage_12_pc853::TABLE,
         13 => codepage_13_pc857::TABLE,
+        19 => codepage_19_pc858::TABLE,
         _ => codepage_0_437::TABLE,
     }
 }
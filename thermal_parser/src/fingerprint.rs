@@ -0,0 +1,149 @@
+//! Job Fingerprints
+//!
+//! A fingerprint identifies what a job prints, not how its bytes
+//! arrived. Captures of the same receipt can differ in status
+//! requests that the POS sent while printing, real-time commands
+//! and how the text was chunked between them. None of that is part
+//! of the fingerprint, so duplicate prints have the same one.
+//!
+//! The fingerprint has three hashes: the commands with the text
+//! joined back together, the printed text and every image that is
+//! printed. The command hash is the one to deduplicate by, the text
+//! and image hashes find jobs that print the same receipt with
+//! different commands.
+//!
+//! ```
+//! use thermal_parser::fingerprint_esc_pos;
+//! use thermal_parser::thermal_file::parse_str;
+//!
+//! let printed = fingerprint_esc_pos(&parse_str(r#""TOTAL 4.00" LF"#));
+//! //A status request in the middle of the text
+//! let captured = fingerprint_esc_pos(&parse_str(r#""TOTAL " 0x10 0x04 1 "4.00" LF"#));
+//!
+//! assert_eq!(printed, captured);
+//! ```
+
+use crate::command::{Command, CommandType, DeviceCommand};
+use crate::context::Context;
+use crate::graphics::GraphicsCommand;
+use crate::prelude::*;
+use core::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    /// The commands without status requests and real-time commands
+    pub commands: u64,
+    /// The text as it is printed
+    pub text: u64,
+    /// The pixels of every image, in the order they are printed
+    pub images: Vec<u64>,
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.commands)
+    }
+}
+
+/// The fingerprint of parsed commands
+pub fn fingerprint(commands: &[Command]) -> Fingerprint {
+    let mut context = Context::new();
+    let mut command_hash = Fnv::new();
+    let mut text_hash = Fnv::new();
+    let mut images = vec![];
+    //Text is hashed in runs, commands that are left out don't split them
+    let mut text_run: Vec<u8> = vec![];
+
+    for command in commands {
+        if !prints(command, &context) {
+            continue;
+        }
+
+        if command.kind == CommandType::Text {
+            text_run.extend(command.to_bytes());
+            if let Some(span) = command.handler.get_text(command, &context) {
+                text_hash.write(span.text.as_bytes());
+            }
+            continue;
+        }
+
+        if !text_run.is_empty() {
+            command_hash.write_part(&text_run);
+            text_run.clear();
+        }
+        command_hash.write_part(&command.to_bytes());
+
+        if let Some(GraphicsCommand::Image(image) | GraphicsCommand::PartialImage(image, _)) =
+            command.handler.get_graphics(command, &context)
+        {
+            let mut image_hash = Fnv::new();
+            image_hash.write(&image.w.to_le_bytes());
+            image_hash.write(&image.h.to_le_bytes());
+            image_hash.write(&image.as_rgba_u8());
+            images.push(image_hash.finish());
+        }
+
+        match command.kind {
+            CommandType::Context | CommandType::ContextControl | CommandType::TextStyle => {
+                command.handler.apply_context(command, &mut context)
+            }
+            _ => {}
+        }
+    }
+    if !text_run.is_empty() {
+        command_hash.write_part(&text_run);
+    }
+
+    Fingerprint {
+        commands: command_hash.finish(),
+        text: text_hash.finish(),
+        images,
+    }
+}
+
+//Real-time commands, requests for data and the begin and end of
+//parsing don't change what is printed
+fn prints(command: &Command, context: &Context) -> bool {
+    if command.handler.is_realtime() || command.to_bytes().is_empty() {
+        return false;
+    }
+
+    match command.handler.get_device_command(command, context) {
+        Some(device_commands) if !device_commands.is_empty() => {
+            !device_commands.iter().all(|device_command| {
+                matches!(
+                    device_command,
+                    DeviceCommand::Transmit(_)
+                        | DeviceCommand::TransmitStatus(_)
+                        | DeviceCommand::TransmitPrinterId(_)
+                )
+            })
+        }
+        _ => true,
+    }
+}
+
+//64 bit FNV-1a, the same on every platform and without dependencies
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    //Parts are prefixed with their length so they can't run together
+    fn write_part(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -1,5 +1,8 @@
+use crate::commands::barcode::BarcodeType;
 use crate::context::{HumanReadableInterface, RenderColors};
+use crate::prelude::*;
 use crate::text::TextSpan;
+use core::fmt;
 
 #[derive(Clone, Copy, Debug)]
 pub struct RGBA {
@@ -34,11 +37,18 @@ impl RGBA {
         RGBA {
             r: self.r,
             g: self.g,
-            b: self.g,
+            b: self.b,
             a,
         }
     }
 
+    /// Gray level of the color printed on white paper, 0 is black
+    pub fn as_gray(&self) -> u8 {
+        let luma = (self.r as u32 * 299 + self.g as u32 * 587 + self.b as u32 * 114) / 1000;
+        let alpha = self.a as u32;
+        ((luma * alpha + 255 * (255 - alpha)) / 255) as u8
+    }
+
     /// Blends a foreground color onto this color
     pub fn blend_foreground(&mut self, color: &Self) {
         self.blend_foreground_with_alpha(&color, &color.a);
@@ -82,6 +92,7 @@ impl RGBA {
 
 #[derive(Clone, Debug)]
 pub struct Barcode {
+    pub kind: BarcodeType,
     pub points: Vec<u8>,
     pub point_width: u8,
     pub point_height: u8,
@@ -112,6 +123,8 @@ pub struct Line {
 
 #[derive(Clone, Debug)]
 pub struct Code2D {
+    pub symbology: String,
+    pub payload: String,
     pub points: Vec<u8>,
     pub width: u32,
     pub point_width: u32,
@@ -120,9 +133,10 @@ pub struct Code2D {
 
 #[derive(Clone, Debug, Copy)]
 pub enum ImageFlow {
-    Inline, //Image acts somewhat like text, advances x until line is full
-    Block,  //Image advances y by height and resets x to 0
-    None,   //Image does not advance xy
+    Inline,   //Image acts somewhat like text, advances x until line is full
+    Block,    //Image advances y by height and resets x to 0
+    None,     //Image does not advance xy
+    Absolute, //Image is placed at its own xy from the page origin and does not advance xy
 }
 
 #[derive(Clone, Debug)]
@@ -151,13 +165,44 @@ impl Image {
 
         rgb_bytes
     }
+
+    /// Creates a vec with one gray level per pixel, transparent
+    /// pixels are white paper. See `RGBA::as_gray`
+    pub fn as_grayscale(&self) -> Vec<u8> {
+        self.pixels.iter().map(|pixel| pixel.as_gray()).collect()
+    }
+}
+
+/// Image data that does not match the declared width and height.
+///
+/// The image is cut down to the whole rows (or columns) that the
+/// data has, so that missing or extra bytes don't shift the rest
+/// of the image into noise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageDataError {
+    pub width: u32,
+    pub height: u32,
+    /// Bytes needed for the declared width and height
+    pub expected: usize,
+    /// Bytes that were sent
+    pub actual: usize,
+}
+
+impl fmt::Display for ImageDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Image data does not match {}x{}, expected {} bytes got {}",
+            self.width, self.height, self.expected, self.actual
+        )
+    }
 }
 
 impl GraphicsCommand {
     /// Parses column format into a single GraphicsCommand(Image).
     ///
-    /// GraphicsCommand(Error) can also be returned from this function
-    /// in order to provide meaningful error messages for corrupt data
+    /// GraphicsCommand(PartialImage) is returned with the complete
+    /// columns when the data does not match the width and height
     pub fn image_from_column_bytes_single_color(
         width: u32,
         height: u32,
@@ -166,10 +211,42 @@ impl GraphicsCommand {
         flow: ImageFlow,
         data: &[u8],
     ) -> GraphicsCommand {
-        let raster = column_to_bytes(data, width, height);
+        let bytes_per_column = height.div_ceil(8) as usize;
+        let expected = bytes_per_column * width as usize;
+
+        if data.len() == expected {
+            let raster = column_to_bytes(data, width, height);
+            return Self::image_from_raster_bytes_single_color(
+                width, height, stretch, color, flow, &raster, false,
+            );
+        }
+
+        let error = ImageDataError {
+            width,
+            height,
+            expected,
+            actual: data.len(),
+        };
+
+        let columns = (data.len() / bytes_per_column.max(1)).min(width as usize) as u32;
+        if columns == 0 {
+            return GraphicsCommand::Error(error.to_string());
+        }
+
+        let data = &data[..columns as usize * bytes_per_column];
+        let raster = column_to_bytes(data, columns, height);
         Self::image_from_raster_bytes_single_color(
-            width, height, stretch, color, flow, &raster, false,
+            columns, height, stretch, color, flow, &raster, false,
         )
+        .with_data_error(error)
+    }
+
+    /// Marks an image as partial because of the data error
+    fn with_data_error(self, error: ImageDataError) -> GraphicsCommand {
+        match self {
+            GraphicsCommand::Image(image) => GraphicsCommand::PartialImage(image, error),
+            other => other,
+        }
     }
 
     /// Parses column format that has multiple color layers into
@@ -178,7 +255,8 @@ impl GraphicsCommand {
     /// First decodes the column format into the common raster format.
     ///
     /// Color layer data has one byte to indicate the color and the
-    /// rest of the bytes are bit encoded pixel data.
+    /// rest of the bytes are bit encoded pixel data. Layers are
+    /// blended in order of their color number.
     ///
     /// GraphicsCommand(Error) can also be returned from this function
     /// in order to provide meaningful error messages for corrupt data
@@ -191,36 +269,59 @@ impl GraphicsCommand {
         flow: ImageFlow,
         data: &[u8],
     ) -> GraphicsCommand {
-        let bytes_per_layer = ((width as usize / 8) * height as usize) + 1;
+        let bytes_per_plane = width as usize * height.div_ceil(8) as usize;
+
+        Self::image_from_color_planes(
+            num_colors,
+            bytes_per_plane,
+            render_colors,
+            data,
+            |color, plane| {
+                let raster = column_to_bytes(plane, width, height);
+                Self::image_from_raster_bytes_single_color(
+                    width, height, stretch, color, flow, &raster, false,
+                )
+            },
+        )
+    }
+
+    //Splits the data into planes that start with their color number,
+    //decodes every plane in its color and blends them in color order
+    fn image_from_color_planes(
+        num_colors: u8,
+        bytes_per_plane: usize,
+        render_colors: &RenderColors,
+        data: &[u8],
+        decode: impl Fn(&RGBA, &[u8]) -> GraphicsCommand,
+    ) -> GraphicsCommand {
+        let expected = (bytes_per_plane + 1) * num_colors as usize;
 
-        //Ensure there are enough bytes to construct the final image
-        if data.len() != (bytes_per_layer + 1) * num_colors as usize {
-            return GraphicsCommand::Error("Not enough data to parse raster image".into());
+        if num_colors == 0 || data.len() != expected {
+            return GraphicsCommand::Error(format!(
+                "Not enough data for {} color planes, expected {} bytes got {}",
+                num_colors,
+                expected,
+                data.len()
+            ));
         }
 
-        let mut image_layers = vec![];
+        let mut planes: Vec<&[u8]> = data.chunks(bytes_per_plane + 1).collect();
+        planes.sort_by_key(|plane| plane[0]);
 
-        for layer in 0..=num_colors as usize {
-            let layer_start = layer * bytes_per_layer;
-            let color_number = data[layer_start];
-            let color = render_colors.color_for_number(color_number);
-            let image_data = &data[layer_start..bytes_per_layer - 1];
-            let raster = column_to_bytes(image_data, width, height);
-            let layer = Self::image_from_raster_bytes_single_color(
-                width, height, stretch, color, flow, &raster, false,
-            );
+        let mut layers = vec![];
 
-            match layer {
-                GraphicsCommand::Image(image) => image_layers.push(image),
+        for plane in planes {
+            let color = render_colors.color_for_number(plane[0]);
+
+            match decode(color, &plane[1..]) {
+                GraphicsCommand::Image(image) => layers.push(image),
                 GraphicsCommand::Error(message) => return GraphicsCommand::Error(message),
                 _ => {}
             }
         }
 
-        let merge = merge_image_layers(&mut image_layers);
-
-        match merge {
-            Ok(merge) => GraphicsCommand::Image(merge),
+        match merge_image_layers(&layers) {
+            Ok(merged) => GraphicsCommand::Image(merged),
             Err(e) => GraphicsCommand::Error(e.to_string()),
         }
     }
@@ -228,8 +329,8 @@ impl GraphicsCommand {
     /// Parses column format that has a single color layer into
     /// a single GraphicsCommand(Image).
     ///
-    /// GraphicsCommand(Error) can also be returned from this function
-    /// in order to provide meaningful error messages for corrupt data
+    /// GraphicsCommand(PartialImage) is returned with the complete
+    /// rows when the data does not match the width and height
     pub fn image_from_raster_bytes_single_color(
         width: u32,
         height: u32,
@@ -239,6 +340,39 @@ impl GraphicsCommand {
         data: &[u8],
         process_as_bits: bool,
     ) -> GraphicsCommand {
+        let bytes_per_row = if process_as_bits {
+            width.div_ceil(8) as usize
+        } else {
+            width as usize
+        };
+        let expected = bytes_per_row * height as usize;
+
+        if data.len() != expected {
+            let error = ImageDataError {
+                width,
+                height,
+                expected,
+                actual: data.len(),
+            };
+
+            let rows = (data.len() / bytes_per_row.max(1)).min(height as usize) as u32;
+            if rows == 0 {
+                return GraphicsCommand::Error(error.to_string());
+            }
+
+            let data = &data[..rows as usize * bytes_per_row];
+            return Self::image_from_raster_bytes_single_color(
+                width,
+                rows,
+                stretch,
+                color,
+                flow,
+                data,
+                process_as_bits,
+            )
+            .with_data_error(error);
+        }
+
         let unpacked = if process_as_bits {
             unpack_bytes(data, width, height)
         } else {
@@ -260,11 +394,10 @@ impl GraphicsCommand {
             (width, height, unpacked)
         };
 
-        let mut pixels = Vec::with_capacity(w as usize * h as usize);
-
-        for i in 0..raw_pixels.len() {
-            pixels.push(color.with_alpha(raw_pixels[i]));
-        }
+        let pixels = raw_pixels
+            .iter()
+            .map(|alpha| color.with_alpha(*alpha))
+            .collect();
 
         GraphicsCommand::Image(Image {
             pixels,
@@ -277,11 +410,12 @@ impl GraphicsCommand {
         })
     }
 
-    /// Parses column format that has multiple color layers into
+    /// Parses raster format that has multiple color layers into
     /// a single GraphicsCommand(Image).
     ///
     /// Color layer data has one byte to indicate the color and the
-    /// rest of the bytes are bit encoded pixel data.
+    /// rest of the bytes are bit encoded pixel data. Layers are
+    /// blended in order of their color number.
     ///
     /// GraphicsCommand(Error) can also be returned from this function
     /// in order to provide meaningful error messages for corrupt data
@@ -295,51 +429,29 @@ impl GraphicsCommand {
         data: &[u8],
         process_as_bits: bool,
     ) -> GraphicsCommand {
-        let bytes_per_layer = if process_as_bits {
-            ((width / 8) as usize * height as usize) + 1
+        let bytes_per_row = if process_as_bits {
+            width.div_ceil(8) as usize
         } else {
-            (width as usize * height as usize) + 1
+            width as usize
         };
 
-        //Ensure there are enough bytes to construct the final image
-        if data.len() != bytes_per_layer * num_colors as usize {
-            return GraphicsCommand::Error(
-                "Not enough data to parse raster image multi color".into(),
-            );
-        }
-
-        let mut image_layers = vec![];
-
-        for layer_no in 0..num_colors as usize {
-            let layer_start = layer_no * bytes_per_layer;
-            let layer_end = layer_start + bytes_per_layer - 1;
-            let color_number = data[layer_start];
-            let color = render_colors.color_for_number(color_number);
-            let image_data = &data[layer_start + 1..=layer_end];
-
-            let layer = Self::image_from_raster_bytes_single_color(
-                width,
-                height,
-                stretch,
-                color,
-                flow,
-                &image_data,
-                process_as_bits,
-            );
-
-            match layer {
-                GraphicsCommand::Image(image) => image_layers.push(image),
-                GraphicsCommand::Error(message) => return GraphicsCommand::Error(message),
-                _ => {}
-            }
-        }
-
-        let merge = merge_image_layers(&mut image_layers);
-
-        match merge {
-            Ok(merge) => GraphicsCommand::Image(merge),
-            Err(e) => GraphicsCommand::Error(e.to_string()),
-        }
+        Self::image_from_color_planes(
+            num_colors,
+            bytes_per_row * height as usize,
+            render_colors,
+            data,
+            |color, plane| {
+                Self::image_from_raster_bytes_single_color(
+                    width,
+                    height,
+                    stretch,
+                    color,
+                    flow,
+                    plane,
+                    process_as_bits,
+                )
+            },
+        )
     }
 }
 
@@ -359,49 +471,67 @@ pub fn merge_image_layers(layers: &Vec<Image>) -> Result<Image, &'static str> {
 
     let mut image = layers[0].clone();
 
-    //For the moment, we only merge if all images have the same width and height
+    //Layers can't be larger than the first, smaller layers (i.e. a
+    //partial plane) cover its top left
     for merge_img in layers.iter().skip(1) {
         if merge_img.w > image.w || merge_img.h > image.h {
             return Err("Can't merge image layers with different w and h".into());
         }
 
-        for i in 0..merge_img.pixels.len() {
-            image.pixels[i].blend_foreground(&merge_img.pixels[i]);
+        let row_len = merge_img.w as usize;
+        for (y, row) in merge_img.pixels.chunks(row_len.max(1)).enumerate() {
+            let start = y * image.w as usize;
+            for (pixel, merge_pixel) in image.pixels[start..start + row.len()].iter_mut().zip(row) {
+                pixel.blend_foreground(merge_pixel);
+            }
         }
     }
 
     Ok(image)
 }
 
+//Every byte value unpacked to 8 pixels, most significant bit first
+static UNPACKED_BITS: [[u8; 8]; 256] = {
+    let mut table = [[0u8; 8]; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut bit = 0;
+        while bit < 8 {
+            if byte & (0x80 >> bit) != 0 {
+                table[byte][bit] = 255;
+            }
+            bit += 1;
+        }
+        byte += 1;
+    }
+    table
+};
+
 /// Unpacks bits into bytes and makes sure that extra padding
 /// is added for widths that are not divisible by eight.
 fn unpack_bytes(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(width as usize * height as usize);
+    let bytes_per_row = width.div_ceil(8).max(1) as usize;
 
-    //number of bytes we need to use for the last column of each row of data
-    let mut padding = width % 8;
+    //The last byte of a row only has the bits up to the width
+    let mut padding = width as usize % 8;
     if padding == 0 {
         padding = 8;
     }
-    let mut col = 0;
 
-    for byte in pixels {
-        col += 8;
-        if col >= width {
-            for n in 0..padding {
-                bytes.push(if *byte & 1 << (7 - n) != 0 { 255 } else { 0 });
-            }
-            col = 0;
-        } else {
-            bytes.push(if *byte & 1 << 7 != 0 { 255 } else { 0 });
-            bytes.push(if *byte & 1 << 6 != 0 { 255 } else { 0 });
-            bytes.push(if *byte & 1 << 5 != 0 { 255 } else { 0 });
-            bytes.push(if *byte & 1 << 4 != 0 { 255 } else { 0 });
-            bytes.push(if *byte & 1 << 3 != 0 { 255 } else { 0 });
-            bytes.push(if *byte & 1 << 2 != 0 { 255 } else { 0 });
-            bytes.push(if *byte & 1 << 1 != 0 { 255 } else { 0 });
-            bytes.push(if *byte & 1 << 0 != 0 { 255 } else { 0 });
+    for row in pixels.chunks(bytes_per_row) {
+        let (last, whole) = row.split_last().unwrap();
+
+        for byte in whole {
+            bytes.extend_from_slice(&UNPACKED_BITS[*byte as usize]);
         }
+
+        let last_len = if row.len() == bytes_per_row {
+            padding
+        } else {
+            8
+        };
+        bytes.extend_from_slice(&UNPACKED_BITS[*last as usize][..last_len]);
     }
 
     bytes
@@ -409,37 +539,15 @@ fn unpack_bytes(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
 
 /// Column format is weird and needs to have some special operations
 /// done on the unpacked bytes to get the image in the correct orientation.
+/// Every column unpacks to a row, so the rows are turned into columns.
 fn column_to_bytes(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
-    let unpacked = unpack_bytes(pixels, height, width);
-    let rot = rotate_90_clockwise(unpacked, height, width);
-    flip_right_to_left(rot, width, height)
-}
-
-fn rotate_90_clockwise(data: Vec<u8>, width: u32, height: u32) -> Vec<u8> {
-    let mut result = vec![0; data.len()];
+    let (width, height) = (width as usize, height as usize);
+    let unpacked = unpack_bytes(pixels, height as u32, width as u32);
+    let mut result = vec![0; unpacked.len()];
 
-    for y in 0..height {
-        for x in 0..width {
-            let src_index = y as usize * width as usize + x as usize;
-            let dest_x = height - 1 - y;
-            let dest_y = x;
-            let dest_index = dest_y as usize * height as usize + dest_x as usize; // Note: height is used for new row length
-            result[dest_index] = data[src_index];
-        }
-    }
-
-    result
-}
-
-fn flip_right_to_left(data: Vec<u8>, width: u32, height: u32) -> Vec<u8> {
-    let mut result = vec![0; data.len()];
-
-    for y in 0..height {
-        for x in 0..width {
-            let src_index = y as usize * width as usize + x as usize;
-            let dest_x = width - 1 - x; // Flip the x-coordinate
-            let dest_index = y as usize * width as usize + dest_x as usize; // Calculate the destination index
-            result[dest_index] = data[src_index];
+    for (x, column) in unpacked.chunks(height.max(1)).enumerate() {
+        for (y, pixel) in column.iter().enumerate() {
+            result[y * width + x] = *pixel;
         }
     }
 
@@ -462,19 +570,19 @@ pub fn scale_pixels(
     let new_width = original_width * scale_x as u32;
     let new_height = original_height * scale_y as u32;
 
-    let mut scaled_bytes = vec![0u8; (new_width * new_height) as usize];
+    let mut scaled_bytes = Vec::with_capacity((new_width * new_height) as usize);
 
-    for y in 0..original_height {
-        for x in 0..original_width {
-            let pixel = bytes[(y * original_width + x) as usize];
+    //Each row is stretched once and copied for the other rows
+    for row in bytes.chunks(original_width.max(1) as usize) {
+        let start = scaled_bytes.len();
 
-            for dy in 0..scale_y {
-                for dx in 0..scale_x {
-                    let new_x = x * scale_x as u32 + dx as u32;
-                    let new_y = y * scale_y as u32 + dy as u32;
-                    scaled_bytes[(new_y * new_width + new_x) as usize] = pixel;
-                }
-            }
+        for pixel in row {
+            scaled_bytes.extend(core::iter::repeat_n(*pixel, scale_x as usize));
+        }
+
+        let end = scaled_bytes.len();
+        for _ in 1..scale_y {
+            scaled_bytes.extend_from_within(start..end);
         }
     }
 
@@ -483,7 +591,7 @@ pub fn scale_pixels(
 
 /// Images that were added to storage can be
 /// referenced with an ImageRef
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ImageRef {
     pub kc1: u8,
     pub kc2: u8,
@@ -492,18 +600,15 @@ pub struct ImageRef {
 
 impl ImageRef {
     pub fn from_data(data: &Vec<u8>, storage: ImageRefStorage) -> Option<ImageRef> {
-        if data.len() < 2 {
-            return None;
-        }
         Some(ImageRef {
-            kc1: *data.get(0).unwrap(),
-            kc2: *data.get(1).unwrap(),
+            kc1: *data.first()?,
+            kc2: *data.get(1)?,
             storage,
         })
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ImageRefStorage {
     Disc,
     Ram,
@@ -512,6 +617,8 @@ pub enum ImageRefStorage {
 #[derive(Clone, Debug)]
 pub enum GraphicsCommand {
     Error(String),
+    /// An image made from data that did not match its size
+    PartialImage(Image, ImageDataError),
     Code2D(Code2D),
     Barcode(Barcode),
     Image(Image),
@@ -1,19 +1,107 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use crate::audit::AuditFinding;
 use crate::command::Command;
+use crate::fingerprint::Fingerprint;
+use crate::prelude::*;
+
+//Diagnostics are printed with std and dropped without it
+#[cfg(feature = "std")]
+macro_rules! debug_println {
+    ($($arg:tt)*) => { std::println!($($arg)*) };
+}
 
+#[cfg(not(feature = "std"))]
+macro_rules! debug_println {
+    ($($arg:tt)*) => {{ let _ = format_args!($($arg)*); }};
+}
+
+mod prelude;
+
+pub mod audit;
 pub mod command;
 pub mod command_sets;
 pub mod commands;
 pub mod constants;
 pub mod context;
 pub mod decoder;
+pub mod fingerprint;
 pub mod graphics;
 pub mod parser;
+pub mod profile;
+pub mod reference;
+pub mod round_trip;
 pub mod subcommands;
+pub mod template;
 pub mod thermal_file;
 pub mod utils;
 pub mod util;
 pub mod text;
 
-pub fn parse_esc_pos(bytes: &Vec<u8>) -> Vec<Command> {
-    parser::Parser::new(command_sets::esc_pos::new()).parse_bytes(bytes)
+pub fn parse_esc_pos(bytes: &[u8]) -> Vec<Command> {
+    new_esc_pos_parser().parse_bytes(bytes)
+}
+
+pub fn parse_esc_p(bytes: &[u8]) -> Vec<Command> {
+    new_esc_p_parser().parse_bytes(bytes)
+}
+
+pub fn parse_star(bytes: &[u8]) -> Vec<Command> {
+    new_star_parser().parse_bytes(bytes)
+}
+
+pub fn parse_star_raster(bytes: &[u8]) -> Vec<Command> {
+    new_star_raster_parser().parse_bytes(bytes)
+}
+
+pub fn parse_tspl(bytes: &[u8]) -> Vec<Command> {
+    new_tspl_parser().parse_bytes(bytes)
+}
+
+/// Reports where the framing of the esc/pos commands may
+/// have gone wrong, see the audit module
+pub fn audit_esc_pos(bytes: &[u8]) -> Vec<AuditFinding> {
+    new_esc_pos_parser().audit(bytes)
+}
+
+/// What the esc/pos commands print, the same for duplicate
+/// prints, see the fingerprint module
+pub fn fingerprint_esc_pos(bytes: &[u8]) -> Fingerprint {
+    fingerprint::fingerprint(&parse_esc_pos(bytes))
+}
+
+/// The bytes of the commands, commands parsed with
+/// `Parser::retain_raw` return their original bytes
+pub fn serialize(commands: &[Command]) -> Vec<u8> {
+    commands.iter().flat_map(|command| command.to_bytes()).collect()
+}
+
+pub fn new_esc_pos_parser() -> parser::Parser {
+    parser::Parser::new(command_sets::esc_pos::new())
+}
+
+/// Parser for the ESC/P subset used by impact (dot matrix)
+/// kitchen printers like the TM-U220
+pub fn new_esc_p_parser() -> parser::Parser {
+    parser::Parser::new(command_sets::esc_p::new())
+}
+
+/// Parser for the Star Line Mode dialect used by
+/// Star Micronics printers like the TSP100 and TSP650
+pub fn new_star_parser() -> parser::Parser {
+    parser::Parser::new(command_sets::star::new())
+}
+
+/// Parser for the Star Raster Mode job format used by
+/// graphics only printers like the TSP100
+pub fn new_star_raster_parser() -> parser::Parser {
+    parser::Parser::new(command_sets::star_raster::new())
+}
+
+/// Parser for the TSPL / TSPL2 language used by
+/// TSC label printers
+pub fn new_tspl_parser() -> parser::Parser {
+    parser::Parser::new(command_sets::tspl::new())
 }
@@ -1,59 +1,140 @@
-use crate::command::CommandType;
+use crate::audit::{audit_commands, AuditFinding};
+use crate::command::{CommandType, DataType};
+use crate::prelude::*;
 use crate::{command::Command, command_sets::*};
-use std::mem;
-use std::rc::Rc;
+use alloc::rc::Rc;
+use core::mem;
 
 pub struct Parser {
     cmd_set: CommandSet,
     match_depth: u8,
-    command_matches: Vec<Command>,
+    //Indexes of the commands in the set that match the bytes so far
+    command_matches: Vec<usize>,
     current_command: Option<Command>,
     current_command_is_default: bool,
     command_buffer: Vec<u8>,
     captured_commands: Vec<Command>,
+    streaming: bool,
+    offset: usize,
+    retain_raw: bool,
+    raw: Vec<u8>,
+    raw_offset: usize,
+    realtime: bool,
+    realtime_commands: Vec<Command>,
+    realtime_buffer: Vec<u8>,
 }
 
 impl Parser {
     pub fn new(cmd_set: CommandSet) -> Self {
+        let realtime_commands = realtime_commands(&cmd_set);
+
         Self {
             cmd_set,
             match_depth: 0,
-            command_matches: Vec::<Command>::new(),
+            command_matches: vec![],
             current_command_is_default: false,
             command_buffer: Vec::<u8>::new(),
             current_command: None,
             captured_commands: vec![],
+            streaming: false,
+            offset: 0,
+            retain_raw: false,
+            raw: vec![],
+            raw_offset: 0,
+            realtime: true,
+            realtime_commands,
+            realtime_buffer: vec![],
         }
     }
 
-    pub fn parse_bytes(&mut self, bytes: &Vec<u8>) -> Vec<Command> {
-        self.emit_command(self.cmd_set.begin_parsing.clone());
+    /// Keep the original bytes of every command in `Command::raw`, so
+    /// that `serialize` returns the exact bytes that were parsed.
+    /// Every byte belongs to a command, bytes that were skipped while
+    /// matching belong to the command after them
+    pub fn retain_raw(&mut self, retain_raw: bool) {
+        self.retain_raw = retain_raw;
+    }
+
+    /// Adds a command to the command set of this parser, see
+    /// `CommandSet::add_command`
+    pub fn add_command(&mut self, command: Command) -> Option<Command> {
+        let replaced = self.cmd_set.add_command(command);
+        self.realtime_commands = realtime_commands(&self.cmd_set);
+        replaced
+    }
+
+    /// Parses the bytes and reports the places where data may have
+    /// been parsed as commands or commands as data, see the audit module
+    pub fn audit(&mut self, bytes: &[u8]) -> Vec<AuditFinding> {
+        let retain_raw = self.retain_raw;
+        self.retain_raw = true;
+        let commands = self.parse_bytes(bytes);
+        self.retain_raw = retain_raw;
+
+        audit_commands(&commands, &self.cmd_set)
+    }
+
+    pub fn parse_bytes(&mut self, bytes: &[u8]) -> Vec<Command> {
+        let mut commands = self.parse_chunk(bytes);
+        commands.append(&mut self.finish());
+        commands
+    }
+
+    /// Parses the next part of a stream of bytes. A command is only
+    /// returned once the byte after it arrives, so the last command
+    /// of a chunk is returned by a later chunk or by `finish`
+    pub fn parse_chunk(&mut self, bytes: &[u8]) -> Vec<Command> {
+        if !self.streaming {
+            self.streaming = true;
+            self.emit_command(self.cmd_set.begin_parsing.clone());
+        }
 
         for byte in bytes {
             self.parse(byte);
         }
 
+        mem::take(&mut self.captured_commands)
+    }
+
+    /// Ends the stream, returns the remaining commands and resets
+    /// the parser for the next stream
+    pub fn finish(&mut self) -> Vec<Command> {
+        if !self.streaming {
+            self.emit_command(self.cmd_set.begin_parsing.clone());
+        }
+
         //emit the last command and reset the parser
         let mut new_cmd = None;
         mem::swap(&mut self.current_command, &mut new_cmd); //new_cmd has become the previous command after the swap
 
-        if let Some(new_cmd_unwrapped) = new_cmd {
+        if let Some(mut new_cmd_unwrapped) = new_cmd {
+            self.take_raw(&mut new_cmd_unwrapped, self.offset);
             self.emit_command(new_cmd_unwrapped);
         }
 
-        self.emit_command(self.cmd_set.end_parsing.clone());
+        let mut end_parsing = self.cmd_set.end_parsing.clone();
+        end_parsing.offset = self.offset;
+        self.emit_command(end_parsing);
 
+        self.streaming = false;
+        self.offset = 0;
+        self.raw.clear();
+        self.raw_offset = 0;
         self.match_depth = 0;
         self.command_buffer.clear();
         self.command_matches.clear();
         self.current_command_is_default = false;
+        self.realtime = true;
+        self.realtime_buffer.clear();
 
-        let mut commands: Vec<Command> = Vec::new();
-        mem::swap(&mut self.captured_commands, &mut commands);
-        commands
+        mem::take(&mut self.captured_commands)
     }
 
     fn emit_command(&mut self, mut cmd: Command) {
+        if let Some(enabled) = cmd.handler.realtime_commands(&cmd) {
+            self.realtime = enabled;
+        }
+
         if cmd.kind == CommandType::Subcommand {
             let command = &mut cmd;
 
@@ -69,63 +150,142 @@ impl Parser {
                 }
 
                 subcommand.commands = Rc::new(expanded_cmds);
+                subcommand.offset = command.offset;
+                subcommand.reference = command.reference;
+
+                //The data is only moved to the subcommand when a byte after
+                //it arrives, at the end of the stream it is still here
+                if subcommand.data.is_empty() {
+                    subcommand.data = mem::take(&mut command.data);
+                }
+                subcommand.raw = mem::take(&mut command.raw);
 
                 self.captured_commands.push(subcommand);
+            } else if !cmd.raw.is_empty() {
+                //Keep the bytes of subcommands that were not understood
+                self.captured_commands.push(cmd);
             }
         } else {
             self.captured_commands.push(cmd);
         }
     }
 
+    //Moves the retained bytes up to the end offset to the command
+    fn take_raw(&mut self, command: &mut Command, end: usize) {
+        if !self.retain_raw {
+            return;
+        }
+
+        let len = end.saturating_sub(self.raw_offset).min(self.raw.len());
+        command.raw = self.raw.drain(..len).collect();
+        self.raw_offset += len;
+    }
+
+    //Real-time commands inside the data of another command are executed
+    //by the printer as they arrive, unless GS ( D turned them off. The
+    //bytes stay part of the data, so the command is emitted as embedded
+    fn scan_realtime(&mut self, byte: u8, offset: usize) {
+        if !self.realtime || self.realtime_commands.is_empty() {
+            return;
+        }
+
+        self.realtime_buffer.push(byte);
+
+        while !self.realtime_buffer.is_empty() {
+            let buffer = &self.realtime_buffer;
+            let mut partial = false;
+
+            for cmd in self.realtime_commands.iter() {
+                let len = cmd.commands.len();
+                let full = len + fixed_data_len(&cmd.data_kind).unwrap_or(0);
+
+                if buffer.len() <= len {
+                    partial |= cmd.commands.starts_with(buffer);
+                } else if buffer.len() <= full && buffer.starts_with(&cmd.commands) {
+                    if buffer.len() < full {
+                        partial = true;
+                        continue;
+                    }
+
+                    let mut realtime = cmd.clone();
+                    realtime.data = buffer[len..].to_vec();
+                    realtime.offset = offset + 1 - buffer.len();
+                    realtime.embedded = true;
+
+                    self.realtime_buffer.clear();
+                    self.emit_command(realtime);
+                    return;
+                }
+            }
+
+            if partial {
+                return;
+            }
+
+            //The sequence broke off, it may start again further in
+            self.realtime_buffer.remove(0);
+        }
+    }
+
     fn parse(&mut self, byte: &u8) {
+        let offset = self.offset;
+        self.offset += 1;
+        if self.retain_raw {
+            self.raw.push(*byte);
+        }
+
         //If a command is willing to accept bytes and it is not the
         // default command, we don't need to do any filtering
         if self.match_depth == 0 && !self.current_command_is_default {
             if let Some(cmd) = &mut self.current_command {
                 if cmd.push(*byte) {
+                    self.scan_realtime(*byte, offset);
                     return;
                 };
             }
         }
+        self.realtime_buffer.clear();
 
         //Keep track of the search in case we need to match for an unknown command
         self.command_buffer.push(*byte);
 
-        //Look for matching commands
-        let mut new_command_matches: Vec<Command> = Vec::with_capacity(0);
-        let subset = if self.match_depth == 0 {
-            &self.cmd_set.commands
-        } else {
-            &self.command_matches
-        };
+        //Look for matching commands, only the one that matches is cloned
+        let depth = self.match_depth as usize;
+        let commands = &self.cmd_set.commands;
 
-        for cmd in subset.iter() {
-            if let Some(b) = cmd.commands.get(self.match_depth as usize) {
-                if b == byte {
-                    new_command_matches.push(cmd.clone())
-                }
-            }
+        if depth == 0 {
+            self.command_matches.clear();
+            self.command_matches.extend(
+                (0..commands.len()).filter(|index| commands[*index].commands.first() == Some(byte)),
+            );
+        } else {
+            self.command_matches
+                .retain(|index| commands[*index].commands.get(depth) == Some(byte));
         }
 
-        self.command_matches = new_command_matches;
-
         //if the command subset has one match, we create a new command by cloning the command
         if self.command_matches.len() == 1 {
-            if let Some(matched_command) = &mut self.command_matches.first() {
+            if let Some(matched_command) = self.command_matches.first().map(|i| &commands[*i]) {
                 //Here we make sure all command bytes are matched
                 if matched_command.commands.len() - 1 != self.match_depth as usize {
                     self.match_depth += 1;
                     return;
                 }
 
+                let start = offset + 1 - self.command_buffer.len();
+                let mut new_cmd = Some(matched_command.clone());
+                if let Some(cmd) = &mut new_cmd {
+                    cmd.offset = start;
+                }
+
                 self.current_command_is_default = false;
                 self.command_buffer.clear();
                 self.match_depth = 0;
 
-                let mut new_cmd = Some(matched_command.clone());
                 mem::swap(&mut self.current_command, &mut new_cmd); //new_cmd has become the previous command after the swap
 
-                if let Some(new_cmd_unwrapped) = new_cmd {
+                if let Some(mut new_cmd_unwrapped) = new_cmd {
+                    self.take_raw(&mut new_cmd_unwrapped, start);
                     self.emit_command(new_cmd_unwrapped);
                 }
             }
@@ -135,17 +295,17 @@ impl Parser {
         //If the matched command set is empty we either make a new default command
         //or make a new unknown command or append to the last default command
         if self.command_matches.is_empty() {
+            let start = offset + 1 - self.command_buffer.len();
             let mut new_cmd = None;
 
-            if self.command_buffer.len() > 0
-                && self
-                    .cmd_set
-                    .unknown
-                    .commands
-                    .contains(self.command_buffer.first().unwrap())
+            if self
+                .command_buffer
+                .first()
+                .is_some_and(|byte| self.cmd_set.unknown.commands.contains(byte))
             {
                 let mut unknown_command = self.cmd_set.unknown.clone();
                 unknown_command.data = self.command_buffer.clone();
+                unknown_command.offset = start;
                 new_cmd = Some(unknown_command);
             } else if self.current_command_is_default {
                 if let Some(cmd) = &mut self.current_command {
@@ -154,6 +314,7 @@ impl Parser {
             } else {
                 let mut default_command = self.cmd_set.default.clone();
                 default_command.push(*byte);
+                default_command.offset = offset;
                 new_cmd = Some(default_command);
             }
 
@@ -163,12 +324,12 @@ impl Parser {
 
             if new_cmd.is_some() {
                 mem::swap(&mut self.current_command, &mut new_cmd);
-                if new_cmd.is_some() {
+
+                //At the start of the stream there is no previous command
+                if let Some(mut previous) = new_cmd {
                     //new_command has become the previous command after the swap
-                    self.emit_command(new_cmd.unwrap());
-                } else {
-                    //Generally an unknown command at the start of the binary
-                    self.emit_command(self.cmd_set.unknown.clone());
+                    self.take_raw(&mut previous, start);
+                    self.emit_command(previous);
                 }
                 return;
             }
@@ -178,3 +339,25 @@ impl Parser {
         self.match_depth += 1;
     }
 }
+
+//Real-time commands with a fixed length can be found inside data
+fn realtime_commands(cmd_set: &CommandSet) -> Vec<Command> {
+    cmd_set
+        .commands
+        .iter()
+        .filter(|cmd| cmd.handler.is_realtime() && fixed_data_len(&cmd.data_kind).is_some())
+        .cloned()
+        .collect()
+}
+
+pub(crate) fn fixed_data_len(data_kind: &DataType) -> Option<usize> {
+    match data_kind {
+        DataType::Empty => Some(0),
+        DataType::Single => Some(1),
+        DataType::Double => Some(2),
+        DataType::Triple => Some(3),
+        DataType::Quad => Some(4),
+        DataType::Octet => Some(8),
+        _ => None,
+    }
+}
@@ -0,0 +1,35 @@
+//! The parts of the std prelude that come from alloc, so that
+//! modules work the same with and without the std feature
+
+pub use alloc::borrow::ToOwned;
+pub use alloc::boxed::Box;
+pub use alloc::format;
+pub use alloc::string::{String, ToString};
+pub use alloc::vec;
+pub use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+pub use self::float::Round;
+
+#[cfg(not(feature = "std"))]
+mod float {
+    /// f32::round comes from std, this rounds half away from zero the same way
+    pub trait Round {
+        fn round(self) -> Self;
+    }
+
+    impl Round for f32 {
+        fn round(self) -> f32 {
+            let whole = self as i64 as f32;
+            let fraction = self - whole;
+
+            if fraction >= 0.5 {
+                whole + 1.0
+            } else if fraction <= -0.5 {
+                whole - 1.0
+            } else {
+                whole
+            }
+        }
+    }
+}
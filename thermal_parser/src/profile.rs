@@ -0,0 +1,164 @@
+//! Printer Profile
+//!
+//! A profile describes the physical printer that a job is
+//! rendered for. The context is created from a profile so
+//! that the defaults match the printer.
+
+use crate::command::Command;
+use crate::context::FontMetrics;
+use crate::prelude::*;
+
+/// How page mode data is combined with data already in the page
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compositing {
+    /// Dots are added to what is already printed, like most printers
+    Or,
+    /// New data replaces what is already printed, including blank dots
+    Overwrite,
+}
+
+/// What the printer reports about itself when a job asks with GS I
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrinterId {
+    pub model_id: u8,
+    /// Bit flags, 0x02 is a printer with an auto cutter
+    pub type_id: u8,
+    pub version_id: u8,
+    pub firmware_version: String,
+    pub maker: String,
+    pub model: String,
+    pub serial_number: String,
+    pub font_language: String,
+}
+
+impl Default for PrinterId {
+    fn default() -> Self {
+        PrinterId {
+            model_id: 0x20,
+            type_id: 0x02,
+            version_id: 0x01,
+            firmware_version: "1.00".to_string(),
+            maker: "Generic".to_string(),
+            model: "Generic 80mm".to_string(),
+            serial_number: "000000000000".to_string(),
+            font_language: "PC437".to_string(),
+        }
+    }
+}
+
+impl PrinterId {
+    /// The bytes the printer sends back for GS I n, none when the
+    /// printer does not know the id type
+    pub fn response(&self, id_type: u8) -> Option<Vec<u8>> {
+        let text = match id_type {
+            1 | 49 => return Some(vec![self.model_id]),
+            2 | 50 => return Some(vec![self.type_id]),
+            3 | 51 => return Some(vec![self.version_id]),
+            65 => &self.firmware_version,
+            66 => &self.maker,
+            67 => &self.model,
+            68 => &self.serial_number,
+            69 => &self.font_language,
+            _ => return None,
+        };
+
+        //Text ids are sent between a header (_) and a NUL
+        let mut response = vec![0x5F];
+        response.extend_from_slice(text.as_bytes());
+        response.push(0x00);
+        Some(response)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PrinterProfile {
+    pub name: String,
+    pub dots_per_inch: u16,
+    /// Paper feed speed in millimeters per second
+    pub feed_speed: f32,
+    /// Label length in millimeters for label or black mark
+    /// paper, none for continuous receipt paper
+    pub label_length_mm: Option<f32>,
+    /// Used when page mode print areas overlap
+    pub page_compositing: Compositing,
+    /// Default horizontal and vertical motion units per inch,
+    /// restored by GS P 0 0. None is one unit per dot
+    pub motion_units: Option<(u16, u16)>,
+    /// Horizontal and vertical dots per inch of a normal density
+    /// image dot (GS v 0 m = 0, ESC * 33), for printers whose image
+    /// dots aren't the dots of the print head. None is one dot
+    pub image_density: Option<(u16, u16)>,
+    /// Power on code table (ESC t), 19 for printers that
+    /// are set up with PC858
+    pub code_table: u8,
+    /// Power on international character set (ESC R)
+    pub character_set: u8,
+    /// Power on line spacing in dots (ESC 2)
+    pub line_spacing: u8,
+    /// Commands the printer does not have, matched against the start
+    /// of `Command::opcode`. GS ( k 53 for example is every Aztec
+    /// function for printers without Aztec codes
+    pub unsupported_commands: Vec<Vec<u8>>,
+    /// Answers to GS I, POS apps often enable features based on
+    /// the model that the printer reports
+    pub printer_id: PrinterId,
+    /// Character sizes of the printer fonts, Font B is 9x24
+    /// on some printers which changes where lines wrap
+    pub fonts: FontMetrics,
+}
+
+impl Default for PrinterProfile {
+    fn default() -> Self {
+        PrinterProfile {
+            name: "Generic 80mm".to_string(),
+            dots_per_inch: 203,
+            feed_speed: 200.0,
+            label_length_mm: None,
+            page_compositing: Compositing::Or,
+            motion_units: None,
+            image_density: None,
+            code_table: 0,
+            character_set: 0,
+            line_spacing: 24,
+            unsupported_commands: vec![],
+            printer_id: PrinterId::default(),
+            fonts: FontMetrics::default(),
+        }
+    }
+}
+
+impl PrinterProfile {
+    /// Whether the printer has the command
+    pub fn supports_command(&self, command: &Command) -> bool {
+        let opcode = command.opcode();
+        !self
+            .unsupported_commands
+            .iter()
+            .any(|unsupported| opcode.starts_with(unsupported))
+    }
+
+    /// Converts printer dots to millimeters
+    pub fn dots_to_mm(&self, dots: u32) -> f32 {
+        dots as f32 / self.dots_per_inch as f32 * 25.4
+    }
+
+    /// Printer dots per image dot, the scale is the number of normal
+    /// density image dots per image dot, i.e. (2, 2) for quadruple
+    pub fn image_stretch(&self, scale: (u8, u8)) -> (u8, u8) {
+        let Some((h_density, v_density)) = self.image_density else {
+            return scale;
+        };
+
+        let stretch = |scale: u8, density: u16| {
+            let density = density.max(1) as u32;
+            let dots = (scale as u32 * self.dots_per_inch as u32 * 2 + density) / (density * 2);
+            dots.clamp(1, u8::MAX as u32) as u8
+        };
+        (stretch(scale.0, h_density), stretch(scale.1, v_density))
+    }
+
+    /// Converts millimeters to printer dots
+    pub fn mm_to_dots(&self, mm: f32) -> u32 {
+        (mm / 25.4 * self.dots_per_inch as f32).round() as u32
+    }
+}
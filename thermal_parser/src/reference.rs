@@ -0,0 +1,960 @@
+//! Command Reference
+//!
+//! Metadata from the Epson ESC/POS command reference for the
+//! commands of the ESC/POS command set: the official name, how the
+//! reference writes the command (`GS ( L <Function 112>`), the
+//! printer families that have it and what the parameters mean.
+//!
+//! Commands find their entry by `Command::opcode`, so subcommands
+//! like the functions of GS ( k and GS ( L have entries of their
+//! own. Commands that are not part of ESC/POS (and commands that
+//! were added to a parser) have no entry.
+//!
+//! ```
+//! let commands = thermal_parser::parse_esc_pos(b"\x1b@HELLO\n");
+//! let info = commands[1].info().unwrap();
+//!
+//! assert_eq!(info.reference, "ESC @");
+//! assert_eq!(info.name, "Initialize printer");
+//! ```
+
+//The statics of `constants` are not promoted to 'static in the opcodes
+const HT: u8 = 0x09;
+const LF: u8 = 0x0A;
+const FF: u8 = 0x0C;
+const CR: u8 = 0x0D;
+const CAN: u8 = 0x18;
+const DLE: u8 = 0x10;
+const DC4: u8 = 0x14;
+const EOT: u8 = 0x04;
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+
+/// Reference metadata of a command
+#[derive(Debug, PartialEq)]
+pub struct CommandInfo {
+    /// Opcodes (see `Command::opcode`) that the entry is for
+    pub opcodes: &'static [&'static [u8]],
+    /// The command as the reference writes it, i.e. `GS ( L <Function 112>`
+    pub reference: &'static str,
+    /// Name of the command in the reference
+    pub name: &'static str,
+    /// Printer families that have the command
+    pub models: &'static [&'static str],
+    pub parameters: &'static [ParameterInfo],
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParameterInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// The entry of an opcode in a reference table
+pub fn find(table: &'static [CommandInfo], opcode: &[u8]) -> Option<&'static CommandInfo> {
+    table.iter().find(|info| info.opcodes.contains(&opcode))
+}
+
+const ALL: &[&str] = &[
+    "TM-T88VI",
+    "TM-T88V",
+    "TM-T20III",
+    "TM-T20II",
+    "TM-m30",
+    "TM-U220",
+];
+const THERMAL: &[&str] = &["TM-T88VI", "TM-T88V", "TM-T20III", "TM-T20II", "TM-m30"];
+const RECENT: &[&str] = &["TM-T88VI", "TM-T20III", "TM-m30"];
+
+const fn info(
+    opcodes: &'static [&'static [u8]],
+    reference: &'static str,
+    name: &'static str,
+    models: &'static [&'static str],
+    parameters: &'static [ParameterInfo],
+) -> CommandInfo {
+    CommandInfo {
+        opcodes,
+        reference,
+        name,
+        models,
+        parameters,
+    }
+}
+
+const fn param(name: &'static str, description: &'static str) -> ParameterInfo {
+    ParameterInfo { name, description }
+}
+
+const ON_OFF: &[ParameterInfo] = &[param("n", "On when the lowest bit is 1")];
+const NONE: &[ParameterInfo] = &[];
+const LENGTH: ParameterInfo = param("pL pH", "Number of bytes after pH");
+const KEY_CODES: ParameterInfo = param("kc1 kc2", "Key code of the graphics");
+const SYMBOL_DATA: ParameterInfo = param("d1...dk", "Symbol data");
+
+/// The functions of GS ( L and GS 8 L
+const fn graphics(
+    function: u8,
+    reference: &'static str,
+    name: &'static str,
+    parameters: &'static [ParameterInfo],
+) -> CommandInfo {
+    let opcodes: &'static [&'static [u8]] = match function {
+        48 => &[&[GS, b'(', b'L', 48, 48], &[GS, b'8', b'L', 48, 48]],
+        49 => &[&[GS, b'(', b'L', 48, 49], &[GS, b'8', b'L', 48, 49]],
+        50 => &[&[GS, b'(', b'L', 48, 50], &[GS, b'8', b'L', 48, 50]],
+        51 => &[&[GS, b'(', b'L', 48, 51], &[GS, b'8', b'L', 48, 51]],
+        52 => &[&[GS, b'(', b'L', 48, 52], &[GS, b'8', b'L', 48, 52]],
+        64 => &[&[GS, b'(', b'L', 48, 64], &[GS, b'8', b'L', 48, 64]],
+        65 => &[&[GS, b'(', b'L', 48, 65], &[GS, b'8', b'L', 48, 65]],
+        66 => &[&[GS, b'(', b'L', 48, 66], &[GS, b'8', b'L', 48, 66]],
+        67 => &[&[GS, b'(', b'L', 48, 67], &[GS, b'8', b'L', 48, 67]],
+        68 => &[&[GS, b'(', b'L', 48, 68], &[GS, b'8', b'L', 48, 68]],
+        69 => &[&[GS, b'(', b'L', 48, 69], &[GS, b'8', b'L', 48, 69]],
+        80 => &[&[GS, b'(', b'L', 48, 80], &[GS, b'8', b'L', 48, 80]],
+        81 => &[&[GS, b'(', b'L', 48, 81], &[GS, b'8', b'L', 48, 81]],
+        82 => &[&[GS, b'(', b'L', 48, 82], &[GS, b'8', b'L', 48, 82]],
+        83 => &[&[GS, b'(', b'L', 48, 83], &[GS, b'8', b'L', 48, 83]],
+        84 => &[&[GS, b'(', b'L', 48, 84], &[GS, b'8', b'L', 48, 84]],
+        85 => &[&[GS, b'(', b'L', 48, 85], &[GS, b'8', b'L', 48, 85]],
+        112 => &[&[GS, b'(', b'L', 48, 112], &[GS, b'8', b'L', 48, 112]],
+        113 => &[&[GS, b'(', b'L', 48, 113], &[GS, b'8', b'L', 48, 113]],
+        _ => &[],
+    };
+    info(opcodes, reference, name, THERMAL, parameters)
+}
+
+const DEFINE_GRAPHICS: &[ParameterInfo] = &[
+    LENGTH,
+    param("a", "48, the tone of the graphics (monochrome)"),
+    KEY_CODES,
+    param("b", "Number of colors"),
+    param("xL xH", "Width in dots"),
+    param("yL yH", "Height in dots"),
+    param("c", "Color of the data that follows, 49 to 52"),
+    param("d1...dk", "Graphics data"),
+];
+
+const STORE_GRAPHICS: &[ParameterInfo] = &[
+    LENGTH,
+    param("a", "Tone, 48 is monochrome and 52 is multiple tone"),
+    param("bx by", "Horizontal and vertical scale, 1 or 2"),
+    param("c", "Color, 49 to 52"),
+    param("xL xH", "Width in dots"),
+    param("yL yH", "Height in dots"),
+    param("d1...dk", "Graphics data"),
+];
+
+const STORE_SYMBOL: &[ParameterInfo] = &[LENGTH, param("m", "48"), SYMBOL_DATA];
+const PRINT_SYMBOL: &[ParameterInfo] = &[LENGTH, param("m", "48")];
+const TRANSMIT_SYMBOL: &[ParameterInfo] = &[LENGTH, param("m", "48")];
+const MODULE_SIZE: &[ParameterInfo] = &[LENGTH, param("n", "Width of a module in dots")];
+const MAX_WIDTH: &[ParameterInfo] = &[LENGTH, param("nL nH", "Maximum width in dots")];
+
+/// The ESC/POS command reference
+pub static ESC_POS: &[CommandInfo] = &[
+    info(&[&[HT]], "HT", "Horizontal tab", ALL, NONE),
+    info(&[&[LF]], "LF", "Print and line feed", ALL, NONE),
+    info(
+        &[&[FF]],
+        "FF",
+        "Print and return to standard mode (in page mode)",
+        ALL,
+        NONE,
+    ),
+    info(&[&[CR]], "CR", "Print and carriage return", ALL, NONE),
+    info(&[&[CAN]], "CAN", "Cancel print data in page mode", THERMAL, NONE),
+    info(
+        &[&[DLE, EOT]],
+        "DLE EOT",
+        "Transmit real-time status",
+        ALL,
+        &[param("n", "Status to transmit, 1 to 4")],
+    ),
+    info(
+        &[&[DLE, DC4, 1]],
+        "DLE DC4 <Function 1>",
+        "Generate pulse in real-time",
+        ALL,
+        &[
+            param("m", "Drawer kick connector pin, 0 or 1"),
+            param("t", "Pulse on time in 100ms, 1 to 8"),
+        ],
+    ),
+    info(
+        &[&[DLE, DC4, 2, 1, 8]],
+        "DLE DC4 <Function 2>",
+        "Execute power-off sequence",
+        THERMAL,
+        &[param("a b", "1 8")],
+    ),
+    info(
+        &[&[DLE, DC4, 8, 1, 3, 20, 1, 6, 2, 8]],
+        "DLE DC4 <Function 8>",
+        "Clear buffer (s)",
+        THERMAL,
+        &[param("d1...d7", "1 3 20 1 6 2 8")],
+    ),
+    info(&[&[ESC, FF]], "ESC FF", "Print data in page mode", THERMAL, NONE),
+    info(
+        &[&[ESC, b'!']],
+        "ESC !",
+        "Select print mode(s)",
+        ALL,
+        &[param("n", "Bits for font B, emphasized, double height, double width and underline")],
+    ),
+    info(
+        &[&[ESC, b'$']],
+        "ESC $",
+        "Set absolute print position",
+        ALL,
+        &[param("nL nH", "Position from the start of the line in motion units")],
+    ),
+    info(
+        &[&[ESC, b'*']],
+        "ESC *",
+        "Select bit-image mode",
+        ALL,
+        &[
+            param("m", "Density, 0, 1, 32 or 33"),
+            param("nL nH", "Width in dots"),
+            param("d1...dk", "Column data, one or three bytes per column"),
+        ],
+    ),
+    info(
+        &[&[ESC, b'-']],
+        "ESC -",
+        "Turn underline mode on/off",
+        ALL,
+        &[param("n", "0 is off, 1 is one dot thick and 2 is two dots thick")],
+    ),
+    info(&[&[ESC, b'2']], "ESC 2", "Select default line spacing", ALL, NONE),
+    info(
+        &[&[ESC, b'3']],
+        "ESC 3",
+        "Set line spacing",
+        ALL,
+        &[param("n", "Line spacing in motion units")],
+    ),
+    info(
+        &[&[ESC, b'=']],
+        "ESC =",
+        "Select peripheral device",
+        ALL,
+        &[param("n", "The printer is enabled when the lowest bit is 1")],
+    ),
+    info(&[&[ESC, b'@']], "ESC @", "Initialize printer", ALL, NONE),
+    info(
+        &[&[ESC, b'D']],
+        "ESC D",
+        "Set horizontal tab positions",
+        ALL,
+        &[param("n1...nk", "Tab columns in ascending order, ended by NUL")],
+    ),
+    info(&[&[ESC, b'E']], "ESC E", "Turn emphasized mode on/off", ALL, ON_OFF),
+    info(&[&[ESC, b'G']], "ESC G", "Turn double-strike mode on/off", ALL, ON_OFF),
+    info(
+        &[&[ESC, b'J']],
+        "ESC J",
+        "Print and feed paper",
+        ALL,
+        &[param("n", "Feed in motion units")],
+    ),
+    info(&[&[ESC, b'L']], "ESC L", "Select page mode", THERMAL, NONE),
+    info(
+        &[&[ESC, b'M']],
+        "ESC M",
+        "Select character font",
+        ALL,
+        &[param("n", "Font, 0 is font A and 1 is font B")],
+    ),
+    info(
+        &[&[ESC, b'R']],
+        "ESC R",
+        "Select an international character set",
+        ALL,
+        &[param("n", "Character set, 0 is U.S.A.")],
+    ),
+    info(&[&[ESC, b'S']], "ESC S", "Select standard mode", THERMAL, NONE),
+    info(
+        &[&[ESC, b'T']],
+        "ESC T",
+        "Select print direction in page mode",
+        THERMAL,
+        &[param("n", "Direction, 0 is left to right starting at the upper left")],
+    ),
+    info(
+        &[&[ESC, b'W']],
+        "ESC W",
+        "Set print area in page mode",
+        THERMAL,
+        &[
+            param("xL xH yL yH", "Origin in motion units"),
+            param("dxL dxH dyL dyH", "Width and height in motion units"),
+        ],
+    ),
+    info(
+        &[&[ESC, b'\\']],
+        "ESC \\",
+        "Set relative print position",
+        ALL,
+        &[param("nL nH", "Signed offset from the current position in motion units")],
+    ),
+    info(
+        &[&[ESC, b'a']],
+        "ESC a",
+        "Select justification",
+        ALL,
+        &[param("n", "0 is left, 1 is centered and 2 is right")],
+    ),
+    info(
+        &[&[ESC, b'c', 3]],
+        "ESC c 3",
+        "Select paper sensor(s) to output paper-end signals",
+        ALL,
+        &[param("n", "Bits of the sensors")],
+    ),
+    info(
+        &[&[ESC, b'c', 4]],
+        "ESC c 4",
+        "Select paper sensor(s) to stop printing",
+        ALL,
+        &[param("n", "Bits of the sensors")],
+    ),
+    info(
+        &[&[ESC, b'c', 5]],
+        "ESC c 5",
+        "Enable/disable panel buttons",
+        ALL,
+        &[param("n", "Buttons are disabled when the lowest bit is 1")],
+    ),
+    info(
+        &[&[ESC, b'd']],
+        "ESC d",
+        "Print and feed n lines",
+        ALL,
+        &[param("n", "Number of lines")],
+    ),
+    info(
+        &[&[ESC, b'e']],
+        "ESC e",
+        "Print and reverse feed n lines",
+        ALL,
+        &[param("n", "Number of lines")],
+    ),
+    info(
+        &[&[ESC, b'p']],
+        "ESC p",
+        "Generate pulse",
+        ALL,
+        &[
+            param("m", "Connector pin, 0 is pin 2 and 1 is pin 5"),
+            param("t1 t2", "On and off time in 2 ms units"),
+        ],
+    ),
+    info(
+        &[&[ESC, b'r']],
+        "ESC r",
+        "Select print color",
+        ALL,
+        &[param("n", "0 is black and 1 is red")],
+    ),
+    info(
+        &[&[ESC, b't']],
+        "ESC t",
+        "Select character code table",
+        ALL,
+        &[param("n", "Code table, 0 is PC437")],
+    ),
+    info(
+        &[&[ESC, b'{']],
+        "ESC {",
+        "Turn upside-down print mode on/off",
+        ALL,
+        ON_OFF,
+    ),
+    info(
+        &[&[GS, FF]],
+        "GS FF",
+        "Feed marked paper to print starting position",
+        THERMAL,
+        NONE,
+    ),
+    info(
+        &[&[GS, b'!']],
+        "GS !",
+        "Select character size",
+        ALL,
+        &[param("n", "Width (high bits) and height (low bits) multiplier minus one")],
+    ),
+    info(
+        &[&[GS, b'$']],
+        "GS $",
+        "Set absolute vertical print position in page mode",
+        THERMAL,
+        &[param("nL nH", "Position from the start of the print area in motion units")],
+    ),
+    info(
+        &[&[GS, b'(', b'D']],
+        "GS ( D",
+        "Enable/disable real-time command",
+        THERMAL,
+        &[
+            LENGTH,
+            param("m", "20"),
+            param("a1 b1...", "Real-time command type and 0 to disable or 1 to enable"),
+        ],
+    ),
+    info(
+        &[&[GS, b'(', b'F']],
+        "GS ( F",
+        "Set adjustment values for cut and print positions",
+        THERMAL,
+        &[
+            LENGTH,
+            param("a", "1 is the print starting position and 2 the cut position"),
+            param("m", "0 adjusts forward and 1 backward"),
+            param("nL nH", "Adjustment in motion units"),
+        ],
+    ),
+    info(
+        &[&[GS, b'(', b'H']],
+        "GS ( H",
+        "Request transmission of response or status",
+        THERMAL,
+        &[LENGTH, param("fn m", "48 48"), param("d1...d4", "Process id")],
+    ),
+    info(
+        &[&[GS, b'(', b'K']],
+        "GS ( K",
+        "Select print control method(s)",
+        THERMAL,
+        &[
+            LENGTH,
+            param("fn", "Function, 48 method, 49 density, 50 speed and 97 head divisions"),
+            param("m", "Value for the function"),
+        ],
+    ),
+    graphics(
+        48,
+        "GS ( L <Function 48>",
+        "Transmit the NV graphics memory capacity",
+        &[LENGTH],
+    ),
+    graphics(
+        49,
+        "GS ( L <Function 49>",
+        "Set the reference dot density for graphics",
+        &[
+            LENGTH,
+            param("x y", "Horizontal and vertical density, 50 is 180 dpi and 51 is 360 dpi"),
+        ],
+    ),
+    graphics(
+        50,
+        "GS ( L <Function 50>",
+        "Print the graphics data in the print buffer",
+        &[LENGTH],
+    ),
+    graphics(
+        51,
+        "GS ( L <Function 51>",
+        "Transmit the remaining capacity of the NV graphics memory",
+        &[LENGTH],
+    ),
+    graphics(
+        52,
+        "GS ( L <Function 52>",
+        "Transmit the remaining capacity of the download graphics memory",
+        &[LENGTH],
+    ),
+    graphics(
+        64,
+        "GS ( L <Function 64>",
+        "Transmit the key code list for defined NV graphics",
+        &[LENGTH, param("d1 d2", "75 67 (KC)")],
+    ),
+    graphics(
+        65,
+        "GS ( L <Function 65>",
+        "Delete all NV graphics data",
+        &[LENGTH, param("d1 d2 d3", "67 76 82 (CLR)")],
+    ),
+    graphics(
+        66,
+        "GS ( L <Function 66>",
+        "Delete the specified NV graphics data",
+        &[LENGTH, KEY_CODES],
+    ),
+    graphics(
+        67,
+        "GS ( L <Function 67>",
+        "Define the NV graphics data (raster format)",
+        DEFINE_GRAPHICS,
+    ),
+    graphics(
+        68,
+        "GS ( L <Function 68>",
+        "Define the NV graphics data (column format)",
+        DEFINE_GRAPHICS,
+    ),
+    graphics(
+        69,
+        "GS ( L <Function 69>",
+        "Print the specified NV graphics data",
+        &[
+            LENGTH,
+            KEY_CODES,
+            param("x y", "Horizontal and vertical scale, 1 or 2"),
+        ],
+    ),
+    graphics(
+        80,
+        "GS ( L <Function 80>",
+        "Transmit the key code list for defined download graphics",
+        &[LENGTH, param("d1 d2", "75 67 (KC)")],
+    ),
+    graphics(
+        81,
+        "GS ( L <Function 81>",
+        "Delete all download graphics data",
+        &[LENGTH, param("d1 d2 d3", "67 76 82 (CLR)")],
+    ),
+    graphics(
+        82,
+        "GS ( L <Function 82>",
+        "Delete the specified download graphics data",
+        &[LENGTH, KEY_CODES],
+    ),
+    graphics(
+        83,
+        "GS ( L <Function 83>",
+        "Define the downloaded graphics data (raster format)",
+        DEFINE_GRAPHICS,
+    ),
+    graphics(
+        84,
+        "GS ( L <Function 84>",
+        "Define the downloaded graphics data (column format)",
+        DEFINE_GRAPHICS,
+    ),
+    graphics(
+        85,
+        "GS ( L <Function 85>",
+        "Print the specified download graphics data",
+        &[
+            LENGTH,
+            KEY_CODES,
+            param("x y", "Horizontal and vertical scale, 1 or 2"),
+        ],
+    ),
+    graphics(
+        112,
+        "GS ( L <Function 112>",
+        "Store the graphics data in the print buffer (raster format)",
+        STORE_GRAPHICS,
+    ),
+    graphics(
+        113,
+        "GS ( L <Function 113>",
+        "Store the graphics data in the print buffer (column format)",
+        STORE_GRAPHICS,
+    ),
+    info(
+        &[&[GS, b'(', b'N']],
+        "GS ( N",
+        "Select character effects",
+        RECENT,
+        &[
+            LENGTH,
+            param("fn", "48 character color, 49 background color and 50 shading"),
+            param("m n", "Values for the function"),
+        ],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 65]],
+        "GS ( k <Function 065>",
+        "PDF417: Set the number of columns in the data region",
+        THERMAL,
+        &[LENGTH, param("n", "Number of columns, 0 is automatic")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 66]],
+        "GS ( k <Function 066>",
+        "PDF417: Set the number of rows",
+        THERMAL,
+        &[LENGTH, param("n", "Number of rows, 0 is automatic")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 67]],
+        "GS ( k <Function 067>",
+        "PDF417: Set the width of the module",
+        THERMAL,
+        MODULE_SIZE,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 69]],
+        "GS ( k <Function 069>",
+        "PDF417: Set the error correction level",
+        THERMAL,
+        &[
+            LENGTH,
+            param("m", "48 sets a level and 49 a ratio"),
+            param("n", "Level or ratio"),
+        ],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 70]],
+        "GS ( k <Function 070>",
+        "PDF417: Select the options",
+        THERMAL,
+        &[LENGTH, param("m", "0 is standard and 1 is truncated PDF417")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 80]],
+        "GS ( k <Function 080>",
+        "PDF417: Store the data in the symbol storage area",
+        THERMAL,
+        STORE_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 81]],
+        "GS ( k <Function 081>",
+        "PDF417: Print the symbol data in the symbol storage area",
+        THERMAL,
+        PRINT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 48, 82]],
+        "GS ( k <Function 082>",
+        "PDF417: Transmit the size information of the symbol data in the symbol storage area",
+        THERMAL,
+        TRANSMIT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 49, 65]],
+        "GS ( k <Function 165>",
+        "QR Code: Select the model",
+        THERMAL,
+        &[LENGTH, param("n1 n2", "Model, 49 is model 1, 50 model 2 and 51 micro QR")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 49, 67]],
+        "GS ( k <Function 167>",
+        "QR Code: Set the size of module",
+        THERMAL,
+        MODULE_SIZE,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 49, 69]],
+        "GS ( k <Function 169>",
+        "QR Code: Select the error correction level",
+        THERMAL,
+        &[LENGTH, param("n", "48 is L, 49 is M, 50 is Q and 51 is H")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 49, 80]],
+        "GS ( k <Function 180>",
+        "QR Code: Store the data in the symbol storage area",
+        THERMAL,
+        STORE_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 49, 81]],
+        "GS ( k <Function 181>",
+        "QR Code: Print the symbol data in the symbol storage area",
+        THERMAL,
+        PRINT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 49, 82]],
+        "GS ( k <Function 182>",
+        "QR Code: Transmit the size information of the symbol data in the symbol storage area",
+        THERMAL,
+        TRANSMIT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 50, 65]],
+        "GS ( k <Function 265>",
+        "MaxiCode: Select the mode",
+        THERMAL,
+        &[LENGTH, param("n", "Mode, 50 to 54")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 50, 80]],
+        "GS ( k <Function 280>",
+        "MaxiCode: Store the data in the symbol storage area",
+        THERMAL,
+        STORE_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 50, 81]],
+        "GS ( k <Function 281>",
+        "MaxiCode: Print the symbol data in the symbol storage area",
+        THERMAL,
+        PRINT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 50, 82]],
+        "GS ( k <Function 282>",
+        "MaxiCode: Transmit the size information of the symbol data in the symbol storage area",
+        THERMAL,
+        TRANSMIT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 51, 67]],
+        "GS ( k <Function 367>",
+        "2-dimensional GS1 DataBar: Set the width of the module",
+        THERMAL,
+        MODULE_SIZE,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 51, 71]],
+        "GS ( k <Function 371>",
+        "2-dimensional GS1 DataBar: GS1 DataBar Expanded Stacked maximum width setting",
+        THERMAL,
+        MAX_WIDTH,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 51, 80]],
+        "GS ( k <Function 380>",
+        "2-dimensional GS1 DataBar: Store the data in the symbol storage area",
+        THERMAL,
+        &[LENGTH, param("m", "48"), param("n", "Symbol type"), SYMBOL_DATA],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 51, 81]],
+        "GS ( k <Function 381>",
+        "2-dimensional GS1 DataBar: Print the symbol data in the symbol storage area",
+        THERMAL,
+        PRINT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 51, 82]],
+        "GS ( k <Function 382>",
+        "2-dimensional GS1 DataBar: Transmit the size information of the symbol data in the symbol storage area",
+        THERMAL,
+        TRANSMIT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 52, 67]],
+        "GS ( k <Function 467>",
+        "Composite Symbology: Set the width of the module",
+        THERMAL,
+        MODULE_SIZE,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 52, 71]],
+        "GS ( k <Function 471>",
+        "Composite Symbology: GS1 DataBar Expanded Stacked maximum width setting",
+        THERMAL,
+        MAX_WIDTH,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 52, 72]],
+        "GS ( k <Function 472>",
+        "Composite Symbology: Select font for HRI characters",
+        THERMAL,
+        &[LENGTH, param("n", "Font of the HRI text, 0 for none")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 52, 80]],
+        "GS ( k <Function 480>",
+        "Composite Symbology: Store the data in the symbol storage area",
+        THERMAL,
+        &[
+            LENGTH,
+            param("m", "48"),
+            param("a b", "Component and symbol type"),
+            SYMBOL_DATA,
+        ],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 52, 81]],
+        "GS ( k <Function 481>",
+        "Composite Symbology: Print the symbol data in the symbol storage area",
+        THERMAL,
+        PRINT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 52, 82]],
+        "GS ( k <Function 482>",
+        "Composite Symbology: Transmit the size information of the symbol data in the symbol storage area",
+        THERMAL,
+        TRANSMIT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 53, 66]],
+        "GS ( k <Function 566>",
+        "Aztec Code: Set the mode types and data layer",
+        RECENT,
+        &[
+            LENGTH,
+            param("n1", "0 is full range and 1 is compact"),
+            param("n2", "Number of data layers, 0 is automatic"),
+        ],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 53, 67]],
+        "GS ( k <Function 567>",
+        "Aztec Code: Set the size of the module",
+        RECENT,
+        MODULE_SIZE,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 53, 69]],
+        "GS ( k <Function 569>",
+        "Aztec Code: Set the error correction level",
+        RECENT,
+        &[LENGTH, param("n", "Error correction in percent, 5 to 95")],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 53, 80]],
+        "GS ( k <Function 580>",
+        "Aztec Code: Store the data in the symbol storage area",
+        RECENT,
+        STORE_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 53, 81]],
+        "GS ( k <Function 581>",
+        "Aztec Code: Print the symbol data in the symbol storage area",
+        RECENT,
+        PRINT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 53, 82]],
+        "GS ( k <Function 582>",
+        "Aztec Code: Transmit the size information of the symbol data in the symbol storage area",
+        RECENT,
+        TRANSMIT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 54, 66]],
+        "GS ( k <Function 666>",
+        "DataMatrix: Set the symbol type, columns number, and rows number",
+        RECENT,
+        &[
+            LENGTH,
+            param("m", "0 is square and 1 is rectangle"),
+            param("d1 d2", "Columns and rows, 0 is automatic"),
+        ],
+    ),
+    info(
+        &[&[GS, b'(', b'k', 54, 67]],
+        "GS ( k <Function 667>",
+        "DataMatrix: Set the size of the module",
+        RECENT,
+        MODULE_SIZE,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 54, 80]],
+        "GS ( k <Function 680>",
+        "DataMatrix: Store the data in the symbol storage area",
+        RECENT,
+        STORE_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 54, 81]],
+        "GS ( k <Function 681>",
+        "DataMatrix: Print the symbol data in the symbol storage area",
+        RECENT,
+        PRINT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'(', b'k', 54, 82]],
+        "GS ( k <Function 682>",
+        "DataMatrix: Transmit the size information of the symbol data in the symbol storage area",
+        RECENT,
+        TRANSMIT_SYMBOL,
+    ),
+    info(
+        &[&[GS, b'B']],
+        "GS B",
+        "Turn white/black reverse print mode on/off",
+        THERMAL,
+        ON_OFF,
+    ),
+    info(
+        &[&[GS, b'H']],
+        "GS H",
+        "Select print position of Human Readable Interpretation (HRI) characters",
+        ALL,
+        &[param("n", "0 is none, 1 above, 2 below and 3 above and below")],
+    ),
+    info(
+        &[&[GS, b'I']],
+        "GS I",
+        "Transmit printer ID",
+        ALL,
+        &[param("n", "Id to transmit")],
+    ),
+    info(
+        &[&[GS, b'P']],
+        "GS P",
+        "Set horizontal and vertical motion units",
+        ALL,
+        &[param("x y", "Horizontal and vertical units per inch, 0 is the default")],
+    ),
+    info(
+        &[&[GS, b'V']],
+        "GS V",
+        "Select cut mode and cut paper",
+        ALL,
+        &[
+            param("m", "Cut mode, 65 and 66 feed before cutting"),
+            param("n", "Feed in motion units for the feeding cut modes"),
+        ],
+    ),
+    info(
+        &[&[GS, b'\\']],
+        "GS \\",
+        "Set relative vertical print position in page mode",
+        THERMAL,
+        &[param("nL nH", "Signed offset from the current position in motion units")],
+    ),
+    info(&[&[GS, b'b']], "GS b", "Turn smoothing mode on/off", THERMAL, ON_OFF),
+    info(
+        &[&[GS, b'f']],
+        "GS f",
+        "Select font for HRI characters",
+        ALL,
+        &[param("n", "Font, 0 is font A and 1 is font B")],
+    ),
+    info(
+        &[&[GS, b'h']],
+        "GS h",
+        "Set bar code height",
+        ALL,
+        &[param("n", "Height in dots")],
+    ),
+    info(
+        &[&[GS, b'k']],
+        "GS k",
+        "Print bar code",
+        ALL,
+        &[
+            param("m", "Bar code system, 65 and up are followed by a length"),
+            param("d1...dk", "Bar code data"),
+        ],
+    ),
+    info(
+        &[&[GS, b'v', b'0']],
+        "GS v 0",
+        "Print raster bit image",
+        THERMAL,
+        &[
+            param("m", "Scale, 0 is normal"),
+            param("xL xH", "Width in bytes"),
+            param("yL yH", "Height in dots"),
+            param("d1...dk", "Raster data"),
+        ],
+    ),
+    info(
+        &[&[GS, b'w']],
+        "GS w",
+        "Set bar code width",
+        ALL,
+        &[param("n", "Width of a module in dots")],
+    ),
+];
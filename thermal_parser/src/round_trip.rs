@@ -0,0 +1,160 @@
+//! Round Trip
+//!
+//! Checks that commands come back the same after they are serialized
+//! and parsed again, so the bytes a handler builds for a command are
+//! the bytes the parser reads it from. When the two drift apart an
+//! edited or generated job prints something else than it says.
+//!
+//! Commands are rebuilt by their handlers, bytes retained by the
+//! parser are not used. Commands without bytes (begin and end of
+//! parsing, real-time commands inside data) are not compared and
+//! text that follows text is compared as one command, the parser
+//! can't tell them apart either.
+//!
+//! With the `arbitrary` feature `Command` implements `Arbitrary`, it
+//! generates ESC/POS commands that are parsed from their own bytes.
+//! Property testing tools can generate jobs from it, proptest through
+//! `proptest-arbitrary-interop` and cargo fuzz directly.
+//!
+//! ```
+//! use thermal_parser::round_trip::check_round_trip;
+//!
+//! let bytes = [0x1B, 0x45, 0x01, b'H', b'I', 0x0A, 0x1D, 0x56, 0x00];
+//! let commands = thermal_parser::parse_esc_pos(&bytes);
+//!
+//! let mut parser = thermal_parser::new_esc_pos_parser();
+//! assert!(check_round_trip(&mut parser, &commands).is_ok());
+//! ```
+
+use crate::command::{Command, CommandType};
+use crate::parser::Parser;
+use crate::prelude::*;
+use core::fmt;
+
+/// The first command that came back different, a missing command
+/// is None
+#[derive(Debug)]
+pub struct RoundTripMismatch {
+    /// Position of the command among the compared commands
+    pub index: usize,
+    pub expected: Option<Command>,
+    pub actual: Option<Command>,
+}
+
+impl fmt::Display for RoundTripMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "command {}: expected {:?}, parsed {:?}",
+            self.index, self.expected, self.actual
+        )
+    }
+}
+
+/// Serializes the commands, parses them with the parser and compares
+/// the commands that come back, `parse(serialize(commands)) == commands`
+pub fn check_round_trip(
+    parser: &mut Parser,
+    commands: &[Command],
+) -> Result<(), Box<RoundTripMismatch>> {
+    let expected = comparable(commands);
+
+    let bytes: Vec<u8> = expected.iter().flat_map(|c| c.to_bytes()).collect();
+    let actual = comparable(&parser.parse_bytes(&bytes));
+
+    for index in 0..expected.len().max(actual.len()) {
+        let (expected, actual) = (expected.get(index), actual.get(index));
+
+        let same = match (expected, actual) {
+            (Some(e), Some(a)) => e.name == a.name && e.commands == a.commands && e.data == a.data,
+            _ => false,
+        };
+
+        if !same {
+            return Err(Box::new(RoundTripMismatch {
+                index,
+                expected: expected.cloned(),
+                actual: actual.cloned(),
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+//Rebuilt commands that have bytes, with text that follows text joined
+fn comparable(commands: &[Command]) -> Vec<Command> {
+    let mut comparable: Vec<Command> = vec![];
+
+    for command in commands.iter().filter(|c| !c.embedded) {
+        let mut command = command.clone();
+        command.raw.clear();
+
+        if command.to_bytes().is_empty() {
+            continue;
+        }
+
+        if let Some(last) = comparable.last_mut() {
+            if last.kind == CommandType::Text && command.kind == CommandType::Text {
+                last.data.extend(&command.data);
+                continue;
+            }
+        }
+
+        comparable.push(command);
+    }
+
+    comparable
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_command {
+    use super::*;
+    use crate::command::DataType;
+    use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+    //Commands that take more data are not generated
+    const MAX_LEN: usize = 4096;
+
+    impl<'a> Arbitrary<'a> for Command {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            let cmd_set = crate::command_sets::esc_pos::new();
+            let mut bytes = vec![];
+
+            let template = &cmd_set.commands[u.choose_index(cmd_set.commands.len())?];
+            let is_text = matches!(template.data_kind, DataType::Text | DataType::Unknown);
+
+            if is_text || u.ratio(1, 4)? {
+                //Printable text, control bytes would be commands
+                for _ in 0..u.int_in_range(1..=64)? {
+                    bytes.push(u.int_in_range(0x20..=0x7E)?);
+                }
+            } else {
+                //Bytes are pushed until the command has all of its data
+                let mut command = template.clone();
+                bytes.extend(command.commands.iter());
+
+                loop {
+                    let byte = u.arbitrary::<u8>()?;
+                    if !command.push(byte) {
+                        break;
+                    }
+                    if bytes.len() >= MAX_LEN {
+                        return Err(Error::IncorrectFormat);
+                    }
+                    bytes.push(byte);
+                }
+            }
+
+            //The command is what the parser makes of its bytes, which is
+            //how subcommands get their handlers
+            let mut parser = Parser::new(cmd_set);
+            let mut parsed = comparable(&parser.parse_bytes(&bytes));
+
+            match parsed.len() {
+                1 => Ok(parsed.remove(0)),
+                _ => Err(Error::IncorrectFormat),
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::prelude::*;
+use crate::text::TextSpan;
 use crate::{command::*, context::*, graphics::*};
 
 #[derive(Clone)]
@@ -5,18 +7,42 @@ pub struct Handler;
 
 impl CommandHandler for Handler {
     fn get_graphics(&self, _command: &Command, context: &Context) -> Option<GraphicsCommand> {
-        match &context.code2d.symbol_storage {
-            Some(code2d) => {
-                return Some(GraphicsCommand::Code2D(code2d.clone()));
+        let code2d = &context.code2d;
+
+        //The 2D component (CC-A, CC-B or CC-C) is a PDF417 or MicroPDF417
+        //symbol and there is no encoder for those. A linear component
+        //without its 2D component would scan as a different code, so
+        //composite symbols with a 2D component are not printed at all
+        if code2d.composite_2d.is_some() {
+            return Some(GraphicsCommand::Error(
+                "Composite symbols with a 2D component are not supported".to_string(),
+            ));
+        }
+
+        match &code2d.composite_linear {
+            Some(Err(error)) => Some(GraphicsCommand::Error(error.clone())),
+            Some(Ok((kind, points, text))) => {
+                let text = TextSpan::new_for_hri(text.clone(), &code2d.composite_font, context);
+
+                Some(GraphicsCommand::Barcode(Barcode {
+                    kind: kind.clone(),
+                    points: points.clone(),
+                    point_width: code2d.composite_width,
+                    point_height: context.barcode.height,
+                    hri: code2d.composite_hri.clone(),
+                    text,
+                }))
             }
-            None => return None,
+            None => Some(GraphicsCommand::Error(
+                "Composite symbol has no linear component".to_string(),
+            )),
         }
     }
 }
 
 pub fn new() -> Command {
     Command::new(
-        "Print the Code2D data",
+        "Composite Print the Code2D data",
         vec![52, 81],
         CommandType::Graphics,
         DataType::Subcommand,
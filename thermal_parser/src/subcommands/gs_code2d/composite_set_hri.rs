@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, context::*};
 
 #[derive(Clone)]
@@ -5,8 +6,15 @@ pub struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        let n = *command.data.get(0).unwrap_or(&0u8);
-        context.code2d.composite_font = Font::from_raw(n);
+        //0 hides the hri, otherwise n - 1 is the font
+        let n = *command.data.first().unwrap_or(&0u8);
+        match n {
+            0 | 48 => context.code2d.composite_hri = HumanReadableInterface::None,
+            _ => {
+                context.code2d.composite_hri = HumanReadableInterface::Below;
+                context.code2d.composite_font = Font::from_raw(n - 1);
+            }
+        }
     }
 }
 
@@ -1,18 +1,51 @@
+use crate::commands::barcode::{encode_barcode, BarcodeType};
+use crate::prelude::*;
 use crate::{command::*, context::*};
+use core::str::from_utf8;
 
 #[derive(Clone)]
 pub struct Handler;
 
 impl CommandHandler for Handler {
-    fn apply_context(&self, _command: &Command, _context: &mut Context) {
-        //TODO No support for this yet
-        //context.code2d.symbol_storage = Some(Cod2D)
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        //m (48), a (component) and b (symbol) are followed by the data
+        let a = *command.data.get(1).unwrap_or(&0u8);
+        let b = *command.data.get(2).unwrap_or(&0u8);
+        let data = command.data.get(3..).unwrap_or_default();
+        let text = from_utf8(data).unwrap_or("");
+
+        match a {
+            //Linear component
+            48 => {
+                let kind = match b {
+                    65 => BarcodeType::Ean8,
+                    66 => BarcodeType::Ean13,
+                    67 => BarcodeType::UpcA,
+                    68 | 69 => BarcodeType::UpcE,
+                    70 => BarcodeType::Gs1DatabarOmni,
+                    71 => BarcodeType::Gs1DatabarTruncated,
+                    74 => BarcodeType::Gs1DatabarLimited,
+                    75 => BarcodeType::Gs1DatabarExpanded,
+                    77 => BarcodeType::Gs1128,
+                    _ => BarcodeType::Unknown,
+                };
+
+                context.code2d.composite_linear = Some(
+                    encode_barcode(&kind, text)
+                        .map(|(points, text)| (kind, points, text))
+                        .map_err(|e| format!("Composite linear component {:?}: {}", text, e)),
+                );
+            }
+            //2D component, the cc type is selected automatically
+            49 => context.code2d.composite_2d = Some(data.to_vec()),
+            _ => {}
+        }
     }
 }
 
 pub fn new() -> Command {
     Command::new(
-        "PDF417 Store the Code2D data",
+        "Composite Store the Code2D data",
         vec![52, 80],
         CommandType::Context,
         DataType::Subcommand,
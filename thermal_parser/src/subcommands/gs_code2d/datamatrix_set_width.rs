@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, context::*};
 
 #[derive(Clone)]
@@ -12,7 +13,7 @@ impl CommandHandler for Handler {
 pub fn new() -> Command {
     Command::new(
         "Datamatrix Sets the dot Width",
-        vec![52, 67],
+        vec![54, 67],
         CommandType::Context,
         DataType::Subcommand,
         Box::new(Handler),
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, context::*, graphics::*};
 
 #[derive(Clone)]
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, context::*};
 
 #[derive(Clone)]
@@ -5,12 +6,12 @@ pub struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        let n = *command.data.get(0).unwrap_or(&48u8);
+        let n = *command.data.first().unwrap_or(&48u8);
 
         context.code2d.qr_error_correction = match n {
-            1 => QrErrorCorrection::M,
-            2 => QrErrorCorrection::Q,
-            3 => QrErrorCorrection::H,
+            1 | 49 => QrErrorCorrection::M,
+            2 | 50 => QrErrorCorrection::Q,
+            3 | 51 => QrErrorCorrection::H,
             _ => QrErrorCorrection::L,
         }
     }
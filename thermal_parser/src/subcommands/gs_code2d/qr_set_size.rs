@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, context::*};
 
 #[derive(Clone)]
@@ -5,7 +6,11 @@ pub struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        context.code2d.qr_size = *command.data.get(0).unwrap_or(&1u8);
+        //Module sizes outside of 1 - 16 dots are ignored
+        let n = *command.data.first().unwrap_or(&0u8);
+        if (1..=16).contains(&n) {
+            context.code2d.qr_size = n;
+        }
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::util::parse_u16;
 use crate::{command::*, context::*, graphics::*};
 
@@ -7,13 +8,14 @@ pub struct Handler;
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
         if command.data.len() < 8 {
-            println!("Missing parameters for command");
+            debug_println!("Missing parameters for command");
+            return;
         };
 
-        let _a = command.data.get(0).unwrap();
-        let kc1 = *command.data.get(1).unwrap();
-        let kc2 = *command.data.get(2).unwrap();
-        let b = command.data.get(3).unwrap(); //Number of color data
+        let _a = command.data[0];
+        let kc1 = command.data[1];
+        let kc2 = command.data[2];
+        let b = command.data[3]; //Number of color data
 
         let width = parse_u16(&command.data, 4) as u32;
         let height = parse_u16(&command.data, 6) as u32;
@@ -21,15 +23,14 @@ impl CommandHandler for Handler {
         let storage = ImageRefStorage::Disc;
         let image_ref = ImageRef { kc1, kc2, storage };
 
-        let graphics = GraphicsCommand::image_from_raster_bytes_multi_color(
+        let graphics = GraphicsCommand::image_from_column_bytes_multi_color(
             width,
             height,
             stretch,
-            *b,
+            b,
             &context.graphics.render_colors,
             ImageFlow::Block,
             &command.data[8..],
-            true,
         );
 
         context.graphics.stored_graphics.insert(image_ref, graphics);
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::util::parse_u16;
 use crate::{command::*, context::*, graphics::*};
 
@@ -7,14 +8,14 @@ pub struct Handler;
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
         if command.data.len() < 8 {
-            println!("To few parameters");
+            debug_println!("To few parameters");
             return;
         };
 
-        let _a = *command.data.get(0).unwrap();
-        let kc1 = *command.data.get(1).unwrap();
-        let kc2 = *command.data.get(2).unwrap();
-        let b = *command.data.get(3).unwrap(); //Number of colors
+        let _a = command.data[0];
+        let kc1 = command.data[1];
+        let kc2 = command.data[2];
+        let b = command.data[3]; //Number of colors
         let width = parse_u16(&command.data, 4) as u32;
         let height = parse_u16(&command.data, 6) as u32;
         let stretch = (1, 1);
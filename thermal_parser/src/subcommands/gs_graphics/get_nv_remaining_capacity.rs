@@ -1,4 +1,5 @@
 use crate::command::*;
+use crate::prelude::*;
 
 #[derive(Clone)]
 pub struct Handler;
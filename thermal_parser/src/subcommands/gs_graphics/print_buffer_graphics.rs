@@ -1,8 +1,10 @@
 /// This command prints everything that was added into the print buffer
 ///
-/// There is some uncertainty around the actual behavior of this command.
-///
-/// Our best guess is to merge pixels by darkening the pixels.
+/// Every color has one plane, the planes are printed in order of their
+/// color number with the later colors blended over the earlier ones,
+/// whatever order they were stored in. The buffer is cleared after it
+/// is printed.
+use crate::prelude::*;
 use crate::{command::*, context::*, graphics::*};
 
 #[derive(Clone)]
@@ -11,29 +13,31 @@ pub struct Handler;
 impl CommandHandler for Handler {
     fn get_graphics(&self, _command: &Command, context: &Context) -> Option<GraphicsCommand> {
         let mut layers = vec![];
+        let mut data_error = None;
 
-        if context.graphics.buffer_graphics.len() > 0 {
-            for g in context.graphics.buffer_graphics.iter() {
-                match g {
-                    GraphicsCommand::Error(_) => return Some(g.clone()),
-                    GraphicsCommand::Image(img) => layers.push(img.clone()),
-                    _ => {}
+        for g in context.graphics.buffer_graphics.values() {
+            match g {
+                GraphicsCommand::Error(_) => return Some(g.clone()),
+                GraphicsCommand::Image(img) => layers.push(img.clone()),
+                GraphicsCommand::PartialImage(img, error) => {
+                    data_error.get_or_insert(error.clone());
+                    layers.push(img.clone())
                 }
+                _ => {}
             }
+        }
 
-            if layers.is_empty() {
-                return None;
-            }
+        if layers.is_empty() {
+            return None;
+        }
 
-            if let Ok(merged) = merge_image_layers(&layers) {
-                return Some(GraphicsCommand::Image(merged));
-            } else {
-                return Some(GraphicsCommand::Error(
-                    "Could not merge image layers".to_string(),
-                ));
-            }
+        match (merge_image_layers(&layers), data_error) {
+            (Ok(merged), None) => Some(GraphicsCommand::Image(merged)),
+            (Ok(merged), Some(error)) => Some(GraphicsCommand::PartialImage(merged, error)),
+            (Err(_), _) => Some(GraphicsCommand::Error(
+                "Could not merge image layers".to_string(),
+            )),
         }
-        None
     }
 
     fn get_device_command(
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{command::*, context::*, graphics::*};
 
 #[derive(Clone)]
@@ -6,7 +7,7 @@ pub struct Handler;
 impl CommandHandler for Handler {
     fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
         if let Some(img_ref) = ImageRef::from_data(&command.data, ImageRefStorage::Ram) {
-            return context.graphics.stored_graphics.get(&img_ref).cloned();
+            return context.graphics.get_stored_graphic(&img_ref);
         }
         None
     }
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::util::parse_u16;
 use crate::{command::*, context::*, graphics::*};
 
@@ -6,34 +7,49 @@ pub struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        if command.data.len() < 4 {
-            context.graphics.buffer_graphics.push(GraphicsCommand::Error("Not enough parameters for buffer graphics".to_owned()));
+        if command.data.len() < 8 {
+            let c = command.data.get(3).copied().unwrap_or(0);
+            context.graphics.buffer_graphics.insert(
+                c,
+                GraphicsCommand::Error("Not enough parameters for buffer graphics".to_owned()),
+            );
             return;
         }
-        
-        let _a = command.data.get(0).unwrap();
-        let bx = command.data.get(1).unwrap();
-        let by = command.data.get(2).unwrap();
-        let c = command.data.get(3).unwrap();
+
+        let a = command.data[0];
+        let bx = command.data[1];
+        let by = command.data[2];
+        let c = command.data[3];
         let width = parse_u16(&command.data, 4) as u32;
         let height = parse_u16(&command.data, 6) as u32;
-        let stretch = (*bx, *by);
+
+        //The tone is 48 for monochrome or 52 for multiple tone, multiple
+        //tone planes have the same dot data as monochrome ones
+        if a != 48 && a != 52 {
+            context.graphics.buffer_graphics.insert(
+                c,
+                GraphicsCommand::Error(format!("Unknown tone {} for buffer graphics", a)),
+            );
+            return;
+        }
+
+        let stretch = (bx, by);
 
         let graphics = GraphicsCommand::image_from_raster_bytes_single_color(
             width,
             height,
             stretch,
-            context.graphics.render_colors.color_for_number(*c),
+            context.graphics.render_colors.color_for_number(c),
             ImageFlow::Block,
             &command.data[8..],
             true,
         );
 
-        context.graphics.buffer_graphics.push(graphics);
+        context.graphics.buffer_graphics.insert(c, graphics);
     }
 }
 
-//Deletes the Download graphics data defined by the key codes (kc1 and kc2).
+//Stores a plane of raster graphics in the print buffer, printed by Function 50
 pub fn new() -> Command {
     Command::new(
         "Store Print Buffer Graphics Raster Format",
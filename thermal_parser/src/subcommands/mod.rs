@@ -1,8 +1,9 @@
-use std::mem;
-use std::rc::Rc;
+use crate::prelude::*;
+use alloc::rc::Rc;
+use core::mem;
 
 use crate::text::TextSpan;
-use crate::util::{parse_u16, parse_u32};
+use crate::util::{parse_u16, parse_u32, reserve_data};
 use crate::{command::*, context::*, graphics::*};
 
 pub mod gs_code2d;
@@ -53,18 +54,16 @@ impl SubCommandHandler {
         let data_len = data.len();
 
         if data_len == 4 {
-            self.capacity = parse_u16(&data.to_vec(), 0) as u32;
-            self.capacity -= 2;
-            self.m = *data.get(2).unwrap();
-            self.subcommand_id = *data.get(3).unwrap();
+            self.capacity = (parse_u16(&data.to_vec(), 0) as u32).saturating_sub(2);
+            self.m = data[2];
+            self.subcommand_id = data[3];
         }
 
         if data_len == 6 {
-            //TODO why is this number too big
-            self.capacity = parse_u32(&data.to_vec(), 0);
-            self.capacity -= 2;
-            self.m = *data.get(4).unwrap();
-            self.subcommand_id = *data.get(5).unwrap();
+            //The length is four bytes, so the data can be larger than 64KB
+            self.capacity = parse_u32(&data.to_vec(), 0).saturating_sub(2);
+            self.m = data[4];
+            self.subcommand_id = data[5];
         }
 
         if self.use_m {
@@ -113,29 +112,23 @@ impl CommandHandler for SubCommandHandler {
     }
 
     fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
-        let mut data_len = data.len();
-
         if !self.accept_data {
             // Large subcommands use two extra bytes to determine data size
-            if self.is_large {
-                if data_len < 6 {
-                    data.push(byte);
-                    return true;
-                }
-                self.parse_meta(&data[0..6]);
-            } else {
-                if data_len < 4 {
-                    data.push(byte);
-                    return true;
-                }
-                self.parse_meta(&data[0..4]);
+            let meta_len = if self.is_large { 6 } else { 4 };
+            data.push(byte);
+
+            //The meta is parsed as soon as it is complete, a subcommand
+            //without data can be the last bytes of a stream
+            if data.len() == meta_len {
+                self.parse_meta(&data[0..meta_len]);
+                data.clear();
+                reserve_data(data, self.capacity as usize);
             }
-            data.clear();
-            data_len = 0;
+            return true;
         }
 
         //Accept data
-        if data_len < (self.capacity as usize) {
+        if data.len() < (self.capacity as usize) {
             data.push(byte);
             return true;
         }
@@ -144,7 +137,7 @@ impl CommandHandler for SubCommandHandler {
         if let Some(sub) = &mut self.subcommand {
             mem::swap(&mut sub.data, data);
         } else {
-            println!("Missing subcommand");
+            debug_println!("Missing subcommand");
         }
 
         //Stop accepting bytes
@@ -0,0 +1,455 @@
+//! Receipt Templates
+//!
+//! Builds ESC/POS bytes from a layout description, for test receipts
+//! that are written by people who don't want to write commands. The
+//! description is JSON, a list of lines (or an object with `lines` and
+//! the number of `columns` of the paper, 48 by default).
+//!
+//! - `{"text": "TOTAL", "right": "4.00"}` prints a line, `right` is
+//!   printed at the right edge of the same line. The style is set with
+//!   `align` (left, center or right), `bold`, `underline`, `width` and
+//!   `height` (1 to 8)
+//! - `{"barcode": "CODE128", "data": "1234", "hri": true}` prints a
+//!   barcode, the symbologies are UPC-A, UPC-E, EAN13, EAN8, CODE39,
+//!   ITF, CODABAR, CODE93 and CODE128
+//! - `{"qr": "https://example.com", "size": 6}` prints a QR code
+//! - `{"image": ["#..#", ".##."]}` prints an image, `#` is ink
+//! - `{"feed": 3}` feeds lines and `{"cut": true}` (or `"partial"`)
+//!   cuts the paper
+//!
+//! The parameters of every command are checked by its command handler
+//! the way the parser reads them, so the bytes parse back to the
+//! commands the template describes.
+//!
+//! ```
+//! use thermal_parser::template::encode_template;
+//!
+//! let bytes = encode_template(r#"[{"text": "HELLO", "align": "center"}, {"cut": true}]"#).unwrap();
+//! assert_eq!(bytes, b"\x1b@\x1ba\x01HELLO\n\x1ba\x00\x1dVA\x00");
+//! ```
+
+use crate::command::Command;
+use crate::commands::*;
+use crate::decoder::get_codepage;
+use crate::prelude::*;
+
+const DEFAULT_COLUMNS: usize = 48;
+
+/// The ESC/POS bytes of a JSON template
+pub fn encode_template(template: &str) -> Result<Vec<u8>, String> {
+    let value = Json::parse(template)?;
+
+    let (lines, columns) = match &value {
+        Json::Array(lines) => (lines.as_slice(), DEFAULT_COLUMNS),
+        Json::Object(_) => (
+            value.get("lines").and_then(Json::as_array).unwrap_or(&[]),
+            value
+                .get("columns")
+                .and_then(Json::as_number)
+                .map_or(DEFAULT_COLUMNS, |n| n as usize),
+        ),
+        _ => return Err("A template is a list of lines".to_string()),
+    };
+
+    let mut bytes = vec![];
+    encode(&mut bytes, initialize::new(), &[]);
+
+    for (index, line) in lines.iter().enumerate() {
+        encode_line(&mut bytes, line, columns).map_err(|e| format!("Line {}: {}", index + 1, e))?;
+    }
+
+    Ok(bytes)
+}
+
+//The bytes of the command with its parameters. The parameters are
+//pushed like the parser pushes them, so they frame the command the
+//same way when the bytes are parsed
+fn encode(bytes: &mut Vec<u8>, mut command: Command, params: &[u8]) {
+    let taken = params
+        .iter()
+        .take_while(|byte| command.push(**byte))
+        .count();
+    debug_assert_eq!(
+        taken,
+        params.len(),
+        "{} takes fewer parameters",
+        command.name
+    );
+
+    bytes.extend(command.commands.iter());
+    bytes.extend(params);
+}
+
+fn encode_line(bytes: &mut Vec<u8>, line: &Json, columns: usize) -> Result<(), String> {
+    let align = match line.get("align").and_then(Json::as_str) {
+        None | Some("left") => 0,
+        Some("center") => 1,
+        Some("right") => 2,
+        Some(other) => return Err(format!("Unknown alignment {}", other)),
+    };
+    let flag = |name| line.get(name).and_then(Json::as_bool).unwrap_or(false);
+    let bold = flag("bold");
+    let underline = flag("underline");
+    let size = |name| {
+        let n = line.get(name).and_then(Json::as_number).unwrap_or(1.0) as u8;
+        n.clamp(1, 8) - 1
+    };
+    let (width, height) = (size("width"), size("height"));
+
+    if align != 0 {
+        encode(bytes, set_justification::new(), &[align]);
+    }
+    if bold {
+        encode(bytes, set_emphasis::new(), &[1]);
+    }
+    if underline {
+        encode(bytes, set_underline::new(), &[1]);
+    }
+    if width > 0 || height > 0 {
+        encode(bytes, set_character_size::new(), &[width << 4 | height]);
+    }
+
+    if let Some(text) = line.get("text").and_then(Json::as_str) {
+        let mut text = text.to_string();
+
+        if let Some(right) = line.get("right").and_then(Json::as_str) {
+            let used = text.chars().count() + right.chars().count();
+            let line_columns = columns / (width as usize + 1);
+            text.push_str(&" ".repeat(line_columns.saturating_sub(used).max(1)));
+            text.push_str(right);
+        }
+
+        encode(bytes, text::new(), &encode_text(&text));
+        encode(bytes, linefeed::new(), &[]);
+    } else if let Some(symbology) = line.get("barcode").and_then(Json::as_str) {
+        encode_barcode(bytes, line, symbology)?;
+    } else if let Some(data) = line.get("qr").and_then(Json::as_str) {
+        let size = line.get("size").and_then(Json::as_number).unwrap_or(6.0) as u8;
+        encode_qr(bytes, data, size.clamp(1, 16));
+    } else if let Some(rows) = line.get("image").and_then(Json::as_array) {
+        encode_image(bytes, rows)?;
+    } else if let Some(lines) = line.get("feed").and_then(Json::as_number) {
+        encode(bytes, print_and_feed_lines::new(), &[lines as u8]);
+    } else if let Some(cut) = line.get("cut") {
+        match (cut.as_bool(), cut.as_str()) {
+            (Some(false), _) => {}
+            (_, Some("partial")) => encode(bytes, feed_and_cut::new(), &[66, 0]),
+            _ => encode(bytes, feed_and_cut::new(), &[65, 0]),
+        }
+    } else {
+        return Err("Expected text, barcode, qr, image, feed or cut".to_string());
+    }
+
+    //Styles only apply to their line
+    if width > 0 || height > 0 {
+        encode(bytes, set_character_size::new(), &[0]);
+    }
+    if underline {
+        encode(bytes, set_underline::new(), &[0]);
+    }
+    if bold {
+        encode(bytes, set_emphasis::new(), &[0]);
+    }
+    if align != 0 {
+        encode(bytes, set_justification::new(), &[0]);
+    }
+
+    Ok(())
+}
+
+//Text in the power on code page, characters it doesn't have are ?
+fn encode_text(text: &str) -> Vec<u8> {
+    let codepage = get_codepage(0, 0);
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            _ => codepage.encode(c).unwrap_or(b'?'),
+        })
+        .collect()
+}
+
+fn encode_barcode(bytes: &mut Vec<u8>, line: &Json, symbology: &str) -> Result<(), String> {
+    let kind = match symbology {
+        "UPC-A" => 65,
+        "UPC-E" => 66,
+        "EAN13" => 67,
+        "EAN8" => 68,
+        "CODE39" => 69,
+        "ITF" => 70,
+        "CODABAR" => 71,
+        "CODE93" => 72,
+        "CODE128" => 73,
+        _ => return Err(format!("Unknown barcode {}", symbology)),
+    };
+    let mut data = line
+        .get("data")
+        .and_then(Json::as_str)
+        .ok_or("A barcode needs data")?
+        .as_bytes()
+        .to_vec();
+
+    //Code 128 data starts with the code set
+    if kind == 73 && !data.starts_with(b"{") {
+        data.splice(0..0, *b"{B");
+    }
+    if data.len() > 255 {
+        return Err("Barcode data is longer than 255 bytes".to_string());
+    }
+
+    let hri = line.get("hri").and_then(Json::as_bool).unwrap_or(false);
+    encode(bytes, set_barcode_hri::new(), &[if hri { 2 } else { 0 }]);
+
+    let mut params = vec![kind, data.len() as u8];
+    params.extend(data);
+    encode(bytes, barcode::new(), &params);
+    Ok(())
+}
+
+fn encode_qr(bytes: &mut Vec<u8>, data: &str, size: u8) {
+    //Model 2, module size, error correction level M
+    encode(bytes, code_2d::new(), &[4, 0, 49, 65, 50, 0]);
+    encode(bytes, code_2d::new(), &[3, 0, 49, 67, size]);
+    encode(bytes, code_2d::new(), &[3, 0, 49, 69, 49]);
+
+    let len = data.len() + 3;
+    let mut params = vec![(len % 256) as u8, (len / 256) as u8, 49, 80, 48];
+    params.extend(data.as_bytes());
+    encode(bytes, code_2d::new(), &params);
+    encode(bytes, code_2d::new(), &[3, 0, 49, 81, 48]);
+}
+
+fn encode_image(bytes: &mut Vec<u8>, rows: &[Json]) -> Result<(), String> {
+    let rows: Vec<&str> = rows
+        .iter()
+        .map(|row| row.as_str().ok_or("Image rows are strings"))
+        .collect::<Result<_, _>>()?;
+    let width = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+    let width_bytes = width.div_ceil(8);
+
+    if width == 0 || width_bytes > 0xFFFF || rows.len() > 0xFFFF {
+        return Err("The image is empty or too large".to_string());
+    }
+
+    let mut params = vec![
+        0,
+        (width_bytes % 256) as u8,
+        (width_bytes / 256) as u8,
+        (rows.len() % 256) as u8,
+        (rows.len() / 256) as u8,
+    ];
+
+    for row in rows {
+        let mut packed = vec![0u8; width_bytes];
+        for (x, c) in row.chars().enumerate() {
+            if c == '#' {
+                packed[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        params.extend(packed);
+    }
+
+    encode(bytes, raster_bit_image::new(), &params);
+    Ok(())
+}
+
+/// The parts of JSON that templates are written in
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn parse(text: &str) -> Result<Json, String> {
+        let mut reader = Reader {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+
+        match reader.peek() {
+            Some(c) => Err(format!("Unexpected {} after the template", c)),
+            None => Ok(value),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+struct Reader {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Result<char, String> {
+        let c = self.peek().ok_or("The template ended early")?;
+        self.position += 1;
+        Ok(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next()? {
+            c if c == expected => Ok(()),
+            c => Err(format!("Expected {} but found {}", expected, c)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+
+        match self.peek().ok_or("The template ended early")? {
+            '{' => self.object(),
+            '[' => self.array(),
+            '"' => Ok(Json::String(self.string()?)),
+            't' => self.word("true", Json::Bool(true)),
+            'f' => self.word("false", Json::Bool(false)),
+            'n' => self.word("null", Json::Null),
+            _ => self.number(),
+        }
+    }
+
+    fn word(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for c in word.chars() {
+            self.expect(c)?;
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        {
+            self.position += 1;
+        }
+
+        let number: String = self.chars[start..self.position].iter().collect();
+        number
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| format!("Expected a value but found {:?}", number))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.next()? {
+                '"' => return Ok(string),
+                '\\' => match self.next()? {
+                    'n' => string.push('\n'),
+                    't' => string.push('\t'),
+                    'r' => string.push('\r'),
+                    'u' => {
+                        let hex: String = (0..4).map(|_| self.next()).collect::<Result<_, _>>()?;
+                        let code = u32::from_str_radix(&hex, 16).map_err(|_| "Bad \\u escape")?;
+                        string.push(char::from_u32(code).unwrap_or('?'));
+                    }
+                    c => string.push(c),
+                },
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => continue,
+                ']' => return Ok(Json::Array(values)),
+                c => return Err(format!("Expected , or ] but found {}", c)),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut fields = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => continue,
+                '}' => return Ok(Json::Object(fields)),
+                c => return Err(format!("Expected , or }} but found {}", c)),
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::context::{Context, Font, TextJustify, TextStrikethrough, TextUnderline};
 use crate::graphics::RGBA;
-use std::fmt;
+use crate::prelude::*;
+use core::fmt;
+use core::ops::Range;
 
 #[derive(Clone)]
 pub struct TextSpan {
@@ -12,8 +14,13 @@ pub struct TextSpan {
     pub text: String,
     pub bold: bool,
     pub italic: bool,
+    /// Thickness of an underline in pixels, 0 for no underline
     pub underline: u32,
+    /// Thickness of a strikethrough in pixels, 0 for no strikethrough
     pub strikethrough: u32,
+    /// Double underlines are drawn as two lines of the thickness
+    pub double_underline: bool,
+    pub double_strikethrough: bool,
     pub stretch_width: f32,
     pub stretch_height: f32,
     pub inverted: bool,
@@ -30,22 +37,27 @@ pub struct Dimensions {
     pub y: u32,
     pub w: u32,
     pub h: u32,
+    /// How far each character moves x in dots, output renderers
+    /// can place characters with these instead of their own font
+    /// metrics so lines are as wide as in the layout
+    pub advances: Vec<u32>,
 }
 
 impl TextSpan {
     pub fn new(text: String, context: &Context) -> Self {
         let style = &context.text;
 
+        //Lines get thicker with the character height, like the glyph strokes
+        let thickness = context.points_to_pixels(1.0).max(1) * style.height_mult.max(1) as u32;
+
         let underline = match style.underline {
-            TextUnderline::On => context.points_to_pixels(1.0) as u32,
-            TextUnderline::Double => context.points_to_pixels(2.0) as u32,
-            _ => 0,
+            TextUnderline::Off => 0,
+            _ => thickness,
         };
 
         let strikethrough = match style.strikethrough {
-            TextStrikethrough::On => context.points_to_pixels(1.0) as u32,
-            TextStrikethrough::Double => context.points_to_pixels(2.0) as u32,
-            _ => 0,
+            TextStrikethrough::Off => 0,
+            _ => thickness,
         };
 
         Self {
@@ -59,6 +71,8 @@ impl TextSpan {
             italic: style.italic,
             underline,
             strikethrough,
+            double_underline: style.underline == TextUnderline::Double,
+            double_strikethrough: style.strikethrough == TextStrikethrough::Double,
             stretch_width: style.width_mult as f32,
             stretch_height: style.height_mult as f32,
             inverted: style.invert,
@@ -71,8 +85,28 @@ impl TextSpan {
     }
 
     pub fn new_for_barcode(text: String, context: &Context) -> Self {
+        TextSpan::new_for_hri(text, &context.barcode.font, context)
+    }
+
+    /// Human readable text is printed in the hri font at its normal
+    /// size, character styles like emphasis or scaling don't apply
+    pub fn new_for_hri(text: String, font: &Font, context: &Context) -> Self {
+        let (width, height) = context.text.font_metrics.size(font);
         let mut span = TextSpan::new(text, context);
-        span.font = context.barcode.font.clone();
+        span.font = font.clone();
+        span.base_character_width = width as u32;
+        span.base_character_height = height as u32;
+        span.character_width = width as u32;
+        span.character_height = height as u32;
+        span.stretch_width = 1.0;
+        span.stretch_height = 1.0;
+        span.bold = false;
+        span.italic = false;
+        span.underline = 0;
+        span.strikethrough = 0;
+        span.double_underline = false;
+        span.double_strikethrough = false;
+        span.inverted = false;
         span
     }
 
@@ -82,6 +116,7 @@ impl TextSpan {
             y: context.get_y(),
             w: self.get_width(),
             h: self.character_height,
+            advances: self.get_advances(),
         });
     }
 
@@ -89,6 +124,11 @@ impl TextSpan {
         self.character_count() * self.character_width
     }
 
+    //Fonts are monospaced, every character moves the scaled width
+    pub fn get_advances(&self) -> Vec<u32> {
+        vec![self.character_width; self.character_count() as usize]
+    }
+
     pub fn character_count(&self) -> u32 {
         self.text.chars().count() as u32
     }
@@ -105,6 +145,8 @@ impl TextSpan {
             italic: self.italic,
             underline: self.underline,
             strikethrough: self.strikethrough,
+            double_underline: self.double_underline,
+            double_strikethrough: self.double_strikethrough,
             stretch_width: self.stretch_width,
             stretch_height: self.stretch_height,
             inverted: self.inverted,
@@ -119,71 +161,82 @@ impl TextSpan {
     }
 
     pub fn break_apart(&self, first_line_length: usize, line_length: usize) -> Vec<TextSpan> {
-        if line_length == 0 {
-            panic!("break_apart called with zero line length");
-        }
-
-        let chars: Vec<char> = self.text.chars().collect();
-        let mut result = Vec::new();
-        let mut index = 0;
-
-        // First split, which is often smaller
-        if first_line_length > 0 && chars.len() > 0 {
-            let first_chunk = chars
-                .iter()
-                .take(first_line_length)
-                .cloned()
-                .collect::<String>();
-            result.push(self.clone_with(first_chunk));
-            index += first_line_length;
-        }
-
-        //We are always expecting a first line value, even if there isn't one
-        if index == 0 {
-            result.push(self.clone_with("".to_string()));
-        }
-
-        // Split the rest of the string into chunks
-        while index < chars.len() {
-            let chunk = chars
-                .iter()
-                .skip(index)
-                .take(line_length)
-                .cloned()
-                .collect::<String>();
-            result.push(self.clone_with(chunk));
-            index += line_length;
-        }
-
-        result
+        break_ranges(&self.text, first_line_length, line_length)
+            .into_iter()
+            .map(|range| self.clone_with(self.text[range].to_string()))
+            .collect()
     }
 
     pub fn break_into_words(&self) -> Vec<TextSpan> {
-        let mut words = Vec::new();
-        let mut current_word = String::new();
+        word_ranges(&self.text)
+            .map(|range| self.clone_with(self.text[range].to_string()))
+            .collect()
+    }
+}
+
+/// Byte ranges of the words of the text. A word ends after a whitespace
+/// character, so every word keeps the whitespace that follows it
+pub fn word_ranges(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut chars = text.char_indices();
+    let mut start = 0;
 
-        for c in self.text.chars() {
+    core::iter::from_fn(move || {
+        for (index, c) in chars.by_ref() {
             if c.is_whitespace() {
-                // If we encounter a whitespace character, we add it to the current word
-                current_word.push(c);
-
-                if !current_word.is_empty() {
-                    words.push(self.clone_with(current_word.clone()));
-                    current_word.clear();
-                }
-            } else {
-                // Add regular characters to the current word
-                current_word.push(c);
+                let word = start..index + c.len_utf8();
+                start = word.end;
+                return Some(word);
             }
         }
 
-        // Push the last word if there is any
-        if !current_word.is_empty() {
-            words.push(self.clone_with(current_word));
+        if start < text.len() {
+            let word = start..text.len();
+            start = text.len();
+            return Some(word);
         }
 
-        words
+        None
+    })
+}
+
+/// Byte ranges of the parts of a text that is too long for a line, the
+/// first part has first_line_length characters and can be empty, the
+/// other parts have line_length characters
+pub fn break_ranges(text: &str, first_line_length: usize, line_length: usize) -> Vec<Range<usize>> {
+    if line_length == 0 {
+        panic!("break_apart called with zero line length");
+    }
+
+    //Byte offset of every character and of the end of the text
+    let offsets: Vec<usize> = text
+        .char_indices()
+        .map(|(index, _)| index)
+        .chain(core::iter::once(text.len()))
+        .collect();
+    let char_count = offsets.len() - 1;
+    let offset = |chars: usize| offsets[chars.min(char_count)];
+
+    let mut result = Vec::new();
+    let mut index = 0;
+
+    // First split, which is often smaller
+    if first_line_length > 0 && char_count > 0 {
+        result.push(0..offset(first_line_length));
+        index += first_line_length;
+    }
+
+    //We are always expecting a first line value, even if there isn't one
+    if index == 0 {
+        result.push(0..0);
     }
+
+    // Split the rest of the string into chunks
+    while index < char_count {
+        result.push(offset(index)..offset(index + line_length));
+        index += line_length;
+    }
+
+    result
 }
 
 impl fmt::Debug for TextSpan {
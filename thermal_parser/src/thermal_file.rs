@@ -13,7 +13,7 @@
 //! "Hex values look like this ->" 0xFF
 //! "Decimal Values look like this -> " 23
 //! "There are a few constant values that can also be used"
-//! NUL ESC HT LF FF CR GS FS DLE CAN "Are all valid"```
+//! NUL BEL ESC HT LF FF CR GS FS DLE CAN RS "Are all valid"```
 //!
 //!
 //! Some examples:
@@ -38,6 +38,7 @@
 use crate::command::{Command, CommandType};
 use crate::constants;
 use crate::constants::*;
+use crate::prelude::*;
 
 pub static COMMENT_PREFIX: &str = "'//";
 pub static HEX_PREFIX: &str = "0x";
@@ -59,6 +60,7 @@ pub fn parse_str(text: &str) -> Vec<u8> {
         for token in tokens {
             match token {
                 "NUL" => parsed.push(NUL),
+                "BEL" => parsed.push(BEL),
                 "ESC" => parsed.push(ESC),
                 "HT" => parsed.push(HT),
                 "LF" => parsed.push(LF),
@@ -68,6 +70,7 @@ pub fn parse_str(text: &str) -> Vec<u8> {
                 "FS" => parsed.push(FS),
                 "DLE" => parsed.push(DLE),
                 "CAN" => parsed.push(CAN),
+                "RS" => parsed.push(RS),
                 _ => {
                     //Hex 0xFF for example
                     if token.starts_with(HEX_PREFIX) {
@@ -263,6 +266,7 @@ pub fn try_const(byte: &u8) -> String {
         0x0D => "CR".to_string(),
         0x10 => "DLE".to_string(),
         0x18 => "CAN".to_string(),
+        0x1E => "RS".to_string(),
         _ => format!("0x{:02X}", byte),
     }
 }
@@ -1,3 +1,5 @@
+use crate::prelude::*;
+
 pub fn parse_u16(bytes: &Vec<u8>, index: usize) -> u16 {
     let low = bytes.get(index).unwrap_or(&0);
     let high = bytes.get(index + 1).unwrap_or(&0);
@@ -42,3 +44,13 @@ pub fn bitflags_msb(byte: &u8) -> BitFlags {
         (byte & (1 << 0)) != 0,
     )
 }
+
+//Sizes come from the job and can be anything up to 4gb, data beyond
+//this grows as bytes arrive
+const MAX_RESERVE: usize = 4 * 1024 * 1024;
+
+/// Makes room for data of a known size up front, so large images
+/// are not copied every time the data outgrows its allocation
+pub fn reserve_data(data: &mut Vec<u8>, capacity: usize) {
+    data.reserve(capacity.min(MAX_RESERVE));
+}
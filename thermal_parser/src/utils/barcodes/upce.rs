@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use barcoders::sym::ean13::{ENCODINGS, LEFT_GUARD, MIDDLE_GUARD};
 
 pub const UPCE_RIGHT_GUARD: [u8; 1] = [1];
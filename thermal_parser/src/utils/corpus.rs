@@ -0,0 +1,348 @@
+//! Corpus Generator
+//!
+//! Generates random but realistic ESC/POS jobs for benchmarks, stress
+//! tests and as a seed corpus for fuzzing, so no real receipts with
+//! customer data have to be shared.
+//!
+//! Jobs look like the receipts of a store: a header, item lines with
+//! prices in different styles and code pages, totals, barcodes, QR
+//! codes, images, page mode tickets and a cut. All names and numbers
+//! are made up. The same seed always generates the same jobs.
+//!
+//! ```
+//! use thermal_parser::utils::corpus::CorpusGenerator;
+//!
+//! for job in CorpusGenerator::new(42).take(10) {
+//!     let commands = thermal_parser::parse_esc_pos(&job);
+//!     assert!(!commands.is_empty());
+//! }
+//! ```
+
+use crate::constants::{DLE, ESC, FF, GS, LF};
+use crate::prelude::*;
+use crate::utils::dither::{raster_bit_image, Dithering};
+
+const STORES: [&str; 6] = [
+    "CORNER MARKET",
+    "BLUE DOOR CAFE",
+    "HARBOR HARDWARE",
+    "NORTH STAR PHARMACY",
+    "GREEN LEAF GROCERY",
+    "CITY BOOKS",
+];
+
+const ITEMS: [&str; 16] = [
+    "COFFEE",
+    "BAGEL",
+    "ORANGE JUICE",
+    "SCREWDRIVER SET",
+    "PAPER TOWELS",
+    "NOTEBOOK",
+    "BANANAS",
+    "SPARKLING WATER",
+    "BATTERIES AA",
+    "TOOTHPASTE",
+    "GARDEN HOSE",
+    "CHOCOLATE BAR",
+    "OLIVE OIL",
+    "DISH SOAP",
+    "LIGHT BULB",
+    "GREETING CARD",
+];
+
+//Code tables (ESC t) that have a decoder, PC437, PC850, WPC1252 and PC866
+const CODE_TABLES: [u8; 4] = [0, 2, 16, 17];
+
+/// What the generated jobs contain
+#[derive(Clone, Debug)]
+pub struct CorpusOptions {
+    /// Item lines are between 1 and this many
+    pub max_items: usize,
+    pub barcodes: bool,
+    pub images: bool,
+    pub page_mode: bool,
+    /// Switch code tables and use characters above 0x7F
+    pub code_tables: bool,
+}
+
+impl Default for CorpusOptions {
+    fn default() -> Self {
+        CorpusOptions {
+            max_items: 12,
+            barcodes: true,
+            images: true,
+            page_mode: true,
+            code_tables: true,
+        }
+    }
+}
+
+/// Generates ESC/POS jobs from a seed, as an iterator it never ends
+pub struct CorpusGenerator {
+    state: u64,
+    pub options: CorpusOptions,
+}
+
+impl CorpusGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self::new_with_options(seed, CorpusOptions::default())
+    }
+
+    pub fn new_with_options(seed: u64, options: CorpusOptions) -> Self {
+        Self {
+            state: seed,
+            options,
+        }
+    }
+
+    //SplitMix64, small and the same on every platform
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    //A number from 0 up to but not including max
+    fn below(&mut self, max: usize) -> usize {
+        (self.next_u64() % max.max(1) as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a>(&mut self, from: &[&'a str]) -> &'a str {
+        from[self.below(from.len())]
+    }
+
+    /// Generates the next job
+    pub fn job(&mut self) -> Vec<u8> {
+        let mut job = vec![ESC, b'@'];
+
+        self.header(&mut job);
+
+        let mut total = 0;
+        for _ in 0..1 + self.below(self.options.max_items) {
+            total += self.item(&mut job);
+        }
+
+        self.totals(&mut job, total);
+
+        if self.options.barcodes && self.chance(70) {
+            self.barcode(&mut job);
+        }
+        if self.options.barcodes && self.chance(40) {
+            self.qr_code(&mut job);
+        }
+        if self.options.images && self.chance(40) {
+            self.image(&mut job);
+        }
+        if self.options.page_mode && self.chance(25) {
+            self.page(&mut job);
+        }
+
+        //Feed and partial cut
+        job.extend([GS, b'V', 66, self.below(6) as u8]);
+        job
+    }
+
+    fn header(&mut self, job: &mut Vec<u8>) {
+        let store = self.pick(&STORES);
+
+        //Centered, large or emphasized
+        job.extend([ESC, b'a', 1]);
+        if self.chance(50) {
+            job.extend([GS, b'!', 0x11]);
+        } else {
+            job.extend([ESC, b'E', 1]);
+        }
+        job.extend(store.as_bytes());
+        job.push(LF);
+        job.extend([GS, b'!', 0, ESC, b'E', 0]);
+
+        let street = 100 + self.below(9900);
+        job.extend(format!("{} MAIN ST", street).as_bytes());
+        job.push(LF);
+        job.extend([ESC, b'a', 0, LF]);
+    }
+
+    //Writes an item line and returns its price in cents
+    fn item(&mut self, job: &mut Vec<u8>) -> usize {
+        let name = self.pick(&ITEMS);
+        let price = 50 + self.below(9950);
+
+        if self.options.code_tables && self.chance(20) {
+            job.extend([ESC, b't', CODE_TABLES[self.below(CODE_TABLES.len())]]);
+        }
+
+        match self.below(6) {
+            0 => job.extend([ESC, b'E', 1]),
+            1 => job.extend([ESC, b'-', 1 + self.below(2) as u8]),
+            2 => job.extend([ESC, b'M', 1]),
+            3 => job.extend([GS, b'B', 1]),
+            _ => {}
+        }
+
+        let quantity = 1 + self.below(4);
+        let mut line: Vec<u8> = format!("{} {}", quantity, name).into_bytes();
+
+        //Accented or box drawing characters of the code table
+        if self.options.code_tables && self.chance(20) {
+            line.push(b' ');
+            line.push(0x80 + self.below(0x80) as u8);
+        }
+
+        let amount = cents(price * quantity);
+        let padding = 42usize.saturating_sub(line.len() + amount.len()).max(1);
+        line.extend(core::iter::repeat_n(b' ', padding));
+        line.extend(amount.as_bytes());
+
+        job.extend(line);
+        job.push(LF);
+
+        //Back to the default style and code table
+        job.extend([ESC, b'E', 0, ESC, b'-', 0, ESC, b'M', 0, GS, b'B', 0]);
+        job.extend([ESC, b't', 0]);
+
+        price * quantity
+    }
+
+    fn totals(&mut self, job: &mut Vec<u8>, total: usize) {
+        let tax = total * (5 + self.below(5)) / 100;
+
+        job.extend(b"------------------------------------------");
+        job.push(LF);
+        for (label, amount) in [("SUBTOTAL", total), ("TAX", tax)] {
+            job.extend(format!("{:<30}{:>12}", label, cents(amount)).as_bytes());
+            job.push(LF);
+        }
+
+        job.extend([ESC, b'E', 1, GS, b'!', 0x01]);
+        job.extend(format!("{:<30}{:>12}", "TOTAL", cents(total + tax)).as_bytes());
+        job.extend([LF, GS, b'!', 0, ESC, b'E', 0, LF]);
+    }
+
+    fn barcode(&mut self, job: &mut Vec<u8>) {
+        //Height, module width and human readable text below
+        job.extend([ESC, b'a', 1]);
+        job.extend([GS, b'h', 40 + self.below(80) as u8]);
+        job.extend([GS, b'w', 2 + self.below(2) as u8]);
+        job.extend([GS, b'H', self.below(4) as u8]);
+
+        let digits = |generator: &mut Self, count: usize| -> Vec<u8> {
+            (0..count)
+                .map(|_| b'0' + generator.below(10) as u8)
+                .collect()
+        };
+
+        match self.below(3) {
+            //EAN13, the check digit is calculated by the printer
+            0 => {
+                let data = digits(self, 12);
+                job.extend([GS, b'k', 67, data.len() as u8]);
+                job.extend(data);
+            }
+            //CODE39
+            1 => {
+                let count = 4 + self.below(8);
+                let data = digits(self, count);
+                job.extend([GS, b'k', 69, data.len() as u8]);
+                job.extend(data);
+            }
+            //CODE128 with code set B
+            _ => {
+                let mut data = b"{B".to_vec();
+                data.extend(format!("TX{}", self.below(1_000_000)).as_bytes());
+                job.extend([GS, b'k', 73, data.len() as u8]);
+                job.extend(data);
+            }
+        }
+
+        job.extend([LF, ESC, b'a', 0]);
+    }
+
+    fn qr_code(&mut self, job: &mut Vec<u8>) {
+        let data = format!("https://example.com/r/{}", self.below(10_000_000));
+        let store_len = data.len() + 3;
+
+        job.extend([ESC, b'a', 1]);
+        job.extend([GS, b'(', b'k', 3, 0, 49, 67, 3 + self.below(6) as u8]);
+        job.extend([GS, b'(', b'k', 3, 0, 49, 69, 48 + self.below(4) as u8]);
+        job.extend([
+            GS,
+            b'(',
+            b'k',
+            (store_len & 0xFF) as u8,
+            (store_len >> 8) as u8,
+            49,
+            80,
+            48,
+        ]);
+        job.extend(data.as_bytes());
+        job.extend([GS, b'(', b'k', 3, 0, 49, 81, 48]);
+        job.extend([LF, ESC, b'a', 0]);
+    }
+
+    //A gradient logo, dithered like a picture would be
+    fn image(&mut self, job: &mut Vec<u8>) {
+        let width = 32 + self.below(12) as u32 * 16;
+        let height = 16 + self.below(48) as u32;
+        let gray: Vec<u8> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                ((x + y) * 255 / (width + height)) as u8
+            })
+            .collect();
+
+        let dithering = match self.below(3) {
+            0 => Dithering::Threshold(128),
+            1 => Dithering::Bayer,
+            _ => Dithering::FloydSteinberg,
+        };
+
+        let mut image = raster_bit_image(&gray, width, height, dithering);
+
+        //Printers execute real-time commands (DLE EOT, DLE ENQ and DLE DC4)
+        //even inside of image data, a few pixels are cleared to avoid them
+        for index in 8..image.len().saturating_sub(1) {
+            if image[index] == DLE && matches!(image[index + 1], 0x04 | 0x05 | 0x14) {
+                image[index] = 0;
+            }
+        }
+
+        job.extend([ESC, b'a', self.below(3) as u8]);
+        job.extend(image);
+        job.extend([ESC, b'a', 0, LF]);
+    }
+
+    //A ticket printed in page mode with a direction
+    fn page(&mut self, job: &mut Vec<u8>) {
+        let width: u16 = 256 + self.below(256) as u16;
+        let height: u16 = 120 + self.below(200) as u16;
+        let [w_low, w_high] = width.to_le_bytes();
+        let [h_low, h_high] = height.to_le_bytes();
+
+        job.extend([ESC, b'L']);
+        job.extend([ESC, b'W', 0, 0, 0, 0, w_low, w_high, h_low, h_high]);
+        job.extend([ESC, b'T', self.below(4) as u8]);
+        job.extend(b"TICKET");
+        job.push(LF);
+        job.extend(format!("NO. {}", self.below(100_000)).as_bytes());
+        job.push(LF);
+        job.push(FF);
+    }
+}
+
+fn cents(amount: usize) -> String {
+    format!("{}.{:02}", amount / 100, amount % 100)
+}
+
+impl Iterator for CorpusGenerator {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.job())
+    }
+}
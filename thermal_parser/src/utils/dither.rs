@@ -0,0 +1,143 @@
+//! Dithering
+//!
+//! Thermal printers can only print dots or no dots, so grayscale
+//! or color pictures have to be turned into monochrome raster data
+//! before they can be sent to the printer.
+//!
+//! A plain threshold works for logos and line art, photos need
+//! dithering to keep their shading. The output is packed raster
+//! data (most significant bit first, rows padded to whole bytes)
+//! which is what GS v 0 and GS ( L expect.
+
+use crate::constants::GS;
+use crate::graphics::RGBA;
+use crate::prelude::*;
+
+/// The way gray levels are turned into dots
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dithering {
+    /// Pixels darker than the level are printed
+    Threshold(u8),
+    /// Error diffusion, best for photos
+    FloydSteinberg,
+    /// Ordered 4x4 pattern, keeps flat areas regular
+    Bayer,
+}
+
+impl Default for Dithering {
+    fn default() -> Self {
+        Dithering::Threshold(128)
+    }
+}
+
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Converts rgba pixels to gray levels, transparent pixels are paper (white)
+pub fn rgba_to_gray(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks_exact(4)
+        .map(|p| {
+            RGBA {
+                r: p[0],
+                g: p[1],
+                b: p[2],
+                a: p[3],
+            }
+            .as_gray()
+        })
+        .collect()
+}
+
+/// Converts gray levels (0 is black) to one dot per pixel, true is printed
+pub fn dither(gray: &[u8], width: u32, height: u32, dithering: Dithering) -> Vec<bool> {
+    let (w, h) = (width as usize, height as usize);
+    let mut dots = vec![false; w * h];
+
+    if gray.len() < w * h {
+        return dots;
+    }
+
+    match dithering {
+        Dithering::Threshold(level) => {
+            for (dot, value) in dots.iter_mut().zip(gray) {
+                *dot = *value < level;
+            }
+        }
+        Dithering::Bayer => {
+            for y in 0..h {
+                for x in 0..w {
+                    let level = BAYER_4X4[y % 4][x % 4] as u32 * 16 + 8;
+                    dots[y * w + x] = (gray[y * w + x] as u32) < level;
+                }
+            }
+        }
+        Dithering::FloydSteinberg => {
+            let mut values: Vec<i32> = gray[..w * h].iter().map(|v| *v as i32).collect();
+
+            for y in 0..h {
+                for x in 0..w {
+                    let i = y * w + x;
+                    let dark = values[i] < 128;
+                    let error = values[i] - if dark { 0 } else { 255 };
+                    dots[i] = dark;
+
+                    if x + 1 < w {
+                        values[i + 1] += error * 7 / 16;
+                    }
+                    if y + 1 < h {
+                        if x > 0 {
+                            values[i + w - 1] += error * 3 / 16;
+                        }
+                        values[i + w] += error * 5 / 16;
+                        if x + 1 < w {
+                            values[i + w + 1] += error / 16;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    dots
+}
+
+/// Converts gray levels to packed raster data
+pub fn to_raster_bits(gray: &[u8], width: u32, height: u32, dithering: Dithering) -> Vec<u8> {
+    let dots = dither(gray, width, height, dithering);
+    let bytes_per_row = width.div_ceil(8) as usize;
+    let mut raster = vec![0u8; bytes_per_row * height as usize];
+
+    for (i, dot) in dots.iter().enumerate() {
+        if *dot {
+            let (x, y) = (i % width as usize, i / width as usize);
+            raster[y * bytes_per_row + x / 8] |= 0x80 >> (x % 8);
+        }
+    }
+
+    raster
+}
+
+/// Creates a GS v 0 command that prints the gray levels as an image
+pub fn raster_bit_image(gray: &[u8], width: u32, height: u32, dithering: Dithering) -> Vec<u8> {
+    let bytes_per_row = width.div_ceil(8);
+    let mut bytes = vec![
+        GS,
+        b'v',
+        b'0',
+        0,
+        (bytes_per_row & 0xFF) as u8,
+        (bytes_per_row >> 8) as u8,
+        (height & 0xFF) as u8,
+        (height >> 8) as u8,
+    ];
+    bytes.extend(to_raster_bits(gray, width, height, dithering));
+    bytes
+}
+
+/// Creates a GS v 0 command from a picture of the image crate,
+/// transparent pixels are paper
+#[cfg(feature = "image")]
+pub fn raster_bit_image_from(image: &image::DynamicImage, dithering: Dithering) -> Vec<u8> {
+    let rgba = image.to_rgba8();
+    let gray = rgba_to_gray(rgba.as_raw());
+    raster_bit_image(&gray, rgba.width(), rgba.height(), dithering)
+}
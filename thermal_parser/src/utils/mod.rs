@@ -1 +1,3 @@
-pub mod barcodes;
\ No newline at end of file
+pub mod barcodes;
+pub mod corpus;
+pub mod dither;
@@ -1,12 +1,26 @@
 use std::path::PathBuf;
 use thermal_parser::thermal_file::parse_str;
-use thermal_parser::{context::*, parse_esc_pos};
+use thermal_parser::round_trip::check_round_trip;
+use thermal_parser::text::{break_ranges, word_ranges, TextSpan};
+use thermal_parser::utils::corpus::{CorpusGenerator, CorpusOptions};
+use thermal_parser::utils::dither::{dither, raster_bit_image, Dithering};
+use thermal_parser::audit::AuditKind;
+use thermal_parser::command::{Command, CommandHandler, CommandType, DataType};
+use thermal_parser::{
+    audit_esc_pos, context::*, fingerprint_esc_pos, new_esc_pos_parser, parse_esc_p, parse_esc_pos,
+    parse_star, parse_star_raster, parse_tspl, serialize,
+};
 
 #[test]
 fn code_pages() {
     test_sample("code_pages", "thermal")
 }
 
+#[test]
+fn buffer_graphics() {
+    test_sample("buffer_graphics", "thermal")
+}
+
 #[test]
 fn issuing_receipts() {
     test_sample("issuing_receipts", "thermal")
@@ -62,24 +76,29 @@ fn discount() {
     test_sample("discount", "thermal")
 }
 
-fn test_sample(name: &str, ext: &str) {
+/// The bytes of a file in sample_files/in, .thermal files are parsed
+/// from their text form
+fn sample_bytes(file: &str) -> Vec<u8> {
     let sample_file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("..")
         .join("sample_files")
         .join("in")
-        .join(format!("{}.{}", name, ext));
+        .join(file);
 
-    let bytes = if ext == "thermal" {
-        let text = std::fs::read_to_string(sample_file.to_str().unwrap()).unwrap();
-        parse_str(&text)
+    if file.ends_with(".thermal") {
+        parse_str(&std::fs::read_to_string(sample_file).unwrap())
     } else {
-        std::fs::read(sample_file.to_str().unwrap()).unwrap()
-    };
+        std::fs::read(sample_file).unwrap()
+    }
+}
+
+fn test_sample(name: &str, ext: &str) {
+    let bytes = sample_bytes(&format!("{}.{}", name, ext));
 
     parse(&bytes, true, name);
 }
 
-fn parse(bytes: &Vec<u8>, debug: bool, name: &str) {
+fn parse(bytes: &[u8], debug: bool, name: &str) {
     let context = Context::new();
 
     let commands = parse_esc_pos(bytes);
@@ -94,3 +113,920 @@ fn parse(bytes: &Vec<u8>, debug: bool, name: &str) {
 
     println!("└─ End")
 }
+
+#[test]
+fn star_receipt() {
+    let bytes = sample_bytes("star_receipt.thermal");
+    let commands = parse_star(&bytes);
+
+    for cmd in commands.iter() {
+        assert!(
+            cmd.kind != CommandType::Unknown,
+            "Unknown command in star receipt: {}",
+            cmd.handler.debug(cmd, &Context::new())
+        );
+    }
+
+    let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+
+    assert!(names.contains(&"Star Set Text Justification"));
+    assert!(names.contains(&"Star Set Character Expansion"));
+    assert!(names.contains(&"Star Raster Image"));
+    assert!(names.contains(&"Star Barcode"));
+    assert!(names.contains(&"Star Cut"));
+    assert!(names.contains(&"Star Open Drawer"));
+}
+
+#[test]
+fn star_code_table() {
+    //Star code table 4 is PC858, PC850 with the euro sign at 0xD5
+    let bytes = parse_str(r#"ESC GS "t" 4 "5" 0xD5"#);
+    let mut context = Context::new();
+    let mut text = String::new();
+
+    for cmd in parse_star(&bytes).iter() {
+        cmd.handler.apply_context(cmd, &mut context);
+        if let Some(span) = cmd.handler.get_text(cmd, &context) {
+            text.push_str(&span.text);
+        }
+    }
+
+    assert_eq!(text, "5€");
+}
+
+#[test]
+fn esc_p_kitchen() {
+    let commands = parse_esc_p(&sample_bytes("esc_p_kitchen.thermal"));
+
+    for cmd in commands.iter() {
+        assert!(
+            cmd.kind != CommandType::Unknown,
+            "Unknown command in esc/p kitchen ticket: {}",
+            cmd.handler.debug(cmd, &Context::new())
+        );
+    }
+
+    let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+
+    assert!(names.contains(&"ESC/P Master Select"));
+    assert!(names.contains(&"ESC/P Bit Image"));
+    assert!(names.contains(&"ESC/P 9 Pin Bit Image"));
+    assert!(names.contains(&"ESC/P Set Line Spacing"));
+    assert!(names.contains(&"Set Print Color to Alternate"));
+}
+
+#[test]
+fn star_raster() {
+    let commands = parse_star_raster(&sample_bytes("star_raster.thermal"));
+
+    for cmd in commands.iter() {
+        assert!(
+            cmd.kind != CommandType::Unknown && cmd.kind != CommandType::Text,
+            "Unexpected command in star raster job: {}",
+            cmd.handler.debug(cmd, &Context::new())
+        );
+    }
+
+    let lines = commands
+        .iter()
+        .filter(|c| c.name.as_str() == "Star Transfer Raster Data")
+        .count();
+
+    assert_eq!(lines, 48);
+    assert!(commands.iter().any(|c| c.name.as_str() == "Star Move Vertical Direction"));
+    assert!(commands.iter().any(|c| c.name.as_str() == "Star Execute EOT Mode"));
+}
+
+#[test]
+fn tspl_labels() {
+    let bytes = sample_bytes("tspl_labels.bin");
+    let commands = parse_tspl(&bytes);
+
+    for cmd in commands.iter() {
+        assert!(
+            cmd.kind != CommandType::Unknown,
+            "Unknown command in tspl labels: {}",
+            cmd.handler.debug(cmd, &Context::new())
+        );
+    }
+
+    let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+
+    assert_eq!(names.iter().filter(|n| **n == "TSPL Print Label").count(), 2);
+    assert!(names.contains(&"TSPL Set Label Size"));
+    assert!(names.contains(&"TSPL Text"));
+    assert!(names.contains(&"TSPL Barcode"));
+    assert!(names.contains(&"TSPL QR Code"));
+    assert!(names.contains(&"TSPL Bitmap"));
+    assert!(names.contains(&"TSPL Box"));
+    assert!(names.contains(&"TSPL Bar"));
+}
+
+#[test]
+fn print_control() {
+    let bytes = parse_str(r#"GS "(K" 2 0 49 250 GS "(K" 2 0 50 9 GS "(K" 2 0 97 2 "TEXT""#);
+    let commands = parse_esc_pos(&bytes);
+    let mut context = Context::new();
+
+    for cmd in commands.iter() {
+        assert!(cmd.kind != CommandType::Unknown);
+        cmd.handler.apply_context(cmd, &mut context);
+    }
+
+    assert_eq!(context.graphics.print_density, -6);
+    assert_eq!(context.graphics.print_density_percent(), 70);
+    assert_eq!(context.graphics.print_speed, 9);
+    assert_eq!(context.graphics.head_divisions, 2);
+}
+
+#[test]
+fn dithering() {
+    //A flat 50% gray, 8 by 8 pixels
+    let gray = vec![128u8; 64];
+    let dark = |d: Dithering| dither(&gray, 8, 8, d).iter().filter(|d| **d).count();
+
+    assert_eq!(dark(Dithering::Threshold(128)), 0);
+    assert_eq!(dark(Dithering::Threshold(129)), 64);
+    assert_eq!(dark(Dithering::Bayer), 32);
+    assert!((28..=36).contains(&dark(Dithering::FloydSteinberg)));
+
+    //The generated command parses back into an image of the same size
+    let bytes = raster_bit_image(&gray, 12, 3, Dithering::FloydSteinberg);
+    let commands = parse_esc_pos(&bytes);
+    let context = Context::new();
+
+    let image = commands
+        .iter()
+        .find(|c| c.kind == CommandType::Graphics)
+        .expect("No image command");
+    match image.handler.get_graphics(image, &context) {
+        Some(thermal_parser::graphics::GraphicsCommand::Image(image)) => {
+            assert_eq!((image.w, image.h), (16, 3));
+        }
+        _ => panic!("Expected an image"),
+    }
+}
+
+#[test]
+fn qr_versions() {
+    //Module counts match the smallest version a reference encoder picks
+    let modules = |error_correction: u8, data: &[u8]| -> Option<u32> {
+        let mut bytes = parse_str(&format!(r#"GS "(k" 3 0 49 69 {}"#, error_correction));
+        let len = data.len() + 3;
+        bytes.extend([0x1D, b'(', b'k', (len & 0xFF) as u8, (len >> 8) as u8]);
+        bytes.extend([49, 80, 48]);
+        bytes.extend(data);
+        bytes.push(b'\n');
+
+        let mut context = Context::new();
+        for cmd in parse_esc_pos(&bytes).iter() {
+            cmd.handler.apply_context(cmd, &mut context);
+        }
+        context.code2d.symbol_storage.map(|qr| qr.width)
+    };
+
+    //Alphanumeric, version 1 at M
+    assert_eq!(modules(49, b"HELLO WORLD"), Some(21));
+    //41 digits fit version 1 at L, 42 don't
+    assert_eq!(modules(48, "1".repeat(41).as_bytes()), Some(21));
+    assert_eq!(modules(48, "1".repeat(42).as_bytes()), Some(25));
+    //10 shift jis kanji fit version 1 at L, as bytes they would need version 2
+    assert_eq!(modules(48, &[0x93, 0x5F].repeat(10)), Some(21));
+    //The error correction level changes the version
+    assert_eq!(modules(51, b"HELLO WORLD"), Some(25));
+    //Binary data, 32 bytes is version 2 at L and version 3 at M
+    assert_eq!(modules(48, &[0xFFu8; 32]), Some(25));
+    assert_eq!(modules(49, &[0xFFu8; 32]), Some(29));
+}
+
+#[test]
+fn composite_symbols() {
+    let composite = |symbol: u8,
+                     data: &str,
+                     cc: Option<&str>|
+     -> Option<thermal_parser::graphics::GraphicsCommand> {
+        let mut bytes = parse_str(r#"GS "(k" 3 0 52 67 3 GS "(k" 3 0 52 72 50"#);
+        let len = data.len() + 5;
+        bytes.extend([0x1D, b'(', b'k', (len & 0xFF) as u8, (len >> 8) as u8]);
+        bytes.extend([52, 80, 48, 48, symbol]);
+        bytes.extend(data.as_bytes());
+        if let Some(cc) = cc {
+            let len = cc.len() + 5;
+            bytes.extend([0x1D, b'(', b'k', (len & 0xFF) as u8, (len >> 8) as u8]);
+            bytes.extend([52, 80, 48, 49, 65]);
+            bytes.extend(cc.as_bytes());
+        }
+        bytes.extend(parse_str(r#"GS "(k" 3 0 52 81 48"#));
+        bytes.push(b'\n');
+
+        let mut context = Context::new();
+        let mut graphics = None;
+        for cmd in parse_esc_pos(&bytes).iter() {
+            cmd.handler.apply_context(cmd, &mut context);
+            if cmd.kind == CommandType::Graphics {
+                graphics = cmd.handler.get_graphics(cmd, &context);
+            }
+        }
+        assert_eq!(
+            context.code2d.composite_2d,
+            cc.map(|cc| cc.as_bytes().to_vec())
+        );
+        graphics
+    };
+
+    //The linear component is printed with the composite width and hri font
+    match composite(66, "490123456789", None) {
+        Some(thermal_parser::graphics::GraphicsCommand::Barcode(barcode)) => {
+            assert_eq!(barcode.points.len(), 95);
+            assert_eq!(barcode.point_width, 3);
+            assert_eq!(barcode.text.text, "490123456789");
+            assert_eq!(barcode.text.font, Font::B);
+            assert!(matches!(barcode.hri, HumanReadableInterface::Below));
+        }
+        _ => panic!("Expected a barcode"),
+    }
+
+    //GS1-128 starts with FNC1 and hides the parentheses in the bars only
+    match composite(77, "(01)09501101530003", None) {
+        Some(thermal_parser::graphics::GraphicsCommand::Barcode(barcode)) => {
+            assert_eq!(barcode.text.text, "(01)09501101530003");
+            //Start C, FNC1, 8 digit pairs, check and stop
+            assert_eq!(barcode.points.len(), 11 * 11 + 13);
+        }
+        _ => panic!("Expected a barcode"),
+    }
+
+    //A linear component that can't be encoded is an error
+    assert!(matches!(
+        composite(66, "12", None),
+        Some(thermal_parser::graphics::GraphicsCommand::Error(_))
+    ));
+
+    //The 2D component can't be encoded, the symbol isn't printed without it
+    match composite(66, "490123456789", Some("(17)2501")) {
+        Some(thermal_parser::graphics::GraphicsCommand::Error(error)) => {
+            assert!(error.contains("2D component"), "{}", error)
+        }
+        _ => panic!("Expected an error"),
+    }
+}
+
+#[derive(Clone)]
+struct VendorHandler;
+
+impl CommandHandler for VendorHandler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        context.text.bold = command.data.first() == Some(&1);
+    }
+}
+
+#[test]
+fn custom_commands() {
+    let mut parser = new_esc_pos_parser();
+
+    //A vendor command that isn't part of ESC/POS
+    let vendor = Command::new(
+        "Vendor Bold",
+        vec![0x1B, 0x99],
+        CommandType::Context,
+        DataType::Single,
+        Box::new(VendorHandler),
+    );
+    assert!(parser.add_command(vendor).is_none());
+
+    //Replacing a built in command returns the replaced one
+    let pulse = Command::new(
+        "Vendor Pulse",
+        vec![0x1B, b'p'],
+        CommandType::Control,
+        DataType::Triple,
+        Box::new(VendorHandler),
+    );
+    let replaced = parser.add_command(pulse).expect("No command replaced");
+    assert_eq!(replaced.name.as_str(), "Pulse");
+
+    let bytes = parse_str(r#"ESC 0x99 1 "BOLD" ESC "p" 0 25 250 LF"#);
+    let commands = parser.parse_bytes(&bytes);
+    let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+
+    assert!(names.contains(&"Vendor Bold"));
+    assert!(names.contains(&"Vendor Pulse"));
+    assert!(!names.contains(&"Pulse"));
+
+    let mut context = Context::new();
+    for cmd in commands.iter().filter(|c| c.name.as_str() == "Vendor Bold") {
+        assert_eq!(cmd.data, vec![1]);
+        cmd.handler.apply_context(cmd, &mut context);
+    }
+    assert!(context.text.bold);
+}
+
+#[test]
+fn parse_chunks() {
+    let bytes = parse_str(
+        r#"ESC "@" "HELLO" LF GS "(k" 4 0 49 65 50 0 GS "(k" 3 0 49 67 4 GS "(k" 8 0 49 80 48 "QR" LF GS "V" 65 0"#,
+    );
+    let describe = |commands: Vec<Command>| -> Vec<(String, Vec<u8>)> {
+        commands
+            .iter()
+            .map(|c| (c.name.to_string(), c.data.clone()))
+            .collect()
+    };
+    let expected = describe(parse_esc_pos(&bytes));
+
+    //Commands come out in the same order however the bytes are split
+    let mut parser = new_esc_pos_parser();
+    let mut commands = vec![];
+    for byte in &bytes {
+        commands.append(&mut parser.parse_chunk(&[*byte]));
+    }
+    commands.append(&mut parser.finish());
+    assert_eq!(describe(commands), expected);
+
+    //A command is held back until the byte after it arrives
+    let mut parser = new_esc_pos_parser();
+    let names = describe(parser.parse_chunk(&parse_str(r#"ESC "@" "HELLO""#)));
+    assert!(names.iter().any(|(name, _)| name == "Initialize"));
+    assert!(!names.iter().any(|(_, data)| data == b"HELLO"));
+    assert!(describe(parser.finish())
+        .iter()
+        .any(|(_, data)| data == b"HELLO"));
+
+    //The parser can be reused after finishing
+    assert_eq!(describe(parser.parse_bytes(&bytes)), expected);
+}
+
+#[test]
+fn command_offsets() {
+    let bytes = parse_str(r#"ESC "@" "HELLO" ESC "~" 1 LF GS "(k" 3 0 49 67 4 LF"#);
+    let offsets = |commands: Vec<Command>| -> Vec<(String, usize)> {
+        commands
+            .iter()
+            .map(|c| (c.name.to_string(), c.offset))
+            .collect()
+    };
+    let expected = offsets(parse_esc_pos(&bytes));
+
+    assert!(expected.contains(&("Initialize".to_string(), 0)));
+    assert!(expected.contains(&("Text".to_string(), 2)));
+    assert!(expected.contains(&("Unknown Command".to_string(), 7)));
+    assert!(expected.contains(&("Line Feed".to_string(), 10)));
+    assert!(expected
+        .iter()
+        .any(|(name, offset)| name.contains("QR") && *offset == 11));
+    assert_eq!(
+        expected.last().map(|(_, offset)| *offset),
+        Some(bytes.len())
+    );
+
+    //Offsets continue across chunks
+    let mut parser = new_esc_pos_parser();
+    let mut commands = vec![];
+    for chunk in bytes.chunks(3) {
+        commands.append(&mut parser.parse_chunk(chunk));
+    }
+    commands.append(&mut parser.finish());
+    assert_eq!(offsets(commands), expected);
+}
+
+#[test]
+fn raw_round_trip() {
+    for name in [
+        "receipt_with_barcode.thermal",
+        "page_mode.thermal",
+        "print_graphics.thermal",
+        "barcodes.thermal",
+        "retail.bin",
+        "test_receipt_1.bin",
+        "test_receipt_4.bin",
+    ] {
+        let bytes = sample_bytes(name);
+
+        let mut parser = new_esc_pos_parser();
+        parser.retain_raw(true);
+        assert_eq!(serialize(&parser.parse_bytes(&bytes)), bytes, "{}", name);
+    }
+
+    //Unknown bytes and skipped bytes are kept too
+    let bytes = parse_str(r#"ESC "@" "BEFORE" ESC "~" 1 2 3 LF GS "(k" 3 0 49 67 4 "AFTER" LF"#);
+    let mut parser = new_esc_pos_parser();
+    parser.retain_raw(true);
+    let mut commands = parser.parse_bytes(&bytes);
+    assert_eq!(serialize(&commands), bytes);
+
+    //Only the edited command is rebuilt
+    let text = commands
+        .iter_mut()
+        .find(|c| c.data == b"AFTER")
+        .expect("No text command");
+    text.set_data(b"PATCHED".to_vec());
+    let patched = serialize(&commands);
+    assert_eq!(
+        patched,
+        parse_str(r#"ESC "@" "BEFORE" ESC "~" 1 2 3 LF GS "(k" 3 0 49 67 4 "PATCHED" LF"#)
+    );
+
+    //Without retaining, commands are rebuilt from their data
+    assert!(parse_esc_pos(&bytes).iter().all(|c| c.raw.is_empty()));
+}
+
+#[test]
+fn print_mode_bits() {
+    let mut context = Context::new();
+    let mut apply = |src: &str| {
+        for cmd in parse_esc_pos(&parse_str(src)).iter() {
+            cmd.handler.apply_context(cmd, &mut context);
+        }
+        (
+            context.text.font.clone(),
+            context.text.bold,
+            context.text.underline.clone(),
+            context.text.width_mult,
+            context.text.height_mult,
+        )
+    };
+
+    //Bits that are 0 turn their mode off
+    assert_eq!(
+        apply(r#"ESC "M" 1 ESC "E" 1 ESC "-" 1 ESC "!" 0"#),
+        (Font::A, false, TextUnderline::Off, 1, 1)
+    );
+    assert_eq!(
+        apply(r#"ESC "!" 0xB9"#),
+        (Font::B, true, TextUnderline::On, 2, 2)
+    );
+
+    //The underline comes back with the thickness of the last ESC -
+    assert_eq!(
+        apply(r#"ESC "-" 2 ESC "-" 0 ESC "!" 0x80"#),
+        (Font::A, false, TextUnderline::Double, 1, 1)
+    );
+    assert!(apply(r#"ESC "E" 1"#).1);
+}
+
+#[test]
+fn realtime_commands() {
+    let names = |src: &str| -> Vec<(String, Vec<u8>, bool)> {
+        parse_esc_pos(&parse_str(src))
+            .iter()
+            .filter(|c| !c.name.starts_with("Begin") && !c.name.starts_with("End"))
+            .filter(|c| !c.name.starts_with("Unknown"))
+            .map(|c| (c.name.to_string(), c.data.clone(), c.embedded))
+            .collect()
+    };
+
+    //DLE EOT takes a single byte, the text after it is not swallowed
+    assert_eq!(
+        names(r#"DLE 4 1 "HELLO""#),
+        vec![
+            ("Transmit Real-Time Status".to_string(), vec![1], false),
+            ("Text".to_string(), b"HELLO".to_vec(), false),
+        ]
+    );
+
+    //Inside image data the sequence is executed and stays image data
+    let image = r#"GS "v0" 0 1 0 4 0 0xFF 0x10 0x04 0x02"#;
+    assert_eq!(
+        names(image),
+        vec![
+            ("Transmit Real-Time Status".to_string(), vec![2], true),
+            (
+                "Raster Bit Image".to_string(),
+                vec![0xFF, 0x10, 0x04, 0x02],
+                false
+            ),
+        ]
+    );
+
+    //Embedded commands are not serialized a second time
+    let bytes = parse_str(image);
+    assert_eq!(serialize(&parse_esc_pos(&bytes)), bytes);
+
+    //GS ( D turns real-time processing off for the data after it
+    let disabled = names(&format!(r#"GS "(D" 5 0 20 1 0 2 0 {}"#, image));
+    assert_eq!(disabled.len(), 2);
+    assert_eq!(disabled[0].0, "Enable Real-Time Commands");
+    assert_eq!(disabled[1].0, "Raster Bit Image");
+
+    let enabled = names(&format!(r#"GS "(D" 5 0 20 1 0 2 1 {}"#, image));
+    assert!(enabled.iter().any(|c| c.2));
+}
+
+#[test]
+fn parse_audit() {
+    let kinds = |src: &str| -> Vec<(usize, String, AuditKind)> {
+        audit_esc_pos(&parse_str(src))
+            .into_iter()
+            .map(|f| (f.offset, f.command, f.kind))
+            .collect()
+    };
+
+    //A clean job has nothing to report
+    assert!(kinds(r#"ESC "@" "HELLO" LF GS "V" 0"#).is_empty());
+
+    //ESC @ inside bit image data
+    assert_eq!(
+        kinds(r#"ESC "*" 0 3 0 0xFF ESC "@" "HI""#),
+        vec![(
+            6,
+            "Bit Image".to_string(),
+            AuditKind::CommandInData {
+                command: "Initialize".to_string()
+            }
+        )]
+    );
+
+    //Image data that spilled into text
+    assert_eq!(
+        kinds(r#""AB" 0x00 0x01 "CD""#),
+        vec![(0, "Text".to_string(), AuditKind::BinaryText { control_bytes: 2 })]
+    );
+
+    //An unknown command takes the text after it
+    assert_eq!(
+        kinds(r#"ESC 0xFE "HELLO" ESC "@""#),
+        vec![(
+            0,
+            "Unknown Command".to_string(),
+            AuditKind::UnknownCommand { swallowed: 6 }
+        )]
+    );
+
+    //The stream ends in the middle of a command
+    assert_eq!(
+        kinds(r#""A" ESC "!""#),
+        vec![(
+            1,
+            "Set Print Mode".to_string(),
+            AuditKind::Truncated {
+                expected: 1,
+                actual: 0
+            }
+        )]
+    );
+
+    //Real-time commands executed from image data
+    let findings = audit_esc_pos(&parse_str(r#"GS "v0" 0 1 0 3 0 0x10 0x04 0x01"#));
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, AuditKind::RealtimeInData);
+    assert_eq!(findings[0].offset, 8);
+    assert_eq!(
+        findings[0].to_string(),
+        "0x0008 Transmit Real-Time Status: executed from inside the data of a command"
+    );
+}
+
+#[test]
+fn initialize_reset() {
+    let bytes = parse_str(
+        r#"GS "(L" 11 0 48 67 48 "N1" 1 8 0 1 0 0xFF GS "(L" 11 0 48 83 48 "R1" 1 8 0 1 0 0xFF
+        ESC "E" 1 ESC "@""#,
+    );
+    let mut context = Context::new();
+
+    for cmd in parse_esc_pos(&bytes).iter() {
+        if cmd.name.as_str() == "Initialize" {
+            context.page_mode.enabled = true;
+            context.graphics.render_area.y = 120;
+            assert!(context.text.bold);
+            assert_eq!(context.graphics.stored_graphics.len(), 2);
+        }
+        cmd.handler.apply_context(cmd, &mut context);
+    }
+
+    //Graphics downloaded to RAM are gone, NV graphics are kept
+    let kept: Vec<(u8, u8)> = context
+        .graphics
+        .stored_graphics
+        .keys()
+        .map(|k| (k.kc1, k.kc2))
+        .collect();
+    assert_eq!(kept, vec![(b'N', b'1')]);
+
+    //The paper did not move, page mode and styles are reset
+    assert_eq!(context.graphics.render_area.y, 120);
+    assert!(!context.page_mode.enabled);
+    assert!(!context.text.bold);
+}
+
+#[test]
+fn corpus_generator() {
+    let jobs: Vec<Vec<u8>> = CorpusGenerator::new(7).take(50).collect();
+
+    //The same seed generates the same jobs
+    assert_eq!(jobs, CorpusGenerator::new(7).take(50).collect::<Vec<_>>());
+    assert_ne!(jobs, CorpusGenerator::new(8).take(50).collect::<Vec<_>>());
+
+    //Every command is framed right, nothing unknown or truncated
+    for job in &jobs {
+        let findings = audit_esc_pos(job);
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+
+    //Options leave parts out
+    let options = CorpusOptions {
+        images: false,
+        page_mode: false,
+        ..CorpusOptions::default()
+    };
+    for job in CorpusGenerator::new_with_options(7, options).take(50) {
+        let names: Vec<String> = parse_esc_pos(&job)
+            .iter()
+            .map(|c| c.name.to_string())
+            .collect();
+        assert!(!names.iter().any(|n| n.contains("Page") || n.contains("Raster")));
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut parser = new_esc_pos_parser();
+
+    for name in [
+        "test_receipt_1.bin",
+        "test_receipt_2.bin",
+        "test_receipt_3.bin",
+        "test_receipt_4.bin",
+        "discount.bin",
+        "retail.bin",
+    ] {
+        let commands = parse_esc_pos(&sample_bytes(name));
+
+        if let Err(mismatch) = check_round_trip(&mut parser, &commands) {
+            panic!("{}: {}", name, mismatch);
+        }
+    }
+
+    for job in CorpusGenerator::new(3).take(50) {
+        assert!(check_round_trip(&mut parser, &parse_esc_pos(&job)).is_ok());
+    }
+
+    //Text at the start of a stream is only text
+    assert_eq!(serialize(&parse_esc_pos(b"HELLO")), b"HELLO");
+
+    //Commands at the end of the stream keep their data
+    for src in [r#""A" GS "k" 4 "1234" NUL"#, r#""A" GS "(k" 3 0 49 81 48"#] {
+        let bytes = parse_str(src);
+        assert_eq!(serialize(&parse_esc_pos(&bytes)), bytes, "{}", src);
+    }
+
+    //Commands that don't come back are reported
+    let mut commands = parse_esc_pos(&parse_str(r#"ESC "E" 1 "HI""#));
+    let bold = commands
+        .iter_mut()
+        .find(|c| c.name.as_str() == "Enable Emphasis")
+        .expect("No emphasis command");
+    bold.set_data(vec![]);
+
+    let mismatch = check_round_trip(&mut parser, &commands).unwrap_err();
+    assert_eq!(mismatch.index, 0);
+    assert_eq!(mismatch.actual.unwrap().data, b"H");
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_round_trip() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let mut parser = new_esc_pos_parser();
+    let mut generated = 0;
+
+    //Jobs make for bytes with a bit of everything in them
+    for job in CorpusGenerator::new(11).take(200) {
+        let mut u = Unstructured::new(&job);
+
+        if let Ok(commands) = Vec::<Command>::arbitrary(&mut u) {
+            if let Err(mismatch) = check_round_trip(&mut parser, &commands) {
+                panic!("{}", mismatch);
+            }
+            generated += commands.len();
+        }
+    }
+
+    assert!(generated > 100);
+}
+
+#[test]
+fn text_ranges() {
+    let text = "TWO  WORDS\nÉTÉ";
+    let words: Vec<&str> = word_ranges(text).map(|r| &text[r]).collect();
+    assert_eq!(words, vec!["TWO ", " ", "WORDS\n", "ÉTÉ"]);
+
+    //The first part can be empty, the rest is broken by characters
+    let text = "ÅBCDÉFG";
+    let parts: Vec<&str> = break_ranges(text, 2, 3).into_iter().map(|r| &text[r]).collect();
+    assert_eq!(parts, vec!["ÅB", "CDÉ", "FG"]);
+    let parts: Vec<&str> = break_ranges(text, 0, 4).into_iter().map(|r| &text[r]).collect();
+    assert_eq!(parts, vec!["", "ÅBCD", "ÉFG"]);
+
+    //Spans keep their style
+    let mut context = Context::new();
+    context.text.bold = true;
+    let span = TextSpan::new("A B".to_string(), &context);
+    let words = span.break_into_words();
+    assert_eq!(words.len(), 2);
+    assert!(words.iter().all(|w| w.bold));
+    assert_eq!(span.break_apart(1, 1).len(), 3);
+}
+
+#[test]
+fn color_planes() {
+    use thermal_parser::graphics::{GraphicsCommand, ImageRef, ImageRefStorage, RGBA};
+
+    let stored = |src: &str| {
+        let mut context = Context::new();
+        for cmd in parse_esc_pos(&parse_str(src)).iter() {
+            cmd.handler.apply_context(cmd, &mut context);
+        }
+        let image_ref = ImageRef {
+            kc1: b'A',
+            kc2: b'1',
+            storage: ImageRefStorage::Ram,
+        };
+        match context.graphics.stored_graphics.get(&image_ref) {
+            Some(GraphicsCommand::Image(image)) => (image.clone(), context),
+            other => panic!("Expected an image, got {:?}", other),
+        }
+    };
+    let rgba = |c: &RGBA| (c.r, c.g, c.b, c.a);
+
+    //Raster, 12 dots wide so rows are padded, color 2 is defined first
+    let (image, context) = stored(
+        r#"GS "(L" 20 0 48 83 48 "A1" 2 12 0 2 0 50 0xFF 0xF0 0 0 49 0x80 0 0x80 0"#,
+    );
+    let colors = &context.graphics.render_colors;
+    let pixel = |x: u32, y: u32| rgba(&image.pixels[(y * image.w + x) as usize]);
+    assert_eq!((image.w, image.h), (12, 2));
+    assert_eq!(pixel(0, 0), rgba(&colors.color_2));
+    assert_eq!(pixel(11, 0), rgba(&colors.color_2));
+    assert_eq!(pixel(0, 1), rgba(&colors.color_1));
+    assert_eq!(pixel(1, 1).3, 0);
+
+    //Column, 2 dots wide and 12 tall
+    let (image, context) = stored(
+        r#"GS "(L" 20 0 48 84 48 "A1" 2 2 0 12 0 50 0xFF 0xF0 0 0 49 0 0 0x80 0"#,
+    );
+    let colors = &context.graphics.render_colors;
+    let pixel = |x: u32, y: u32| rgba(&image.pixels[(y * image.w + x) as usize]);
+    assert_eq!((image.w, image.h), (2, 12));
+    assert_eq!(pixel(0, 0), rgba(&colors.color_2));
+    assert_eq!(pixel(0, 11), rgba(&colors.color_2));
+    assert_eq!(pixel(1, 0), rgba(&colors.color_1));
+    assert_eq!(pixel(1, 1).3, 0);
+}
+
+#[test]
+fn large_graphics_chunks() {
+    //A 4096 x 4096 dot photo is 2MB, the 4 byte length of GS 8 L is needed
+    let (width, height) = (4096u32, 4096u32);
+    let plane = (width.div_ceil(8) * height) as usize;
+    let mut bytes = vec![0x1D, b'8', b'L'];
+    bytes.extend_from_slice(&(plane as u32 + 10).to_le_bytes());
+    bytes.extend_from_slice(&[48, 112, 48, 1, 1, 49]);
+    bytes.extend_from_slice(&(width as u16).to_le_bytes());
+    bytes.extend_from_slice(&(height as u16).to_le_bytes());
+    bytes.extend((0..plane).map(|i| (i % 251) as u8));
+    //Print Buffer Graphics without data as the last bytes of the stream
+    bytes.extend_from_slice(&[0x1D, b'8', b'L', 2, 0, 0, 0, 48, 50]);
+
+    let describe = |commands: &[Command]| -> Vec<(String, usize, usize)> {
+        commands
+            .iter()
+            .map(|c| (c.name.to_string(), c.data.len(), c.offset))
+            .collect()
+    };
+    let commands = parse_esc_pos(&bytes);
+    let expected = describe(&commands);
+    let store = "Store Print Buffer Graphics Raster Format";
+    assert!(expected.contains(&(store.into(), plane + 8, 0)));
+    assert!(expected.contains(&("Print Buffer Graphics".into(), 0, plane + 17)));
+
+    //Chunks split the length, the header and the data at different places
+    for size in [1, 5, 4093, 65536, 1 << 20] {
+        let mut parser = new_esc_pos_parser();
+        let mut chunked = vec![];
+        for chunk in bytes.chunks(size) {
+            chunked.append(&mut parser.parse_chunk(chunk));
+        }
+        chunked.append(&mut parser.finish());
+        assert_eq!(describe(&chunked), expected, "chunks of {}", size);
+    }
+
+    let mut context = Context::new();
+    let store = commands.iter().find(|c| c.name.as_str() == store).unwrap();
+    store.handler.apply_context(store, &mut context);
+    assert!(matches!(
+        context.graphics.buffer_graphics.get(&49),
+        Some(thermal_parser::graphics::GraphicsCommand::Image(image))
+            if (image.w, image.h) == (width, height)
+    ));
+}
+
+#[test]
+fn image_gray_levels() {
+    use thermal_parser::graphics::{GraphicsCommand, RGBA};
+
+    let blue = RGBA {
+        r: 0,
+        g: 0,
+        b: 255,
+        a: 255,
+    };
+    assert_eq!(blue.with_alpha(128), RGBA { a: 128, ..blue });
+    assert_eq!(blue.as_gray(), 29);
+    assert_eq!(blue.with_alpha(0).as_gray(), 255);
+
+    let buffered = |src: &str| {
+        let mut context = Context::new();
+        for cmd in parse_esc_pos(&parse_str(src)).iter() {
+            cmd.handler.apply_context(cmd, &mut context);
+        }
+        context.graphics.buffer_graphics.get(&49).cloned()
+    };
+
+    //Monochrome and multiple tone planes print their dots in the plane color
+    for tone in [48, 52] {
+        let src = format!(r#"GS "(L" 12 0 48 112 {} 1 1 49 8 0 2 0 0xF0 0x0F"#, tone);
+        match buffered(&src) {
+            Some(GraphicsCommand::Image(image)) => {
+                let gray = image.as_grayscale();
+                assert_eq!(gray.len(), 16);
+                assert_eq!(gray[..8], [0, 0, 0, 0, 255, 255, 255, 255]);
+                assert_eq!(gray[8..], [255, 255, 255, 255, 0, 0, 0, 0]);
+            }
+            other => panic!("Expected an image, got {:?}", other),
+        }
+    }
+
+    //Other tones are not guessed at
+    assert!(matches!(
+        buffered(r#"GS "(L" 12 0 48 112 50 1 1 49 8 0 2 0 0xF0 0x0F"#),
+        Some(GraphicsCommand::Error(_))
+    ));
+}
+
+#[test]
+fn command_reference() {
+    let commands = parse_esc_pos(&parse_str(
+        r#"ESC "@" GS "(L" 2 0 48 50 GS "8L" 2 0 0 0 48 50 GS "(k" 3 0 49 81 48 ESC "4" 1"#,
+    ));
+    let references: Vec<_> = commands
+        .iter()
+        .map(|c| c.info().map(|info| info.reference))
+        .collect();
+
+    assert_eq!(
+        references[1..],
+        [
+            Some("ESC @"),
+            Some("GS ( L <Function 50>"),
+            Some("GS ( L <Function 50>"),
+            Some("GS ( k <Function 181>"),
+            None, //Italic is not part of ESC/POS
+            None,
+        ]
+    );
+
+    let info = commands[4].info().unwrap();
+    assert_eq!(
+        info.name,
+        "QR Code: Print the symbol data in the symbol storage area"
+    );
+    assert!(info.models.contains(&"TM-T88VI"));
+    assert_eq!(info.parameters[1].name, "m");
+
+    //Other command sets have no reference
+    assert!(parse_star(&[0x1B, 0x40]).iter().all(|c| c.info().is_none()));
+}
+
+#[test]
+fn fingerprint() {
+    let printed = fingerprint_esc_pos(&parse_str(r#"ESC "@" "TOTAL 4.00" LF GS "V" 0"#));
+
+    //Status requests and real-time commands don't change the fingerprint
+    let captured = fingerprint_esc_pos(&parse_str(
+        r#"ESC "@" "TOTAL " 0x10 0x04 1 "4." 0x10 0x04 2 "00" 0x10 0x04 4 LF GS "V" 0"#,
+    ));
+    assert_eq!(printed, captured);
+    assert_eq!(printed.to_string(), captured.to_string());
+    assert!(printed.images.is_empty());
+
+    let other = fingerprint_esc_pos(&parse_str(r#"ESC "@" "TOTAL 5.00" LF GS "V" 0"#));
+    assert_ne!(printed.commands, other.commands);
+    assert_ne!(printed.text, other.text);
+
+    //The same text with another style
+    let bold = fingerprint_esc_pos(&parse_str(r#"ESC "@" ESC "E" 1 "TOTAL 4.00" LF GS "V" 0"#));
+    assert_ne!(printed.commands, bold.commands);
+    assert_eq!(printed.text, bold.text);
+
+    let raster = |byte: &str| {
+        fingerprint_esc_pos(&parse_str(&format!(
+            r#"ESC "@" GS "v0" 0 1 0 2 0 {} {}"#,
+            byte, byte
+        )))
+    };
+    assert_eq!(raster("0xF0").images.len(), 1);
+    assert_eq!(raster("0xF0").images, raster("0xF0").images);
+    assert_ne!(raster("0xF0").images, raster("0x0F").images);
+}
@@ -0,0 +1,69 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use std::path::PathBuf;
+use thermal_parser::context::Context;
+use thermal_parser::text::TextSpan;
+use thermal_parser::thermal_file::parse_str;
+use thermal_parser::utils::corpus::CorpusGenerator;
+use thermal_renderer::html_renderer::HtmlRenderer;
+use thermal_renderer::image_renderer::ImageRenderer;
+use thermal_renderer::layout::layout_text;
+
+fn sample(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("sample_files")
+        .join("in")
+        .join(name);
+    let bytes = std::fs::read(&path).unwrap();
+
+    match name.ends_with(".thermal") {
+        true => parse_str(&String::from_utf8(bytes).unwrap()),
+        false => bytes,
+    }
+}
+
+fn text_layout(c: &mut Criterion) {
+    let context = Context::new();
+    let words = "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG 12.50 ";
+
+    //A page of text in a few styles, with a word longer than a line
+    let mut spans = vec![];
+    for line in 0..200 {
+        spans.push(TextSpan::new(words.repeat(1 + line % 3), &context));
+        spans.push(TextSpan::new("\n".to_string(), &context));
+    }
+    spans.push(TextSpan::new("X".repeat(200), &context));
+
+    c.bench_function("layout/text", |b| {
+        b.iter(|| {
+            let mut context = Context::new();
+            layout_text(black_box(&spans), &mut context)
+        })
+    });
+}
+
+fn rendering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    group.sample_size(20);
+
+    let jobs: Vec<u8> = CorpusGenerator::new(1).take(20).flatten().collect();
+
+    for (name, bytes) in [
+        ("discount.bin", sample("discount.bin")),
+        ("gs_images_raster", sample("gs_images_raster.thermal")),
+        ("corpus", jobs),
+    ] {
+        group.bench_function(format!("image/{}", name), |b| {
+            b.iter(|| ImageRenderer::render(black_box(&bytes), None))
+        });
+        group.bench_function(format!("html/{}", name), |b| {
+            b.iter(|| HtmlRenderer::render(black_box(&bytes), None))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, text_layout, rendering);
+criterion_main!(benches);
@@ -0,0 +1,142 @@
+//! Barcode Renderer
+//!
+//! Barcodes and 2d codes are grids of dark and light modules.
+//! The BarcodeRenderer trait turns a symbol into that grid, or
+//! into positioned rectangles in dots, so output renderers that
+//! draw symbols their own way (svg, pdf, label languages) don't
+//! have to repeat the module math.
+//!
+//! A barcode is a symbol with a single row of modules that are
+//! as tall as the bar height.
+
+use thermal_parser::graphics::{Barcode, Code2D, Rectangle, VectorGraphic};
+
+/// Dark (true) and light modules of a symbol, row by row
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModuleMatrix {
+    pub width: u32,
+    pub height: u32,
+    pub modules: Vec<bool>,
+}
+
+impl ModuleMatrix {
+    /// Whether the module at column x and row y is dark
+    pub fn is_dark(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        *self
+            .modules
+            .get((y * self.width + x) as usize)
+            .unwrap_or(&false)
+    }
+}
+
+pub trait BarcodeRenderer {
+    /// The modules of the symbol
+    fn module_matrix(&self) -> ModuleMatrix;
+
+    /// Width and height of a single module in dots
+    fn module_size(&self) -> (u32, u32);
+
+    /// Width and height of the whole symbol in dots
+    fn size(&self) -> (u32, u32) {
+        let matrix = self.module_matrix();
+        let (w, h) = self.module_size();
+        (matrix.width * w, matrix.height * h)
+    }
+
+    /// One rectangle per dark module with the top left of the symbol
+    /// at x, y. Columns that don't fit in max_width dots are left out
+    fn rectangles(&self, x: u32, y: u32, max_width: u32) -> Vec<VectorGraphic> {
+        let mut graphics = vec![];
+        self.rectangles_into(x, y, max_width, &mut graphics);
+        graphics
+    }
+
+    /// Same as `rectangles`, the rectangles are added to the graphics
+    /// so a buffer can be reused for every symbol
+    fn rectangles_into(&self, x: u32, y: u32, max_width: u32, graphics: &mut Vec<VectorGraphic>) {
+        let matrix = self.module_matrix();
+        let (w, h) = self.module_size();
+        let columns = max_width
+            .checked_div(w)
+            .map_or(matrix.width, |fit| matrix.width.min(fit));
+
+        for row in 0..matrix.height {
+            for column in 0..columns {
+                if matrix.is_dark(column, row) {
+                    graphics.push(VectorGraphic::Rectangle(Rectangle {
+                        x: x + column * w,
+                        y: y + row * h,
+                        w,
+                        h,
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Same as `rectangles_into` with one rectangle per run of dark
+    /// modules in a row, so a symbol is drawn from far fewer rectangles
+    fn runs_into(&self, x: u32, y: u32, max_width: u32, graphics: &mut Vec<VectorGraphic>) {
+        let matrix = self.module_matrix();
+        let (w, h) = self.module_size();
+        let columns = max_width
+            .checked_div(w)
+            .map_or(matrix.width, |fit| matrix.width.min(fit));
+
+        for row in 0..matrix.height {
+            let mut run_start = None;
+
+            //One column past the end closes the last run
+            for column in 0..=columns {
+                let dark = column < columns && matrix.is_dark(column, row);
+
+                match (run_start, dark) {
+                    (None, true) => run_start = Some(column),
+                    (Some(start), false) => {
+                        graphics.push(VectorGraphic::Rectangle(Rectangle {
+                            x: x + start * w,
+                            y: y + row * h,
+                            w: (column - start) * w,
+                            h,
+                        }));
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl BarcodeRenderer for Barcode {
+    fn module_matrix(&self) -> ModuleMatrix {
+        ModuleMatrix {
+            width: self.points.len() as u32,
+            height: 1,
+            modules: self.points.iter().map(|p| *p > 0).collect(),
+        }
+    }
+
+    fn module_size(&self) -> (u32, u32) {
+        (self.point_width as u32, self.point_height as u32)
+    }
+}
+
+impl BarcodeRenderer for Code2D {
+    fn module_matrix(&self) -> ModuleMatrix {
+        let width = self.width.max(1);
+
+        ModuleMatrix {
+            width,
+            height: (self.points.len() as u32).div_ceil(width),
+            modules: self.points.iter().map(|p| *p > 0).collect(),
+        }
+    }
+
+    fn module_size(&self) -> (u32, u32) {
+        (self.point_width, self.point_height)
+    }
+}
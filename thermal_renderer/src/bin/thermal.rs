@@ -0,0 +1,207 @@
+//! Thermal command line tool
+//!
+//! thermal diff a.bin b.bin -o diff.png [--tolerance 16] [--semantic]
+//!
+//! Renders both jobs and writes the first render, a diff and the
+//! second render side by side. With --semantic the changed text,
+//! styles and codes are listed instead. Files ending in .thermal
+//! are read as thermal files, everything else as raw ESC/POS bytes.
+//! Exits with 1 when the renders differ.
+//!
+//! thermal pace job.bin [--baud 9600 | --usb] [--realtime]
+//!
+//! Renders the job at the speed of a serial or usb connection and
+//! lists the jobs, status requests and other events with the time
+//! they happened. With --realtime the events come at that time.
+//!
+//! thermal trace job.bin [--text]
+//!
+//! Lists the commands of every receipt with their offsets, bytes and
+//! what they printed, as markdown or with --text as plain text.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use thermal_parser::thermal_file::parse_str;
+use thermal_renderer::image_diff::diff_renders;
+use thermal_renderer::length_renderer::{LengthRenderer, ReceiptLength};
+use thermal_renderer::pacing::{Link, PacedRenderer};
+use thermal_renderer::renderer::{DebugProfile, OutputRenderer, Renderer};
+use thermal_renderer::semantic_diff::diff_jobs;
+use thermal_renderer::stream::{StreamEvent, StreamRenderer};
+use thermal_renderer::trace_renderer::{TraceFormat, TraceRenderer};
+
+const USAGE: &str = "Usage: thermal diff <a> <b> [-o diff.png] [--tolerance 16] [--semantic]
+       thermal pace <job> [--baud 9600 | --usb] [--realtime]
+       thermal trace <job> [--text]";
+
+fn read_job(path: &Path) -> Result<Vec<u8>, String> {
+    let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+
+    if path.extension().is_some_and(|ext| ext == "thermal") {
+        std::fs::read_to_string(path)
+            .map(|text| parse_str(&text))
+            .map_err(error)
+    } else {
+        std::fs::read(path).map_err(error)
+    }
+}
+
+fn diff(args: &[String]) -> Result<bool, String> {
+    let mut inputs = vec![];
+    let mut output = PathBuf::from("diff.png");
+    let mut tolerance = 16;
+    let mut semantic = false;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                output = args.next().ok_or(USAGE)?.into();
+            }
+            "-t" | "--tolerance" => {
+                let value = args.next().ok_or(USAGE)?;
+                tolerance = value
+                    .parse()
+                    .map_err(|_| format!("Invalid tolerance {}", value))?;
+            }
+            "-s" | "--semantic" => semantic = true,
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if inputs.len() != 2 {
+        return Err(USAGE.to_string());
+    }
+
+    let a = read_job(&inputs[0])?;
+    let b = read_job(&inputs[1])?;
+
+    if semantic {
+        let diff = diff_jobs(&a, &b);
+        for change in &diff.changes {
+            println!("{}", change);
+        }
+        println!("{} changes", diff.changes.len());
+        return Ok(diff.is_same());
+    }
+
+    let (diff, composite) = diff_renders(&a, &b, tolerance).ok_or("Nothing was rendered")?;
+
+    composite.save_png(&output)?;
+
+    println!(
+        "{} changed pixels ({:.2}%) in {} regions",
+        diff.changed_pixels,
+        diff.ratio() * 100.0,
+        diff.regions.len()
+    );
+    for region in &diff.regions {
+        println!(
+            "  x {} y {} w {} h {}",
+            region.x, region.y, region.w, region.h
+        );
+    }
+    println!("Wrote {}", output.display());
+
+    Ok(diff.is_same())
+}
+
+fn pace(args: &[String]) -> Result<bool, String> {
+    let mut inputs = vec![];
+    let mut link = Link::serial(9600);
+    let mut realtime = false;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-b" | "--baud" => {
+                let value = args.next().ok_or(USAGE)?;
+                let baud = value
+                    .parse()
+                    .map_err(|_| format!("Invalid baud rate {}", value))?;
+                link = Link::serial(baud);
+            }
+            "-u" | "--usb" => link = Link::usb(),
+            "-r" | "--realtime" => realtime = true,
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if inputs.len() != 1 {
+        return Err(USAGE.to_string());
+    }
+
+    let bytes = read_job(&inputs[0])?;
+
+    let mut length_renderer: Box<dyn OutputRenderer<ReceiptLength>> =
+        Box::new(LengthRenderer::new());
+    let renderer = Renderer::new(&mut length_renderer, DebugProfile::default());
+    let stream = StreamRenderer::new(thermal_parser::new_esc_pos_parser(), renderer);
+    let mut paced = PacedRenderer::new(stream, link);
+    paced.realtime = realtime;
+
+    let mut jobs = 0;
+    paced.render_with(&bytes, |timed| {
+        let description = match timed.event {
+            StreamEvent::Output(length) => {
+                jobs += 1;
+                format!("Job {} ended, {} dots of paper", jobs, length.height)
+            }
+            StreamEvent::Error(error) => format!("{:?}", error),
+            StreamEvent::Event(event) => format!("{:?}", event.kind),
+            StreamEvent::Display(display) => format!("Display {:?}", display.text),
+            _ => return,
+        };
+        println!("{:>9.3}s {}", timed.at.as_secs_f32(), description);
+    });
+
+    Ok(true)
+}
+
+fn trace(args: &[String]) -> Result<bool, String> {
+    let mut inputs = vec![];
+    let mut format = TraceFormat::Markdown;
+
+    for arg in args {
+        match arg.as_str() {
+            "-t" | "--text" => format = TraceFormat::Text,
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if inputs.len() != 1 {
+        return Err(USAGE.to_string());
+    }
+
+    let bytes = read_job(&inputs[0])?;
+    let traces = TraceRenderer::render(&bytes, format, None);
+
+    for trace in &traces.output {
+        println!("{}", trace.content);
+    }
+    for error in &traces.errors {
+        eprintln!("{:?}", error);
+    }
+
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(|a| a.as_str()) {
+        Some("diff") => diff(&args[1..]),
+        Some("pace") => pace(&args[1..]),
+        Some("trace") => trace(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Event Renderer
+//!
+//! The event renderer sends layout events through a channel while
+//! the job renders instead of collecting them until the end, so a
+//! preview can be painted while a long job is still rendering.
+//!
+//! Elements are the same as the ones of the JSON renderer. Text is
+//! sent one line at a time.
+//!
+//! The channel is bounded, when the receiver falls behind the
+//! renderer waits for it to catch up. When the receiver is dropped
+//! the renderer stops sending and reports an error.
+//!
+//! ```no_run
+//! use std::thread;
+//! use thermal_renderer::event_renderer::{EventRenderer, LayoutEvent};
+//!
+//! let bytes = std::fs::read("receipt.bin").unwrap();
+//! let (sender, receiver) = EventRenderer::channel();
+//!
+//! thread::spawn(move || EventRenderer::render(&bytes, sender, None));
+//!
+//! for event in receiver {
+//!     if let LayoutEvent::Element(element) = event {
+//!         println!("{}", element.to_json());
+//!     }
+//! }
+//! ```
+
+use crate::json_renderer::{Bounds, JsonRenderer, LayoutElement, ReceiptLayout};
+use crate::renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
+use std::mem;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use thermal_parser::command::DeviceCommand;
+use thermal_parser::context::{Context, Rotation, TextJustify};
+use thermal_parser::graphics::{Image, VectorGraphic};
+use thermal_parser::text::TextSpan;
+
+/// Events the channel holds before the renderer waits
+pub const CHANNEL_BOUND: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutEvent {
+    /// A new receipt (output) started
+    RenderStarted,
+    PageStarted,
+    /// Page mode elements inside the bounds were deleted
+    PageCleared {
+        bounds: Bounds,
+    },
+    Element(LayoutElement),
+    /// The receipt is finished
+    RenderEnded {
+        width: u32,
+        height: u32,
+    },
+}
+
+/// EventSummary is the main output for the event renderer, the
+/// elements themselves were already sent
+pub struct EventSummary {
+    pub width: u32,
+    pub height: u32,
+    /// Events sent for this receipt
+    pub events: usize,
+}
+
+pub struct EventRenderer {
+    sender: SyncSender<LayoutEvent>,
+    layout: JsonRenderer,
+    events: usize,
+    disconnected: bool,
+    errors: Vec<String>,
+}
+
+impl EventRenderer {
+    pub fn new(sender: SyncSender<LayoutEvent>) -> Self {
+        Self {
+            sender,
+            layout: JsonRenderer::new(),
+            events: 0,
+            disconnected: false,
+            errors: vec![],
+        }
+    }
+
+    /// A bounded channel for the renderer
+    pub fn channel() -> (SyncSender<LayoutEvent>, Receiver<LayoutEvent>) {
+        sync_channel(CHANNEL_BOUND)
+    }
+
+    /// Renders the bytes and sends the events, this blocks while
+    /// the channel is full
+    pub fn render(
+        bytes: &[u8],
+        sender: SyncSender<LayoutEvent>,
+        debug_profile: Option<DebugProfile>,
+    ) -> RenderOutput<EventSummary> {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(EventRenderer::new(sender));
+        let mut renderer = Renderer::new(&mut child_renderer, debug_profile.unwrap_or_default());
+        renderer.render(bytes)
+    }
+
+    fn send(&mut self, event: LayoutEvent) {
+        if self.disconnected {
+            return;
+        }
+
+        match self.sender.send(event) {
+            Ok(()) => self.events += 1,
+            Err(_) => {
+                self.disconnected = true;
+                self.errors
+                    .push("Event receiver disconnected, events were dropped".to_string());
+            }
+        }
+    }
+
+    //Sends what the layout added and keeps the layout empty, so
+    //elements are never merged with elements that were already sent
+    fn send_elements(&mut self) {
+        for element in mem::take(&mut self.layout.elements) {
+            self.send(LayoutEvent::Element(element));
+        }
+    }
+}
+
+impl OutputRenderer<EventSummary> for EventRenderer {
+    fn set_debug_profile(&mut self, profile: DebugProfile) {
+        self.layout.set_debug_profile(profile);
+    }
+
+    fn begin_render(&mut self, context: &mut Context) {
+        self.layout.begin_render(context);
+        self.events = 0;
+        self.send(LayoutEvent::RenderStarted);
+    }
+
+    fn page_begin(&mut self, _context: &mut Context) {
+        self.send(LayoutEvent::PageStarted);
+    }
+
+    fn page_clear(&mut self, context: &mut Context) {
+        let area = context.page_mode.print_area();
+        let bounds = Bounds {
+            x: area.x,
+            y: area.y,
+            w: area.w,
+            h: area.h,
+        };
+
+        self.send(LayoutEvent::PageCleared { bounds });
+    }
+
+    fn page_area_changed(
+        &mut self,
+        _context: &mut Context,
+        _rotation: Rotation,
+        _width: u32,
+        _height: u32,
+    ) {
+    }
+
+    fn render_page(&mut self, context: &mut Context) {
+        self.layout.render_page(context);
+        self.send_elements();
+    }
+
+    fn render_graphics(&mut self, context: &mut Context, graphics: &Vec<VectorGraphic>) {
+        self.layout.render_graphics(context, graphics);
+        self.send_elements();
+    }
+
+    fn render_code(
+        &mut self,
+        context: &mut Context,
+        graphics: &Vec<VectorGraphic>,
+        symbology: &str,
+        label: &str,
+        payload: &str,
+        hri: Option<&str>,
+    ) {
+        self.layout
+            .render_code(context, graphics, symbology, label, payload, hri);
+        self.send_elements();
+    }
+
+    fn render_image(&mut self, context: &mut Context, image: &Image) {
+        self.layout.render_image(context, image);
+        self.send_elements();
+    }
+
+    fn render_text(
+        &mut self,
+        context: &mut Context,
+        spans: &Vec<TextSpan>,
+        x_offset: u32,
+        max_height: u32,
+        text_justify: TextJustify,
+    ) {
+        self.layout
+            .render_text(context, spans, x_offset, max_height, text_justify);
+        self.send_elements();
+    }
+
+    fn device_command(&mut self, context: &mut Context, command: &DeviceCommand) {
+        self.layout.device_command(context, command);
+        self.send_elements();
+    }
+
+    fn get_render_errors(&mut self) -> Vec<String> {
+        mem::take(&mut self.errors)
+    }
+
+    fn end_render(&mut self, context: &mut Context) -> EventSummary {
+        let ReceiptLayout { width, height, .. } = self.layout.end_render(context);
+        self.send(LayoutEvent::RenderEnded { width, height });
+
+        EventSummary {
+            width,
+            height,
+            events: mem::take(&mut self.events),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Font Subsetting
+//!
+//! Embedding a whole font in every self-contained document costs
+//! about 200kb of base64 per face, while a receipt only uses a few
+//! dozen characters. Subsetting cuts a TrueType font down to the
+//! glyphs of the characters that are used.
+//!
+//! Glyph ids don't change, so every table that refers to glyphs by
+//! id stays valid. The outlines of unused glyphs are removed, the
+//! character map only maps the used characters and the glyph names
+//! are dropped. Glyphs that are made of other glyphs keep their parts.
+//!
+//! ```
+//! use std::collections::BTreeSet;
+//! use thermal_renderer::font_subset::subset_font;
+//!
+//! let font = include_bytes!("../resources/fonts/JetBrainsMonoNL-Medium.ttf");
+//! let chars: BTreeSet<char> = "TOTAL 12.50".chars().collect();
+//!
+//! let subset = subset_font(font, &chars).unwrap();
+//! assert!(subset.len() < font.len() / 4);
+//! ```
+
+use std::collections::BTreeSet;
+
+//Component flags of composite glyphs
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+//The checksum of a whole font adds up to this
+const CHECKSUM_MAGIC: u32 = 0xB1B0AFBA;
+
+//The tag and data of every table
+type Tables<'a> = Vec<([u8; 4], &'a [u8])>;
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, String> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| format!("Font data ends at {}", offset))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| format!("Font data ends at {}", offset))
+}
+
+/// Cuts the font down to the glyphs of the chars, chars the font
+/// doesn't have are left out
+pub fn subset_font(font: &[u8], chars: &BTreeSet<char>) -> Result<Vec<u8>, String> {
+    let face = ttf_parser::Face::from_slice(font, 0).map_err(|e| e.to_string())?;
+    let tables = read_tables(font)?;
+
+    let table = |tag: &[u8; 4]| -> Result<&[u8], String> {
+        tables
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, data)| *data)
+            .ok_or_else(|| format!("Font has no {} table", String::from_utf8_lossy(tag)))
+    };
+
+    let head = table(b"head")?;
+    let loca = table(b"loca")?;
+    let glyf = table(b"glyf")?;
+    let post = table(b"post")?;
+    let long_loca = read_u16(head, 50)? == 1;
+    let num_glyphs = read_u16(table(b"maxp")?, 4)? as usize;
+
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    for index in 0..=num_glyphs {
+        offsets.push(match long_loca {
+            true => read_u32(loca, index * 4)? as usize,
+            false => read_u16(loca, index * 2)? as usize * 2,
+        });
+    }
+
+    let glyph = |id: u16| -> &[u8] {
+        let start = offsets.get(id as usize).copied().unwrap_or(0);
+        let end = offsets.get(id as usize + 1).copied().unwrap_or(0);
+        glyf.get(start..end).unwrap_or(&[])
+    };
+
+    //The character map only has the basic multilingual plane
+    let mapped: Vec<(u16, u16)> = chars
+        .iter()
+        .filter(|c| (**c as u32) < 0xFFFF)
+        .filter_map(|c| face.glyph_index(*c).map(|id| (*c as u16, id.0)))
+        .collect();
+
+    //The missing glyph (.notdef) is always kept
+    let mut keep: BTreeSet<u16> = mapped.iter().map(|(_, id)| *id).collect();
+    keep.insert(0);
+
+    let mut pending: Vec<u16> = keep.iter().copied().collect();
+    while let Some(id) = pending.pop() {
+        for component in components(glyph(id)) {
+            if keep.insert(component) {
+                pending.push(component);
+            }
+        }
+    }
+
+    //Unused glyphs become empty, every glyph starts on 4 bytes
+    let mut new_glyf = vec![];
+    let mut new_loca = vec![];
+    for id in 0..=num_glyphs {
+        let offset = new_glyf.len();
+        match long_loca {
+            true => new_loca.extend_from_slice(&(offset as u32).to_be_bytes()),
+            false => new_loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes()),
+        }
+
+        if id < num_glyphs && keep.contains(&(id as u16)) {
+            new_glyf.extend_from_slice(glyph(id as u16));
+            while !new_glyf.len().is_multiple_of(4) {
+                new_glyf.push(0);
+            }
+        }
+    }
+
+    //Version 3 of post has no glyph names
+    let mut new_post = post
+        .get(..32)
+        .ok_or("Font post table is too short")?
+        .to_vec();
+    new_post[..4].copy_from_slice(&0x00030000u32.to_be_bytes());
+
+    let mut new_tables: Vec<([u8; 4], Vec<u8>)> = vec![];
+    for (tag, data) in &tables {
+        let data = match tag {
+            //The signature is not valid for the changed font
+            b"DSIG" => continue,
+            b"glyf" => new_glyf.clone(),
+            b"loca" => new_loca.clone(),
+            b"cmap" => character_map(&mapped),
+            b"post" => new_post.clone(),
+            _ => data.to_vec(),
+        };
+        new_tables.push((*tag, data));
+    }
+
+    Ok(write_font(&font[..4], new_tables))
+}
+
+fn read_tables(font: &[u8]) -> Result<Tables<'_>, String> {
+    let count = read_u16(font, 4)? as usize;
+    let mut tables = Vec::with_capacity(count);
+
+    for index in 0..count {
+        let record = 12 + index * 16;
+        let tag = font
+            .get(record..record + 4)
+            .ok_or("Font table directory is too short")?;
+        let offset = read_u32(font, record + 8)? as usize;
+        let length = read_u32(font, record + 12)? as usize;
+        let data = font
+            .get(offset..offset + length)
+            .ok_or("Font table is outside of the font")?;
+
+        tables.push(([tag[0], tag[1], tag[2], tag[3]], data));
+    }
+
+    Ok(tables)
+}
+
+//The glyphs a composite glyph is made of
+fn components(glyph: &[u8]) -> Vec<u16> {
+    let mut components = vec![];
+
+    //Simple glyphs have a positive number of contours
+    match read_u16(glyph, 0) {
+        Ok(contours) if (contours as i16) < 0 => {}
+        _ => return components,
+    }
+
+    let mut offset = 10;
+    while let (Ok(flags), Ok(id)) = (read_u16(glyph, offset), read_u16(glyph, offset + 2)) {
+        components.push(id);
+        offset += 4;
+        offset += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+
+        if flags & WE_HAVE_A_SCALE != 0 {
+            offset += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+
+    components
+}
+
+//A windows unicode character map (format 4) with a segment per char
+fn character_map(mapped: &[(u16, u16)]) -> Vec<u8> {
+    let mut segments: Vec<(u16, u16)> = mapped.to_vec();
+    segments.sort();
+    segments.dedup_by_key(|(c, _)| *c);
+
+    //The last segment has to end at 0xFFFF
+    segments.push((0xFFFF, 0));
+
+    let seg_count = segments.len() as u16;
+    let entry_selector = 15 - seg_count.leading_zeros() as u16;
+    let search_range = 2 << entry_selector;
+    let length = 16 + seg_count * 8;
+
+    let mut subtable = vec![];
+    for value in [
+        4,
+        length,
+        0,
+        seg_count * 2,
+        search_range,
+        entry_selector,
+        seg_count * 2 - search_range,
+    ] {
+        subtable.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let ends = segments.iter().map(|(c, _)| *c);
+    let starts = segments.iter().map(|(c, _)| *c);
+    let deltas = segments.iter().map(|(c, id)| id.wrapping_sub(*c));
+
+    subtable.extend(ends.flat_map(u16::to_be_bytes));
+    subtable.extend_from_slice(&[0, 0]);
+    subtable.extend(starts.flat_map(u16::to_be_bytes));
+    subtable.extend(deltas.flat_map(u16::to_be_bytes));
+    subtable.extend(segments.iter().flat_map(|_| [0, 0]));
+
+    let mut cmap = vec![];
+    for value in [0u16, 1, 3, 1] {
+        cmap.extend_from_slice(&value.to_be_bytes());
+    }
+    cmap.extend_from_slice(&12u32.to_be_bytes());
+    cmap.extend(subtable);
+    cmap
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+//Writes the tables sorted by tag with a new directory and checksums
+fn write_font(version: &[u8], mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = 16 << entry_selector;
+
+    let mut font = version.to_vec();
+    for value in [
+        count,
+        search_range,
+        entry_selector,
+        count * 16 - search_range,
+    ] {
+        font.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut offset = 12 + tables.len() * 16;
+    let mut head_offset = None;
+
+    for (tag, data) in tables.iter_mut() {
+        //The adjustment is calculated over the whole font at the end
+        if tag == b"head" && data.len() >= 12 {
+            data[8..12].copy_from_slice(&[0; 4]);
+            head_offset = Some(offset);
+        }
+
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&checksum(data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().div_ceil(4) * 4;
+    }
+
+    for (_, data) in &tables {
+        font.extend_from_slice(data);
+        while !font.len().is_multiple_of(4) {
+            font.push(0);
+        }
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = CHECKSUM_MAGIC.wrapping_sub(checksum(&font));
+        font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+
+    font
+}
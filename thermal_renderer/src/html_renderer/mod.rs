@@ -13,17 +13,60 @@
 //!
 //! Barcodes and Qr Codes are rendered to SVG that is
 //! inlined into the html content.
+//!
+//! Styles can be themed with the css custom properties
+//! `--thermal-background`, `--thermal-paper-color`, `--thermal-ink-color`,
+//! `--thermal-color-2`, `--thermal-color-3`, `--thermal-font`,
+//! `--thermal-font-size` and `--thermal-line-height`. The wrapping
+//! elements have the stable classes `thermal-preview`, `thermal-receipt`
+//! and `thermal-content`.
+//!
+//! Fonts are not embedded by default, the browser's monospace
+//! font is used. Setting `embed_font` embeds the receipt font so
+//! the html file is fully self-contained. The embedded fonts are
+//! subset to the characters of the receipt and faces that are not
+//! used (bold, italic) are left out, unless `subset_font` is off.
+//! The first patch is sent before any text, it embeds whole fonts.
+//!
+//! There are two layouts, the positioned layout places every element
+//! exactly where it would be printed. The semantic layout emits lines
+//! of styled spans that flow like a normal document, which is better
+//! for embedding receipts in emails and copying text.
+//!
+//! For live previews the renderer can send patches instead of
+//! waiting for the whole document. The first patch is the document
+//! without content, every row is appended to the `thermal-content`
+//! element as it is rendered and the last patch sets the final
+//! style of the `thermal-receipt` element. Combined with the
+//! `StreamRenderer`, the patches of every chunk can be sent to a
+//! websocket client.
 
 mod thermal_html;
 
-use crate::html_renderer::thermal_html::{encode_html_image, graphics_to_svg, spans_to_html};
+use crate::html_renderer::thermal_html::{
+    code_to_semantic_svg, code_to_svg, embedded_font_face, encode_html_image, font_face_index,
+    graphics_to_semantic_svg, graphics_to_svg, image_to_semantic_html, spans_to_html,
+    spans_to_semantic_html,
+};
 use crate::image_renderer::thermal_image::ThermalImage;
+use crate::json_renderer::json_string;
 use crate::renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
+use std::collections::BTreeSet;
+use std::sync::mpsc::{channel, Receiver, Sender};
 use thermal_parser::context::{Context, PrintDirection, Rotation, TextJustify};
 use thermal_parser::graphics::{Image, ImageFlow, VectorGraphic};
 use thermal_parser::text::TextSpan;
 
 static TEMPLATE: &str = include_str!("../../resources/templates/thermal.html");
+static SEMANTIC_TEMPLATE: &str = include_str!("../../resources/templates/thermal_semantic.html");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HtmlLayout {
+    /// Elements are absolutely positioned to match the printed receipt
+    Positioned,
+    /// Flow based lines (`<div class="line">`) with styled spans
+    Semantic,
+}
 
 /// ReceiptHtml is the main output for the html renderer
 /// the content contains all html necessary to render the
@@ -32,12 +75,49 @@ pub struct ReceiptHtml {
     pub content: String,
 }
 
+/// A change to the html document of the receipt that is rendering
+#[derive(Debug, Clone, PartialEq)]
+pub enum HtmlPatch {
+    /// The whole document without content, a new receipt started
+    Begin { html: String },
+    /// Html to append to the `thermal-content` element
+    Append { html: String },
+    /// The receipt is finished, the style replaces the style of
+    /// the `thermal-receipt` element
+    End { style: String },
+}
+
+impl HtmlPatch {
+    /// Serializes the patch for sending it to a client
+    pub fn to_json(&self) -> String {
+        match self {
+            HtmlPatch::Begin { html } => {
+                format!("{{\"type\":\"begin\",\"html\":{}}}", json_string(html))
+            }
+            HtmlPatch::Append { html } => {
+                format!("{{\"type\":\"append\",\"html\":{}}}", json_string(html))
+            }
+            HtmlPatch::End { style } => {
+                format!("{{\"type\":\"end\",\"style\":{}}}", json_string(style))
+            }
+        }
+    }
+}
+
 pub struct HtmlRenderer {
+    pub layout: HtmlLayout,
+    pub embed_font: bool,
+    /// Embed only the glyphs of the characters that are used
+    pub subset_font: bool,
     pub last_y: u32,
     pub content: Vec<String>,
     pub template: String,
     pub page_image: ThermalImage,
     pub debug_profile: DebugProfile,
+    /// Receives patches while the receipt renders
+    pub patches: Option<Sender<HtmlPatch>>,
+    //Characters of each font face, see font_face_index
+    used_chars: [BTreeSet<char>; 4],
 }
 
 pub struct HtmlRow {
@@ -58,21 +138,70 @@ impl HtmlRow {
 
 impl HtmlRenderer {
     pub fn new() -> Self {
+        Self::new_with_layout(HtmlLayout::Positioned)
+    }
+
+    pub fn new_with_layout(layout: HtmlLayout) -> Self {
+        let template = match layout {
+            HtmlLayout::Positioned => TEMPLATE,
+            HtmlLayout::Semantic => SEMANTIC_TEMPLATE,
+        };
+
         Self {
+            layout,
+            embed_font: false,
+            subset_font: true,
             last_y: 0,
             content: vec![],
-            template: TEMPLATE.to_string(),
+            template: template.to_string(),
             page_image: ThermalImage::new(0),
             debug_profile: DebugProfile::default(),
+            patches: None,
+            used_chars: Default::default(),
         }
     }
 
+    /// A renderer that sends patches of the document to the receiver
+    /// while it renders
+    pub fn new_with_patches(layout: HtmlLayout) -> (Self, Receiver<HtmlPatch>) {
+        let (sender, receiver) = channel();
+        let mut html_renderer = Self::new_with_layout(layout);
+        html_renderer.patches = Some(sender);
+        (html_renderer, receiver)
+    }
+
     /// This is the normal way to render bytes to an html
-    pub fn render(
-        bytes: &Vec<u8>,
+    pub fn render(bytes: &[u8], debug_profile: Option<DebugProfile>) -> RenderOutput<ReceiptHtml> {
+        Self::render_with_layout(bytes, debug_profile, HtmlLayout::Positioned)
+    }
+
+    /// Render bytes to html with the given layout
+    pub fn render_with_layout(
+        bytes: &[u8],
+        debug_profile: Option<DebugProfile>,
+        layout: HtmlLayout,
+    ) -> RenderOutput<ReceiptHtml> {
+        Self::render_with_renderer(HtmlRenderer::new_with_layout(layout), bytes, debug_profile)
+    }
+
+    /// Render bytes to a single html file with the font embedded,
+    /// nothing is loaded from outside the file
+    pub fn render_self_contained(
+        bytes: &[u8],
+        debug_profile: Option<DebugProfile>,
+        layout: HtmlLayout,
+    ) -> RenderOutput<ReceiptHtml> {
+        let mut html_renderer = HtmlRenderer::new_with_layout(layout);
+        html_renderer.embed_font = true;
+        Self::render_with_renderer(html_renderer, bytes, debug_profile)
+    }
+
+    fn render_with_renderer(
+        html_renderer: HtmlRenderer,
+        bytes: &[u8],
         debug_profile: Option<DebugProfile>,
     ) -> RenderOutput<ReceiptHtml> {
-        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(HtmlRenderer::new());
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(html_renderer);
         let mut renderer = Renderer::new(
             &mut child_renderer,
             debug_profile.unwrap_or(DebugProfile::default()),
@@ -80,14 +209,101 @@ impl HtmlRenderer {
         renderer.render(bytes)
     }
 
+    fn send_patch(&mut self, patch: HtmlPatch) {
+        if let Some(patches) = &self.patches {
+            //Nobody is listening anymore
+            if patches.send(patch).is_err() {
+                self.patches = None;
+            }
+        }
+    }
+
     fn push_row(&mut self, row: HtmlRow) {
-        self.content.push(format!(
-            "<p style='height: {}px; margin-top: {}px'>{}</p>",
-            row.height,
-            row.y.saturating_sub(self.last_y),
-            row.content
-        ));
+        let start = self.content.len();
+
+        match self.layout {
+            HtmlLayout::Positioned => {
+                self.content.push(format!(
+                    "<p style='height: {}px; margin-top: {}px'>{}</p>",
+                    row.height,
+                    row.y.saturating_sub(self.last_y),
+                    row.content
+                ));
+            }
+            HtmlLayout::Semantic => {
+                //Feeds and blank lines become spacing between lines
+                let space = row.y.saturating_sub(self.last_y);
+                if space > 0 {
+                    self.content.push(format!(
+                        "<div class='space' style='height: {}px'></div>",
+                        space
+                    ));
+                }
+                self.content.push(row.content);
+            }
+        }
         self.last_y = row.y + row.height;
+
+        if self.patches.is_some() {
+            let html = self.content[start..].join("");
+            self.send_patch(HtmlPatch::Append { html });
+        }
+    }
+
+    //The font faces to embed, subset fonts only have the characters
+    //that were rendered so far
+    fn font_face(&self, subset: bool) -> String {
+        match (self.embed_font, subset && self.subset_font) {
+            (false, _) => "".to_string(),
+            (true, false) => embedded_font_face(None),
+            (true, true) => embedded_font_face(Some(&self.used_chars)),
+        }
+    }
+
+    fn document(
+        &self,
+        context: &Context,
+        content: &str,
+        padding_bottom: u32,
+        font_face: &str,
+    ) -> String {
+        self.template
+            .replace("{{font-face}}", font_face)
+            .replace(
+                "{{color-0}}",
+                &*context.graphics.render_colors.paper_color.as_hex(),
+            )
+            .replace(
+                "{{color-1}}",
+                &*context.graphics.render_colors.color_1.as_hex(),
+            )
+            .replace(
+                "{{color-2}}",
+                &*context.graphics.render_colors.color_2.as_hex(),
+            )
+            .replace(
+                "{{color-3}}",
+                &*context.graphics.render_colors.color_3.as_hex(),
+            )
+            .replace("{{content}}", content)
+            .replace(
+                "{{receipt-style}}",
+                &self.receipt_style(context, padding_bottom),
+            )
+    }
+
+    fn receipt_style(&self, context: &Context, padding_bottom: u32) -> String {
+        format!(
+            "{}: {}px; padding-left: {}px; padding-right: {}px; padding-bottom: {}px;",
+            match self.layout {
+                HtmlLayout::Positioned => "width",
+                HtmlLayout::Semantic => "max-width",
+            },
+            context.graphics.paper_area.w,
+            context.graphics.paper_area.x,
+            context.graphics.paper_area.y,
+            padding_bottom
+        )
     }
 }
 
@@ -97,7 +313,7 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
     }
 
     fn begin_render(&mut self, context: &mut Context) {
-        self.page_image.debug_profile = self.debug_profile;
+        self.page_image.debug_profile = self.debug_profile.clone();
         self.page_image.paper_color = context.graphics.render_colors.paper_color;
 
         //Initialize image area for page mode
@@ -114,6 +330,11 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
         self.last_y = context.get_y();
 
         self.content.clear();
+        self.used_chars = Default::default();
+        if self.patches.is_some() {
+            let html = self.document(context, "", 0, &self.font_face(false));
+            self.send_patch(HtmlPatch::Begin { html });
+        }
         self.push_row(HtmlRow {
             y: 0,
             height: context.get_y(),
@@ -121,8 +342,14 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
         })
     }
 
-    fn page_begin(&mut self, _context: &mut Context) {
+    fn page_begin(&mut self, context: &mut Context) {
         self.page_image.set_width(0);
+        self.page_image.compositing = context.profile.page_compositing;
+    }
+
+    fn page_clear(&mut self, context: &mut Context) {
+        self.page_image
+            .clear_print_area(&context.page_mode.print_area());
     }
 
     fn page_area_changed(
@@ -141,7 +368,7 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
             _ => {}
         }
 
-        if width > self.page_image.width {
+        if width > self.page_image.width() {
             self.page_image.expand_to_width(width)
         }
         if height > self.page_image.get_height() {
@@ -188,7 +415,12 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
             upside_down: false,
         };
 
-        self.push_row(encode_html_image(&image));
+        match self.layout {
+            HtmlLayout::Positioned => self.push_row(encode_html_image(&image)),
+            HtmlLayout::Semantic => {
+                self.push_row(image_to_semantic_html(&image, &TextJustify::Left))
+            }
+        }
     }
 
     fn render_graphics(&mut self, context: &mut Context, graphics: &Vec<VectorGraphic>) {
@@ -201,7 +433,40 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
                 }
             }
         } else {
-            self.push_row(graphics_to_svg(graphics));
+            match self.layout {
+                HtmlLayout::Positioned => self.push_row(graphics_to_svg(graphics)),
+                HtmlLayout::Semantic => {
+                    self.push_row(graphics_to_semantic_svg(graphics, &context.text.justify))
+                }
+            }
+        }
+    }
+
+    fn render_code(
+        &mut self,
+        context: &mut Context,
+        graphics: &Vec<VectorGraphic>,
+        symbology: &str,
+        label: &str,
+        payload: &str,
+        _hri: Option<&str>,
+    ) {
+        if context.page_mode.enabled {
+            self.render_graphics(context, graphics);
+            return;
+        }
+
+        match self.layout {
+            HtmlLayout::Positioned => {
+                self.push_row(code_to_svg(graphics, symbology, label, payload))
+            }
+            HtmlLayout::Semantic => self.push_row(code_to_semantic_svg(
+                graphics,
+                &context.text.justify,
+                symbology,
+                label,
+                payload,
+            )),
         }
     }
 
@@ -209,7 +474,12 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
         if context.page_mode.enabled {
             self.page_image.put_render_img(image);
         } else {
-            self.push_row(encode_html_image(image));
+            match self.layout {
+                HtmlLayout::Positioned => self.push_row(encode_html_image(image)),
+                HtmlLayout::Semantic => {
+                    self.push_row(image_to_semantic_html(image, &context.text.justify))
+                }
+            }
         }
     }
 
@@ -219,7 +489,7 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
         spans: &Vec<TextSpan>,
         x_offset: u32,
         max_height: u32,
-        _text_justify: TextJustify,
+        text_justify: TextJustify,
     ) {
         if context.page_mode.enabled {
             for span in spans {
@@ -227,6 +497,20 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
                     self.page_image.render_span(x_offset, max_height, span);
                 }
             }
+            return;
+        }
+
+        for span in spans {
+            let index = font_face_index(span.bold, span.italic);
+            self.used_chars[index].extend(span.text.chars());
+        }
+
+        if self.layout == HtmlLayout::Semantic {
+            self.push_row(spans_to_semantic_html(
+                spans,
+                &text_justify,
+                &context.graphics.render_colors,
+            ));
         } else {
             self.push_row(spans_to_html(
                 spans,
@@ -241,35 +525,13 @@ impl OutputRenderer<ReceiptHtml> for HtmlRenderer {
     fn end_render(&mut self, context: &mut Context) -> ReceiptHtml {
         let padding_bottom = context.get_y().saturating_sub(self.last_y);
 
-        let content = self
-            .template
-            .replace(
-                "{{color-0}}",
-                &*context.graphics.render_colors.paper_color.as_hex(),
-            )
-            .replace(
-                "{{color-1}}",
-                &*context.graphics.render_colors.color_1.as_hex(),
-            )
-            .replace(
-                "{{color-2}}",
-                &*context.graphics.render_colors.color_2.as_hex(),
-            )
-            .replace(
-                "{{color-3}}",
-                &*context.graphics.render_colors.color_3.as_hex(),
-            )
-            .replace("{{content}}", &self.content.join(""))
-            .replace(
-                "{{receipt-style}}",
-                &*format!(
-                    "width: {}px; padding-left: {}px; padding-right: {}px; padding-bottom: {}px;",
-                    context.graphics.paper_area.w,
-                    context.graphics.paper_area.x,
-                    context.graphics.paper_area.y,
-                    padding_bottom
-                ),
-            );
+        if self.patches.is_some() {
+            let style = self.receipt_style(context, padding_bottom);
+            self.send_patch(HtmlPatch::End { style });
+        }
+
+        let font_face = self.font_face(true);
+        let content = self.document(context, &self.content.join(""), padding_bottom, &font_face);
 
         ReceiptHtml { content }
     }
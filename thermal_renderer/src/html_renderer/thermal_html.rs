@@ -1,12 +1,101 @@
+use crate::font_subset::subset_font;
 use crate::html_renderer::HtmlRow;
 use base64::engine::general_purpose;
 use base64::Engine;
 use png::{ColorType, Encoder};
-use thermal_parser::context::{Font, RenderColors};
+use std::collections::BTreeSet;
+use thermal_parser::context::{Font, RenderColors, TextJustify};
 use thermal_parser::graphics::{Image, VectorGraphic, RGBA};
 use thermal_parser::text::TextSpan;
 
 pub fn encode_html_image(image: &Image) -> HtmlRow {
+    HtmlRow {
+        y: image.y,
+        height: image.h,
+        content: format!(
+            "<img style='left: {}px;' class='img' width='{}' src='{}' />",
+            image.x,
+            image.w,
+            image_data_uri(image)
+        ),
+    }
+}
+
+/// The receipt fonts with their css weight and style, in the
+/// order regular, bold, italic and bold italic
+const FONT_FACES: [(&[u8], &str, &str); 4] = [
+    (
+        include_bytes!("../../resources/fonts/JetBrainsMonoNL-Medium.ttf"),
+        "normal",
+        "normal",
+    ),
+    (
+        include_bytes!("../../resources/fonts/JetBrainsMonoNL-Bold.ttf"),
+        "bold",
+        "normal",
+    ),
+    (
+        include_bytes!("../../resources/fonts/JetBrainsMonoNL-MediumItalic.ttf"),
+        "normal",
+        "italic",
+    ),
+    (
+        include_bytes!("../../resources/fonts/JetBrainsMonoNL-BoldItalic.ttf"),
+        "bold",
+        "italic",
+    ),
+];
+
+/// Index of the face in FONT_FACES
+pub fn font_face_index(bold: bool, italic: bool) -> usize {
+    match (bold, italic) {
+        (false, false) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (true, true) => 3,
+    }
+}
+
+/// Css @font-face rules that embed the same fonts used
+/// by the image renderer as base64 data urls. With the chars
+/// used in each face the fonts are subset to those chars and
+/// faces without chars are left out
+pub fn embedded_font_face(used_chars: Option<&[BTreeSet<char>; 4]>) -> String {
+    FONT_FACES
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (font, weight, style))| {
+            let font = match used_chars.map(|chars| &chars[index]) {
+                Some(chars) if chars.is_empty() => return None,
+                //A font that can't be subset is embedded whole
+                Some(chars) => subset_font(font, chars).unwrap_or(font.to_vec()),
+                None => font.to_vec(),
+            };
+
+            Some(format!(
+                "@font-face {{ font-family: 'Thermal Mono'; font-weight: {}; font-style: {}; src: url(data:font/ttf;base64,{}) format('truetype'); }}",
+                weight,
+                style,
+                general_purpose::STANDARD.encode(font)
+            ))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Encodes the image as a png in the base64 data url format,
+/// images that can't be encoded (no width or height) are empty
+pub fn image_data_uri(image: &Image) -> String {
+    match encode_png(image) {
+        Ok(png_data) => format!(
+            "data:image/png;base64, {}",
+            general_purpose::STANDARD_NO_PAD.encode(&png_data)
+        ),
+        Err(_) => "data:,".to_string(),
+    }
+}
+
+fn encode_png(image: &Image) -> Result<Vec<u8>, png::EncodingError> {
     // Create a buffer to hold the PNG image data
     let mut png_data: Vec<u8> = Vec::new();
     let image_data: Vec<u8> = image.as_rgba_u8();
@@ -14,28 +103,64 @@ pub fn encode_html_image(image: &Image) -> HtmlRow {
     let mut encoder = Encoder::new(&mut png_data, image.w, image.h);
     encoder.set_color(ColorType::Rgba);
     encoder.set_depth(png::BitDepth::Eight);
-    let mut writer = encoder.write_header().expect("Failed to write PNG header");
-    writer
-        .write_image_data(&image_data)
-        .expect("Failed to write PNG image data");
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image_data)?;
+    writer.finish()?;
 
-    writer.finish().expect("Error encoding png");
+    Ok(png_data)
+}
 
-    let base64_encoded_image = general_purpose::STANDARD_NO_PAD.encode(&png_data);
+pub fn graphics_to_svg(graphics: &Vec<VectorGraphic>) -> HtmlRow {
+    positioned_svg(graphics, "", "")
+}
 
-    HtmlRow {
-        y: image.y,
-        height: image.h,
-        content: format!(
-            "<img style='left: {}px;' class='img' width='{}' src='data:image/png;base64, {}' />",
-            image.x, image.w, base64_encoded_image
-        ),
+/// Barcodes and 2d codes carry their payload in data attributes
+/// and an accessible label so the svg isn't just a stack of bars
+pub fn code_to_svg(
+    graphics: &Vec<VectorGraphic>,
+    symbology: &str,
+    label: &str,
+    payload: &str,
+) -> HtmlRow {
+    let (attributes, title) = code_attributes(symbology, label, payload);
+    positioned_svg(graphics, &attributes, &title)
+}
+
+fn positioned_svg(graphics: &Vec<VectorGraphic>, attributes: &str, title: &str) -> HtmlRow {
+    match graphics_to_svg_rects(graphics) {
+        None => HtmlRow::empty(),
+        Some((min_x, min_y, width, height, rects)) => HtmlRow {
+            y: min_y,
+            height,
+            content: format!(
+                "<svg style='left: {}px;' class='gfx' width='{}' height='{}'{}>{}{}</svg>",
+                min_x, width, height, attributes, title, rects
+            ),
+        },
     }
 }
 
-pub fn graphics_to_svg(graphics: &Vec<VectorGraphic>) -> HtmlRow {
+//Data attributes keep the symbology as is for scripts, the label
+//that people read is localized
+//                                                                  attributes, title
+fn code_attributes(symbology: &str, label: &str, payload: &str) -> (String, String) {
+    let label = escape_html(&format!("{}: {}", label, payload));
+
+    (
+        format!(
+            " role='img' aria-label='{}' data-symbology='{}' data-payload='{}'",
+            label,
+            escape_html(symbology),
+            escape_html(payload)
+        ),
+        format!("<title>{}</title>", label),
+    )
+}
+
+//                                   min_x, min_y, width, height, rects
+fn graphics_to_svg_rects(graphics: &Vec<VectorGraphic>) -> Option<(u32, u32, u32, u32, String)> {
     if graphics.is_empty() {
-        return HtmlRow::empty();
+        return None;
     }
 
     let mut svg: Vec<String> = vec![];
@@ -69,17 +194,7 @@ pub fn graphics_to_svg(graphics: &Vec<VectorGraphic>) -> HtmlRow {
         }
     }
 
-    HtmlRow {
-        y: min_y,
-        height,
-        content: format!(
-            "<svg style='left: {}px;' class='gfx' width='{}' height='{}'>{}</svg>",
-            min_x,
-            width,
-            height,
-            svg.join("\n")
-        ),
-    }
+    Some((min_x, min_y, width, height, svg.join("\n")))
 }
 
 //                                             y,   height, content
@@ -147,7 +262,7 @@ fn span_to_html(
         class_list.push("s");
     }
 
-    if text.strikethrough > 1 {
+    if text.double_strikethrough {
         class_list.push("sd");
     }
 
@@ -189,7 +304,7 @@ fn span_to_html(
         class_list.push("u");
     }
 
-    if text.underline > 1 {
+    if text.double_underline {
         class_list.push("ud");
     }
 
@@ -216,15 +331,51 @@ fn span_to_html(
     (
         y,
         format!(
-            "<span style='left: {}px; top: {}px' class='{}'>{}</span>",
+            "<span style='left: {}px; top: {}px{}' class='{}'>{}</span>",
             x_offset + x,
             baseline_offset,
+            advance_style(text),
             class_list.join(" "),
-            text.text
+            advance_content(text)
         ),
     )
 }
 
+//Advances are scaled by the width classes, the css is in unscaled dots
+fn unscaled_advances(text: &TextSpan) -> Vec<u32> {
+    let stretch = (text.stretch_width as u32).max(1);
+    match &text.dimensions {
+        Some(dimensions) => dimensions.advances.iter().map(|a| a / stretch).collect(),
+        None => vec![],
+    }
+}
+
+//When every character has the same advance the letter spacing makes
+//the font advance (1ch for a monospace font) match the layout
+fn advance_style(text: &TextSpan) -> String {
+    let advances = unscaled_advances(text);
+    match advances.first() {
+        Some(first) if advances.iter().all(|a| a == first) => {
+            format!("; letter-spacing: calc({}px - 1ch)", first)
+        }
+        _ => "".to_string(),
+    }
+}
+
+//Other text has every character in a box as wide as its advance
+fn advance_content(text: &TextSpan) -> String {
+    let advances = unscaled_advances(text);
+    match advances.first() {
+        Some(first) if advances.iter().any(|a| a != first) => text
+            .text
+            .chars()
+            .zip(advances)
+            .map(|(c, a)| format!("<i style='width: {}px'>{}</i>", a, c))
+            .collect(),
+        _ => text.text.clone(),
+    }
+}
+
 fn color_to_class(color: RGBA, render_colors: &RenderColors, fg: bool) -> &str {
     //Most common color by far
     if color == render_colors.color_1 {
@@ -241,3 +392,201 @@ fn color_to_class(color: RGBA, render_colors: &RenderColors, fg: bool) -> &str {
 
     return if fg { "fg_0" } else { "" };
 }
+
+/// Semantic rows are lines in the html flow, alignment is
+/// done with css instead of absolute positioning
+pub fn image_to_semantic_html(image: &Image, justify: &TextJustify) -> HtmlRow {
+    HtmlRow {
+        y: image.y,
+        height: image.h,
+        content: format!(
+            "<div class='line {}'><img class='image' width='{}' src='{}' alt='' /></div>",
+            justify_class(justify),
+            image.w,
+            image_data_uri(image)
+        ),
+    }
+}
+
+pub fn graphics_to_semantic_svg(graphics: &Vec<VectorGraphic>, justify: &TextJustify) -> HtmlRow {
+    semantic_svg(graphics, justify, "", "")
+}
+
+pub fn code_to_semantic_svg(
+    graphics: &Vec<VectorGraphic>,
+    justify: &TextJustify,
+    symbology: &str,
+    label: &str,
+    payload: &str,
+) -> HtmlRow {
+    let (attributes, title) = code_attributes(symbology, label, payload);
+    semantic_svg(graphics, justify, &attributes, &title)
+}
+
+fn semantic_svg(
+    graphics: &Vec<VectorGraphic>,
+    justify: &TextJustify,
+    attributes: &str,
+    title: &str,
+) -> HtmlRow {
+    match graphics_to_svg_rects(graphics) {
+        None => HtmlRow::empty(),
+        Some((_min_x, min_y, width, height, rects)) => HtmlRow {
+            y: min_y,
+            height,
+            content: format!(
+                "<div class='line {}'><svg width='{}' height='{}' viewBox='0 0 {} {}'{}>{}{}</svg></div>",
+                justify_class(justify),
+                width,
+                height,
+                width,
+                height,
+                attributes,
+                title,
+                rects
+            ),
+        },
+    }
+}
+
+pub fn spans_to_semantic_html(
+    spans: &Vec<TextSpan>,
+    text_justify: &TextJustify,
+    render_colors: &RenderColors,
+) -> HtmlRow {
+    if spans.is_empty() {
+        return HtmlRow::empty();
+    }
+
+    let mut height = 0;
+    let mut min_y = u32::MAX;
+    let mut groups: Vec<(String, String)> = vec![];
+
+    //Where the previous span ended, left justified text starts at the left edge
+    let mut end_x = match (
+        text_justify,
+        spans.first().and_then(|s| s.dimensions.as_ref()),
+    ) {
+        (TextJustify::Left, _) | (_, None) => 0,
+        (_, Some(d)) => d.x,
+    };
+
+    for span in spans {
+        height = height.max(span.character_height);
+
+        //Gaps from tabs or horizontal positioning are filled
+        //with spaces so that the text stays aligned when copied
+        if let Some(d) = &span.dimensions {
+            min_y = min_y.min(d.y);
+
+            let gap = d.x.saturating_sub(end_x) / span.base_character_width.max(1);
+            if gap > 0 {
+                groups.push(("".to_string(), " ".repeat(gap as usize)));
+            }
+            end_x = d.x + d.w;
+        }
+
+        //Neighbouring spans with the same style are merged
+        let classes = semantic_classes(span, render_colors);
+        match groups.last_mut() {
+            Some((last_classes, text)) if *last_classes == classes => text.push_str(&span.text),
+            _ => groups.push((classes, span.text.clone())),
+        }
+    }
+
+    let mut content = vec![];
+
+    for (classes, text) in groups {
+        if classes.is_empty() {
+            content.push(escape_html(&text));
+        } else {
+            content.push(format!(
+                "<span class='{}'>{}</span>",
+                classes,
+                escape_html(&text)
+            ));
+        }
+    }
+
+    HtmlRow {
+        y: if min_y == u32::MAX { 0 } else { min_y },
+        height,
+        content: format!(
+            "<div class='line {}'>{}</div>",
+            justify_class(text_justify),
+            content.join("")
+        ),
+    }
+}
+
+fn semantic_classes(text: &TextSpan, render_colors: &RenderColors) -> String {
+    let mut class_list = vec![];
+
+    if text.bold {
+        class_list.push("bold".to_string());
+    }
+
+    if text.italic {
+        class_list.push("italic".to_string());
+    }
+
+    if text.double_underline {
+        class_list.push("double-underline".to_string());
+    } else if text.underline > 0 {
+        class_list.push("underline".to_string());
+    }
+
+    if text.double_strikethrough {
+        class_list.push("double-strikethrough".to_string());
+    } else if text.strikethrough > 0 {
+        class_list.push("strikethrough".to_string());
+    }
+
+    if text.upside_down {
+        class_list.push("upside-down".to_string());
+    }
+
+    let stretch_w = text.stretch_width as u32;
+    let stretch_h = text.stretch_height as u32;
+
+    if (2..=8).contains(&stretch_w) {
+        class_list.push(format!("width-{}", stretch_w));
+    }
+
+    if (2..=8).contains(&stretch_h) {
+        class_list.push(format!("height-{}", stretch_h));
+    }
+
+    match text.font {
+        Font::B => class_list.push("font-b".to_string()),
+        Font::C => class_list.push("font-c".to_string()),
+        _ => {}
+    }
+
+    let fg_class = color_to_class(text.text_color, render_colors, true);
+    let bg_class = color_to_class(text.background_color, render_colors, false);
+    if !fg_class.is_empty() {
+        class_list.push(fg_class.to_string());
+    }
+    if !bg_class.is_empty() {
+        class_list.push(bg_class.to_string());
+    }
+
+    class_list.join(" ")
+}
+
+fn justify_class(justify: &TextJustify) -> &str {
+    match justify {
+        TextJustify::Center => "center",
+        TextJustify::Right => "right",
+        _ => "left",
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\'', "&#39;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,170 @@
+//! Image Diff
+//!
+//! Compares two rendered images pixel by pixel. Pixels are the
+//! same when their brightness differs by no more than the
+//! tolerance, so small color changes are ignored.
+//!
+//! The diff image shows the second image faded with changed pixels
+//! in red. Changed pixels are also grouped into regions, one per
+//! band of consecutive changed rows, which are outlined in blue on
+//! the composite (first image, diff, second image side by side).
+
+use crate::image_renderer::color::ColorProfile;
+use crate::image_renderer::{ImageRenderer, ReceiptImage};
+
+const CHANGED: [u8; 3] = [255, 0, 0];
+const REGION: [u8; 3] = [0, 0, 255];
+const GUTTER: [u8; 3] = [128, 128, 128];
+const GUTTER_WIDTH: u32 = 8;
+
+/// A rectangle of changed pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+pub struct ImageDiff {
+    /// Faded second image with changed pixels in red
+    pub image: ReceiptImage,
+    pub changed_pixels: u32,
+    pub regions: Vec<Region>,
+}
+
+impl ImageDiff {
+    /// Share of pixels (0.0 - 1.0) that changed
+    pub fn ratio(&self) -> f32 {
+        let total = (self.image.width * self.image.height).max(1);
+        self.changed_pixels as f32 / total as f32
+    }
+
+    pub fn is_same(&self) -> bool {
+        self.changed_pixels == 0
+    }
+}
+
+fn brightness(image: &ReceiptImage, x: u32, y: u32) -> Option<i32> {
+    if x >= image.width || y >= image.height {
+        return None;
+    }
+    let index = ((y * image.width + x) * 3) as usize;
+    let pixel = image.bytes.get(index..index + 3)?;
+    Some((pixel[0] as i32 * 299 + pixel[1] as i32 * 587 + pixel[2] as i32 * 114) / 1000)
+}
+
+/// Compares two images, parts that only one of the images covers
+/// are changed
+pub fn diff_images(a: &ReceiptImage, b: &ReceiptImage, tolerance: u8) -> ImageDiff {
+    let width = a.width.max(b.width);
+    let height = a.height.max(b.height);
+    let mut bytes = Vec::with_capacity((width * height * 3) as usize);
+    let mut changed_pixels = 0;
+    let mut regions: Vec<Region> = vec![];
+
+    for y in 0..height {
+        let mut row: Option<(u32, u32)> = None;
+
+        for x in 0..width {
+            match (brightness(a, x, y), brightness(b, x, y)) {
+                (Some(pa), Some(pb)) if (pa - pb).abs() <= tolerance as i32 => {
+                    let faded = (192 + pb / 4) as u8;
+                    bytes.extend([faded, faded, faded]);
+                }
+                _ => {
+                    changed_pixels += 1;
+                    bytes.extend(CHANGED);
+                    row = Some(row.map_or((x, x), |(start, _)| (start, x)));
+                }
+            }
+        }
+
+        //Rows with changes extend the region of the row above
+        if let Some((start, end)) = row {
+            match regions.last_mut() {
+                Some(region) if region.y + region.h == y => {
+                    let right = (region.x + region.w).max(end + 1);
+                    region.x = region.x.min(start);
+                    region.w = right - region.x;
+                    region.h += 1;
+                }
+                _ => regions.push(Region {
+                    x: start,
+                    y,
+                    w: end + 1 - start,
+                    h: 1,
+                }),
+            }
+        }
+    }
+
+    ImageDiff {
+        image: ReceiptImage {
+            bytes,
+            width,
+            height,
+            color_profile: ColorProfile::default(),
+        },
+        changed_pixels,
+        regions,
+    }
+}
+
+/// Renders both jobs and compares their first outputs
+pub fn diff_renders(a: &[u8], b: &[u8], tolerance: u8) -> Option<(ImageDiff, ReceiptImage)> {
+    let a = ImageRenderer::render(a, None).output.into_iter().next()?;
+    let b = ImageRenderer::render(b, None).output.into_iter().next()?;
+    let diff = diff_images(&a, &b, tolerance);
+    let composite = composite(&a, &diff, &b);
+    Some((diff, composite))
+}
+
+/// Places the first image, the diff with its regions outlined and the
+/// second image next to each other
+pub fn composite(a: &ReceiptImage, diff: &ImageDiff, b: &ReceiptImage) -> ReceiptImage {
+    let panels = [a, &diff.image, b];
+    let width = panels.iter().map(|p| p.width).sum::<u32>() + GUTTER_WIDTH * 2;
+    let height = panels.iter().map(|p| p.height).max().unwrap_or(0);
+    let mut bytes = GUTTER.repeat((width * height) as usize);
+
+    let mut put = |x: u32, y: u32, color: &[u8]| {
+        if x < width && y < height {
+            let index = ((y * width + x) * 3) as usize;
+            bytes[index..index + 3].copy_from_slice(color);
+        }
+    };
+
+    let mut offset = 0;
+    for panel in panels {
+        for y in 0..panel.height {
+            for x in 0..panel.width {
+                let index = ((y * panel.width + x) * 3) as usize;
+                put(offset + x, y, &panel.bytes[index..index + 3]);
+            }
+        }
+        offset += panel.width + GUTTER_WIDTH;
+    }
+
+    //Outline the regions on the diff panel
+    for region in &diff.regions {
+        let (left, top) = (a.width + GUTTER_WIDTH + region.x, region.y);
+        let (right, bottom) = (left + region.w, top + region.h);
+
+        for x in left.saturating_sub(1)..=right {
+            put(x, top.saturating_sub(1), &REGION);
+            put(x, bottom, &REGION);
+        }
+        for y in top.saturating_sub(1)..=bottom {
+            put(left.saturating_sub(1), y, &REGION);
+            put(right, y, &REGION);
+        }
+    }
+
+    ReceiptImage {
+        bytes,
+        width,
+        height,
+        color_profile: a.color_profile.clone(),
+    }
+}
@@ -0,0 +1,187 @@
+//! Image Extractor
+//!
+//! The image extractor runs the layout of a job and keeps only the
+//! images that were printed (logos, stored graphics, raster images),
+//! nothing is drawn. Every image comes with where it was printed,
+//! where its data came from and the image encoded as a png.
+//!
+//! ```no_run
+//! use thermal_renderer::image_extractor::ImageExtractor;
+//!
+//! let bytes = std::fs::read("receipt.bin").unwrap();
+//!
+//! for receipt in ImageExtractor::extract(&bytes, None).output {
+//!     for image in receipt {
+//!         println!("{}x{} at {},{}", image.w, image.h, image.x, image.y);
+//!     }
+//! }
+//! ```
+
+use crate::renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
+use crate::signature::{looks_like_signature, near_end};
+use png::{BitDepth, ColorType, Encoder};
+use thermal_parser::command::Command;
+use thermal_parser::constants::GS;
+use thermal_parser::context::Context;
+use thermal_parser::graphics::{Image, ImageRef, ImageRefStorage};
+
+/// Where the data of a printed image came from
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageOrigin {
+    /// The data was part of the command that printed it (i.e. GS v 0)
+    Inline,
+    /// A graphic that was defined before (download or NV), printed by
+    /// its key codes. Substituted graphics have the ref they replaced
+    Stored(ImageRef),
+    /// The planes of the print buffer (GS ( L <Function 50>)
+    Buffer,
+}
+
+impl ImageOrigin {
+    /// The origin of the images that a command prints
+    pub fn of(command: &Command) -> Self {
+        let opcode = command.opcode();
+        let graphics =
+            opcode.starts_with(&[GS, b'(', b'L']) || opcode.starts_with(&[GS, b'8', b'L']);
+
+        let storage = match (graphics, opcode.last()) {
+            (true, Some(50)) => return ImageOrigin::Buffer,
+            (true, Some(69)) => ImageRefStorage::Disc,
+            (true, Some(85)) => ImageRefStorage::Ram,
+            _ => return ImageOrigin::Inline,
+        };
+
+        match ImageRef::from_data(&command.data, storage) {
+            Some(image_ref) => ImageOrigin::Stored(image_ref),
+            None => ImageOrigin::Inline,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExtractedImage {
+    /// Position in dots, inside the page mode print area when
+    /// page_mode is true and on the paper otherwise
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    pub page_mode: bool,
+    pub origin: ImageOrigin,
+    /// Byte offset of the command that printed the image
+    pub offset: usize,
+    /// Rgba png, the paper is transparent
+    pub png: Vec<u8>,
+    /// The image looks like a captured signature, the same images
+    /// the renderer reports in `RenderOutput::signatures`
+    pub signature: bool,
+}
+
+/// The output for each receipt is the images in the order they were printed
+#[derive(Default)]
+pub struct ImageExtractor {
+    //Origin and offset of every command, by command index
+    commands: Vec<(ImageOrigin, usize)>,
+    current: usize,
+    images: Vec<ExtractedImage>,
+    errors: Vec<String>,
+}
+
+impl ImageExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Extracts the images of esc/pos bytes
+    pub fn extract(
+        bytes: &[u8],
+        debug_profile: Option<DebugProfile>,
+    ) -> RenderOutput<Vec<ExtractedImage>> {
+        Self::extract_commands(thermal_parser::parse_esc_pos(bytes), debug_profile)
+    }
+
+    /// Extracts the images of commands that were already parsed,
+    /// i.e. the commands returned by `thermal_parser::parse_star`
+    pub fn extract_commands(
+        commands: Vec<Command>,
+        debug_profile: Option<DebugProfile>,
+    ) -> RenderOutput<Vec<ExtractedImage>> {
+        let mut extractor = ImageExtractor::new();
+        extractor.commands = commands
+            .iter()
+            .map(|command| (ImageOrigin::of(command), command.offset))
+            .collect();
+
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(extractor);
+        let mut renderer = Renderer::new(&mut child_renderer, debug_profile.unwrap_or_default());
+        renderer.render_commands(commands)
+    }
+}
+
+impl OutputRenderer<Vec<ExtractedImage>> for ImageExtractor {
+    fn command_begin(&mut self, index: usize) {
+        self.current = index;
+    }
+
+    fn begin_render(&mut self, _context: &mut Context) {
+        self.images.clear();
+    }
+
+    fn render_image(&mut self, context: &mut Context, image: &Image) {
+        let png = match encode_png(image) {
+            Ok(png) => png,
+            Err(error) => {
+                self.errors
+                    .push(format!("Could not encode image: {}", error));
+                return;
+            }
+        };
+
+        let (origin, offset) = self
+            .commands
+            .get(self.current)
+            .cloned()
+            .unwrap_or((ImageOrigin::Inline, 0));
+
+        //Whether it is near the end is only known when the receipt ends
+        let signature = origin == ImageOrigin::Inline
+            && !context.page_mode.enabled
+            && looks_like_signature(image);
+
+        self.images.push(ExtractedImage {
+            x: image.x,
+            y: image.y,
+            w: image.w,
+            h: image.h,
+            page_mode: context.page_mode.enabled,
+            origin,
+            offset,
+            png,
+            signature,
+        });
+    }
+
+    fn get_render_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn end_render(&mut self, context: &mut Context) -> Vec<ExtractedImage> {
+        let height = context.graphics.render_area.y;
+        for image in self.images.iter_mut().filter(|image| image.signature) {
+            image.signature = near_end(image.y + image.h, height);
+        }
+
+        std::mem::take(&mut self.images)
+    }
+}
+
+fn encode_png(image: &Image) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = vec![];
+    let mut encoder = Encoder::new(&mut png, image.w, image.h);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.as_rgba_u8())?;
+    writer.finish()?;
+    Ok(png)
+}
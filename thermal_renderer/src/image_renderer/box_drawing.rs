@@ -0,0 +1,244 @@
+//! Box Drawing
+//!
+//! Tables and frames printed with line characters (`─ │ ┌ ╔ ═`, the
+//! line glyphs of code page 437 and the other PC code pages decode to
+//! these) look gappy when they are drawn with the font. The glyphs
+//! don't reach the edges of the cell and don't cover the space
+//! between lines.
+//!
+//! With box drawing enabled the image renderer draws these characters
+//! as lines on the pixel grid instead. Lines go through the center of
+//! the cell and reach its edges, vertical lines span the whole line
+//! pitch so that rows of a table join up. Block elements (`█ ▀ ▄ ▌ ▐`)
+//! fill their part of the cell.
+//!
+//! Dashed lines and shades are still drawn with the font.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stroke {
+    Light,
+    Heavy,
+    Double,
+}
+
+/// The strokes from the center of the cell to each of its edges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxLines {
+    pub up: Option<Stroke>,
+    pub down: Option<Stroke>,
+    pub left: Option<Stroke>,
+    pub right: Option<Stroke>,
+}
+
+const N: Option<Stroke> = None;
+const L: Option<Stroke> = Some(Stroke::Light);
+const H: Option<Stroke> = Some(Stroke::Heavy);
+const D: Option<Stroke> = Some(Stroke::Double);
+
+/// The lines of a box drawing character, none for other characters
+pub fn box_lines(c: char) -> Option<BoxLines> {
+    let (up, down, left, right) = match c {
+        '─' => (N, N, L, L),
+        '━' => (N, N, H, H),
+        '│' => (L, L, N, N),
+        '┃' => (H, H, N, N),
+        '┌' | '╭' => (N, L, N, L),
+        '┏' => (N, H, N, H),
+        '┐' | '╮' => (N, L, L, N),
+        '┓' => (N, H, H, N),
+        '└' | '╰' => (L, N, N, L),
+        '┗' => (H, N, N, H),
+        '┘' | '╯' => (L, N, L, N),
+        '┛' => (H, N, H, N),
+        '├' => (L, L, N, L),
+        '┣' => (H, H, N, H),
+        '┤' => (L, L, L, N),
+        '┫' => (H, H, H, N),
+        '┬' => (N, L, L, L),
+        '┳' => (N, H, H, H),
+        '┴' => (L, N, L, L),
+        '┻' => (H, N, H, H),
+        '┼' => (L, L, L, L),
+        '╋' => (H, H, H, H),
+        '═' => (N, N, D, D),
+        '║' => (D, D, N, N),
+        '╒' => (N, L, N, D),
+        '╓' => (N, D, N, L),
+        '╔' => (N, D, N, D),
+        '╕' => (N, L, D, N),
+        '╖' => (N, D, L, N),
+        '╗' => (N, D, D, N),
+        '╘' => (L, N, N, D),
+        '╙' => (D, N, N, L),
+        '╚' => (D, N, N, D),
+        '╛' => (L, N, D, N),
+        '╜' => (D, N, L, N),
+        '╝' => (D, N, D, N),
+        '╞' => (L, L, N, D),
+        '╟' => (D, D, N, L),
+        '╠' => (D, D, N, D),
+        '╡' => (L, L, D, N),
+        '╢' => (D, D, L, N),
+        '╣' => (D, D, D, N),
+        '╤' => (N, L, D, D),
+        '╥' => (N, D, L, L),
+        '╦' => (N, D, D, D),
+        '╧' => (L, N, D, D),
+        '╨' => (D, N, L, L),
+        '╩' => (D, N, D, D),
+        '╪' => (L, L, D, D),
+        '╫' => (D, D, L, L),
+        '╬' => (D, D, D, D),
+        '╴' => (N, N, L, N),
+        '╵' => (L, N, N, N),
+        '╶' => (N, N, N, L),
+        '╷' => (N, L, N, N),
+        '╸' => (N, N, H, N),
+        '╹' => (H, N, N, N),
+        '╺' => (N, N, N, H),
+        '╻' => (N, H, N, N),
+        _ => return None,
+    };
+
+    Some(BoxLines {
+        up,
+        down,
+        left,
+        right,
+    })
+}
+
+/// The part of the cell a block element fills as left, top, right
+/// and bottom in eighths, none for other characters
+pub fn block(c: char) -> Option<(u32, u32, u32, u32)> {
+    match c {
+        '█' => Some((0, 0, 8, 8)),
+        '▀' => Some((0, 0, 8, 4)),
+        '▄' => Some((0, 4, 8, 8)),
+        '▌' => Some((0, 0, 4, 8)),
+        '▐' => Some((4, 0, 8, 8)),
+        '▔' => Some((0, 0, 8, 1)),
+        '▁' => Some((0, 7, 8, 8)),
+        '▏' => Some((0, 0, 1, 8)),
+        '▕' => Some((7, 0, 8, 8)),
+        _ => None,
+    }
+}
+
+/// A rectangle of the cell to fill, in dots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// The cell of a character, the pitch is the height from this line
+/// to the next and the center is the middle of the glyph
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub pitch: u32,
+    pub center_y: u32,
+}
+
+impl Cell {
+    /// The rectangles that draw the character, none for characters
+    /// that are drawn with the font
+    pub fn rects(&self, c: char) -> Option<Vec<Rect>> {
+        if let Some((left, top, right, bottom)) = block(c) {
+            let x = self.x + self.w * left / 8;
+            let y = self.y + self.pitch * top / 8;
+            return Some(vec![Rect {
+                x,
+                y,
+                w: self.x + self.w * right / 8 - x,
+                h: self.y + self.pitch * bottom / 8 - y,
+            }]);
+        }
+
+        let lines = box_lines(c)?;
+        let light = (self.w / 6).max(1);
+        let center_x = self.x + self.w / 2;
+        let bottom = self.y + self.pitch;
+        let right = self.x + self.w;
+
+        //Lines run past the center by half of the crossing line so corners are closed
+        let vertical = lines.up.or(lines.down).map_or(0, |s| width(s, light));
+        let horizontal = lines.left.or(lines.right).map_or(0, |s| width(s, light));
+        let mut rects = vec![];
+
+        if let Some(stroke) = lines.left {
+            let end = center_x + vertical / 2;
+            strokes(stroke, light, self.center_y, |y, h| {
+                rects.push(Rect {
+                    x: self.x,
+                    y,
+                    w: end.max(self.x + 1) - self.x,
+                    h,
+                })
+            });
+        }
+        if let Some(stroke) = lines.right {
+            let start = center_x.saturating_sub(vertical / 2).max(self.x);
+            strokes(stroke, light, self.center_y, |y, h| {
+                rects.push(Rect {
+                    x: start,
+                    y,
+                    w: right - start,
+                    h,
+                })
+            });
+        }
+        if let Some(stroke) = lines.up {
+            let end = self.center_y + horizontal / 2;
+            strokes(stroke, light, center_x, |x, w| {
+                rects.push(Rect {
+                    x,
+                    y: self.y,
+                    w,
+                    h: end.max(self.y + 1) - self.y,
+                })
+            });
+        }
+        if let Some(stroke) = lines.down {
+            let start = self.center_y.saturating_sub(horizontal / 2).max(self.y);
+            strokes(stroke, light, center_x, |x, w| {
+                rects.push(Rect {
+                    x,
+                    y: start,
+                    w,
+                    h: bottom.saturating_sub(start),
+                })
+            });
+        }
+
+        Some(rects)
+    }
+}
+
+//Width of all lines of a stroke, double lines have a light gap
+fn width(stroke: Stroke, light: u32) -> u32 {
+    match stroke {
+        Stroke::Light => light,
+        Stroke::Heavy => light * 2,
+        Stroke::Double => light * 3,
+    }
+}
+
+//Calls draw with the start and thickness of each line of the stroke
+fn strokes(stroke: Stroke, light: u32, center: u32, mut draw: impl FnMut(u32, u32)) {
+    let start = center.saturating_sub(width(stroke, light) / 2);
+
+    match stroke {
+        Stroke::Light => draw(start, light),
+        Stroke::Heavy => draw(start, light * 2),
+        Stroke::Double => {
+            draw(start, light);
+            draw(start + light * 2, light);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Canvas
+//!
+//! The pixels of a ThermalImage are stored in a canvas. The image does
+//! all of the drawing, text, images, rectangles and compositing, and
+//! reads and writes the result through the canvas, so a backend only
+//! decides how pixels are stored.
+//!
+//! `RgbaCanvas` keeps full colors and is the default. `GrayCanvas`
+//! stores one byte per pixel, which is a quarter of the memory, for
+//! targets that only ever print black. Building with the
+//! `gray-canvas` feature makes it the default canvas.
+//!
+//! ```
+//! use thermal_renderer::image_renderer::canvas::GrayCanvas;
+//! use thermal_renderer::image_renderer::ImageRenderer;
+//!
+//! let renderer = ImageRenderer::with_canvas(|| Box::new(GrayCanvas::default()));
+//! ```
+
+use thermal_parser::graphics::RGBA;
+
+/// Pixel storage of a ThermalImage, pixels are addressed row by row
+/// and the canvas is always a whole number of rows tall
+pub trait Canvas {
+    fn width(&self) -> u32;
+
+    fn height(&self) -> u32;
+
+    /// Replaces the pixels, rows are width pixels long
+    fn set_pixels(&mut self, width: u32, pixels: Vec<RGBA>);
+
+    /// A copy of all pixels
+    fn pixels(&self) -> Vec<RGBA>;
+
+    /// Takes the pixels out, the canvas keeps its width
+    fn take_pixels(&mut self) -> Vec<RGBA>;
+
+    fn get(&self, x: u32, y: u32) -> RGBA;
+
+    fn set(&mut self, x: u32, y: u32, color: RGBA);
+
+    /// Adds rows of the color until the canvas is height tall
+    fn grow(&mut self, height: u32, color: RGBA);
+}
+
+/// The canvas images get when none is given
+#[cfg(not(feature = "gray-canvas"))]
+pub fn default_canvas() -> Box<dyn Canvas> {
+    Box::new(RgbaCanvas::default())
+}
+
+/// The canvas images get when none is given
+#[cfg(feature = "gray-canvas")]
+pub fn default_canvas() -> Box<dyn Canvas> {
+    Box::new(GrayCanvas::default())
+}
+
+/// Full color pixels
+#[derive(Default)]
+pub struct RgbaCanvas {
+    width: u32,
+    pixels: Vec<RGBA>,
+}
+
+impl Canvas for RgbaCanvas {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        if self.width == 0 {
+            0
+        } else {
+            (self.pixels.len() / self.width as usize) as u32
+        }
+    }
+
+    fn set_pixels(&mut self, width: u32, pixels: Vec<RGBA>) {
+        self.width = width;
+        self.pixels = pixels;
+    }
+
+    fn pixels(&self) -> Vec<RGBA> {
+        self.pixels.clone()
+    }
+
+    fn take_pixels(&mut self) -> Vec<RGBA> {
+        std::mem::take(&mut self.pixels)
+    }
+
+    fn get(&self, x: u32, y: u32) -> RGBA {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    fn set(&mut self, x: u32, y: u32, color: RGBA) {
+        self.pixels[y as usize * self.width as usize + x as usize] = color;
+    }
+
+    fn grow(&mut self, height: u32, color: RGBA) {
+        let len = (self.width as usize).saturating_mul(height as usize);
+        if self.pixels.len() < len {
+            self.pixels.resize(len, color);
+        }
+    }
+}
+
+/// One byte of lightness per pixel, colors are stored as their gray
+/// level and transparency is dropped
+#[derive(Default)]
+pub struct GrayCanvas {
+    width: u32,
+    levels: Vec<u8>,
+}
+
+impl GrayCanvas {
+    fn level(color: &RGBA) -> u8 {
+        ((color.r as u32 * 299 + color.g as u32 * 587 + color.b as u32 * 114) / 1000) as u8
+    }
+
+    fn color(level: u8) -> RGBA {
+        RGBA {
+            r: level,
+            g: level,
+            b: level,
+            a: 255,
+        }
+    }
+}
+
+impl Canvas for GrayCanvas {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        if self.width == 0 {
+            0
+        } else {
+            (self.levels.len() / self.width as usize) as u32
+        }
+    }
+
+    fn set_pixels(&mut self, width: u32, pixels: Vec<RGBA>) {
+        self.width = width;
+        self.levels = pixels.iter().map(Self::level).collect();
+    }
+
+    fn pixels(&self) -> Vec<RGBA> {
+        self.levels
+            .iter()
+            .map(|level| Self::color(*level))
+            .collect()
+    }
+
+    fn take_pixels(&mut self) -> Vec<RGBA> {
+        let pixels = self.pixels();
+        self.levels = vec![];
+        pixels
+    }
+
+    fn get(&self, x: u32, y: u32) -> RGBA {
+        Self::color(self.levels[y as usize * self.width as usize + x as usize])
+    }
+
+    fn set(&mut self, x: u32, y: u32, color: RGBA) {
+        self.levels[y as usize * self.width as usize + x as usize] = Self::level(&color);
+    }
+
+    fn grow(&mut self, height: u32, color: RGBA) {
+        let len = (self.width as usize).saturating_mul(height as usize);
+        if self.levels.len() < len {
+            self.levels.resize(len, Self::level(&color));
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Color
+//!
+//! Receipts are rendered as raw rgb, the png has no color space and
+//! every viewer shows it the way it likes. A color profile tags the
+//! png so the colors are read as sRGB or as an ICC profile, which
+//! matters when previews are compared with photographs of receipts.
+//!
+//! Thermal paper isn't white and its ink isn't black. A paper tint
+//! maps the white of the receipt to the paper color and the black to
+//! the ink color, colors in between are mixed from the two.
+//!
+//! ```
+//! use thermal_renderer::image_renderer::color::{ColorProfile, PaperTint};
+//! use thermal_renderer::image_renderer::ImageRenderer;
+//!
+//! let mut renderer = ImageRenderer::new();
+//! renderer.color_profile = ColorProfile::Srgb;
+//! renderer.paper_tint = Some(PaperTint::thermal());
+//! ```
+
+use std::io::Write;
+
+/// The color space a png is tagged with
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ColorProfile {
+    /// No color chunks, the colors are raw rgb
+    #[default]
+    Untagged,
+    /// sRGB with the perceptual intent, plus matching gamma and
+    /// chromaticities for older viewers
+    Srgb,
+    /// An ICC profile, stored compressed in the png
+    Icc(Vec<u8>),
+}
+
+impl ColorProfile {
+    pub(crate) fn set_png_header<W: Write>(&self, encoder: &mut png::Encoder<W>) {
+        if *self == ColorProfile::Srgb {
+            encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+        }
+    }
+
+    //The iCCP chunk goes after the header and before the image data
+    pub(crate) fn write_png_chunks<W: Write>(
+        &self,
+        writer: &mut png::Writer<W>,
+    ) -> Result<(), png::EncodingError> {
+        if let ColorProfile::Icc(profile) = self {
+            //Profile name, its terminator and the compression method
+            let mut chunk = b"ICC profile\0\0".to_vec();
+            chunk.extend(miniz_oxide::deflate::compress_to_vec_zlib(profile, 6));
+            writer.write_chunk(png::chunk::iCCP, &chunk)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn from_png_info(info: &png::Info) -> Self {
+        match (&info.icc_profile, info.srgb) {
+            (Some(profile), _) => ColorProfile::Icc(profile.to_vec()),
+            (None, Some(_)) => ColorProfile::Srgb,
+            (None, None) => ColorProfile::Untagged,
+        }
+    }
+}
+
+/// The colors of the paper and the ink as rgb
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperTint {
+    pub paper: [u8; 3],
+    pub ink: [u8; 3],
+}
+
+impl PaperTint {
+    /// Warm white paper with blue black ink, like a fresh receipt
+    pub fn thermal() -> Self {
+        Self {
+            paper: [246, 243, 235],
+            ink: [38, 40, 56],
+        }
+    }
+
+    /// Tints rgb pixels
+    pub fn apply_rgb(&self, bytes: &mut [u8]) {
+        for pixel in bytes.chunks_exact_mut(3) {
+            for (channel, byte) in pixel.iter_mut().enumerate() {
+                let ink = self.ink[channel] as u32;
+                let paper = self.paper[channel] as u32;
+                let mixed = (ink * (255 - *byte as u32) + paper * *byte as u32 + 127) / 255;
+                *byte = mixed as u8;
+            }
+        }
+    }
+}
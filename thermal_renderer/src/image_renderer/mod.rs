@@ -15,19 +15,96 @@
 //! Page mode shares a lot of context from the main context, but it
 //! has some of its own as well.
 //!
+//! With the page debug profile the page is outlined and the logical
+//! area (ESC W), the translated print area and the print direction
+//! (ESC T) are drawn on top, which helps with checking the translation.
+//!
+//! A note on Image Dumps:
+//!
+//! With the image debug profile and an image_dir set, every image
+//! the renderer receives is written to the directory as it was
+//! decoded by the command, and page mode canvases are written when
+//! they are printed, both in the print direction and rotated to the
+//! paper. File names start with the index of the command, i.e.
+//! `00012_image.png`, `00031_page.png` and `00031_page_rotated.png`.
+//! Column format and scaling are decoded by the parser, so the image
+//! is already in raster format at its printed size.
+//!
+//! A note on Canvases:
+//!
+//! Pixels are stored in a canvas, see the canvas module. Use
+//! `ImageRenderer::with_canvas` to render into a different backend.
+//!
+//! A note on Print Density:
+//!
+//! When simulate_density is enabled the print density set by GS ( K
+//! changes how strong the ink is drawn. Lower densities fade the ink
+//! towards the paper color, higher densities darken it towards black.
+//!
+//! A note on Watermarks:
+//!
+//! A watermark is stamped across every receipt when it is finished,
+//! see the watermark module.
+//!
+//! A note on Box Drawing:
+//!
+//! With box_drawing enabled, line characters like `─ │ ┌` are drawn
+//! as crisp lines that join up between rows instead of font glyphs,
+//! see the box_drawing module.
+//!
+//! A note on Wear:
+//!
+//! Wear drops dots and fades weak heater columns of the finished
+//! receipt before the watermark is stamped, see the wear module.
+//!
+//! A note on Color:
+//!
+//! The paper tint is applied last, so the watermark looks printed on
+//! the paper too. The color profile is kept with every receipt and
+//! written into its png, see the color module.
+//!
 
+use crate::image_renderer::canvas::Canvas;
+use crate::image_renderer::color::{ColorProfile, PaperTint};
 use crate::image_renderer::thermal_image::ThermalImage;
+use crate::image_renderer::watermark::Watermark;
+use crate::image_renderer::wear::Wear;
+use crate::pool::BufferPool;
 use crate::renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
-use thermal_parser::context::{Context, PrintDirection, Rotation, TextJustify};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use thermal_parser::context::{Context, PrintDirection, RenderArea, Rotation, TextJustify};
 use thermal_parser::graphics::{Image, VectorGraphic, RGBA};
 use thermal_parser::text::TextSpan;
 
+pub mod box_drawing;
+pub mod canvas;
+pub mod color;
 pub mod thermal_image;
+pub mod watermark;
+pub mod wear;
 
 pub struct ImageRenderer {
     pub paper_image: ThermalImage,
     pub page_image: ThermalImage,
     pub debug_profile: DebugProfile,
+    pub simulate_density: bool,
+    /// Draws box drawing characters as lines on the pixel grid
+    pub box_drawing: bool,
+    /// Stamped across every receipt, i.e. `COPY` on reprints
+    pub watermark: Option<Watermark>,
+    /// Seeded print head artifacts, i.e. for degraded test fixtures
+    pub wear: Option<Wear>,
+    /// Paper and ink colors of the finished receipt
+    pub paper_tint: Option<PaperTint>,
+    /// The color space the receipt pngs are tagged with
+    pub color_profile: ColorProfile,
+    command_index: usize,
+    dump_errors: Vec<String>,
+    //Density adjusted copies of images, cleared at the end of a job
+    pixel_pool: BufferPool<RGBA>,
 }
 
 impl ImageRenderer {
@@ -36,12 +113,99 @@ impl ImageRenderer {
             paper_image: ThermalImage::new(0),
             page_image: ThermalImage::new(0),
             debug_profile: DebugProfile::default(),
+            simulate_density: false,
+            box_drawing: false,
+            watermark: None,
+            wear: None,
+            paper_tint: None,
+            color_profile: ColorProfile::default(),
+            command_index: 0,
+            dump_errors: vec![],
+            pixel_pool: BufferPool::new(),
+        }
+    }
+
+    /// A renderer that draws into canvases made by the function, the
+    /// paper and page mode each get their own
+    pub fn with_canvas(canvas: fn() -> Box<dyn Canvas>) -> Self {
+        Self {
+            paper_image: ThermalImage::with_canvas(0, canvas()),
+            page_image: ThermalImage::with_canvas(0, canvas()),
+            ..Self::new()
+        }
+    }
+
+    /// Renders bytes to an image with the print density applied to the ink
+    pub fn render_with_density(
+        bytes: &[u8],
+        debug_profile: Option<DebugProfile>,
+    ) -> RenderOutput<ReceiptImage> {
+        let mut image_renderer = ImageRenderer::new();
+        image_renderer.simulate_density = true;
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(image_renderer);
+        let mut renderer = Renderer::new(&mut child_renderer, debug_profile.unwrap_or_default());
+        renderer.render(bytes)
+    }
+
+    //The dump directory when image debugging is on
+    fn dump_dir(&self) -> Option<PathBuf> {
+        match &self.debug_profile.image_dir {
+            Some(dir) if self.debug_profile.image => Some(dir.clone()),
+            _ => None,
+        }
+    }
+
+    //Writes rgba pixels to the dump directory, failures are render errors
+    fn dump_pixels(&mut self, stage: &str, width: u32, height: u32, pixels: &[RGBA]) {
+        let Some(dir) = self.dump_dir() else {
+            return;
+        };
+
+        let image = ReceiptImage {
+            bytes: pixels.iter().flat_map(|p| [p.r, p.g, p.b]).collect(),
+            width,
+            height,
+            color_profile: self.color_profile.clone(),
+        };
+
+        let path = dir.join(format!("{:05}_{}.png", self.command_index, stage));
+        if let Err(error) = image.save_png(&path) {
+            self.dump_errors.push(error);
+        }
+    }
+
+    //Adjusts an ink color for the current print density
+    fn ink(&self, context: &Context, color: &RGBA) -> RGBA {
+        let percent = context.graphics.print_density_percent();
+        let paper = &context.graphics.render_colors.paper_color;
+
+        if !self.simulate_density || percent == 100 || color == paper {
+            return *color;
+        }
+
+        let mix = |c: u8, p: u8| -> u8 {
+            if percent < 100 {
+                //Fade towards the paper
+                let fade = (100 - percent) as f32 / 100f32;
+                (c as f32 + (p as f32 - c as f32) * fade) as u8
+            } else {
+                //Darken towards black
+                let darken = (percent - 100) as f32 / 100f32;
+                (c as f32 * (1f32 - darken)) as u8
+            }
+        };
+
+        RGBA {
+            r: mix(color.r, paper.r),
+            g: mix(color.g, paper.g),
+            b: mix(color.b, paper.b),
+            a: color.a,
         }
     }
 
     /// This is the normal way to render bytes to an image
     pub fn render(
-        bytes: &Vec<u8>,
+        bytes: &[u8],
         debug_profile: Option<DebugProfile>,
     ) -> RenderOutput<ReceiptImage> {
         let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
@@ -53,11 +217,191 @@ impl ImageRenderer {
     }
 }
 
+/// Outlines the logical area (ESC W) and the translated print area
+/// and points an arrow from the print start in the print direction
+fn draw_page_overlay(
+    pixels: &mut [RGBA],
+    w: u32,
+    h: u32,
+    context: &Context,
+    print_area: &RenderArea,
+) {
+    ThermalImage::draw_outline(
+        pixels,
+        w,
+        h,
+        &context.page_mode.logical_area,
+        &RGBA {
+            r: 46,
+            g: 160,
+            b: 67,
+            a: 200,
+        },
+    );
+    ThermalImage::draw_outline(
+        pixels,
+        w,
+        h,
+        print_area,
+        &RGBA {
+            r: 27,
+            g: 57,
+            b: 169,
+            a: 200,
+        },
+    );
+
+    if print_area.w == 0 || print_area.h == 0 {
+        return;
+    }
+
+    let left = print_area.x as i32 + 4;
+    let top = print_area.y as i32 + 4;
+    let right = (print_area.x + print_area.w) as i32 - 5;
+    let bottom = (print_area.y + print_area.h) as i32 - 5;
+
+    let (start, direction) = match context.page_mode.direction {
+        PrintDirection::TopLeft2Right => ((left, top), (1, 0)),
+        PrintDirection::BottomRight2Left => ((right, bottom), (-1, 0)),
+        PrintDirection::TopRight2Bottom => ((right, top), (0, 1)),
+        PrintDirection::BottomLeft2Top => ((left, bottom), (0, -1)),
+    };
+
+    let length = (print_area.w.min(print_area.h) as i32 / 2).clamp(8, 48);
+
+    ThermalImage::draw_arrow(
+        pixels,
+        w,
+        h,
+        start,
+        direction,
+        length,
+        &RGBA {
+            r: 216,
+            g: 30,
+            b: 30,
+            a: 255,
+        },
+    );
+}
+
+/// Moves an area along with a rotation of an image that is w by h
+fn rotate_area(area: &RenderArea, w: u32, h: u32, rotation: &Rotation) -> RenderArea {
+    match rotation {
+        Rotation::R90 => RenderArea {
+            x: h.saturating_sub(area.y + area.h),
+            y: area.x,
+            w: area.h,
+            h: area.w,
+        },
+        Rotation::R180 => RenderArea {
+            x: w.saturating_sub(area.x + area.w),
+            y: h.saturating_sub(area.y + area.h),
+            w: area.w,
+            h: area.h,
+        },
+        Rotation::R270 => RenderArea {
+            x: area.y,
+            y: w.saturating_sub(area.x + area.w),
+            w: area.h,
+            h: area.w,
+        },
+        Rotation::R0 => area.clone(),
+    }
+}
+
 /// ReceiptImage is the main output for the image renderer
 pub struct ReceiptImage {
     pub bytes: Vec<u8>,
     pub width: u32,
     pub height: u32,
+    /// Written into the png, see the color module
+    pub color_profile: ColorProfile,
+}
+
+impl ReceiptImage {
+    /// Writes the image as an rgb png
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let error = |e: &dyn fmt::Display| format!("{}: {}", path.display(), e);
+
+        let file = File::create(path).map_err(|e| error(&e))?;
+        self.write_png(BufWriter::new(file)).map_err(|e| error(&e))
+    }
+
+    /// Encodes the image as an rgb png
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut png = vec![];
+        self.write_png(&mut png).map_err(|e| e.to_string())?;
+        Ok(png)
+    }
+
+    fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        self.color_profile.set_png_header(&mut encoder);
+
+        let mut writer = encoder.write_header()?;
+        self.color_profile.write_png_chunks(&mut writer)?;
+        writer.write_image_data(&self.bytes)
+    }
+
+    /// Reads a png, colors are converted to rgb without alpha
+    pub fn load_png(path: &Path) -> Result<ReceiptImage, String> {
+        let error = |e: &dyn fmt::Display| format!("{}: {}", path.display(), e);
+
+        let file = File::open(path).map_err(|e| error(&e))?;
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| error(&e))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(|e| error(&e))?;
+        buffer.truncate(info.buffer_size());
+        let color_profile = ColorProfile::from_png_info(reader.info());
+
+        let bytes = match info.color_type {
+            png::ColorType::Rgb => buffer,
+            png::ColorType::Rgba => buffer
+                .chunks_exact(4)
+                .flat_map(|p| [p[0], p[1], p[2]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|g| [*g, *g, *g]).collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0]])
+                .collect(),
+            png::ColorType::Indexed => return Err(error(&"Indexed pngs are not supported")),
+        };
+
+        Ok(ReceiptImage {
+            bytes,
+            width: info.width,
+            height: info.height,
+            color_profile,
+        })
+    }
+}
+
+#[cfg(feature = "image")]
+impl ReceiptImage {
+    /// The receipt as a picture of the image crate
+    pub fn to_rgba_image(&self) -> image::RgbaImage {
+        let rgba = self
+            .bytes
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect();
+        image::RgbaImage::from_raw(self.width, self.height, rgba)
+            .unwrap_or_else(|| image::RgbaImage::new(self.width, self.height))
+    }
+}
+
+#[cfg(feature = "image")]
+impl RenderOutput<ReceiptImage> {
+    /// Every receipt as a picture of the image crate
+    pub fn to_rgba_images(&self) -> Vec<image::RgbaImage> {
+        self.output.iter().map(ReceiptImage::to_rgba_image).collect()
+    }
 }
 
 impl OutputRenderer<ReceiptImage> for ImageRenderer {
@@ -65,9 +409,20 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
         self.debug_profile = profile;
     }
 
+    fn command_begin(&mut self, index: usize) {
+        self.command_index = index;
+    }
+
     fn begin_render(&mut self, context: &mut Context) {
-        self.paper_image.debug_profile = self.debug_profile;
-        self.page_image.debug_profile = self.debug_profile;
+        if let Some(dir) = self.dump_dir() {
+            if let Err(error) = std::fs::create_dir_all(&dir) {
+                self.dump_errors
+                    .push(format!("{}: {}", dir.display(), error));
+            }
+        }
+
+        self.paper_image.debug_profile = self.debug_profile.clone();
+        self.page_image.debug_profile = self.debug_profile.clone();
         self.paper_image.paper_color = context.graphics.render_colors.paper_color;
         self.page_image.paper_color = context.graphics.render_colors.paper_color;
 
@@ -84,8 +439,14 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
         self.page_image.auto_grow = false;
     }
 
-    fn page_begin(&mut self, _context: &mut Context) {
+    fn page_begin(&mut self, context: &mut Context) {
         self.page_image.set_width(0);
+        self.page_image.compositing = context.profile.page_compositing;
+    }
+
+    fn page_clear(&mut self, context: &mut Context) {
+        self.page_image
+            .clear_print_area(&context.page_mode.print_area());
     }
 
     fn page_area_changed(
@@ -104,7 +465,7 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
             _ => {}
         }
 
-        if width > self.page_image.width {
+        if width > self.page_image.width() {
             self.page_image.expand_to_width(width);
         }
         if height > self.page_image.get_height() {
@@ -118,6 +479,19 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
             &PrintDirection::TopLeft2Right,
         );
 
+        if self.dump_dir().is_some() {
+            let (w, h, pixels) = self.page_image.copy();
+            self.dump_pixels("page", w, h, &pixels);
+        }
+
+        //The print area is in the coordinates of the current direction
+        let print_area = rotate_area(
+            &context.page_mode.print_area(),
+            self.page_image.width(),
+            self.page_image.get_height(),
+            &rotation_to_standard,
+        );
+
         //Rotate to standard direction
         match rotation_to_standard {
             Rotation::R90 => self.page_image.rotate_90(),
@@ -128,6 +502,10 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
 
         let (w, h, mut pixels) = self.page_image.copy();
 
+        if !matches!(rotation_to_standard, Rotation::R0) {
+            self.dump_pixels("page_rotated", w, h, &pixels);
+        }
+
         if self.debug_profile.page {
             ThermalImage::draw_border(
                 &mut pixels,
@@ -140,6 +518,7 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
                     a: 255,
                 },
             );
+            draw_page_overlay(&mut pixels, w, h, context, &print_area);
         }
 
         //Rotate back to how it was
@@ -172,10 +551,11 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
         for graphic in graphics {
             match graphic {
                 VectorGraphic::Rectangle(rectangle) => {
+                    let color = self.ink(context, &context.text.color);
                     if page {
-                        self.page_image.put_rect(rectangle, &context.text.color);
+                        self.page_image.put_rect(rectangle, &color);
                     } else {
-                        self.paper_image.put_rect(rectangle, &context.text.color);
+                        self.paper_image.put_rect(rectangle, &color);
                     }
                 }
             }
@@ -183,11 +563,35 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
     }
 
     fn render_image(&mut self, context: &mut Context, image: &Image) {
+        self.dump_pixels("image", image.w, image.h, &image.pixels);
+
+        let mut adjusted = None;
+        let image = if self.simulate_density && context.graphics.print_density_percent() != 100 {
+            let mut pixels = self.pixel_pool.take();
+            pixels.extend(image.pixels.iter().map(|pixel| self.ink(context, pixel)));
+
+            &*adjusted.insert(Image {
+                pixels,
+                x: image.x,
+                y: image.y,
+                w: image.w,
+                h: image.h,
+                flow: image.flow,
+                upside_down: image.upside_down,
+            })
+        } else {
+            image
+        };
+
         if context.page_mode.enabled {
             self.page_image.put_render_img(image);
         } else {
             self.paper_image.put_render_img(image);
         }
+
+        if let Some(adjusted) = adjusted {
+            self.pixel_pool.give(adjusted.pixels);
+        }
     }
 
     fn render_text(
@@ -198,15 +602,37 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
         max_height: u32,
         _text_justify: TextJustify,
     ) {
+        let text_colors: Vec<RGBA> = spans
+            .iter()
+            .map(|span| self.ink(context, &span.text_color))
+            .collect();
+
+        //Vertical lines reach down to the next line
+        let pitch = match self.box_drawing {
+            true => Some((context.text.line_spacing as u32).max(max_height)),
+            false => None,
+        };
+
         let canvas = if context.page_mode.enabled {
             &mut self.page_image
         } else {
             &mut self.paper_image
         };
 
-        for span in spans {
+        let mut render = |span: &TextSpan| match pitch {
+            Some(pitch) => canvas.render_span_box_drawing(x_offset, max_height, pitch, span),
+            None => canvas.render_span(x_offset, max_height, span),
+        };
+
+        for (span, text_color) in spans.iter().zip(text_colors) {
             if let Some(_) = &span.dimensions {
-                canvas.render_span(x_offset, max_height, span);
+                if span.text_color == text_color {
+                    render(span);
+                } else {
+                    let mut span = span.clone();
+                    span.text_color = text_color;
+                    render(&span);
+                }
             }
         }
     }
@@ -224,24 +650,46 @@ impl OutputRenderer<ReceiptImage> for ImageRenderer {
             errors.push(page_error.to_owned());
         }
 
+        errors.append(&mut self.dump_errors);
+
         errors
     }
 
     fn end_render(&mut self, context: &mut Context) -> ReceiptImage {
         //Add in the left and right margin;
-        self.paper_image
-            .expand_to_width(context.graphics.paper_area.w);
+        self.paper_image.expand_to_width_at(
+            context.graphics.paper_area.w,
+            context.graphics.paper_area.x,
+        );
 
         //Feed to the y height to ensure we catch any cut advances
         self.paper_image
             .expand_to_height(context.graphics.render_area.y);
 
-        let rendered = self.paper_image.consume_rgb_u8();
+        let mut rendered = self.paper_image.consume_rgb_u8();
+        if let Some(wear) = &self.wear {
+            wear.apply_rgb(&mut rendered.2, rendered.0, rendered.1);
+        }
+        if let Some(watermark) = &self.watermark {
+            watermark.stamp_rgb(
+                &mut rendered.2,
+                rendered.0,
+                rendered.1,
+                &self.paper_image.font.bold,
+            );
+        }
+        if let Some(tint) = &self.paper_tint {
+            tint.apply_rgb(&mut rendered.2);
+        }
+        self.pixel_pool.clear();
+        self.paper_image.release_buffers();
+        self.page_image.release_buffers();
 
         ReceiptImage {
             width: rendered.0,
             height: rendered.1,
             bytes: rendered.2,
+            color_profile: self.color_profile.clone(),
         }
     }
 }
@@ -1,15 +1,30 @@
 extern crate fontdue;
 extern crate png;
 
+use crate::image_renderer::box_drawing::Cell;
+use crate::image_renderer::canvas::{default_canvas, Canvas};
+use crate::pool::BufferPool;
 use crate::renderer::DebugProfile;
 use fontdue::layout::CharacterData;
 use std::rc::Rc;
-use thermal_parser::context::Font;
+use thermal_parser::context::{Font, RenderArea};
 use thermal_parser::graphics::{Image, Rectangle, RGBA};
+use thermal_parser::profile::Compositing;
 use thermal_parser::text::TextSpan;
 
 const SIZE_TO_FONT_RATIO: f32 = 1.68;
 const SIZE_TO_BASELINE_RATIO: f32 = 0.0315;
+pub(crate) const DEFAULT_FONT_SIZE: f32 = 12f32 * SIZE_TO_FONT_RATIO;
+
+/// The size the font is drawn at and where its baseline sits, as a
+/// ratio of the character height from the top of the character
+pub(crate) fn font_size_and_baseline(font_size: f32, font: &Font) -> (f32, f32) {
+    match font {
+        Font::B => (font_size * 0.8, (font_size * 0.8) * SIZE_TO_BASELINE_RATIO),
+        Font::C => (font_size * 0.7, (font_size * 0.5) * SIZE_TO_BASELINE_RATIO),
+        _ => (font_size, font_size * SIZE_TO_BASELINE_RATIO),
+    }
+}
 
 pub struct FontFamily {
     pub regular: Rc<fontdue::Font>,
@@ -22,41 +37,46 @@ pub struct FontFamily {
 /// This allows for an image with a fixed width that can grow in height
 /// to accommodate sets of pixels being pushed at arbitrary x and y values
 pub struct ThermalImage {
-    bytes: Vec<RGBA>,
-    pub width: u32,
+    canvas: Box<dyn Canvas>,
     pub font: FontFamily,
     pub auto_grow: bool,
     pub debug_profile: DebugProfile,
     pub font_size: f32,
     pub paper_color: RGBA,
+    pub compositing: Compositing,
     pub text_debug_color: RGBA,
     pub baseline_debug_color: RGBA,
     pub image_debug_color: RGBA,
     pub errors: Vec<String>,
+    //Pixels of rectangles, reused for every bar and line
+    rect_pool: BufferPool<RGBA>,
+}
+
+fn load_font(bytes: &[u8]) -> fontdue::Font {
+    fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default()).unwrap()
 }
 
 impl ThermalImage {
     pub fn new(width: u32) -> Self {
-        let regular = fontdue::Font::from_bytes(
-            include_bytes!("../../resources/fonts/JetBrainsMonoNL-Medium.ttf") as &[u8],
-            fontdue::FontSettings::default(),
-        )
-        .unwrap();
-        let bold = fontdue::Font::from_bytes(
-            include_bytes!("../../resources/fonts/JetBrainsMonoNL-Bold.ttf") as &[u8],
-            fontdue::FontSettings::default(),
-        )
-        .unwrap();
-        let italic = fontdue::Font::from_bytes(
-            include_bytes!("../../resources/fonts/JetBrainsMonoNL-MediumItalic.ttf") as &[u8],
-            fontdue::FontSettings::default(),
-        )
-        .unwrap();
-        let bold_italic = fontdue::Font::from_bytes(
-            include_bytes!("../../resources/fonts/JetBrainsMonoNL-BoldItalic.ttf") as &[u8],
-            fontdue::FontSettings::default(),
-        )
-        .unwrap();
+        Self::with_canvas(width, default_canvas())
+    }
+
+    /// An image that stores its pixels in the canvas
+    pub fn with_canvas(width: u32, mut canvas: Box<dyn Canvas>) -> Self {
+        canvas.set_pixels(width, vec![]);
+
+        let regular = load_font(include_bytes!(
+            "../../resources/fonts/JetBrainsMonoNL-Medium.ttf"
+        ));
+        let bold = load_font(include_bytes!(
+            "../../resources/fonts/JetBrainsMonoNL-Bold.ttf"
+        ));
+        let italic = load_font(include_bytes!(
+            "../../resources/fonts/JetBrainsMonoNL-MediumItalic.ttf"
+        ));
+        let bold_italic = load_font(include_bytes!(
+            "../../resources/fonts/JetBrainsMonoNL-BoldItalic.ttf"
+        ));
 
         let font = FontFamily {
             regular: Rc::from(regular),
@@ -67,9 +87,9 @@ impl ThermalImage {
 
         Self {
             errors: vec![],
-            bytes: Vec::<RGBA>::new(),
+            rect_pool: BufferPool::new(),
+            canvas,
             font,
-            width,
             auto_grow: true,
             debug_profile: DebugProfile::default(),
             text_debug_color: RGBA {
@@ -96,7 +116,8 @@ impl ThermalImage {
                 b: 255,
                 a: 255,
             },
-            font_size: 12f32 * SIZE_TO_FONT_RATIO,
+            compositing: Compositing::Or,
+            font_size: DEFAULT_FONT_SIZE,
         }
     }
 
@@ -114,76 +135,95 @@ impl ThermalImage {
     }
 
     fn get_font_size(&self, font: &Font) -> (f32, f32) {
-        match font {
-            Font::B => (
-                self.font_size * 0.8,
-                (self.font_size * 0.8) * SIZE_TO_BASELINE_RATIO,
-            ),
-            Font::C => (
-                self.font_size * 0.7,
-                (self.font_size * 0.5) * SIZE_TO_BASELINE_RATIO,
-            ),
-            _ => (self.font_size, self.font_size * SIZE_TO_BASELINE_RATIO),
-        }
+        font_size_and_baseline(self.font_size, font)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.canvas.width()
     }
 
     pub fn rotate_90(&mut self) {
-        let w = self.width as usize;
+        let w = self.width() as usize;
         let h = self.get_height() as usize;
+        let bytes = self.canvas.take_pixels();
         let mut rotated_image = vec![RGBA::blank(); (w * h) as usize];
 
         for y in 0..h {
             for x in 0..w {
-                rotated_image[x * h + (h - 1 - y)] = self.bytes[y * w + x];
+                rotated_image[x * h + (h - 1 - y)] = bytes[y * w + x];
             }
         }
 
-        self.bytes = rotated_image;
-        self.width = h as u32;
+        self.canvas.set_pixels(h as u32, rotated_image);
     }
 
     pub fn rotate_180(&mut self) {
-        let w = self.width as usize;
+        let w = self.width() as usize;
         let h = self.get_height() as usize;
+        let bytes = self.canvas.take_pixels();
         let mut rotated_image = vec![RGBA::blank(); (w * h) as usize];
 
         for y in 0..h {
             for x in 0..w {
-                rotated_image[(h - 1 - y) * w + (w - 1 - x)] = self.bytes[y * w + x];
+                rotated_image[(h - 1 - y) * w + (w - 1 - x)] = bytes[y * w + x];
             }
         }
 
-        self.bytes = rotated_image;
+        self.canvas.set_pixels(w as u32, rotated_image);
     }
 
     pub fn rotate_270(&mut self) {
-        let w = self.width as usize;
+        let w = self.width() as usize;
         let h = self.get_height() as usize;
+        let bytes = self.canvas.take_pixels();
         let mut rotated_image = vec![RGBA::blank(); (w * h) as usize];
 
         for y in 0..h {
             for x in 0..w {
-                rotated_image[(w - 1 - x) * h + y] = self.bytes[y * w + x];
+                rotated_image[(w - 1 - x) * h + y] = bytes[y * w + x];
             }
         }
 
-        self.bytes = rotated_image;
-        self.width = h as u32;
+        self.canvas.set_pixels(h as u32, rotated_image);
     }
 
     //Setting the width clears any bytes
     pub fn set_width(&mut self, width: u32) {
-        self.width = width;
-        self.bytes = Vec::<RGBA>::new();
+        self.canvas.set_pixels(width, vec![]);
     }
 
     pub fn reset(&mut self) {
-        self.bytes.clear();
-        self.bytes.shrink_to(0);
+        self.set_width(self.width());
     }
 
     pub fn draw_rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: &RGBA, multiply: bool) {
-        self.put_pixels(x, y, w, h, vec![*color; (w * h) as usize], false, multiply);
+        let mut pixels = self.rect_pool.take();
+        pixels.resize((w * h) as usize, *color);
+        self.put_pixel_slice(x, y, w, h, &pixels, multiply);
+        self.rect_pool.give(pixels);
+    }
+
+    /// Frees the buffers that are kept for drawing
+    pub fn release_buffers(&mut self) {
+        self.rect_pool.clear();
+    }
+
+    //Total height of a line that may be doubled, double lines
+    //have a gap of the same thickness
+    fn line_height(thickness: u32, double: bool) -> u32 {
+        if double {
+            thickness * 3
+        } else {
+            thickness
+        }
+    }
+
+    fn draw_lines(&mut self, x: u32, y: u32, w: u32, thickness: u32, double: bool, color: &RGBA) {
+        self.draw_rect(x, y, w, thickness, color, true);
+
+        if double {
+            self.draw_rect(x, y + thickness * 2, w, thickness, color, true);
+        }
     }
 
     pub fn draw_border(bytes: &mut Vec<RGBA>, width: u32, height: u32, color: &RGBA) {
@@ -208,6 +248,67 @@ impl ThermalImage {
         }
     }
 
+    /// Draws a one pixel outline of an area, clipped to the image
+    pub fn draw_outline(
+        bytes: &mut [RGBA],
+        width: u32,
+        height: u32,
+        area: &RenderArea,
+        color: &RGBA,
+    ) {
+        if area.w == 0 || area.h == 0 {
+            return;
+        }
+
+        let right = area.x + area.w - 1;
+        let bottom = area.y + area.h - 1;
+
+        for x in area.x..=right.min(width.saturating_sub(1)) {
+            for y in [area.y, bottom] {
+                if y < height {
+                    bytes[(y * width + x) as usize].blend_foreground(color);
+                }
+            }
+        }
+
+        for y in area.y..=bottom.min(height.saturating_sub(1)) {
+            for x in [area.x, right] {
+                if x < width {
+                    bytes[(y * width + x) as usize].blend_foreground(color);
+                }
+            }
+        }
+    }
+
+    /// Draws an arrow starting at x, y pointing in the direction of dx, dy
+    pub fn draw_arrow(
+        bytes: &mut [RGBA],
+        width: u32,
+        height: u32,
+        (x, y): (i32, i32),
+        (dx, dy): (i32, i32),
+        length: i32,
+        color: &RGBA,
+    ) {
+        let mut put = |px: i32, py: i32| {
+            if px >= 0 && py >= 0 && (px as u32) < width && (py as u32) < height {
+                bytes[(py as u32 * width + px as u32) as usize].blend_foreground(color);
+            }
+        };
+
+        for i in 0..length {
+            put(x + dx * i, y + dy * i);
+        }
+
+        //Arrow head, two lines going back from the tip
+        let tip_x = x + dx * (length - 1);
+        let tip_y = y + dy * (length - 1);
+        for i in 1..=length / 3 {
+            put(tip_x - dx * i + dy * i, tip_y - dy * i + dx * i);
+            put(tip_x - dx * i - dy * i, tip_y - dy * i - dx * i);
+        }
+    }
+
     fn render_char(
         char: char,
         width: u32,
@@ -278,6 +379,22 @@ impl ThermalImage {
     }
 
     pub fn render_span(&mut self, x_offset: u32, max_height: u32, span: &TextSpan) {
+        self.draw_span(x_offset, max_height, None, span);
+    }
+
+    /// Renders the span with box drawing characters as lines on the
+    /// pixel grid, the pitch is the distance to the next line
+    pub fn render_span_box_drawing(
+        &mut self,
+        x_offset: u32,
+        max_height: u32,
+        pitch: u32,
+        span: &TextSpan,
+    ) {
+        self.draw_span(x_offset, max_height, Some(pitch), span);
+    }
+
+    fn draw_span(&mut self, x_offset: u32, max_height: u32, pitch: Option<u32>, span: &TextSpan) {
         if span.dimensions.is_none() {
             return;
         }
@@ -294,7 +411,45 @@ impl ThermalImage {
             y_offset = (max_height_baseline - span_baseline) as u32;
         }
 
+        //Reverse printing fills the whole cell up to the line height,
+        //blanks and unprintable chars included
+        if span.inverted {
+            self.draw_rect(
+                dimensions.x + x_offset,
+                dimensions.y,
+                dimensions.w,
+                max_height.max(span.character_height),
+                &span.background_color,
+                true,
+            );
+        }
+
         for char in span.text.chars() {
+            if let Some(pitch) = pitch {
+                let cell = Cell {
+                    x: cur_x,
+                    y: dimensions.y,
+                    w: span.character_width,
+                    pitch: pitch.max(max_height),
+                    center_y: dimensions.y + y_offset + span.character_height / 2,
+                };
+
+                if let Some(rects) = cell.rects(char) {
+                    for rect in rects {
+                        self.draw_rect(
+                            rect.x,
+                            rect.y,
+                            rect.w,
+                            rect.h,
+                            &span.text_color,
+                            !span.inverted,
+                        );
+                    }
+                    cur_x += span.character_width;
+                    continue;
+                }
+            }
+
             let char_bitmap = ThermalImage::render_char(
                 char,
                 span.base_character_width,
@@ -329,7 +484,7 @@ impl ThermalImage {
                     bitmap.2,
                     bitmap.0,
                     true,
-                    true,
+                    !span.inverted,
                 );
             }
 
@@ -350,26 +505,37 @@ impl ThermalImage {
         }
 
         if span.underline > 0 {
-            self.draw_rect(
+            //Below the baseline, kept inside the line so tall text
+            //doesn't push its underline into the next line
+            let height = ThermalImage::line_height(span.underline, span.double_underline);
+            let below = (3.0 * span.stretch_height) as u32;
+            let y = ((dimensions.y + y_offset + below)
+                + (span.character_height as f32 * baseline_ratio) as u32)
+                .min((dimensions.y + max_height).saturating_sub(height));
+
+            self.draw_lines(
                 dimensions.x + x_offset,
-                (dimensions.y + y_offset + 3)
-                    + (span.character_height as f32 * baseline_ratio) as u32,
+                y,
                 dimensions.w,
-                1,
+                span.underline,
+                span.double_underline,
                 &span.text_color,
-                true,
-            )
+            );
         }
 
         if span.strikethrough > 0 {
-            self.draw_rect(
+            let height = ThermalImage::line_height(span.strikethrough, span.double_strikethrough);
+            let y = (dimensions.y + y_offset + (span.character_height as f32 / 2.5) as u32)
+                .saturating_sub(height / 2);
+
+            self.draw_lines(
                 dimensions.x + x_offset,
-                (dimensions.y + y_offset) + (span.character_height as f32 / 2.5) as u32,
+                y,
                 dimensions.w,
                 span.strikethrough,
+                span.double_strikethrough,
                 &span.text_color,
-                true,
-            )
+            );
         }
 
         if span.upside_down {
@@ -420,7 +586,7 @@ impl ThermalImage {
     }
 
     pub fn flip_pixels(&mut self, x: u32, y: u32, width: u32, height: u32) {
-        if x + width > self.width {
+        if x + width > self.width() {
             return;
         };
 
@@ -431,11 +597,9 @@ impl ThermalImage {
 
         // Collect the sub-image row by row
         for cur_y in y..y + height {
-            let start_idx = (cur_y * self.width + x) as usize;
-            let end_idx = start_idx + width as usize;
-
-            // Collect the current row and push it to the sub_image
-            sub_image.extend_from_slice(&self.bytes[start_idx..end_idx]);
+            for cur_x in x..x + width {
+                sub_image.push(self.canvas.get(cur_x, cur_y));
+            }
         }
 
         // Now reverse the rows to flip the image top-to-bottom
@@ -466,14 +630,15 @@ impl ThermalImage {
     }
 
     pub fn put_render_img(&mut self, image: &Image) {
-        let mut pixels = image.pixels.clone();
-
+        //The pixels are only copied to draw the debug border on them
         if self.debug_profile.image {
+            let mut pixels = image.pixels.clone();
             ThermalImage::draw_border(&mut pixels, image.w, image.h, &self.image_debug_color);
+            self.put_pixels(image.x, image.y, image.w, image.h, pixels, false, true);
+        } else {
+            self.put_pixel_slice(image.x, image.y, image.w, image.h, &image.pixels, true);
         }
 
-        self.put_pixels(image.x, image.y, image.w, image.h, pixels, false, true);
-
         if image.upside_down {
             self.flip_pixels(image.x, image.y, image.w, image.h);
         }
@@ -485,7 +650,8 @@ impl ThermalImage {
     /// unless auto_grow is set to false.
     /// invert will reverse black and white pixels.
     /// multiply will ensure that a white pixel does
-    /// not overwrite a black existing pixel.
+    /// not overwrite a black existing pixel, unless
+    /// compositing is set to overwrite.
     pub fn put_pixels(
         &mut self,
         x: u32,
@@ -495,6 +661,18 @@ impl ThermalImage {
         pixels: Vec<RGBA>,
         _invert: bool,
         multiply: bool,
+    ) -> bool {
+        self.put_pixel_slice(x, y, width, height, &pixels, multiply)
+    }
+
+    fn put_pixel_slice(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        pixels: &[RGBA],
+        multiply: bool,
     ) -> bool {
         let mut cur_x = x;
         let mut cur_y = y;
@@ -507,7 +685,7 @@ impl ThermalImage {
         }
 
         //Out of bounds
-        let exceeds_w = x >= self.width;
+        let exceeds_w = x >= self.width();
         let exceeds_h = y >= self.get_height();
 
         //Completely out of bounds, unrenderable
@@ -516,7 +694,7 @@ impl ThermalImage {
                 "Image exceeded paper x{} y{} w{} h{} : exceeded: width? {} height? {}",
                 x,
                 y,
-                self.width,
+                self.width(),
                 self.get_height(),
                 exceeds_w,
                 exceeds_h,
@@ -525,36 +703,52 @@ impl ThermalImage {
         }
 
         //Width can never grow, height can grow is auto_grow = true
-        let needs_crop_w = x + width > self.width;
+        let needs_crop_w = x + width > self.width();
         let needs_crop_h = !self.auto_grow && (y + width > self.get_height());
 
+        let cropped;
         let (final_width, final_height, final_pixels) = if needs_crop_w || needs_crop_h {
-            let max_width = self.width - x;
+            let max_width = self.width() - x;
             let max_height = if self.get_height() <= y {
                 0
             } else {
                 self.get_height() - y
             };
 
-            Self::crop_pixels(
-                &pixels,
+            cropped = Self::crop_pixels(
+                pixels,
                 width,
                 height,
                 max_width,
                 max_height,
                 !self.auto_grow,
-            )
+            );
+            (cropped.0, cropped.1, cropped.2.as_slice())
         } else {
             (width, height, pixels)
         };
 
         self.expand_to_height(y + final_height);
 
-        if multiply {
-            for pixel in &final_pixels {
-                let idx = cur_y * self.width + cur_x;
+        if multiply && self.compositing == Compositing::Overwrite {
+            for pixel in final_pixels {
+                //Transparent pixels are unprinted dots, they replace what was there
+                let mut replaced = self.paper_color;
+                replaced.blend_foreground(pixel);
+                self.canvas.set(cur_x, cur_y, replaced);
 
-                self.bytes[idx as usize].multiply_foreground(pixel);
+                if cur_x == x + final_width - 1 {
+                    cur_x = x;
+                    cur_y += 1;
+                } else {
+                    cur_x += 1;
+                }
+            }
+        } else if multiply {
+            for pixel in final_pixels {
+                let mut current = self.canvas.get(cur_x, cur_y);
+                current.multiply_foreground(pixel);
+                self.canvas.set(cur_x, cur_y, current);
 
                 if cur_x == x + final_width - 1 {
                     cur_x = x;
@@ -564,9 +758,10 @@ impl ThermalImage {
                 }
             }
         } else {
-            for pixel in &final_pixels {
-                let idx = cur_y * self.width + cur_x;
-                self.bytes[idx as usize].blend_foreground(pixel);
+            for pixel in final_pixels {
+                let mut current = self.canvas.get(cur_x, cur_y);
+                current.blend_foreground(pixel);
+                self.canvas.set(cur_x, cur_y, current);
                 if cur_x == x + final_width - 1 {
                     cur_x = x;
                     cur_y += 1;
@@ -580,7 +775,7 @@ impl ThermalImage {
     }
 
     pub fn crop_pixels(
-        pixels: &Vec<RGBA>,
+        pixels: &[RGBA],
         width: u32,
         height: u32,
         max_width: u32,
@@ -611,70 +806,56 @@ impl ThermalImage {
     }
 
     pub fn get_height(&self) -> u32 {
-        if self.width == 0 {
-            0
-        } else {
-            (self.bytes.len() / self.width as usize) as u32
-        }
+        self.canvas.height()
     }
 
     pub fn expand_to_height(&mut self, height: u32) {
-        let len = (self.width * height) as usize;
-        let cur_len = self.bytes.len();
-        if cur_len >= len {
-            return;
-        }
-        let to_add = len - cur_len;
-
-        for _ in 0..to_add {
-            self.bytes.push(self.paper_color);
-        }
+        self.canvas.grow(height, self.paper_color);
     }
 
     pub fn add_top_margin(&mut self, height: u32) {
         //TODO maybe add debug line for margin
-        self.bytes
-            .splice(0..0, vec![self.paper_color; (self.width * height) as usize]);
+        let width = self.width();
+        let mut bytes = self.canvas.take_pixels();
+        bytes.splice(0..0, vec![self.paper_color; (width * height) as usize]);
+        self.canvas.set_pixels(width, bytes);
     }
 
     pub fn expand_to_width(&mut self, new_width: u32) {
-        let old_width = self.width;
+        let left = (new_width.saturating_sub(self.width()) as f32 / 2.0).floor() as u32;
+        self.expand_to_width_at(new_width, left);
+    }
+
+    /// Expands the image to the new width with `left` columns of paper
+    /// on the left, as much as fits
+    pub fn expand_to_width_at(&mut self, new_width: u32, left: u32) {
+        let old_width = self.width();
         if new_width < old_width {
             return;
         };
         let height = self.get_height();
-        let left = ((new_width - old_width) as f32 / 2.0).floor() as u32;
+        let left = left.min(new_width - old_width);
         let right = new_width - (left + old_width);
 
-        self.bytes
-            .try_reserve((height as usize * new_width as usize - self.bytes.len()) as usize)
-            .unwrap();
-
-        let mut insert_idx = 0;
+        let bytes = self.canvas.take_pixels();
+        let mut expanded = Vec::with_capacity(height as usize * new_width as usize);
 
-        let left_bytes = vec![self.paper_color; left as usize];
-        let right_bytes = vec![self.paper_color; right as usize];
-
-        for _ in 0..height {
-            self.bytes
-                .splice(insert_idx..insert_idx, left_bytes.clone());
-            insert_idx += left as usize + old_width as usize;
-
-            self.bytes
-                .splice(insert_idx..insert_idx, right_bytes.clone());
-            insert_idx += right as usize;
+        for row in bytes.chunks(old_width.max(1) as usize) {
+            expanded.extend(std::iter::repeat_n(self.paper_color, left as usize));
+            expanded.extend_from_slice(row);
+            expanded.extend(std::iter::repeat_n(self.paper_color, right as usize));
         }
 
-        self.width = new_width;
+        self.canvas.set_pixels(new_width, expanded);
     }
 
     pub fn consume_rgb_u8(&mut self) -> (u32, u32, Vec<u8>) {
-        let w = self.width;
+        let w = self.width();
         let h = self.get_height();
 
         let mut pixels = Vec::with_capacity((w * h) as usize * 3);
 
-        for byte in self.bytes.iter() {
+        for byte in self.canvas.take_pixels().iter() {
             pixels.push(byte.r);
             pixels.push(byte.g);
             pixels.push(byte.b);
@@ -686,14 +867,35 @@ impl ThermalImage {
     }
 
     pub fn copy(&mut self) -> (u32, u32, Vec<RGBA>) {
-        let pixels = self.bytes.clone();
-        let w = self.width;
+        let pixels = self.canvas.pixels();
+        let w = self.width();
         let h = self.get_height();
         (w, h, pixels)
     }
 
     // empty the pixels
     pub fn empty(&mut self) {
-        self.bytes.clear()
+        self.reset()
+    }
+
+    /// Resets an area back to the paper color
+    pub fn clear_area(&mut self, x: u32, y: u32, w: u32, h: u32) {
+        let max_x = (x + w).min(self.width());
+        let max_y = (y + h).min(self.get_height());
+
+        for row in y..max_y {
+            for col in x..max_x {
+                self.canvas.set(col, row, self.paper_color);
+            }
+        }
+    }
+
+    /// Resets the area or the whole image if the area is empty
+    pub fn clear_print_area(&mut self, area: &RenderArea) {
+        if area.w == 0 || area.h == 0 {
+            self.clear_area(0, 0, self.width(), self.get_height());
+        } else {
+            self.clear_area(area.x, area.y, area.w, area.h);
+        }
     }
 }
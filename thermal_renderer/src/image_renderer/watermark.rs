@@ -0,0 +1,165 @@
+//! Watermark
+//!
+//! A watermark is stamped diagonally across the finished receipt,
+//! from the bottom left to the top right, so reprints and previews
+//! can't be mistaken for the original. The stamp is text, like
+//! `COPY` or `VOID`, or an image and is blended over the receipt
+//! at the watermark opacity.
+//!
+//! ```
+//! use thermal_renderer::image_renderer::watermark::Watermark;
+//! use thermal_renderer::image_renderer::ImageRenderer;
+//!
+//! let mut renderer = ImageRenderer::new();
+//! renderer.watermark = Some(Watermark::text("COPY"));
+//! ```
+
+use fontdue::Font;
+use thermal_parser::graphics::{Image, ImageFlow, RGBA};
+
+//Text is drawn at this size and scaled with the stamp
+const TEXT_SIZE: f32 = 96.0;
+//Part of the diagonal that the stamp spans
+const DIAGONAL_FILL: f32 = 0.8;
+
+#[derive(Debug, Clone)]
+pub enum WatermarkContent {
+    /// Text in the watermark color, drawn in the bold font
+    Text(String),
+    Image(Image),
+}
+
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    pub content: WatermarkContent,
+    /// 0 is invisible and 1 covers the receipt
+    pub opacity: f32,
+    /// Color of text watermarks
+    pub color: RGBA,
+}
+
+impl Watermark {
+    /// Red text at 30% opacity
+    pub fn text(text: &str) -> Self {
+        Self {
+            content: WatermarkContent::Text(text.to_string()),
+            opacity: 0.3,
+            color: RGBA {
+                r: 200,
+                g: 0,
+                b: 0,
+                a: 255,
+            },
+        }
+    }
+
+    /// An image at 30% opacity, transparent pixels are not stamped
+    pub fn image(image: Image) -> Self {
+        Self {
+            content: WatermarkContent::Image(image),
+            opacity: 0.3,
+            color: RGBA::blank(),
+        }
+    }
+
+    /// Stamps the watermark across rgb pixels that are width wide
+    pub fn stamp_rgb(&self, bytes: &mut [u8], width: u32, height: u32, font: &Font) {
+        let text_stamp;
+        let stamp = match &self.content {
+            WatermarkContent::Text(text) => {
+                text_stamp = text_image(text, &self.color, font);
+                &text_stamp
+            }
+            WatermarkContent::Image(image) => image,
+        };
+
+        if stamp.w == 0 || stamp.h == 0 || width == 0 || height == 0 {
+            return;
+        }
+
+        let (w, h) = (width as f32, height as f32);
+        let angle = h.atan2(w);
+        let (sin, cos) = angle.sin_cos();
+
+        //The stamp spans the diagonal, a short receipt limits its height
+        let diagonal = (w * w + h * h).sqrt();
+        let scale = (diagonal * DIAGONAL_FILL / stamp.w as f32)
+            .min(w.min(h) * DIAGONAL_FILL / stamp.h as f32);
+
+        let opacity = self.opacity.clamp(0.0, 1.0);
+        let (center_x, center_y) = (w / 2.0, h / 2.0);
+        let (stamp_x, stamp_y) = (stamp.w as f32 / 2.0, stamp.h as f32 / 2.0);
+
+        for y in 0..height {
+            for x in 0..width {
+                //Rotates the pixel back onto the stamp
+                let dx = x as f32 + 0.5 - center_x;
+                let dy = y as f32 + 0.5 - center_y;
+                let sx = (dx * cos - dy * sin) / scale + stamp_x;
+                let sy = (dx * sin + dy * cos) / scale + stamp_y;
+
+                if sx < 0.0 || sy < 0.0 || sx >= stamp.w as f32 || sy >= stamp.h as f32 {
+                    continue;
+                }
+
+                let pixel = stamp.pixels[sy as usize * stamp.w as usize + sx as usize];
+                let alpha = pixel.a as f32 / 255.0 * opacity;
+                if alpha == 0.0 {
+                    continue;
+                }
+
+                let index = (y as usize * width as usize + x as usize) * 3;
+                for (byte, color) in bytes[index..index + 3]
+                    .iter_mut()
+                    .zip([pixel.r, pixel.g, pixel.b])
+                {
+                    *byte = (*byte as f32 * (1.0 - alpha) + color as f32 * alpha).round() as u8;
+                }
+            }
+        }
+    }
+}
+
+/// The text on one line, the coverage of the glyphs is the alpha
+fn text_image(text: &str, color: &RGBA, font: &Font) -> Image {
+    let (ascent, descent) = font
+        .horizontal_line_metrics(TEXT_SIZE)
+        .map_or((TEXT_SIZE, 0.0), |metrics| {
+            (metrics.ascent, metrics.descent)
+        });
+
+    let glyphs: Vec<_> = text.chars().map(|c| font.rasterize(c, TEXT_SIZE)).collect();
+    let w = glyphs
+        .iter()
+        .map(|(metrics, _)| metrics.advance_width.ceil() as u32)
+        .sum::<u32>();
+    let h = (ascent - descent).ceil() as u32;
+
+    let mut pixels = vec![RGBA::blank(); (w * h) as usize];
+    let mut pen_x = 0i32;
+
+    for (metrics, coverage) in &glyphs {
+        let top = (ascent - metrics.height as f32 - metrics.ymin as f32).round() as i32;
+
+        for (index, &alpha) in coverage.iter().enumerate() {
+            let x = pen_x + metrics.xmin + (index % metrics.width.max(1)) as i32;
+            let y = top + (index / metrics.width.max(1)) as i32;
+
+            if alpha > 0 && x >= 0 && y >= 0 && (x as u32) < w && (y as u32) < h {
+                pixels[y as usize * w as usize + x as usize] = color.with_alpha(alpha);
+            }
+        }
+
+        pen_x += metrics.advance_width.ceil() as i32;
+    }
+
+    Image {
+        pixels,
+        x: 0,
+        y: 0,
+        w,
+        h,
+        flow: ImageFlow::None,
+        upside_down: false,
+    }
+}
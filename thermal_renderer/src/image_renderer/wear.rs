@@ -0,0 +1,106 @@
+//! Wear
+//!
+//! Wear simulates a worn print head on the finished receipt. Dots
+//! of ink are dropped at random and weak heater columns print
+//! lighter from the top to the bottom of the receipt, like the pale
+//! streaks of an old printer.
+//!
+//! The artifacts come from a seeded generator, the same seed gives
+//! the same artifacts for the same receipt. Every receipt of a
+//! renderer uses the seed, so the columns of the head stay weak
+//! from one job to the next.
+//!
+//! ```
+//! use thermal_renderer::image_renderer::wear::Wear;
+//! use thermal_renderer::image_renderer::ImageRenderer;
+//!
+//! let mut renderer = ImageRenderer::new();
+//! renderer.wear = Some(Wear {
+//!     dropped_dots: 0.05,
+//!     ..Wear::new(7)
+//! });
+//! ```
+
+//Ink is faded towards white paper
+const PAPER: u8 = 255;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wear {
+    pub seed: u64,
+    /// Chance that a dot of ink is not printed, 0 to 1
+    pub dropped_dots: f32,
+    /// Chance that the heater of a dot column is weak, 0 to 1
+    pub weak_columns: f32,
+    /// Ink that weak columns still print, 0 is none and 1 is all
+    pub weak_strength: f32,
+}
+
+impl Wear {
+    /// 2% dropped dots and 1% weak columns that print 40% of the ink
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            dropped_dots: 0.02,
+            weak_columns: 0.01,
+            weak_strength: 0.4,
+        }
+    }
+
+    /// Wears rgb pixels that are width wide
+    pub fn apply_rgb(&self, bytes: &mut [u8], width: u32, height: u32) {
+        let mut random = SplitMix::new(self.seed);
+        let weak_strength = self.weak_strength.clamp(0.0, 1.0);
+
+        //The columns are picked first so they only depend on the width
+        let strengths: Vec<f32> = (0..width)
+            .map(|_| match random.chance(self.weak_columns) {
+                true => weak_strength,
+                false => 1.0,
+            })
+            .collect();
+
+        for y in 0..height as usize {
+            for (x, strength) in strengths.iter().enumerate() {
+                let index = (y * width as usize + x) * 3;
+                let pixel = &mut bytes[index..index + 3];
+
+                if pixel.iter().all(|byte| *byte == PAPER) {
+                    continue;
+                }
+
+                let strength = match random.chance(self.dropped_dots) {
+                    true => 0.0,
+                    false => *strength,
+                };
+
+                for byte in pixel.iter_mut() {
+                    let ink = (PAPER - *byte) as f32 * strength;
+                    *byte = PAPER - ink.round() as u8;
+                }
+            }
+        }
+    }
+}
+
+/// SplitMix64, small and the same on every platform
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// True with the probability, a random number is taken either way
+    fn chance(&mut self, probability: f32) -> bool {
+        let value = (self.next() >> 40) as f32 / (1u64 << 24) as f32;
+        value < probability
+    }
+}
@@ -0,0 +1,231 @@
+//! Job Splitter
+//!
+//! Printers without a cutter never get a cut, the paper is torn off
+//! by hand and every job of a capture ends up on one long receipt.
+//! The job splitter is middleware that ends a job where a rule says
+//! a receipt ended, the jobs are output one by one like jobs that
+//! were ended by a form feed.
+//!
+//! A rule sees every command and returns true when the job ends at
+//! the command. The new job starts before the next command that
+//! prints, so commands after the end of a receipt (feeds, pulses,
+//! status requests) stay with it. Jobs that didn't print anything
+//! are never split.
+//!
+//! ```
+//! use thermal_parser::thermal_file::parse_str;
+//! use thermal_renderer::job_splitter::JobSplitter;
+//! use thermal_renderer::json_renderer::JsonRenderer;
+//! use thermal_renderer::renderer::{DebugProfile, OutputRenderer, Renderer};
+//!
+//! let bytes = parse_str(r#"ESC "@" "COFFEE" LF ESC "@" "TEA" LF"#);
+//!
+//! let mut json_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+//! let mut renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+//! renderer.add_middleware(Box::new(JobSplitter::cutterless()));
+//!
+//! assert_eq!(renderer.render(&bytes).output.len(), 2);
+//! ```
+//!
+//! Rules are closures or implement `SplitRule`, they are added with
+//! `JobSplitter::with_rule`.
+
+use crate::renderer::CommandMiddleware;
+use std::collections::VecDeque;
+use thermal_parser::command::{Command, CommandType, DeviceCommand};
+use thermal_parser::commands::{begin_print, end_print};
+use thermal_parser::constants::ESC;
+use thermal_parser::context::Context;
+
+/// Decides where a job ends
+pub trait SplitRule {
+    /// True when the job ends at the command
+    fn ends_job(&mut self, command: &Command, context: &Context) -> bool;
+}
+
+impl<F> SplitRule for F
+where
+    F: FnMut(&Command, &Context) -> bool,
+{
+    fn ends_job(&mut self, command: &Command, context: &Context) -> bool {
+        self(command, context)
+    }
+}
+
+/// ESC @ starts the next job
+pub struct SplitOnInitialize;
+
+impl SplitRule for SplitOnInitialize {
+    fn ends_job(&mut self, command: &Command, _context: &Context) -> bool {
+        command.commands[..] == [ESC, b'@']
+    }
+}
+
+/// A drawer pulse after the paper was fed past the tear bar ends the
+/// job, receipts of cash sales end like this
+pub struct SplitOnFeedAndPulse {
+    /// Millimeters of paper that are fed after the last thing printed
+    pub min_feed_mm: f32,
+    //Dots fed since something was printed
+    fed: u32,
+}
+
+impl SplitOnFeedAndPulse {
+    pub fn new(min_feed_mm: f32) -> Self {
+        Self {
+            min_feed_mm,
+            fed: 0,
+        }
+    }
+}
+
+impl SplitRule for SplitOnFeedAndPulse {
+    fn ends_job(&mut self, command: &Command, context: &Context) -> bool {
+        if prints(command, context) {
+            self.fed = 0;
+            return false;
+        }
+
+        let line = context.text.line_spacing as u32;
+        if command.kind == CommandType::Text {
+            //Only blank lines get here
+            self.fed += line;
+            return false;
+        }
+
+        let mut pulse = false;
+        for device_command in command
+            .handler
+            .get_device_command(command, context)
+            .unwrap_or_default()
+        {
+            match device_command {
+                DeviceCommand::FeedLine(lines) if lines > 0 => self.fed += lines as u32 * line,
+                DeviceCommand::Feed(units) if units > 0 => {
+                    self.fed += context.v_units_to_dots(units as i32) as u32
+                }
+                DeviceCommand::Pulse => pulse = true,
+                _ => {}
+            }
+        }
+
+        pulse && self.fed >= context.profile.mm_to_dots(self.min_feed_mm)
+    }
+}
+
+/// Capture tools can write a marker into the bytes when the connection
+/// was idle for a while, the job ends at the marker. The marker has to
+/// be a command that doesn't print or change anything, i.e. `ESC = 1`
+/// which selects the printer again.
+pub struct SplitOnMarker {
+    pub marker: Vec<u8>,
+    //The last bytes of the commands, as long as the marker
+    recent: VecDeque<u8>,
+}
+
+impl SplitOnMarker {
+    pub fn new(marker: &[u8]) -> Self {
+        Self {
+            marker: marker.to_vec(),
+            recent: VecDeque::with_capacity(marker.len()),
+        }
+    }
+}
+
+impl SplitRule for SplitOnMarker {
+    fn ends_job(&mut self, command: &Command, _context: &Context) -> bool {
+        if self.marker.is_empty() {
+            return false;
+        }
+
+        //The marker can be spread over commands, i.e. parts of text
+        let mut found = false;
+        for byte in command.to_bytes() {
+            if self.recent.len() == self.marker.len() {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(byte);
+            found |= self.recent.iter().eq(self.marker.iter());
+        }
+        found
+    }
+}
+
+/// Middleware that splits jobs by its rules
+#[derive(Default)]
+pub struct JobSplitter {
+    rules: Vec<Box<dyn SplitRule>>,
+    //Something was printed since the job started
+    printed: bool,
+    //A rule ended the job, the next job starts with the next print
+    ended: bool,
+}
+
+impl JobSplitter {
+    /// A splitter without rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Splits on ESC @ and on a drawer pulse after 10mm of feed
+    pub fn cutterless() -> Self {
+        Self::new()
+            .with_rule(SplitOnInitialize)
+            .with_rule(SplitOnFeedAndPulse::new(10.0))
+    }
+
+    pub fn with_rule(mut self, rule: impl SplitRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+}
+
+impl CommandMiddleware for JobSplitter {
+    fn process(&mut self, command: Command, context: &Context) -> Vec<Command> {
+        let mut commands = vec![];
+
+        if prints(&command, context) {
+            if self.ended {
+                commands.push(end_print::new());
+                commands.push(begin_print::new());
+                self.ended = false;
+            }
+            self.printed = true;
+        }
+
+        //Every rule sees every command to keep track of the job
+        let mut ends_job = false;
+        for rule in &mut self.rules {
+            ends_job |= rule.ends_job(&command, context);
+        }
+
+        //Form feeds already end the job
+        let ends_print = command
+            .handler
+            .get_device_command(&command, context)
+            .is_some_and(|device_commands| device_commands.contains(&DeviceCommand::EndPrint));
+
+        if ends_print {
+            self.printed = false;
+            self.ended = false;
+        } else if ends_job && self.printed {
+            self.printed = false;
+            self.ended = true;
+        }
+
+        commands.push(command);
+        commands
+    }
+}
+
+//Text that isn't blank, images, barcodes and 2d codes
+fn prints(command: &Command, context: &Context) -> bool {
+    match command.kind {
+        CommandType::Text => command
+            .handler
+            .get_text(command, context)
+            .is_some_and(|span| !span.text.trim().is_empty()),
+        CommandType::Graphics | CommandType::Subcommand => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,534 @@
+//! JSON Renderer
+//!
+//! The JSON renderer outputs the structure of a receipt instead
+//! of pixels. Each receipt is a list of positioned elements: text
+//! runs with their style, image bounds, barcode payloads and cut
+//! positions.
+//!
+//! Coordinates are in dots from the top left of the render area,
+//! the same coordinates the other renderers draw at.
+//!
+//! Elements that are drawn in page mode are marked with
+//! `page_mode` and are relative to the page area. The page area
+//! itself is output as a `page` element when it gets printed.
+//!
+//! Text runs have metrics: the y of their baseline, and how far
+//! the character cell reaches above (ascent) and below (descent) it.
+//! The baseline sits where the image renderer draws it for the font.
+//! Every line of text is output as well, with the box around all of
+//! its runs and the baseline of its tallest run, to line highlights
+//! up with the text of a preview.
+
+use crate::image_renderer::thermal_image::{font_size_and_baseline, DEFAULT_FONT_SIZE};
+use crate::renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
+use thermal_parser::command::DeviceCommand;
+use thermal_parser::context::{Context, Rotation, TextJustify};
+use thermal_parser::graphics::{Image, VectorGraphic};
+use thermal_parser::text::TextSpan;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bounds {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub font: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub inverted: bool,
+    pub upside_down: bool,
+    pub width_mult: u8,
+    pub height_mult: u8,
+    pub justify: String,
+    pub color: String,
+}
+
+/// Where the baseline of text is, in dots
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextMetrics {
+    /// The y of the baseline
+    pub baseline: u32,
+    /// From the top of the text to the baseline
+    pub ascent: u32,
+    /// From the baseline to the bottom of the text
+    pub descent: u32,
+}
+
+/// A line of text, the bounds are around all of its runs
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineLayout {
+    pub bounds: Bounds,
+    pub page_mode: bool,
+    /// The y of the baseline of the tallest run
+    pub baseline: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutElement {
+    Text {
+        bounds: Bounds,
+        page_mode: bool,
+        text: String,
+        style: TextStyle,
+        metrics: TextMetrics,
+    },
+    Image {
+        bounds: Bounds,
+        page_mode: bool,
+    },
+    Graphics {
+        bounds: Bounds,
+        page_mode: bool,
+    },
+    Code {
+        bounds: Bounds,
+        page_mode: bool,
+        symbology: String,
+        payload: String,
+        hri: Option<String>,
+    },
+    Page {
+        bounds: Bounds,
+    },
+    Cut {
+        y: u32,
+        partial: bool,
+    },
+}
+
+/// ReceiptLayout is the main output for the json renderer
+pub struct ReceiptLayout {
+    pub width: u32,
+    pub height: u32,
+    pub elements: Vec<LayoutElement>,
+    pub lines: Vec<LineLayout>,
+}
+
+#[derive(Default)]
+pub struct JsonRenderer {
+    pub elements: Vec<LayoutElement>,
+    pub lines: Vec<LineLayout>,
+    pub debug_profile: DebugProfile,
+    //Lines of pages that were printed, they stay when a page is cleared
+    printed_lines: usize,
+}
+
+impl JsonRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This is the normal way to render bytes to a layout
+    pub fn render(
+        bytes: &[u8],
+        debug_profile: Option<DebugProfile>,
+    ) -> RenderOutput<ReceiptLayout> {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, debug_profile.unwrap_or_default());
+        renderer.render(bytes)
+    }
+}
+
+impl OutputRenderer<ReceiptLayout> for JsonRenderer {
+    fn set_debug_profile(&mut self, profile: DebugProfile) {
+        self.debug_profile = profile;
+    }
+
+    fn begin_render(&mut self, _context: &mut Context) {
+        self.elements.clear();
+        self.lines.clear();
+        self.printed_lines = 0;
+    }
+
+    fn page_begin(&mut self, _context: &mut Context) {}
+
+    fn page_clear(&mut self, context: &mut Context) {
+        let area = context.page_mode.print_area();
+        let whole_page = area.w == 0 || area.h == 0;
+        let inside = |bounds: &Bounds| {
+            whole_page
+                || (bounds.x >= area.x
+                    && bounds.y >= area.y
+                    && bounds.x < area.x + area.w
+                    && bounds.y < area.y + area.h)
+        };
+
+        //Only elements of the page that was not printed yet can be removed
+        let printed = self
+            .elements
+            .iter()
+            .rposition(|e| matches!(e, LayoutElement::Page { .. }))
+            .map_or(0, |i| i + 1);
+
+        let mut index = 0;
+        self.elements.retain(|element| {
+            index += 1;
+            if index <= printed {
+                return true;
+            }
+
+            match element {
+                LayoutElement::Text {
+                    bounds, page_mode, ..
+                }
+                | LayoutElement::Image { bounds, page_mode }
+                | LayoutElement::Graphics { bounds, page_mode }
+                | LayoutElement::Code {
+                    bounds, page_mode, ..
+                } => !(*page_mode && inside(bounds)),
+                _ => true,
+            }
+        });
+
+        let mut index = 0;
+        self.lines.retain(|line| {
+            index += 1;
+            index <= self.printed_lines || !(line.page_mode && inside(&line.bounds))
+        });
+    }
+
+    fn page_area_changed(
+        &mut self,
+        _context: &mut Context,
+        _rotation: Rotation,
+        _width: u32,
+        _height: u32,
+    ) {
+    }
+
+    fn render_page(&mut self, context: &mut Context) {
+        self.printed_lines = self.lines.len();
+        self.elements.push(LayoutElement::Page {
+            bounds: Bounds {
+                x: context.graphics.render_area.x,
+                y: context.graphics.render_area.y,
+                w: context.page_mode.page_area.w,
+                h: context.page_mode.page_area.h,
+            },
+        });
+    }
+
+    fn render_graphics(&mut self, context: &mut Context, graphics: &Vec<VectorGraphic>) {
+        if let Some(bounds) = graphics_bounds(graphics) {
+            self.elements.push(LayoutElement::Graphics {
+                bounds,
+                page_mode: context.page_mode.enabled,
+            });
+        }
+    }
+
+    fn render_code(
+        &mut self,
+        context: &mut Context,
+        graphics: &Vec<VectorGraphic>,
+        symbology: &str,
+        _label: &str,
+        payload: &str,
+        hri: Option<&str>,
+    ) {
+        if let Some(bounds) = graphics_bounds(graphics) {
+            self.elements.push(LayoutElement::Code {
+                bounds,
+                page_mode: context.page_mode.enabled,
+                symbology: symbology.to_string(),
+                payload: payload.to_string(),
+                hri: hri.map(|hri| hri.to_string()),
+            });
+        }
+    }
+
+    fn render_image(&mut self, context: &mut Context, image: &Image) {
+        self.elements.push(LayoutElement::Image {
+            bounds: Bounds {
+                x: image.x,
+                y: image.y,
+                w: image.w,
+                h: image.h,
+            },
+            page_mode: context.page_mode.enabled,
+        });
+    }
+
+    fn render_text(
+        &mut self,
+        context: &mut Context,
+        spans: &Vec<TextSpan>,
+        x_offset: u32,
+        max_height: u32,
+        _text_justify: TextJustify,
+    ) {
+        let page_mode = context.page_mode.enabled;
+        let mut line: Option<LineLayout> = None;
+        let mut tallest = 0;
+
+        for span in spans {
+            if let Some(dimensions) = &span.dimensions {
+                //Spans share a baseline, shorter spans sit at the bottom of the line
+                let bounds = Bounds {
+                    x: dimensions.x + x_offset,
+                    y: dimensions.y + max_height.saturating_sub(span.character_height),
+                    w: dimensions.w,
+                    h: span.character_height,
+                };
+                let (_, baseline_ratio) = font_size_and_baseline(DEFAULT_FONT_SIZE, &span.font);
+                let ascent = (bounds.h as f32 * baseline_ratio) as u32;
+                let metrics = TextMetrics {
+                    baseline: bounds.y + ascent,
+                    ascent,
+                    descent: bounds.h - ascent,
+                };
+
+                match &mut line {
+                    Some(line) => {
+                        let right = (line.bounds.x + line.bounds.w).max(bounds.x + bounds.w);
+                        let bottom = (line.bounds.y + line.bounds.h).max(bounds.y + bounds.h);
+                        line.bounds.x = line.bounds.x.min(bounds.x);
+                        line.bounds.y = line.bounds.y.min(bounds.y);
+                        line.bounds.w = right - line.bounds.x;
+                        line.bounds.h = bottom - line.bounds.y;
+                        if bounds.h > tallest {
+                            line.baseline = metrics.baseline;
+                        }
+                    }
+                    None => {
+                        line = Some(LineLayout {
+                            bounds: bounds.clone(),
+                            page_mode,
+                            baseline: metrics.baseline,
+                        })
+                    }
+                }
+                tallest = tallest.max(bounds.h);
+
+                let style = TextStyle {
+                    font: format!("{:?}", span.font),
+                    bold: span.bold,
+                    italic: span.italic,
+                    underline: span.underline > 0,
+                    strikethrough: span.strikethrough > 0,
+                    inverted: span.inverted,
+                    upside_down: span.upside_down,
+                    width_mult: span.stretch_width as u8,
+                    height_mult: span.stretch_height as u8,
+                    justify: format!("{:?}", span.justify).to_lowercase(),
+                    color: span.text_color.as_hex(),
+                };
+
+                //Words are separate spans, join touching spans with the same style into one run
+                if let Some(LayoutElement::Text {
+                    bounds: last_bounds,
+                    page_mode: last_page_mode,
+                    text: last_text,
+                    style: last_style,
+                    ..
+                }) = self.elements.last_mut()
+                {
+                    if *last_page_mode == page_mode
+                        && *last_style == style
+                        && last_bounds.y == bounds.y
+                        && last_bounds.h == bounds.h
+                        && last_bounds.x + last_bounds.w == bounds.x
+                    {
+                        last_bounds.w += bounds.w;
+                        last_text.push_str(&span.text);
+                        continue;
+                    }
+                }
+
+                self.elements.push(LayoutElement::Text {
+                    bounds,
+                    page_mode,
+                    text: span.text.clone(),
+                    style,
+                    metrics,
+                });
+            }
+        }
+
+        self.lines.extend(line);
+    }
+
+    fn device_command(&mut self, context: &mut Context, command: &DeviceCommand) {
+        match command {
+            DeviceCommand::FullCut | DeviceCommand::PartialCut => {
+                self.elements.push(LayoutElement::Cut {
+                    y: context.get_y(),
+                    partial: matches!(command, DeviceCommand::PartialCut),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn end_render(&mut self, context: &mut Context) -> ReceiptLayout {
+        ReceiptLayout {
+            width: context.graphics.render_area.w,
+            height: context.graphics.render_area.y,
+            elements: std::mem::take(&mut self.elements),
+            lines: std::mem::take(&mut self.lines),
+        }
+    }
+}
+
+fn graphics_bounds(graphics: &Vec<VectorGraphic>) -> Option<Bounds> {
+    if graphics.is_empty() {
+        return None;
+    }
+
+    let mut min_x = u32::MAX;
+    let mut min_y = u32::MAX;
+    let mut max_x = 0;
+    let mut max_y = 0;
+
+    for graphic in graphics {
+        match graphic {
+            VectorGraphic::Rectangle(rectangle) => {
+                min_x = min_x.min(rectangle.x);
+                min_y = min_y.min(rectangle.y);
+                max_x = max_x.max(rectangle.x + rectangle.w);
+                max_y = max_y.max(rectangle.y + rectangle.h);
+            }
+        }
+    }
+
+    Some(Bounds {
+        x: min_x,
+        y: min_y,
+        w: max_x - min_x,
+        h: max_y - min_y,
+    })
+}
+
+impl ReceiptLayout {
+    /// Serializes the layout to a json document
+    pub fn to_json(&self) -> String {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.to_json()).collect();
+        let lines: Vec<String> = self.lines.iter().map(|l| l.to_json()).collect();
+
+        format!(
+            "{{\"width\":{},\"height\":{},\"elements\":[{}],\"lines\":[{}]}}",
+            self.width,
+            self.height,
+            elements.join(","),
+            lines.join(",")
+        )
+    }
+}
+
+impl LayoutElement {
+    pub fn to_json(&self) -> String {
+        match self {
+            LayoutElement::Text {
+                bounds,
+                page_mode,
+                text,
+                style,
+                metrics,
+            } => format!(
+                "{{\"type\":\"text\",{},\"page_mode\":{},\"text\":{},\"style\":{},\"baseline\":{},\"ascent\":{},\"descent\":{}}}",
+                bounds.to_json_fields(),
+                page_mode,
+                json_string(text),
+                style.to_json(),
+                metrics.baseline,
+                metrics.ascent,
+                metrics.descent
+            ),
+            LayoutElement::Image { bounds, page_mode } => format!(
+                "{{\"type\":\"image\",{},\"page_mode\":{}}}",
+                bounds.to_json_fields(),
+                page_mode
+            ),
+            LayoutElement::Graphics { bounds, page_mode } => format!(
+                "{{\"type\":\"graphics\",{},\"page_mode\":{}}}",
+                bounds.to_json_fields(),
+                page_mode
+            ),
+            LayoutElement::Code {
+                bounds,
+                page_mode,
+                symbology,
+                payload,
+                hri,
+            } => format!(
+                "{{\"type\":\"code\",{},\"page_mode\":{},\"symbology\":{},\"payload\":{},\"hri\":{}}}",
+                bounds.to_json_fields(),
+                page_mode,
+                json_string(symbology),
+                json_string(payload),
+                hri.as_deref().map_or("null".to_string(), json_string)
+            ),
+            LayoutElement::Page { bounds } => {
+                format!("{{\"type\":\"page\",{}}}", bounds.to_json_fields())
+            }
+            LayoutElement::Cut { y, partial } => {
+                format!("{{\"type\":\"cut\",\"y\":{},\"partial\":{}}}", y, partial)
+            }
+        }
+    }
+}
+
+impl LineLayout {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{{},\"page_mode\":{},\"baseline\":{}}}",
+            self.bounds.to_json_fields(),
+            self.page_mode,
+            self.baseline
+        )
+    }
+}
+
+impl Bounds {
+    fn to_json_fields(&self) -> String {
+        format!(
+            "\"x\":{},\"y\":{},\"w\":{},\"h\":{}",
+            self.x, self.y, self.w, self.h
+        )
+    }
+}
+
+impl TextStyle {
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"font\":{},\"bold\":{},\"italic\":{},\"underline\":{},\"strikethrough\":{},\"inverted\":{},\"upside_down\":{},\"width_mult\":{},\"height_mult\":{},\"justify\":{},\"color\":{}}}",
+            json_string(&self.font),
+            self.bold,
+            self.italic,
+            self.underline,
+            self.strikethrough,
+            self.inverted,
+            self.upside_down,
+            self.width_mult,
+            self.height_mult,
+            json_string(&self.justify),
+            json_string(&self.color)
+        )
+    }
+}
+
+pub(crate) fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,215 @@
+//! Text Layout
+//!
+//! Lays out text spans into lines the same way the Renderer does,
+//! without going through an OutputRenderer. Text is broken into
+//! words, words that don't fit wrap to the next line and words
+//! wider than the line are broken apart.
+//!
+//! The layout starts at the position of the context and moves the
+//! context to where the next text goes, so consecutive calls continue
+//! on the same line. A span of only "\n" ends the line.
+//!
+//! ```
+//! use thermal_parser::context::Context;
+//! use thermal_parser::text::TextSpan;
+//! use thermal_renderer::layout::layout_text;
+//!
+//! let mut context = Context::new();
+//! let spans = vec![
+//!     TextSpan::new("Hello".to_string(), &context),
+//!     TextSpan::new("\n".to_string(), &context),
+//!     TextSpan::new("World".to_string(), &context),
+//! ];
+//!
+//! for line in layout_text(&spans, &mut context) {
+//!     println!("{} spans at {}", line.spans.len(), line.x_offset);
+//! }
+//! ```
+
+use std::mem;
+use thermal_parser::context::{Context, TextJustify};
+use thermal_parser::text::{break_ranges, word_ranges, TextSpan};
+
+/// A line of text, the spans have their dimensions set
+#[derive(Clone, Debug)]
+pub struct TextLine {
+    pub spans: Vec<TextSpan>,
+    /// How far the line is moved right to justify it
+    pub x_offset: u32,
+    /// Height of the tallest span, the first line is also as tall
+    /// as anything that was already on the line
+    pub height: u32,
+    pub justify: TextJustify,
+}
+
+/// Lays out the spans into lines starting at the context position,
+/// lines that only end a line are left out
+pub fn layout_text(spans: &[TextSpan], context: &mut Context) -> Vec<TextLine> {
+    let mut lines: Vec<Vec<TextSpan>> = vec![];
+    let mut current_line: Vec<TextSpan> = vec![];
+    let max_width = context.get_width();
+
+    //The first line continues a line that may already have graphics on it
+    let start_line_height = context.text.current_line_height;
+
+    //Words are ranges of the span text, a span is only cloned for the
+    //parts that end up on a line
+    let words = spans
+        .iter()
+        .flat_map(|span| word_ranges(&span.text).map(move |range| (span, range)));
+
+    for (span, range) in words {
+        let text = &span.text[range];
+
+        //Calculate available width every loop
+        let avail_width = context.get_available_width();
+        let word_width = text.chars().count() as u32 * span.character_width;
+
+        //Newlines advance y and reset x
+        if text == "\n" {
+            //Advance line height
+            context.newline_for_spans(&current_line);
+
+            //Swap current line
+            let mut finished_line = vec![];
+            mem::swap(&mut current_line, &mut finished_line);
+            lines.push(finished_line);
+
+            //Start a new line
+            lines.push(vec![]); //Newline
+            continue;
+        }
+
+        //Tabs have a special behavior
+        if text == "\t" {
+            let current_x = context.get_x();
+            let mut current_tab_pos = 0;
+            for tab_len in &context.text.tabs {
+                if current_tab_pos >= current_x {
+                    context.set_x(current_tab_pos);
+                    break;
+                }
+                current_tab_pos += *tab_len as u32 * span.character_width;
+            }
+            continue;
+        }
+
+        //A space at the end of a word may hang past the end of the line
+        let trimmed = text.trim_end();
+        if word_width > avail_width
+            && !trimmed.is_empty()
+            && trimmed.chars().count() as u32 * span.character_width <= avail_width
+        {
+            let width = place(span, trimmed, context, &mut current_line);
+            context.offset_x(width);
+            continue;
+        }
+
+        if word_width <= avail_width {
+            //Word fits into the line, add it
+            let width = place(span, text, context, &mut current_line);
+            context.offset_x(width);
+            continue;
+        } else if word_width > max_width {
+            //Break the word into parts for super long words
+            let character_width = span.character_width.max(1);
+            let broken = break_ranges(
+                text,
+                (avail_width / character_width) as usize,
+                (max_width / character_width).max(1) as usize,
+            );
+
+            let broken_len = broken.len().saturating_sub(1);
+            for (i, part) in broken.into_iter().enumerate() {
+                let width = place(span, &text[part], context, &mut current_line);
+
+                if broken_len == i {
+                    //Last word doesn't geta a forced newline
+                    context.offset_x(width);
+                } else {
+                    //Every other line we assume will fit into a line
+
+                    //Advance line
+                    context.newline_for_spans(&current_line);
+
+                    //Swap line
+                    let mut finished_line = vec![];
+                    mem::swap(&mut current_line, &mut finished_line);
+                    lines.push(finished_line);
+                }
+            }
+        } else {
+            //Close out previous line
+            let mut finished_line = vec![];
+            context.newline_for_spans(&current_line);
+            mem::swap(&mut current_line, &mut finished_line);
+            lines.push(finished_line);
+
+            //Add text to newline at 0 x
+            let width = place(span, text, context, &mut current_line);
+
+            //Advance the x
+            context.offset_x(width);
+        }
+    }
+
+    if !current_line.is_empty() {
+        //The line stays open, the next newline needs to clear this text
+        for span in &current_line {
+            context.extend_line_height(span.character_height);
+        }
+        lines.push(current_line);
+    }
+
+    //Adjust lines for justification
+    let mut text_lines = vec![];
+
+    for (index, line) in lines.into_iter().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let justify = line.first().unwrap().justify.clone();
+
+        let mut height = 0;
+        let mut line_width = 0;
+        let mut x_offset = 0;
+
+        for span in &line {
+            line_width += span.get_width();
+            height = height.max(span.character_height);
+        }
+
+        if index == 0 {
+            height = height.max(start_line_height);
+        }
+
+        match justify {
+            TextJustify::Right => {
+                x_offset = max_width.saturating_sub(line_width);
+            }
+            TextJustify::Center if line_width < max_width => {
+                x_offset = (max_width - line_width) / 2;
+            }
+            _ => {}
+        }
+
+        text_lines.push(TextLine {
+            spans: line,
+            x_offset,
+            height,
+            justify,
+        });
+    }
+
+    text_lines
+}
+
+//Adds the text with the style of the span at the context position to
+//the line and returns its width
+fn place(span: &TextSpan, text: &str, context: &Context, line: &mut Vec<TextSpan>) -> u32 {
+    let mut word = span.clone_with(text.to_string());
+    word.get_dimensions(context);
+    let width = word.get_width();
+    line.push(word);
+    width
+}
@@ -0,0 +1,152 @@
+//! Length Renderer
+//!
+//! The length renderer runs the full layout without drawing
+//! anything. It only keeps track of how far down the paper
+//! each element reaches and where the cuts are.
+//!
+//! This is useful for finding the final paper size before
+//! paying the cost of rasterizing, for example to pre allocate
+//! canvases or to reject jobs that are far too long.
+
+use crate::renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
+use thermal_parser::command::DeviceCommand;
+use thermal_parser::context::{Context, PrintDirection, Rotation, TextJustify};
+use thermal_parser::graphics::{Image, VectorGraphic};
+use thermal_parser::text::TextSpan;
+
+/// ReceiptLength is the main output for the length renderer,
+/// width and height match the image renderer output
+pub struct ReceiptLength {
+    pub width: u32,
+    pub height: u32,
+    pub cuts: Vec<u32>,
+}
+
+#[derive(Default)]
+pub struct LengthRenderer {
+    pub max_y: u32,
+    pub page_width: u32,
+    pub page_height: u32,
+    pub cuts: Vec<u32>,
+    pub debug_profile: DebugProfile,
+}
+
+impl LengthRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This is the normal way to measure bytes
+    pub fn render(
+        bytes: &[u8],
+        debug_profile: Option<DebugProfile>,
+    ) -> RenderOutput<ReceiptLength> {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(LengthRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, debug_profile.unwrap_or_default());
+        renderer.render(bytes)
+    }
+
+    fn extend_to(&mut self, context: &Context, y: u32) {
+        //Page mode content ends up on the paper through render_page
+        if !context.page_mode.enabled {
+            self.max_y = self.max_y.max(y);
+        }
+    }
+}
+
+impl OutputRenderer<ReceiptLength> for LengthRenderer {
+    fn set_debug_profile(&mut self, profile: DebugProfile) {
+        self.debug_profile = profile;
+    }
+
+    fn begin_render(&mut self, _context: &mut Context) {
+        self.max_y = 0;
+        self.cuts.clear();
+    }
+
+    fn page_begin(&mut self, _context: &mut Context) {
+        self.page_width = 0;
+        self.page_height = 0;
+    }
+
+    //The page size follows the same rotations and growth as the image renderer page
+    fn page_area_changed(
+        &mut self,
+        _context: &mut Context,
+        rotation: Rotation,
+        width: u32,
+        height: u32,
+    ) {
+        if matches!(rotation, Rotation::R90 | Rotation::R270) {
+            std::mem::swap(&mut self.page_width, &mut self.page_height);
+        }
+
+        if width > self.page_width {
+            self.page_width = width;
+        }
+        if self.page_width > 0 {
+            self.page_height = self.page_height.max(height);
+        }
+    }
+
+    fn render_page(&mut self, context: &mut Context) {
+        let rotation_to_standard = context.page_mode.calculate_directional_rotation(
+            &context.page_mode.direction,
+            &PrintDirection::TopLeft2Right,
+        );
+
+        let height = match rotation_to_standard {
+            Rotation::R90 | Rotation::R270 => self.page_width,
+            _ => self.page_height,
+        };
+
+        self.max_y = self.max_y.max(context.graphics.render_area.y + height);
+    }
+
+    fn render_graphics(&mut self, context: &mut Context, graphics: &Vec<VectorGraphic>) {
+        for graphic in graphics {
+            match graphic {
+                VectorGraphic::Rectangle(rectangle) => {
+                    self.extend_to(context, rectangle.y + rectangle.h);
+                }
+            }
+        }
+    }
+
+    fn render_image(&mut self, context: &mut Context, image: &Image) {
+        self.extend_to(context, image.y + image.h);
+    }
+
+    fn render_text(
+        &mut self,
+        context: &mut Context,
+        spans: &Vec<TextSpan>,
+        _x_offset: u32,
+        max_height: u32,
+        _text_justify: TextJustify,
+    ) {
+        for span in spans {
+            if let Some(dimensions) = &span.dimensions {
+                self.extend_to(context, dimensions.y + max_height);
+            }
+        }
+    }
+
+    fn device_command(&mut self, context: &mut Context, command: &DeviceCommand) {
+        if matches!(command, DeviceCommand::FullCut | DeviceCommand::PartialCut) {
+            self.cuts.push(context.get_y());
+        }
+    }
+
+    fn end_render(&mut self, context: &mut Context) -> ReceiptLength {
+        ReceiptLength {
+            width: context
+                .graphics
+                .render_area
+                .w
+                .max(context.graphics.paper_area.w),
+            height: self.max_y.max(context.graphics.render_area.y),
+            cuts: std::mem::take(&mut self.cuts),
+        }
+    }
+}
@@ -1,4 +1,97 @@
 // pub mod html_renderer;
+pub mod image_diff;
+pub mod barcode_renderer;
+pub mod event_renderer;
+pub mod font_subset;
 pub mod html_renderer;
+pub mod image_extractor;
 pub mod image_renderer;
+pub mod job_splitter;
+pub mod json_renderer;
+pub mod layout;
+pub mod length_renderer;
+pub mod line_display;
+pub mod pacing;
+pub mod pool;
+pub mod profiling;
+pub mod redaction;
 pub mod renderer;
+pub mod semantic_diff;
+pub mod signature;
+pub mod snapshot;
+pub mod stream;
+pub mod strings;
+pub mod trace_renderer;
+
+use html_renderer::{HtmlLayout, HtmlRenderer};
+use image_renderer::ImageRenderer;
+use renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
+use strings::Strings;
+use thermal_parser::profile::PrinterProfile;
+
+const NOTHING_RENDERED: &str = "Nothing was rendered";
+
+/// Options for `render_html`
+#[derive(Clone, Debug)]
+pub struct HtmlOptions {
+    pub layout: HtmlLayout,
+    /// Embed the font so nothing is loaded from outside the file
+    pub self_contained: bool,
+    pub profile: PrinterProfile,
+    /// Translations of the code labels and markers the renderer adds
+    pub strings: Strings,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions {
+            layout: HtmlLayout::Positioned,
+            self_contained: false,
+            profile: PrinterProfile::default(),
+            strings: Strings::default(),
+        }
+    }
+}
+
+/// Renders the bytes to a png of the first receipt, use the
+/// ImageRenderer for jobs with more than one receipt or to see
+/// the render errors
+pub fn render_png(bytes: &[u8], profile: &PrinterProfile) -> Result<Vec<u8>, String> {
+    let renders = render_with_profile(
+        Box::new(ImageRenderer::new()),
+        bytes,
+        profile,
+        &Strings::default(),
+    );
+    let image = renders.output.first().ok_or(NOTHING_RENDERED)?;
+    image.to_png()
+}
+
+/// Renders the bytes to the html of the first receipt, use the
+/// HtmlRenderer for jobs with more than one receipt or to see
+/// the render errors
+pub fn render_html(bytes: &[u8], options: &HtmlOptions) -> Result<String, String> {
+    let mut html_renderer = HtmlRenderer::new_with_layout(options.layout);
+    html_renderer.embed_font = options.self_contained;
+    let renders = render_with_profile(
+        Box::new(html_renderer),
+        bytes,
+        &options.profile,
+        &options.strings,
+    );
+    let html = renders.output.into_iter().next().ok_or(NOTHING_RENDERED)?;
+    Ok(html.content)
+}
+
+fn render_with_profile<Output>(
+    output_renderer: Box<dyn OutputRenderer<Output>>,
+    bytes: &[u8],
+    profile: &PrinterProfile,
+    strings: &Strings,
+) -> RenderOutput<Output> {
+    let mut child_renderer = output_renderer;
+    let mut renderer =
+        Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), profile);
+    renderer.set_strings(strings.clone());
+    renderer.render(bytes)
+}
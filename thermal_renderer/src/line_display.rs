@@ -0,0 +1,208 @@
+//! Line Display
+//!
+//! Customer pole displays (Epson DM-D and compatibles) show two lines
+//! of 20 characters. They understand a small dialect of ESC/POS: text,
+//! clearing the screen or a line, moving the cursor and the display
+//! modes. Jobs that select the display with ESC = send it these
+//! commands in between the receipt commands.
+//!
+//! The line display replays the bytes that were routed to the display
+//! (see `RenderConfig::route_peripherals`) and returns what the screen
+//! showed. A frame is the screen just before it was cleared, and the
+//! screen at the end, so every item of a transaction that was shown
+//! gets a frame.
+//!
+//! ```
+//! use thermal_renderer::line_display::LineDisplay;
+//!
+//! let frames = LineDisplay::new().render(b"\x0cCOFFEE\x1f\x24\x0f\x02 3.50");
+//!
+//! assert_eq!(frames[0].lines[0], "COFFEE              ");
+//! assert_eq!(frames[0].lines[1], "               3.50 ");
+//! ```
+//!
+//! Characters are read as Latin-1, commands that only change how the
+//! screen looks (brightness, blinking, the cursor) are skipped.
+
+const BS: u8 = 0x08;
+const HT: u8 = 0x09;
+const LF: u8 = 0x0A;
+const HOM: u8 = 0x0B;
+const CLR: u8 = 0x0C;
+const CR: u8 = 0x0D;
+const CAN: u8 = 0x18;
+const ESC: u8 = 0x1B;
+const US: u8 = 0x1F;
+
+/// What happens when the cursor moves past the end of the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayMode {
+    /// US MD1, the cursor goes back to the top line
+    Overwrite,
+    /// US MD2, the lines scroll up
+    VerticalScroll,
+}
+
+/// The text on the screen, one string per line
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayFrame {
+    pub lines: Vec<String>,
+}
+
+pub struct LineDisplay {
+    pub columns: usize,
+    pub rows: usize,
+    screen: Vec<Vec<char>>,
+    //Column and row of the cursor
+    cursor: (usize, usize),
+    mode: DisplayMode,
+    //The screen changed since the last frame
+    changed: bool,
+}
+
+impl Default for LineDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineDisplay {
+    /// A 2x20 display
+    pub fn new() -> Self {
+        Self::with_size(20, 2)
+    }
+
+    pub fn with_size(columns: usize, rows: usize) -> Self {
+        Self {
+            columns: columns.max(1),
+            rows: rows.max(1),
+            screen: vec![vec![' '; columns.max(1)]; rows.max(1)],
+            cursor: (0, 0),
+            mode: DisplayMode::Overwrite,
+            changed: false,
+        }
+    }
+
+    /// The frames the display showed for the bytes
+    pub fn render(&mut self, bytes: &[u8]) -> Vec<DisplayFrame> {
+        let mut frames = vec![];
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let byte = bytes[i];
+            let start = i + 1;
+            let param = |n: usize| bytes.get(start + n).copied().unwrap_or(0);
+            i += 1;
+
+            match byte {
+                CLR => {
+                    frames.extend(self.take_frame());
+                    self.clear();
+                }
+                HOM => self.cursor = (0, 0),
+                CR => self.cursor.0 = 0,
+                LF => self.line_down(),
+                BS => self.cursor.0 = self.cursor.0.saturating_sub(1),
+                HT => self.advance(),
+                CAN => {
+                    self.screen[self.cursor.1].fill(' ');
+                    self.cursor.0 = 0;
+                    self.changed = true;
+                }
+                ESC => match param(0) {
+                    //Initialize
+                    b'@' => {
+                        frames.extend(self.take_frame());
+                        self.clear();
+                        self.mode = DisplayMode::Overwrite;
+                        i += 1;
+                    }
+                    //Code table, character set and peripheral selection
+                    b't' | b'R' | b'=' => i += 2,
+                    _ => i += 1,
+                },
+                US => match param(0) {
+                    1 => {
+                        self.mode = DisplayMode::Overwrite;
+                        i += 1;
+                    }
+                    2 => {
+                        self.mode = DisplayMode::VerticalScroll;
+                        i += 1;
+                    }
+                    //Cursor position, both are 1 based
+                    b'$' => {
+                        let x = (param(1) as usize).clamp(1, self.columns);
+                        let y = (param(2) as usize).clamp(1, self.rows);
+                        self.cursor = (x - 1, y - 1);
+                        i += 3;
+                    }
+                    //Brightness, blinking, cursor on/off and reverse
+                    b'X' | b'E' | b'C' | b'r' => i += 2,
+                    _ => i += 1,
+                },
+                0x20.. => {
+                    self.screen[self.cursor.1][self.cursor.0] = byte as char;
+                    self.changed = true;
+                    self.advance();
+                }
+                _ => {}
+            }
+        }
+
+        frames.extend(self.take_frame());
+        frames
+    }
+
+    /// The text on the screen now
+    pub fn frame(&self) -> DisplayFrame {
+        DisplayFrame {
+            lines: self
+                .screen
+                .iter()
+                .map(|line| line.iter().collect())
+                .collect(),
+        }
+    }
+
+    //The screen as a frame if it changed and isn't blank
+    fn take_frame(&mut self) -> Option<DisplayFrame> {
+        let changed = self.changed;
+        self.changed = false;
+        let blank = self.screen.iter().flatten().all(|c| *c == ' ');
+        (changed && !blank).then(|| self.frame())
+    }
+
+    fn clear(&mut self) {
+        for line in &mut self.screen {
+            line.fill(' ');
+        }
+        self.cursor = (0, 0);
+    }
+
+    //Moves the cursor right, past the end of a line it goes to the
+    //start of the next line
+    fn advance(&mut self) {
+        self.cursor.0 += 1;
+        if self.cursor.0 == self.columns {
+            self.cursor.0 = 0;
+            self.line_down();
+        }
+    }
+
+    fn line_down(&mut self) {
+        if self.cursor.1 + 1 < self.rows {
+            self.cursor.1 += 1;
+            return;
+        }
+
+        match self.mode {
+            DisplayMode::Overwrite => self.cursor.1 = 0,
+            DisplayMode::VerticalScroll => {
+                self.screen.rotate_left(1);
+                self.screen[self.rows - 1].fill(' ');
+                self.changed = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Pacing
+//!
+//! Rendering is instant, a printer is not. Bytes reach a printer at
+//! the speed of its connection, a status request at the end of a long
+//! job is answered seconds after it was sent. POS software that waits
+//! too short for an answer works against the renderer and fails at
+//! the printer.
+//!
+//! `PacedRenderer` feeds the bytes to a `StreamRenderer` one packet at
+//! a time, as fast as the link carries them, and stamps every event
+//! with the time its packet arrived. In real time it also waits for
+//! the packets, so a POS talking to it sees the delays of the printer.
+//!
+//! ```
+//! use std::time::Duration;
+//! use thermal_parser::thermal_file::parse_str;
+//! use thermal_renderer::json_renderer::JsonRenderer;
+//! use thermal_renderer::pacing::{Link, PacedRenderer};
+//! use thermal_renderer::renderer::{DebugProfile, OutputRenderer, Renderer};
+//! use thermal_renderer::stream::{StreamEvent, StreamRenderer};
+//!
+//! let bytes = parse_str(r#""TOTAL 4.00" LF 0x10 0x04 1"#);
+//!
+//! let mut json_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+//! let renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+//! let stream = StreamRenderer::new(thermal_parser::new_esc_pos_parser(), renderer);
+//! let mut paced = PacedRenderer::new(stream, Link::serial(9600));
+//!
+//! for timed in paced.render(&bytes) {
+//!     if let StreamEvent::Event(_) = timed.event {
+//!         //The status request is answered when all 14 bytes arrived
+//!         assert_eq!(timed.at, Duration::from_micros(14583));
+//!     }
+//! }
+//! ```
+
+use crate::stream::{queue_events, StreamEvent, StreamRenderer};
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The connection bytes reach the printer over
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub bytes_per_sec: u32,
+    /// Bytes that arrive at once, the printer sees nothing of a
+    /// packet before all of it arrived
+    pub packet_size: usize,
+}
+
+impl Link {
+    /// A serial port with 8 data bits, no parity and a stop bit,
+    /// the printer receives through a 16 byte fifo
+    pub fn serial(baud: u32) -> Self {
+        Self {
+            bytes_per_sec: baud / 10,
+            packet_size: 16,
+        }
+    }
+
+    /// USB full speed with one 64 byte bulk packet per frame, about
+    /// what receipt printers accept
+    pub fn usb() -> Self {
+        Self {
+            bytes_per_sec: 64_000,
+            packet_size: 64,
+        }
+    }
+
+    /// Ethernet with a 1460 byte tcp segment, the printer is slower
+    /// than the network and takes the bytes at the speed given
+    pub fn network(bytes_per_sec: u32) -> Self {
+        Self {
+            bytes_per_sec,
+            packet_size: 1460,
+        }
+    }
+
+    /// Time to carry the bytes over the link
+    pub fn transfer_time(&self, bytes: usize) -> Duration {
+        let micros = bytes as u64 * 1_000_000 / self.bytes_per_sec.max(1) as u64;
+        Duration::from_micros(micros)
+    }
+}
+
+/// An event of the stream and when it happened, from the first byte
+#[derive(Debug)]
+pub struct TimedEvent<Output> {
+    pub at: Duration,
+    pub event: StreamEvent<Output>,
+}
+
+pub struct PacedRenderer<'a, Output> {
+    renderer: StreamRenderer<'a, Output>,
+    pub link: Link,
+    /// Wait for every packet as long as the link would take
+    pub realtime: bool,
+}
+
+impl<'a, Output> PacedRenderer<'a, Output> {
+    pub fn new(renderer: StreamRenderer<'a, Output>, link: Link) -> Self {
+        Self {
+            renderer,
+            link,
+            realtime: false,
+        }
+    }
+
+    /// Renders the bytes of one connection, the clock starts at zero
+    pub fn render(&mut self, bytes: &[u8]) -> Vec<TimedEvent<Output>> {
+        let mut events = vec![];
+        self.render_with(bytes, |event| events.push(event));
+        events
+    }
+
+    /// Renders the bytes of one connection and sends every event as
+    /// soon as it happens, in real time that is while the next
+    /// packets are still on their way
+    pub fn render_with(&mut self, bytes: &[u8], mut on_event: impl FnMut(TimedEvent<Output>)) {
+        let started = Instant::now();
+        let mut pending = VecDeque::new();
+        let mut arrived = 0;
+
+        for packet in bytes.chunks(self.link.packet_size.max(1)) {
+            arrived += packet.len();
+            let at = self.link.transfer_time(arrived);
+            if self.realtime {
+                thread::sleep((started + at).saturating_duration_since(Instant::now()));
+            }
+
+            queue_events(self.renderer.push(packet), &mut pending);
+            for event in pending.drain(..) {
+                on_event(TimedEvent { at, event });
+            }
+        }
+
+        //The job ends with the last byte
+        let at = self.link.transfer_time(arrived);
+        queue_events(self.renderer.finish(), &mut pending);
+        for event in pending.drain(..) {
+            on_event(TimedEvent { at, event });
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Buffer Pool
+//!
+//! Rectangles of barcodes and pixels of images are only needed while
+//! they are drawn. Instead of allocating a new vec for every symbol
+//! and image, the renderers take an empty buffer from a pool and give
+//! it back when they are done, the pool keeps its capacity.
+//!
+//! The pools are cleared when a job ends, so a long running renderer
+//! does not hold on to the buffers of its largest job and the heap
+//! is not left fragmented with many small allocations.
+//!
+//! ```
+//! use thermal_renderer::pool::BufferPool;
+//!
+//! let mut pool: BufferPool<u32> = BufferPool::new();
+//!
+//! let mut buffer = pool.take();
+//! buffer.extend([1, 2, 3]);
+//! pool.give(buffer);
+//!
+//! //The next buffer is empty but reuses the allocation
+//! assert!(pool.take().capacity() >= 3);
+//! ```
+
+/// Buffers kept for reuse, more buffers that are given back are dropped
+const MAX_BUFFERS: usize = 8;
+
+/// Empty vecs that keep their capacity between uses
+#[derive(Debug)]
+pub struct BufferPool<T> {
+    buffers: Vec<Vec<T>>,
+}
+
+impl<T> Default for BufferPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> BufferPool<T> {
+    pub fn new() -> Self {
+        Self { buffers: vec![] }
+    }
+
+    /// An empty buffer, the largest one in the pool
+    pub fn take(&mut self) -> Vec<T> {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Gives the buffer back to the pool, its items are dropped
+    pub fn give(&mut self, mut buffer: Vec<T>) {
+        if buffer.capacity() == 0 || self.buffers.len() >= MAX_BUFFERS {
+            return;
+        }
+
+        buffer.clear();
+
+        //Keep the largest buffer last so it is taken first
+        let position = self
+            .buffers
+            .partition_point(|b| b.capacity() <= buffer.capacity());
+        self.buffers.insert(position, buffer);
+    }
+
+    /// Frees all of the buffers at once
+    pub fn clear(&mut self) {
+        self.buffers = vec![];
+    }
+
+    /// Bytes held by the pool
+    pub fn retained_bytes(&self) -> usize {
+        self.buffers
+            .iter()
+            .map(|b| b.capacity() * size_of::<T>())
+            .sum()
+    }
+}
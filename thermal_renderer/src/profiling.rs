@@ -0,0 +1,106 @@
+//! Profiling
+//!
+//! With profiling on, the renderer measures how long each phase of a
+//! render takes and returns the times in `RenderOutput::timings`.
+//! Jobs that got slow show which phase it was without running them
+//! under a profiler, i.e. a logo that takes long to decode or text
+//! that takes long to lay out.
+//!
+//! ```
+//! use thermal_parser::thermal_file::parse_str;
+//! use thermal_renderer::json_renderer::JsonRenderer;
+//! use thermal_renderer::renderer::{DebugProfile, OutputRenderer, Renderer};
+//!
+//! let bytes = parse_str(r#""TOTAL 4.00" LF"#);
+//!
+//! let mut json_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+//! let mut renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+//! renderer.set_profiling(true);
+//!
+//! let timings = renderer.render(&bytes).timings.unwrap();
+//! assert!(timings.layout <= timings.total);
+//! ```
+//!
+//! The phases don't add up to the total, the rest is the time the
+//! renderer itself spends on the commands.
+
+use std::time::{Duration, Instant};
+
+/// A phase of a render
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderPhase {
+    /// Bytes to commands
+    Parse,
+    /// Text spans to positioned lines
+    Layout,
+    /// Barcode and 2d code data to bars and modules
+    BarcodeEncode,
+    /// Image data of graphics commands to pixels
+    ImageDecode,
+    /// Everything the output renderer does with the positioned elements
+    Rasterize,
+}
+
+/// Time spent in each phase of a render
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderTimings {
+    pub parse: Duration,
+    pub layout: Duration,
+    pub barcode_encode: Duration,
+    pub image_decode: Duration,
+    pub rasterize: Duration,
+    /// The whole render, parsing included
+    pub total: Duration,
+}
+
+impl RenderTimings {
+    pub fn add(&mut self, phase: RenderPhase, duration: Duration) {
+        let time = match phase {
+            RenderPhase::Parse => &mut self.parse,
+            RenderPhase::Layout => &mut self.layout,
+            RenderPhase::BarcodeEncode => &mut self.barcode_encode,
+            RenderPhase::ImageDecode => &mut self.image_decode,
+            RenderPhase::Rasterize => &mut self.rasterize,
+        };
+        *time += duration;
+    }
+}
+
+//Measures nothing unless it is enabled, so renders without
+//profiling don't read the clock
+#[derive(Default)]
+pub(crate) struct Profiler {
+    enabled: bool,
+    timings: RenderTimings,
+    started: Option<Instant>,
+}
+
+impl Profiler {
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    pub(crate) fn stop(&mut self, start: Option<Instant>, phase: RenderPhase) {
+        if let Some(start) = start {
+            self.timings.add(phase, start.elapsed());
+        }
+    }
+
+    //The total starts with the first phase of a render
+    pub(crate) fn begin(&mut self) {
+        if self.started.is_none() {
+            self.started = self.start();
+        }
+    }
+
+    pub(crate) fn finish(&mut self) -> Option<RenderTimings> {
+        let started = self.started.take()?;
+        let mut timings = std::mem::take(&mut self.timings);
+        timings.total = started.elapsed();
+        Some(timings)
+    }
+}
@@ -0,0 +1,105 @@
+//! Redaction
+//!
+//! A redactor masks text that matches configurable patterns
+//! before it is laid out, so personal information like card
+//! numbers never reaches the rendered output.
+//!
+//! Patterns are matched against all text collected for a layout
+//! pass, so a match can cross styled spans. Letters and digits in
+//! a match are replaced by the mask character, everything else is
+//! kept so that the layout does not change.
+
+use regex::Regex;
+use thermal_parser::text::TextSpan;
+
+pub struct Redactor {
+    pub patterns: Vec<(String, Regex)>,
+    pub mask: char,
+}
+
+/// A single redacted match, the original text is not kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redaction {
+    /// Name of the pattern that matched
+    pub pattern: String,
+    /// The match with all letters and digits masked
+    pub masked: String,
+    /// Index of the output (job) that contained the match
+    pub output: usize,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redactor {
+    /// A redactor without any patterns
+    pub fn new() -> Self {
+        Self {
+            patterns: vec![],
+            mask: '*',
+        }
+    }
+
+    /// A redactor with patterns for card numbers (PANs) and phone numbers
+    pub fn with_common_patterns() -> Self {
+        let mut redactor = Self::new();
+        redactor
+            .add_pattern("card number", r"\b(?:\d[ -]?){12,18}\d\b")
+            .unwrap();
+        redactor
+            .add_pattern(
+                "phone number",
+                r"(?:\+\d{1,3}[ .-]?)?\(?\b\d{3}\)?[ .-]?\d{3}[ .-]\d{4}\b",
+            )
+            .unwrap();
+        redactor
+    }
+
+    pub fn add_pattern(&mut self, name: &str, pattern: &str) -> Result<(), regex::Error> {
+        self.patterns.push((name.to_string(), Regex::new(pattern)?));
+        Ok(())
+    }
+
+    /// Masks matches in the spans and returns what was masked
+    pub fn redact_spans(&self, spans: &mut [TextSpan], output: usize) -> Vec<Redaction> {
+        let joined: String = spans.iter().map(|span| span.text.as_str()).collect();
+        let mut chars: Vec<char> = joined.chars().collect();
+        let mut redactions = vec![];
+
+        for (name, regex) in &self.patterns {
+            for found in regex.find_iter(&joined) {
+                let start = joined[..found.start()].chars().count();
+                let end = start + found.as_str().chars().count();
+
+                for c in &mut chars[start..end] {
+                    if c.is_alphanumeric() {
+                        *c = self.mask;
+                    }
+                }
+
+                redactions.push(Redaction {
+                    pattern: name.clone(),
+                    masked: chars[start..end].iter().collect(),
+                    output,
+                });
+            }
+        }
+
+        if redactions.is_empty() {
+            return redactions;
+        }
+
+        //Spans keep their character count, so the masked text can be split back up
+        let mut offset = 0;
+        for span in spans.iter_mut() {
+            let len = span.text.chars().count();
+            span.text = chars[offset..offset + len].iter().collect();
+            offset += len;
+        }
+
+        redactions
+    }
+}
@@ -14,21 +14,39 @@
 //! of how to implement an OutputRenderer.
 //!
 
+use crate::barcode_renderer::BarcodeRenderer;
+use crate::image_extractor::ImageOrigin;
+use crate::layout::layout_text;
+use crate::pool::BufferPool;
+use crate::profiling::{Profiler, RenderPhase, RenderTimings};
+use crate::redaction::{Redaction, Redactor};
 use crate::renderer::RenderErrorKind::ChildRenderError;
+use crate::signature::{looks_like_signature, near_end, Signature};
+use crate::strings::{self, Strings};
+use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::{fmt, mem};
 use thermal_parser::command::{Command, CommandType, DeviceCommand};
+use thermal_parser::constants::ESC;
 use thermal_parser::context::{Context, HumanReadableInterface, Rotation, TextJustify};
 use thermal_parser::graphics::{
-    Barcode, Code2D, GraphicsCommand, Image, ImageFlow, Rectangle, VectorGraphic,
+    Barcode, Code2D, GraphicsCommand, Image, ImageFlow, ImageRef, VectorGraphic,
 };
+use thermal_parser::profile::PrinterProfile;
 use thermal_parser::text::TextSpan;
 
-#[derive(Debug, Clone, Copy)]
+//Dots between the bars of a barcode and its hri text
+const HRI_SPACING: u32 = 8;
+
+#[derive(Debug, Clone)]
 pub struct DebugProfile {
     pub text: bool,
     pub image: bool,
     pub page: bool,
     pub info: bool,
+    /// With image debugging on, the image renderer writes the images
+    /// it receives and the page mode canvases to this directory
+    pub image_dir: Option<PathBuf>,
 }
 
 impl Default for DebugProfile {
@@ -38,6 +56,7 @@ impl Default for DebugProfile {
             image: false,
             page: false,
             info: false,
+            image_dir: None,
         }
     }
 }
@@ -45,13 +64,112 @@ impl Default for DebugProfile {
 pub struct RenderOutput<Output> {
     pub output: Vec<Output>,
     pub errors: Vec<RenderError>,
+    /// Metrics for each job, in the same order as output
+    pub metrics: Vec<JobMetrics>,
+    /// Text that was masked by the redactor
+    pub redactions: Vec<Redaction>,
+    /// Hardware changes the jobs made to the printer
+    pub events: Vec<DeviceEvent>,
+    /// Images that look like captured signatures, see the signature module
+    pub signatures: Vec<Signature>,
+    /// What was sent to the customer display, only with
+    /// `RenderConfig::route_peripherals`
+    pub display: DisplayOutput,
+    /// Time spent in each phase, only with `Renderer::set_profiling`
+    pub timings: Option<RenderTimings>,
+}
+
+/// Commands that were sent to the customer display with ESC =
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayOutput {
+    /// The bytes of the commands in the order they were sent
+    pub bytes: Vec<u8>,
+    /// The text of the text commands, line feeds included
+    pub text: String,
+}
+
+/// Paper usage of a single print job
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobMetrics {
+    pub paper_length_mm: f32,
+    pub cuts: u32,
+    pub lines: u32,
+    pub graphics: u32,
+    /// Estimated from the paper length and the profile feed speed
+    pub print_time_secs: f32,
+}
+
+/// Something a job did to the printer itself rather than the paper
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceEventKind {
+    PanelButtons {
+        enabled: bool,
+    },
+    PeripheralDevice {
+        printer_enabled: bool,
+        display_enabled: bool,
+    },
+    /// Bit flags of the sensors that signal a paper end
+    PaperEndSensors {
+        sensors: u8,
+    },
+    /// Bit flags of the sensors that stop printing
+    PrintStopSensors {
+        sensors: u8,
+    },
+    DrawerPulse,
+    /// GS I, the response is what the profile printer answers,
+    /// none for id types it does not know
+    PrinterIdRequest {
+        id_type: u8,
+        response: Option<Vec<u8>>,
+    },
+    /// DLE EOT, the response is the status of a ready printer, or of
+    /// a printer without paper after a paper out, none for status
+    /// types it does not know
+    StatusRequest {
+        status_type: u8,
+        response: Option<u8>,
+    },
+    /// DLE DC4 2, the response is the power-off notice. The job
+    /// ends and nothing after it is rendered
+    PowerOff {
+        response: Vec<u8>,
+    },
+    /// DLE DC4 8, what was not printed is discarded, the response
+    /// is the clear response
+    BufferClear {
+        response: Vec<u8>,
+    },
+    /// The paper near end of the render config was reached
+    PaperNearEnd,
+    /// The paper limit of the render config was reached
+    PaperOut,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceEvent {
+    pub kind: DeviceEventKind,
+    /// Index of the output (job) that sent the event
+    pub output: usize,
 }
 
 #[derive(Debug)]
 pub enum RenderErrorKind {
     ChildRenderError,
     GraphicsError,
+    /// Image data did not match the image size, the complete
+    /// part of the image is still rendered
+    ImageDataError,
     UnknownCommand,
+    /// The command is not available on the printer of the profile,
+    /// it is not rendered
+    UnsupportedCommand,
+    /// Reading a stream of bytes failed, the stream ended
+    ReadError,
+    /// The paper limit of the render config was reached, nothing
+    /// after it is rendered
+    PaperOut,
 }
 
 pub struct RenderError {
@@ -59,17 +177,175 @@ pub struct RenderError {
     description: String,
 }
 
+impl RenderError {
+    pub(crate) fn new(kind: RenderErrorKind, description: String) -> Self {
+        Self { kind, description }
+    }
+}
+
 impl fmt::Debug for RenderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "❌ [{:?}] {}", self.kind, self.description)
     }
 }
 
+/// Bytes shown before an unknown command and of the unknown
+/// command itself in its error
+const UNKNOWN_DUMP_LEN: usize = 16;
+
+/// Sensor bits of ESC c 3 and ESC c 4, bits 0 and 1 are the roll
+/// paper near end sensor and bits 2 and 3 the roll paper end sensor
+const NEAR_END_SENSORS: u8 = 0x03;
+const ROLL_END_SENSORS: u8 = 0x0C;
+
+/// What the printer answers to DLE DC4 2 and DLE DC4 8
+const POWER_OFF_NOTICE: [u8; 3] = [0x3B, 0x30, 0x00];
+const CLEAR_RESPONSE: [u8; 3] = [0x37, 0x25, 0x00];
+
+/// All sensors report a paper end, only the roll end stops printing
+const DEFAULT_PAPER_END_SENSORS: u8 = NEAR_END_SENSORS | ROLL_END_SENSORS;
+const DEFAULT_PRINT_STOP_SENSORS: u8 = 0;
+
+/// What the renderer does with commands it doesn't know
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum UnknownCommands {
+    /// Ignore them
+    Skip,
+    /// Report an `UnknownCommand` error
+    #[default]
+    Report,
+    /// Report the error and print a marker with the command bytes
+    /// where the command was, i.e. `[?1B 7E 01]`
+    Placeholder,
+    /// Report the error, end the job and render nothing after it
+    Abort,
+}
+
+/// Paper the renderer feeds around what a receipt prints, the
+/// defaults look like a receipt that was torn off the printer.
+///
+/// With a paper limit the renderer simulates a roll that runs out.
+/// The job ends where the paper ran out and a `PaperOut` error and
+/// event are reported. After that only real-time commands are
+/// processed and status requests answer with a paper end.
+///
+/// With a paper near end as well, the near end sensor detects the
+/// end of the roll that much paper before it runs out and a
+/// `PaperNearEnd` event is reported. Printing only stops there when
+/// ESC c 4 selected the near end sensor to stop printing. The roll
+/// paper status (DLE EOT 4) only reports the sensors that ESC c 3
+/// selected, by default all of them.
+///
+/// With peripheral routing the devices selected with ESC = get the
+/// commands. Commands for the customer display are collected in
+/// `RenderOutput::display` and commands sent while the printer is
+/// not selected are not rendered. Without it everything is printed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderConfig {
+    /// Dots of paper above the leading lines, `None` keeps the top
+    /// margin of the profile (three times the side margin)
+    pub top_margin: Option<u32>,
+    /// Lines fed at the start of a receipt
+    pub leading_lines: u32,
+    /// Lines fed before a cut, the distance from the head to the cutter
+    pub cut_lines: u32,
+    /// Dots fed at the end of a receipt
+    pub bottom_margin: u32,
+    /// Millimeters of paper on the roll, for all jobs of the renderer
+    pub paper_limit_mm: Option<f32>,
+    /// Millimeters of paper left on the roll when the near end
+    /// sensor detects it, only with a paper limit
+    pub paper_near_end_mm: Option<f32>,
+    /// Send commands only to the devices selected with ESC =
+    pub route_peripherals: bool,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            top_margin: None,
+            leading_lines: 2,
+            cut_lines: 2,
+            bottom_margin: 0,
+            paper_limit_mm: None,
+            paper_near_end_mm: None,
+            route_peripherals: false,
+        }
+    }
+}
+
+impl RenderConfig {
+    /// No paper around the printed content, for tight crops
+    pub fn tight() -> Self {
+        Self {
+            top_margin: Some(0),
+            leading_lines: 0,
+            cut_lines: 0,
+            bottom_margin: 0,
+            paper_limit_mm: None,
+            paper_near_end_mm: None,
+            route_peripherals: false,
+        }
+    }
+}
+
+/// Middleware sees every command before the renderer processes it.
+///
+/// Return the command to keep it, a modified or different command
+/// to replace it, multiple commands to insert more or an empty vec
+/// to drop it. Closures with the same signature are middleware too.
+pub trait CommandMiddleware {
+    fn process(&mut self, command: Command, context: &Context) -> Vec<Command>;
+}
+
+impl<F> CommandMiddleware for F
+where
+    F: FnMut(Command, &Context) -> Vec<Command>,
+{
+    fn process(&mut self, command: Command, context: &Context) -> Vec<Command> {
+        self(command, context)
+    }
+}
+
 pub struct Renderer<'a, Output> {
     renderer: &'a mut Box<dyn OutputRenderer<Output>>,
+    middleware: Vec<Box<dyn CommandMiddleware + 'a>>,
+    redactor: Option<Redactor>,
+    redactions: Vec<Redaction>,
+    strings: Strings,
     output_buffer: Vec<Output>,
     error_buffer: Vec<RenderError>,
     span_buffer: Vec<TextSpan>,
+    //Rectangles of barcodes and 2d codes, cleared at the end of a job
+    graphics_pool: BufferPool<VectorGraphic>,
+    metrics: JobMetrics,
+    metrics_buffer: Vec<JobMetrics>,
+    events: Vec<DeviceEvent>,
+    //Signature images of the current job, kept when the job ends
+    //if they are near its end
+    signature_candidates: Vec<Signature>,
+    signatures: Vec<Signature>,
+    label_fed: bool,
+    config: RenderConfig,
+    unknown_commands: UnknownCommands,
+    aborted: bool,
+    paper_out: bool,
+    //The sensors detected the near end and the end of the roll
+    near_end: bool,
+    roll_end: bool,
+    //Sensors selected with ESC c 3 and ESC c 4, see the sensor bits
+    paper_end_sensors: u8,
+    print_stop_sensors: u8,
+    //The job still has to end after the paper ran out
+    paper_end_pending: bool,
+    //Byte offset of the command that is processed
+    command_offset: usize,
+    //Devices selected with ESC =, bit 0 is the printer and bit 1
+    //the customer display
+    peripherals: u8,
+    display: DisplayOutput,
+    recent_bytes: VecDeque<u8>,
+    profiler: Profiler,
     context: Context,
     debug_profile: DebugProfile,
 }
@@ -78,13 +354,48 @@ impl<'a, Output> Renderer<'a, Output> {
     pub fn new(
         renderer: &'a mut Box<(dyn OutputRenderer<Output> + 'static)>,
         debug_profile: DebugProfile,
+    ) -> Self {
+        Self::new_with_profile(renderer, debug_profile, &PrinterProfile::default())
+    }
+
+    /// Render for a specific printer, the context defaults
+    /// and metrics are based on the profile
+    pub fn new_with_profile(
+        renderer: &'a mut Box<dyn OutputRenderer<Output> + 'static>,
+        debug_profile: DebugProfile,
+        profile: &PrinterProfile,
     ) -> Self {
         Renderer {
             renderer,
-            context: Context::new(),
+            middleware: vec![],
+            redactor: None,
+            redactions: vec![],
+            strings: Strings::new(),
+            context: Context::new_with_profile(profile),
             span_buffer: vec![],
+            graphics_pool: BufferPool::new(),
             error_buffer: vec![],
             output_buffer: vec![],
+            metrics: JobMetrics::default(),
+            metrics_buffer: vec![],
+            events: vec![],
+            signature_candidates: vec![],
+            signatures: vec![],
+            label_fed: false,
+            config: RenderConfig::default(),
+            unknown_commands: UnknownCommands::default(),
+            aborted: false,
+            paper_out: false,
+            near_end: false,
+            roll_end: false,
+            paper_end_sensors: DEFAULT_PAPER_END_SENSORS,
+            print_stop_sensors: DEFAULT_PRINT_STOP_SENSORS,
+            paper_end_pending: false,
+            command_offset: 0,
+            peripherals: 1,
+            display: DisplayOutput::default(),
+            recent_bytes: VecDeque::with_capacity(UNKNOWN_DUMP_LEN),
+            profiler: Profiler::default(),
             debug_profile,
         }
     }
@@ -113,46 +424,226 @@ impl<'a, Output> Renderer<'a, Output> {
         }
     }
 
+    //Debug line of a command with the command name localized
+    fn localized_debug(&self, command: &Command) -> String {
+        let debug = command.handler.debug(command, &self.context);
+        match debug.strip_prefix(command.name.as_str()) {
+            Some(rest) => format!("{}{}", self.strings.get(&command.name), rest),
+            None => debug,
+        }
+    }
+
     fn log_error(&mut self, kind: RenderErrorKind, description: String) {
         self.error_buffer.push(RenderError { kind, description });
     }
 
-    pub fn render(&mut self, bytes: &Vec<u8>) -> RenderOutput<Output> {
-        self.renderer.set_debug_profile(self.debug_profile);
-        self.log_debug_start("Begin Render");
+    fn log_event(&mut self, kind: DeviceEventKind) {
+        self.events.push(DeviceEvent {
+            kind,
+            output: self.output_buffer.len(),
+        });
+    }
+
+    pub fn render(&mut self, bytes: &[u8]) -> RenderOutput<Output> {
+        self.aborted = false;
+        let commands = self.profiled(RenderPhase::Parse, || thermal_parser::parse_esc_pos(bytes));
+        self.render_commands(commands)
+    }
 
-        let commands = thermal_parser::parse_esc_pos(bytes);
+    /// Render commands that were already parsed. This allows
+    /// for rendering command sets other than ESC/POS, for example
+    /// the commands returned by `thermal_parser::parse_star`
+    pub fn render_commands(&mut self, commands: Vec<Command>) -> RenderOutput<Output> {
+        self.renderer.set_debug_profile(self.debug_profile.clone());
+        self.profiler.begin();
+        self.log_debug_start(self.strings.get(strings::BEGIN_RENDER));
+        let mut index = 0;
 
         for command in commands {
-            self.log_debug(&format!(
-                "{}",
-                command.handler.debug(&command, &self.context)
-            ));
-            self.process_command(&command);
+            if self.aborted {
+                break;
+            }
+            for command in self.apply_middleware(command) {
+                //A printer without paper still answers real-time commands
+                if self.paper_out && !command.handler.is_realtime() {
+                    continue;
+                }
+                if self.config.route_peripherals && !self.route(&command) {
+                    continue;
+                }
+                let debug = self.localized_debug(&command);
+                let debug = match command.info() {
+                    Some(info) => format!("{} [{}]", debug, info.reference),
+                    None => debug,
+                };
+                self.log_debug(&debug);
+                self.renderer.command_begin(index);
+                self.renderer.command_debug(&command, &debug);
+                index += 1;
+                self.command_offset = command.offset;
+                self.process_command(&command);
+                self.remember_bytes(&command);
+                self.check_paper_end();
+                if self.aborted {
+                    break;
+                }
+            }
         }
 
         let mut output = vec![];
         let mut errors = vec![];
+        let mut metrics = vec![];
+        let mut redactions = vec![];
+        let mut events = vec![];
+        let mut signatures = vec![];
 
         mem::swap(&mut output, &mut self.output_buffer);
         mem::swap(&mut errors, &mut self.error_buffer);
+        mem::swap(&mut metrics, &mut self.metrics_buffer);
+        mem::swap(&mut redactions, &mut self.redactions);
+        mem::swap(&mut events, &mut self.events);
+        mem::swap(&mut signatures, &mut self.signatures);
+        let display = mem::take(&mut self.display);
+        let timings = self.profiler.finish();
+
+        self.log_debug_end(self.strings.get(strings::END_RENDER));
+
+        RenderOutput {
+            output,
+            errors,
+            metrics,
+            redactions,
+            events,
+            signatures,
+            display,
+            timings,
+        }
+    }
+
+    //Sends the command to the selected devices, true if the printer
+    //processes it. ESC = itself, real-time commands and the begin
+    //and end of parsing (they have no bytes) always reach the printer
+    fn route(&mut self, command: &Command) -> bool {
+        if command.commands[..] == [ESC, b'='] || command.handler.is_realtime() {
+            return true;
+        }
+
+        let bytes = command.to_bytes();
+        if bytes.is_empty() {
+            return true;
+        }
+
+        if self.peripherals & 2 == 2 {
+            self.display.bytes.extend(bytes);
+            if command.kind == CommandType::Text {
+                if let Some(span) = command.handler.get_text(command, &self.context) {
+                    self.display.text.push_str(&span.text);
+                }
+            }
+        }
+
+        self.peripherals & 1 == 1
+    }
+
+    /// Add a middleware, middleware runs in the order it was added
+    pub fn add_middleware(&mut self, middleware: Box<dyn CommandMiddleware + 'a>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Choose what happens with unknown commands, they are reported by default
+    pub fn set_unknown_commands(&mut self, unknown_commands: UnknownCommands) {
+        self.unknown_commands = unknown_commands;
+    }
+
+    /// Measure how long the phases of a render take, see the profiling module
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiler.set_enabled(enabled);
+    }
+
+    //Runs a phase that happens outside of the renderer, i.e. parsing
+    pub(crate) fn profiled<T>(&mut self, phase: RenderPhase, run: impl FnOnce() -> T) -> T {
+        self.profiler.begin();
+        let start = self.profiler.start();
+        let result = run();
+        self.profiler.stop(start, phase);
+        result
+    }
+
+    /// Choose how much paper is fed around the printed content
+    pub fn set_config(&mut self, config: RenderConfig) {
+        self.config = config;
+    }
+
+    /// Mask text matching the redactor patterns before it is rendered
+    pub fn set_redactor(&mut self, redactor: Redactor) {
+        self.redactor = Some(redactor);
+    }
 
-        self.log_debug_end("End Render");
+    /// Localize the text the renderer adds to the output and the debug output
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// Replace a stored graphic (i.e. a logo) with another image whenever
+    /// its key codes are printed. The graphic does not need to be defined
+    /// in the job, which covers logos that were stored on the printer
+    pub fn substitute_graphic(&mut self, image_ref: ImageRef, mut image: Image) {
+        image.flow = ImageFlow::Block;
+        let graphic = GraphicsCommand::Image(image);
+
+        //The default context is used when the printer is initialized
+        if let Some(default) = &mut self.context.default {
+            default
+                .graphics
+                .substitute_graphics
+                .insert(image_ref.clone(), graphic.clone());
+        }
+
+        self.context
+            .graphics
+            .substitute_graphics
+            .insert(image_ref, graphic);
+    }
 
-        RenderOutput { output, errors }
+    fn apply_middleware(&mut self, command: Command) -> Vec<Command> {
+        let mut commands = vec![command];
+
+        for middleware in self.middleware.iter_mut() {
+            commands = commands
+                .into_iter()
+                .flat_map(|command| middleware.process(command, &self.context))
+                .collect();
+        }
+
+        commands
     }
 
     //default implementation
     fn process_command(&mut self, command: &Command) {
-        match command.kind {
-            CommandType::Unknown => {
-                self.process_text();
-                self.log_error(
-                    RenderErrorKind::UnknownCommand,
+        if !self.context.profile.supports_command(command) {
+            self.log_error(
+                RenderErrorKind::UnsupportedCommand,
+                format!(
+                    "{} is not supported by {}",
                     command.handler.debug(command, &self.context),
-                );
-            }
+                    self.context.profile.name
+                ),
+            );
+            return;
+        }
+
+        match command.kind {
+            CommandType::Unknown => self.process_unknown(command),
             CommandType::Text => {
+                //Text can be positioned by device commands (i.e. label command sets),
+                //any text collected before it needs to be laid out first
+                let device_commands = &command.handler.get_device_command(command, &self.context);
+
+                if device_commands.is_some() {
+                    self.process_text();
+                    self.process_device_commands(device_commands);
+                }
+
                 let maybe_text = command.handler.get_text(command, &self.context);
                 if let Some(text) = maybe_text {
                     self.collect_text(text);
@@ -161,7 +652,11 @@ impl<'a, Output> Renderer<'a, Output> {
             CommandType::Graphics => {
                 self.process_text();
 
+                let start = self.profiler.start();
                 let maybe_gfx = command.handler.get_graphics(command, &mut self.context);
+                if let Some(phase) = maybe_gfx.as_ref().and_then(graphics_phase) {
+                    self.profiler.stop(start, phase);
+                }
 
                 if let Some(gfx) = maybe_gfx {
                     match gfx {
@@ -176,6 +671,12 @@ impl<'a, Output> Renderer<'a, Output> {
                         }
                         GraphicsCommand::Image(mut image) => {
                             self.process_image(&mut image);
+                            self.collect_signature(command, image);
+                        }
+                        GraphicsCommand::PartialImage(mut image, error) => {
+                            self.log_error(RenderErrorKind::ImageDataError, error.to_string());
+                            self.process_image(&mut image);
+                            self.collect_signature(command, image);
                         }
                         GraphicsCommand::Rectangle(_) => {}
                         GraphicsCommand::Line(_) => {}
@@ -194,18 +695,40 @@ impl<'a, Output> Renderer<'a, Output> {
             }
 
             CommandType::ContextControl => {
-                self.process_text();
-                command.handler.apply_context(command, &mut self.context);
-
                 let device_commands = &command
                     .handler
                     .get_device_command(command, &mut self.context);
+
+                //Initializing discards what was not printed yet
+                if let Some(commands) = device_commands {
+                    if commands.contains(&DeviceCommand::Initialize) {
+                        self.discard_unprinted();
+                        self.paper_end_sensors = DEFAULT_PAPER_END_SENSORS;
+                        self.print_stop_sensors = DEFAULT_PRINT_STOP_SENSORS;
+                    }
+                }
+
+                self.process_text();
+                command.handler.apply_context(command, &mut self.context);
                 self.process_device_commands(device_commands);
             }
             CommandType::Control => {
                 let device_commands = &command
                     .handler
                     .get_device_command(command, &mut self.context);
+
+                //Clearing the buffers and turning off discard what was not printed yet
+                if let Some(commands) = device_commands {
+                    if commands.iter().any(|command| {
+                        matches!(
+                            command,
+                            DeviceCommand::ClearBuffers | DeviceCommand::PowerOff
+                        )
+                    }) {
+                        self.discard_unprinted();
+                    }
+                }
+
                 self.process_text();
                 self.process_device_commands(device_commands);
             }
@@ -223,11 +746,164 @@ impl<'a, Output> Renderer<'a, Output> {
         }
     }
 
+    fn process_unknown(&mut self, command: &Command) {
+        if self.unknown_commands == UnknownCommands::Skip {
+            return;
+        }
+
+        //The bytes before the command help to find where the
+        //unknown data started, i.e. a wrong length in the command before
+        let before: Vec<u8> = self.recent_bytes.iter().copied().collect();
+        self.log_error(
+            RenderErrorKind::UnknownCommand,
+            format!(
+                "{} at byte {} (0x{:X}): {} [{}]",
+                command.name,
+                command.offset,
+                command.offset,
+                hex_dump(&before, before.len()),
+                hex_dump(&command.data, UNKNOWN_DUMP_LEN)
+            ),
+        );
+
+        match self.unknown_commands {
+            UnknownCommands::Placeholder => {
+                //The marker is inline with the text around it
+                let bytes = hex_dump(&command.data, 8);
+                let marker_text = self.strings.format(strings::UNKNOWN_COMMAND, &[&bytes]);
+                let mut marker = TextSpan::new(marker_text, &self.context);
+                marker.inverted = true;
+                self.collect_text(marker);
+            }
+            UnknownCommands::Abort => {
+                //End the job so what was rendered before is still output
+                self.process_text();
+                self.process_device_commands(&Some(vec![DeviceCommand::EndPrint]));
+                self.aborted = true;
+            }
+            _ => self.process_text(),
+        }
+    }
+
+    //The paper limit in dots, page mode is checked when the page is printed
+    fn paper_limit(&self) -> Option<u32> {
+        let limit_mm = self.config.paper_limit_mm?;
+        match self.context.page_mode.enabled {
+            true => None,
+            false => Some(self.context.profile.mm_to_dots(limit_mm)),
+        }
+    }
+
+    //Where the near end sensor detects the end of the roll
+    fn near_end_limit(&self) -> Option<u32> {
+        let limit = self.paper_limit()?;
+        let near_end_mm = self.config.paper_near_end_mm?;
+        let near_end = self.context.profile.mm_to_dots(near_end_mm);
+        Some(limit.saturating_sub(near_end))
+    }
+
+    //Where printing stops, the near end when ESC c 4 selected its sensor
+    fn print_stop_limit(&self) -> Option<u32> {
+        match self.print_stop_sensors & NEAR_END_SENSORS {
+            0 => self.paper_limit(),
+            _ => self.near_end_limit().or_else(|| self.paper_limit()),
+        }
+    }
+
+    //Ends the job when the paper of the paper limit was fed
+    fn check_paper_end(&mut self) {
+        let Some(limit) = self.print_stop_limit() else {
+            return;
+        };
+        let y = self.context.graphics.render_area.y;
+
+        if self.near_end_limit().is_some_and(|near_end| y >= near_end) {
+            self.paper_near_end();
+        }
+
+        if !self.paper_out && y >= limit {
+            self.paper_end();
+        }
+
+        if self.paper_end_pending {
+            self.paper_end_pending = false;
+            self.process_text();
+            self.process_device_commands(&Some(vec![DeviceCommand::EndPrint]));
+        }
+    }
+
+    fn paper_near_end(&mut self) {
+        if !self.near_end {
+            self.near_end = true;
+            self.log_event(DeviceEventKind::PaperNearEnd);
+        }
+    }
+
+    //The paper ran out or the near end stopped printing, status requests
+    //of the same command already report it and the job ends once the
+    //command is done
+    fn paper_end(&mut self) {
+        if self.paper_out {
+            return;
+        }
+
+        let limit_mm = self.config.paper_limit_mm.unwrap_or_default();
+        if self.print_stop_limit() == self.paper_limit() {
+            self.log_error(
+                RenderErrorKind::PaperOut,
+                format!(
+                    "Paper ran out after {}mm (byte {})",
+                    limit_mm, self.command_offset
+                ),
+            );
+            self.log_event(DeviceEventKind::PaperOut);
+            self.near_end = true;
+            self.roll_end = true;
+        } else {
+            self.paper_near_end();
+            self.log_error(
+                RenderErrorKind::PaperOut,
+                format!(
+                    "Printing stopped at the paper near end after {}mm (byte {})",
+                    limit_mm - self.config.paper_near_end_mm.unwrap_or_default(),
+                    self.command_offset
+                ),
+            );
+        }
+        self.paper_out = true;
+        self.paper_end_pending = true;
+    }
+
+    /// The paper limit of the render config was reached
+    pub fn is_paper_out(&self) -> bool {
+        self.paper_out
+    }
+
+    //Keeps the last bytes that were rendered for unknown command errors,
+    //the bytes are rebuilt from the command and its data
+    fn remember_bytes(&mut self, command: &Command) {
+        let len = command.commands.len() + command.data.len();
+        let bytes = command.commands.iter().chain(command.data.iter());
+
+        for byte in bytes.skip(len.saturating_sub(UNKNOWN_DUMP_LEN)) {
+            if self.recent_bytes.len() == UNKNOWN_DUMP_LEN {
+                self.recent_bytes.pop_front();
+            }
+            self.recent_bytes.push_back(*byte);
+        }
+    }
+
     fn process_device_commands(&mut self, device_commands: &Option<Vec<DeviceCommand>>) {
         if let Some(device_commands) = device_commands {
             for device_command in device_commands {
-                self.renderer
-                    .device_command(&mut self.context, device_command);
+                //Cuts are sent once the paper is at the cutter
+                if !matches!(
+                    device_command,
+                    DeviceCommand::FullCut | DeviceCommand::PartialCut
+                ) {
+                    self.renderer
+                        .device_command(&mut self.context, device_command);
+                }
 
                 match device_command {
                     DeviceCommand::SetTextWidth(w) => {
@@ -240,28 +916,123 @@ impl<'a, Output> Renderer<'a, Output> {
                         self.context.text.justify = j.clone();
                     }
                     DeviceCommand::BeginPrint => {
-                        //Start the render at two newlines worth of height
-                        self.context.newline(2);
+                        self.metrics = JobMetrics::default();
+                        self.signature_candidates.clear();
+
+                        if self.context.label.length.is_some() {
+                            //Labels start at the top of the label
+                            self.context.graphics.render_area.x = 0;
+                            self.context.graphics.render_area.y =
+                                self.context.label.start_adjustment.max(0) as u32;
+                        } else {
+                            if let Some(top_margin) = self.config.top_margin {
+                                self.context.graphics.render_area.y = top_margin;
+                            }
+                            self.context.newline(self.config.leading_lines);
+                        }
                         self.renderer.begin_render(&mut self.context)
                     }
                     DeviceCommand::EndPrint => {
+                        //Labels always use up whole labels of paper
+                        match self.context.label.length {
+                            Some(length) if length > 0 => {
+                                let y = self.context.graphics.render_area.y.max(1);
+                                self.context.graphics.render_area.y = y.div_ceil(length) * length;
+                            }
+                            Some(_) => {}
+                            None => {
+                                self.context.graphics.render_area.y += self.config.bottom_margin
+                            }
+                        }
+
                         let errors = self.renderer.get_render_errors();
 
                         for error in errors {
                             self.log_error(ChildRenderError, error);
                         }
 
+                        let start = self.profiler.start();
                         let output = self.renderer.end_render(&mut self.context);
+                        self.profiler.stop(start, RenderPhase::Rasterize);
+                        self.graphics_pool.clear();
+
+                        //Nothing was printed on the label after the last label feed
+                        let empty_label =
+                            self.label_fed && self.metrics.lines == 0 && self.metrics.graphics == 0;
+                        self.label_fed = false;
+
+                        if empty_label {
+                            continue;
+                        }
+
                         self.output_buffer.push(output);
+
+                        let height = self.context.graphics.render_area.y;
+                        for mut signature in mem::take(&mut self.signature_candidates) {
+                            let bottom = signature.image.y + signature.image.h;
+                            if near_end(bottom, height) {
+                                signature.output = self.output_buffer.len() - 1;
+                                self.signatures.push(signature);
+                            }
+                        }
+
+                        let profile = &self.context.profile;
+                        let mut metrics = mem::take(&mut self.metrics);
+                        metrics.paper_length_mm =
+                            profile.dots_to_mm(self.context.graphics.render_area.y);
+                        if profile.feed_speed > 0.0 {
+                            metrics.print_time_secs = metrics.paper_length_mm / profile.feed_speed;
+                        }
+                        self.metrics_buffer.push(metrics);
+                    }
+                    DeviceCommand::FeedLine(num_lines) if *num_lines < 0 => {
+                        self.context
+                            .reverse_newline(num_lines.unsigned_abs() as u32);
                     }
                     DeviceCommand::FeedLine(num_lines) => {
                         self.context.newline(*num_lines as u32);
                     }
+                    //The part of a dot that a feed can't move carries over
+                    //to the next feed, like the steps of the paper motor
                     DeviceCommand::Feed(num) => {
-                        self.context.feed(*num as u32);
+                        self.context.offset_y_relative(*num);
+                        self.context.reset_x();
                     }
                     DeviceCommand::FullCut | DeviceCommand::PartialCut => {
-                        self.context.newline(2);
+                        self.metrics.cuts += 1;
+
+                        //Labels are cut at the label gap, there is no feed
+                        if self.context.label.length.is_none() {
+                            self.context.newline(self.config.cut_lines);
+                        }
+                        self.renderer
+                            .device_command(&mut self.context, device_command);
+                    }
+                    DeviceCommand::Cancel if self.context.page_mode.enabled => {
+                        self.renderer.page_clear(&mut self.context);
+                    }
+                    DeviceCommand::FeedToNextLabel => {
+                        self.process_device_commands(&Some(vec![
+                            DeviceCommand::EndPrint,
+                            DeviceCommand::BeginPrint,
+                        ]));
+                        self.label_fed = true;
+                    }
+                    DeviceCommand::ChangePaperLayout => {
+                        if self.metrics.lines > 0 || self.metrics.graphics > 0 {
+                            //What was printed stays on the old paper
+                            self.process_device_commands(&Some(vec![
+                                DeviceCommand::EndPrint,
+                                DeviceCommand::BeginPrint,
+                            ]));
+                        } else {
+                            //Nothing was printed yet, start over on the new paper
+                            if let Some(default) = &self.context.default {
+                                self.context.graphics.render_area.y =
+                                    default.graphics.render_area.y;
+                            }
+                            self.process_device_commands(&Some(vec![DeviceCommand::BeginPrint]));
+                        }
                     }
                     DeviceCommand::BeginPageMode => {
                         self.context.page_mode.enabled = true;
@@ -272,7 +1043,9 @@ impl<'a, Output> Renderer<'a, Output> {
                         self.context.page_mode.enabled = false
                     }
                     DeviceCommand::PrintPageMode => {
+                        let start = self.profiler.start();
                         self.renderer.render_page(&mut self.context);
+                        self.profiler.stop(start, RenderPhase::Rasterize);
 
                         //Advance the y since a page is being rendered
                         self.context.graphics.render_area.y += self.context.page_mode.page_area.h;
@@ -294,9 +1067,82 @@ impl<'a, Output> Renderer<'a, Output> {
                     DeviceCommand::ChangeTabs(count, at) => {
                         self.context.set_tab_len(*count, *at);
                     }
+                    DeviceCommand::MoveX(x) => {
+                        self.context.set_x(*x as u32);
+                    }
+                    DeviceCommand::MoveY(y) => {
+                        self.context.set_y(*y as u32);
+                    }
                     DeviceCommand::ClearBufferGraphics => {
                         self.context.graphics.buffer_graphics.clear();
                     }
+                    DeviceCommand::EnablePanelButtons(enabled) => {
+                        self.log_event(DeviceEventKind::PanelButtons { enabled: *enabled });
+                    }
+                    DeviceCommand::SelectPeripheral(peripherals) => {
+                        self.peripherals = *peripherals;
+                        self.log_event(DeviceEventKind::PeripheralDevice {
+                            printer_enabled: peripherals & 1 == 1,
+                            display_enabled: peripherals & 2 == 2,
+                        });
+                    }
+                    DeviceCommand::SelectPaperEndSensors(sensors) => {
+                        self.paper_end_sensors = *sensors;
+                        self.log_event(DeviceEventKind::PaperEndSensors { sensors: *sensors });
+                    }
+                    DeviceCommand::SelectPrintStopSensors(sensors) => {
+                        self.print_stop_sensors = *sensors;
+                        self.log_event(DeviceEventKind::PrintStopSensors { sensors: *sensors });
+                    }
+                    DeviceCommand::Pulse => {
+                        self.log_event(DeviceEventKind::DrawerPulse);
+                    }
+                    DeviceCommand::ClearBuffers => {
+                        self.log_event(DeviceEventKind::BufferClear {
+                            response: CLEAR_RESPONSE.to_vec(),
+                        });
+                    }
+                    //The printer takes no more data until it is turned off
+                    DeviceCommand::PowerOff => {
+                        self.log_event(DeviceEventKind::PowerOff {
+                            response: POWER_OFF_NOTICE.to_vec(),
+                        });
+                        self.process_device_commands(&Some(vec![DeviceCommand::EndPrint]));
+                        self.aborted = true;
+                    }
+                    DeviceCommand::TransmitPrinterId(id_type) => {
+                        let response = self.context.profile.printer_id.response(*id_type);
+                        self.log_event(DeviceEventKind::PrinterIdRequest {
+                            id_type: *id_type,
+                            response,
+                        });
+                    }
+                    DeviceCommand::TransmitStatus(status_type) => {
+                        //Only the fixed bits are set, the printer is online
+                        //with paper and without errors. When printing stopped
+                        //the printer is offline because of the paper end and
+                        //the roll sensor reports what the sensors selected
+                        //with ESC c 3 detected
+                        let mut roll_paper = 0x12;
+                        if self.near_end && self.paper_end_sensors & NEAR_END_SENSORS != 0 {
+                            roll_paper |= 0x0C;
+                        }
+                        if self.roll_end && self.paper_end_sensors & ROLL_END_SENSORS != 0 {
+                            roll_paper |= 0x60;
+                        }
+                        let response = match (status_type, self.paper_out) {
+                            (1..=3, false) => Some(0x12),
+                            (1, true) => Some(0x1A),
+                            (2, true) => Some(0x32),
+                            (3, true) => Some(0x12),
+                            (4, _) => Some(roll_paper),
+                            _ => None,
+                        };
+                        self.log_event(DeviceEventKind::StatusRequest {
+                            status_type: *status_type,
+                            response,
+                        });
+                    }
                     _ => {}
                 }
             }
@@ -305,89 +1151,96 @@ impl<'a, Output> Renderer<'a, Output> {
 
     fn process_code_2d(&mut self, code_2d: &Code2D) {
         let context = &mut self.context;
-        let mut graphics = vec![];
-
-        let mut i = 1;
-        let origin_x = context.calculate_justification(code_2d.width as u32 * code_2d.point_width);
-        context.set_x(origin_x);
-
-        for p in &code_2d.points {
-            if i != 1 && i % code_2d.width == 1 {
-                context.set_x(origin_x);
-                context.offset_y(code_2d.point_height as u32);
-            }
-
-            if *p > 0 {
-                //Prevent rendering outside of print area
-                if context.get_available_width() < code_2d.point_width as u32 {
-                    continue;
-                }
-
-                graphics.push(VectorGraphic::Rectangle(Rectangle {
-                    x: context.get_x(),
-                    y: context.get_y(),
-                    w: code_2d.point_width as u32,
-                    h: code_2d.point_height as u32,
-                }));
-            }
-            context.offset_x(code_2d.point_width as u32);
-            i += 1;
-        }
+        let (width, height) = code_2d.size();
+
+        context.set_x(context.calculate_justification(width));
+        let mut graphics = self.graphics_pool.take();
+        let start = self.profiler.start();
+        code_2d.runs_into(
+            context.get_x(),
+            context.get_y(),
+            context.get_available_width(),
+            &mut graphics,
+        );
+        self.profiler.stop(start, RenderPhase::BarcodeEncode);
 
+        //The position is left on the last row of modules
+        context.offset_y(height.saturating_sub(code_2d.point_height));
         context.reset_x();
 
-        self.renderer.render_graphics(context, &graphics);
+        let start = self.profiler.start();
+        self.renderer.render_code(
+            context,
+            &graphics,
+            &code_2d.symbology,
+            self.strings.get(&code_2d.symbology),
+            &code_2d.payload,
+            None,
+        );
+        self.profiler.stop(start, RenderPhase::Rasterize);
+        self.graphics_pool.give(graphics);
+        self.metrics.graphics += 1;
     }
 
     fn process_barcode(&mut self, barcode: &Barcode) {
-        let mut graphics = vec![];
+        let above = matches!(
+            barcode.hri,
+            HumanReadableInterface::Above | HumanReadableInterface::Both
+        );
+        let below = matches!(
+            barcode.hri,
+            HumanReadableInterface::Below | HumanReadableInterface::Both
+        );
 
-        match self.context.barcode.human_readable {
-            HumanReadableInterface::Above | HumanReadableInterface::Both => {
-                self.collect_text(barcode.text.clone());
-                self.process_text();
-                self.context.newline(1);
-            }
-            _ => {}
+        if above {
+            self.process_hri(barcode);
+            self.context.offset_y(HRI_SPACING);
         }
 
-        self.context.set_x(
-            self.context
-                .calculate_justification(barcode.points.len() as u32 * barcode.point_width as u32),
+        let (width, height) = barcode.size();
+        self.context
+            .set_x(self.context.calculate_justification(width));
+        let mut graphics = self.graphics_pool.take();
+        let start = self.profiler.start();
+        barcode.runs_into(
+            self.context.get_x(),
+            self.context.get_y(),
+            self.context.get_available_width(),
+            &mut graphics,
+        );
+        self.profiler.stop(start, RenderPhase::BarcodeEncode);
+
+        let hri = (above || below).then_some(barcode.text.text.as_str());
+
+        self.log_debug_icon("║║", self.strings.get(strings::RENDER_BARCODE));
+        let start = self.profiler.start();
+        self.renderer.render_code(
+            &mut self.context,
+            &graphics,
+            barcode.kind.name(),
+            self.strings.get(barcode.kind.name()),
+            &barcode.text.text,
+            hri,
         );
+        self.profiler.stop(start, RenderPhase::Rasterize);
+        self.graphics_pool.give(graphics);
+        self.metrics.graphics += 1;
 
-        for bp in &barcode.points {
-            if *bp > 0 {
-                //Prevent rendering when beyond page bounds
-                if self.context.get_available_width() < barcode.point_width as u32 {
-                    continue;
-                }
+        self.context.reset_x();
+        self.context.offset_y(height);
 
-                graphics.push(VectorGraphic::Rectangle(Rectangle {
-                    x: self.context.get_x(),
-                    y: self.context.get_y(),
-                    w: barcode.point_width as u32,
-                    h: barcode.point_height as u32,
-                }));
-            }
-            self.context.offset_x(barcode.point_width as u32);
+        if below {
+            self.context.offset_y(HRI_SPACING);
+            self.process_hri(barcode);
         }
+    }
 
-        self.log_debug_icon("║║", "Render Barcode");
-        self.renderer.render_graphics(&mut self.context, &graphics);
-
+    //Prints one line of hri text, the next line starts right under it
+    fn process_hri(&mut self, barcode: &Barcode) {
+        self.collect_text(barcode.text.clone());
+        self.process_text();
         self.context.reset_x();
-        self.context.offset_y(barcode.point_height as u32);
-
-        match self.context.barcode.human_readable {
-            HumanReadableInterface::Below | HumanReadableInterface::Both => {
-                self.context.offset_y(8);
-                self.collect_text(barcode.text.clone());
-                self.process_text();
-                self.context.newline(1);
-            }
-            _ => {}
-        }
+        self.context.offset_y(barcode.text.character_height);
     }
 
     fn process_image(&mut self, image: &mut Image) {
@@ -400,22 +1253,42 @@ impl<'a, Output> Renderer<'a, Output> {
                 }
             }
             ImageFlow::Block => {
-                if !self.context.page_mode.enabled {
+                //Page mode justifies within the print area, left keeps the set position
+                if !self.context.page_mode.enabled || self.context.text.justify != TextJustify::Left
+                {
                     self.context
                         .set_x(self.context.calculate_justification(image.w));
                 }
             }
-            ImageFlow::None => {}
+            ImageFlow::None | ImageFlow::Absolute => {}
         }
 
-        image.x = self.context.get_x();
-        image.y = self.context.get_y();
-        self.log_debug_icon("[§]", "Render Image");
+        match image.flow {
+            ImageFlow::Absolute => {
+                image.x += self.context.get_base_x();
+                image.y += self.context.get_base_y();
+            }
+            //Inline images sit on the baseline of the line they are on
+            ImageFlow::Inline => {
+                let line_height = self.context.text.current_line_height;
+                image.x = self.context.get_x();
+                image.y = self.context.get_y() + line_height.saturating_sub(image.h);
+            }
+            _ => {
+                image.x = self.context.get_x();
+                image.y = self.context.get_y();
+            }
+        }
+        self.log_debug_icon("[§]", self.strings.get(strings::RENDER_IMAGE));
+        let start = self.profiler.start();
         self.renderer.render_image(&mut self.context, image);
+        self.profiler.stop(start, RenderPhase::Rasterize);
+        self.metrics.graphics += 1;
 
         match image.flow {
             ImageFlow::Inline => {
                 self.context.offset_x(image.w);
+                self.context.extend_line_height(image.h);
             }
             ImageFlow::Block => {
                 if !self.context.page_mode.enabled {
@@ -427,210 +1300,247 @@ impl<'a, Output> Renderer<'a, Output> {
         }
     }
 
-    fn collect_text(&mut self, text: TextSpan) {
-        self.span_buffer.push(text);
-    }
-
-    fn process_text(&mut self) {
-        if self.span_buffer.is_empty() {
+    //Signatures are printed from the data of the command, outside of page mode
+    fn collect_signature(&mut self, command: &Command, image: Image) {
+        if self.context.page_mode.enabled
+            || ImageOrigin::of(command) != ImageOrigin::Inline
+            || !looks_like_signature(&image)
+        {
             return;
         }
 
-        let mut words: Vec<TextSpan> = vec![];
-
-        for span in &self.span_buffer {
-            let mut spans: Vec<TextSpan> = span.break_into_words();
-            words.append(&mut spans);
-        }
-
-        self.span_buffer.clear();
+        self.signature_candidates.push(Signature {
+            image,
+            offset: command.offset,
+            output: 0,
+        });
+    }
 
-        let mut lines: Vec<Vec<TextSpan>> = vec![];
-        let mut current_line: Vec<TextSpan> = vec![];
-        let max_width = self.context.get_width();
-        words.reverse();
-
-        while let Some(mut word) = words.pop() {
-            //Calculate available width every loop
-            let avail_width = self.context.get_available_width();
-            let word_width = word.get_width();
-
-            //Newlines advance y and reset x
-            if word.text.eq("\n") {
-                //Advance line height
-                self.context.newline_for_spans(&current_line);
-
-                //Swap current line
-                let mut finished_line = vec![];
-                mem::swap(&mut current_line, &mut finished_line);
-                lines.push(finished_line);
-
-                //Start a new line
-                lines.push(vec![]); //Newline
-                continue;
-            }
+    fn collect_text(&mut self, text: TextSpan) {
+        self.span_buffer.push(text);
+    }
 
-            //Tabs have a special behavior
-            if word.text.eq("\t") {
-                let current_x = self.context.get_x();
-                let mut current_tab_pos = 0;
-                for tab_len in &self.context.text.tabs {
-                    if current_tab_pos >= current_x {
-                        self.context.set_x(current_tab_pos);
-                        break;
-                    }
-                    current_tab_pos += *tab_len as u32 * word.character_width;
-                }
-                continue;
+    //Drops the text of the line that was not ended and the page mode
+    //data that was not printed, the whole page is cleared and page mode ends
+    fn discard_unprinted(&mut self) {
+        if self.context.page_mode.enabled {
+            self.span_buffer.clear();
+            if let Some(default) = &self.context.default {
+                self.context.page_mode = default.page_mode.clone();
             }
+            self.context.page_mode.enabled = true;
+            self.renderer.page_clear(&mut self.context);
+            self.renderer.page_end(&mut self.context);
+            self.context.page_mode.enabled = false;
+            return;
+        }
 
-            if word_width <= avail_width {
-                //Word fits into the line, add it
-                word.get_dimensions(&self.context);
-                self.context.offset_x(word.get_width());
-                current_line.push(word);
-                continue;
-            } else if word_width > max_width {
-                //Break the word into parts for super long words
-                let mut broken = word.break_apart(
-                    (avail_width / word.character_width) as usize,
-                    (max_width / word.character_width).max(word.character_width) as usize,
-                );
-
-                let broken_len = broken.len() - 1;
-                for (i, broke) in broken.iter_mut().enumerate() {
-                    let last = broken_len == i;
-                    broke.get_dimensions(&self.context);
-                    current_line.push(broke.clone()); //ugg
-
-                    if last {
-                        //Last word doesn't geta a forced newline
-                        self.context.offset_x(broke.get_width());
-                    } else {
-                        //Every other line we assume will fit into a line
-
-                        //Advance line
-                        self.context.newline_for_spans(&current_line);
-
-                        //Swap line
-                        let mut finished_line = vec![];
-                        mem::swap(&mut current_line, &mut finished_line);
-                        lines.push(finished_line);
-                    }
+        match self.span_buffer.iter().rposition(|s| s.text.contains('\n')) {
+            Some(last) => {
+                self.span_buffer.truncate(last + 1);
+                let span = &mut self.span_buffer[last];
+                if let Some(end) = span.text.rfind('\n') {
+                    span.text.truncate(end + 1);
                 }
-            } else {
-                //Close out previous line
-                let mut finished_line = vec![];
-                self.context.newline_for_spans(&current_line);
-                mem::swap(&mut current_line, &mut finished_line);
-                lines.push(finished_line);
-
-                //Add text to newline at 0 x
-                let word_width = word.get_width();
-                word.get_dimensions(&self.context);
-                current_line.push(word);
-
-                //Advance the x
-                self.context.offset_x(word_width);
             }
+            None => self.span_buffer.clear(),
         }
+    }
 
-        if !current_line.is_empty() {
-            lines.push(current_line);
+    fn process_text(&mut self) {
+        if self.span_buffer.is_empty() {
+            return;
         }
 
-        //Adjust lines for justification
-        for line in &lines {
-            if line.is_empty() {
-                continue;
-            }
-            let justification = line.first().unwrap().justify.clone();
-
-            let max_width = self.context.get_width();
-            let mut max_height = 0;
-            let mut line_width = 0;
-            let mut line_offset = 0;
-
-            for span in line {
-                line_width += span.get_width();
-                max_height = max_height.max(span.character_height);
-            }
+        if let Some(redactor) = &self.redactor {
+            let mut redactions =
+                redactor.redact_spans(&mut self.span_buffer, self.output_buffer.len());
+            self.redactions.append(&mut redactions);
+        }
 
-            match justification {
-                TextJustify::Right => {
-                    line_offset = max_width - line_width;
-                }
-                TextJustify::Center => {
-                    if line_width < max_width {
-                        line_offset = (max_width - line_width) / 2;
-                    }
+        let start = self.profiler.start();
+        let lines = layout_text(&self.span_buffer, &mut self.context);
+        self.profiler.stop(start, RenderPhase::Layout);
+        self.span_buffer.clear();
+        let paper_limit = self.print_stop_limit();
+
+        for line in lines {
+            //Lines past the end of the paper are not printed
+            let top = line.spans.first().and_then(|span| span.dimensions.as_ref());
+            if let (Some(limit), Some(top)) = (paper_limit, top) {
+                if top.y + line.height > limit {
+                    self.context.graphics.render_area.y = limit;
+                    self.paper_end();
+                    break;
                 }
-                _ => {}
             }
 
-            self.log_debug_icon(
-                "🗚",
-                &format!("Render Text {:?} at x offset = {}", line, line_offset),
-            );
+            //The spans are only formatted when they are logged
+            if self.debug_profile.info {
+                self.log_debug_icon(
+                    "🗚",
+                    &self.strings.format(
+                        strings::RENDER_TEXT,
+                        &[&format!("{:?}", line.spans), &line.x_offset],
+                    ),
+                );
+            }
 
+            let start = self.profiler.start();
             self.renderer.render_text(
                 &mut self.context,
-                line,
-                line_offset,
-                max_height,
-                justification,
+                &line.spans,
+                line.x_offset,
+                line.height,
+                line.justify,
             );
+            self.profiler.stop(start, RenderPhase::Rasterize);
+            self.metrics.lines += 1;
         }
     }
 }
 
+/// Everything the Renderer sends to an OutputRenderer, without the
+/// context. Elements are already positioned, a renderer that only
+/// implements `render_event` and `end_render` gets all of them here.
+///
+/// Barcodes and 2d codes are sent as `Graphics` unless the renderer
+/// implements `render_code`.
+pub enum RenderEvent<'a> {
+    Begin,
+    PageBegin,
+    PageAreaChanged {
+        rotation: Rotation,
+        width: u32,
+        height: u32,
+    },
+    PageEnd,
+    PageClear,
+    RenderPage,
+    Graphics(&'a Vec<VectorGraphic>),
+    Image(&'a Image),
+    Text {
+        spans: &'a Vec<TextSpan>,
+        x_offset: u32,
+        max_height: u32,
+        justify: TextJustify,
+    },
+    Device(&'a DeviceCommand),
+}
+
 /// Implement the  Output Renderer in order to render to your own format.
 ///
 /// The main Renderer takes care of all positioning of the xy coordinates.
 ///
 /// You just need to render the elements at the provided xy and width height.
+///
+/// Only `end_render` is required. Every other method sends a
+/// `RenderEvent` to `render_event` unless it is implemented, so
+/// a renderer can handle just the events it cares about, or
+/// implement the methods that need the context.
 pub trait OutputRenderer<Output> {
+    /// Receives every element that the methods below don't handle
+    fn render_event(&mut self, _event: RenderEvent) {}
+
     /// Possibly use the debug profile
-    fn set_debug_profile(&mut self, profile: DebugProfile);
+    fn set_debug_profile(&mut self, _profile: DebugProfile) {}
+
+    /// A command is about to be processed, index counts the
+    /// commands of the render from 0
+    fn command_begin(&mut self, _index: usize) {}
+
+    /// The command that is about to be processed with its debug
+    /// line, the line the debug output shows for it
+    fn command_debug(&mut self, _command: &Command, _debug: &str) {}
 
     /// Do setup steps here for each page output
     /// This can get called multiple times
-    fn begin_render(&mut self, context: &mut Context);
+    fn begin_render(&mut self, _context: &mut Context) {
+        self.render_event(RenderEvent::Begin);
+    }
 
     /// Page mode has started
-    fn page_begin(&mut self, _context: &mut Context);
+    fn page_begin(&mut self, _context: &mut Context) {
+        self.render_event(RenderEvent::PageBegin);
+    }
+
     fn page_area_changed(
         &mut self,
         _context: &mut Context,
-        _rotation: Rotation,
-        _width: u32,
-        _height: u32,
-    );
+        rotation: Rotation,
+        width: u32,
+        height: u32,
+    ) {
+        self.render_event(RenderEvent::PageAreaChanged {
+            rotation,
+            width,
+            height,
+        });
+    }
 
     /// Page mode has ended
-    fn page_end(&mut self, _context: &mut Context) {}
+    fn page_end(&mut self, _context: &mut Context) {
+        self.render_event(RenderEvent::PageEnd);
+    }
+
+    /// Delete everything drawn in the current page mode print area
+    fn page_clear(&mut self, _context: &mut Context) {
+        self.render_event(RenderEvent::PageClear);
+    }
 
     /// Render the page mode area to the main paper
-    fn render_page(&mut self, _context: &mut Context);
+    fn render_page(&mut self, _context: &mut Context) {
+        self.render_event(RenderEvent::RenderPage);
+    }
 
     /// Render vector graphics
-    fn render_graphics(&mut self, context: &mut Context, graphics: &Vec<VectorGraphic>);
+    fn render_graphics(&mut self, _context: &mut Context, graphics: &Vec<VectorGraphic>) {
+        self.render_event(RenderEvent::Graphics(graphics));
+    }
+
+    /// Render the bars or modules of a barcode or 2d code, symbology is
+    /// the name of the code (i.e. CODE128) and label the same name in
+    /// the language of the strings, payload is the encoded data and hri
+    /// the human readable text printed with it. By default this renders
+    /// the vector graphics
+    fn render_code(
+        &mut self,
+        context: &mut Context,
+        graphics: &Vec<VectorGraphic>,
+        _symbology: &str,
+        _label: &str,
+        _payload: &str,
+        _hri: Option<&str>,
+    ) {
+        self.render_graphics(context, graphics);
+    }
 
     /// Render images
-    fn render_image(&mut self, context: &mut Context, image: &Image);
+    fn render_image(&mut self, _context: &mut Context, image: &Image) {
+        self.render_event(RenderEvent::Image(image));
+    }
 
     /// Render text
     fn render_text(
         &mut self,
-        context: &mut Context,
+        _context: &mut Context,
         spans: &Vec<TextSpan>,
         x_offset: u32,
         max_height: u32,
         text_justify: TextJustify,
-    );
+    ) {
+        self.render_event(RenderEvent::Text {
+            spans,
+            x_offset,
+            max_height,
+            justify: text_justify,
+        });
+    }
 
     /// Possibly render or do something with a device command
-    fn device_command(&mut self, _context: &mut Context, _command: &DeviceCommand) {}
+    fn device_command(&mut self, _context: &mut Context, command: &DeviceCommand) {
+        self.render_event(RenderEvent::Device(command));
+    }
 
     /// During rendering, if there are any errors that
     /// would fail a test, return them in this call
@@ -642,3 +1552,29 @@ pub trait OutputRenderer<Output> {
     /// End the render and return the output
     fn end_render(&mut self, context: &mut Context) -> Output;
 }
+
+//The phase that getting the graphics of a command belongs to
+fn graphics_phase(graphics: &GraphicsCommand) -> Option<RenderPhase> {
+    match graphics {
+        GraphicsCommand::Image(_) | GraphicsCommand::PartialImage(..) => {
+            Some(RenderPhase::ImageDecode)
+        }
+        GraphicsCommand::Barcode(_) | GraphicsCommand::Code2D(_) => {
+            Some(RenderPhase::BarcodeEncode)
+        }
+        _ => None,
+    }
+}
+
+/// Hex bytes separated by spaces, at most `max` bytes followed by `..`
+fn hex_dump(bytes: &[u8], max: usize) -> String {
+    let mut hex: Vec<String> = bytes
+        .iter()
+        .take(max)
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+    if bytes.len() > max {
+        hex.push("..".to_string());
+    }
+    hex.join(" ")
+}
@@ -0,0 +1,337 @@
+//! Semantic Diff
+//!
+//! Compares what two jobs print instead of their pixels. Both jobs
+//! are rendered to layouts (see the json renderer) and the text runs
+//! with their style, barcodes and 2d codes, images and cuts are
+//! compared in reading order.
+//!
+//! Bytes that are sent in a different order but print the same, like
+//! styles that are set in another order or page mode areas that are
+//! drawn one after the other, don't show up in the diff. Positions
+//! are not compared either, so a changed line spacing doesn't turn
+//! every line after it into a change.
+//!
+//! ```
+//! use thermal_parser::thermal_file::parse_str;
+//! use thermal_renderer::semantic_diff::diff_jobs;
+//!
+//! let a = parse_str(r#""TOTAL 4.00" LF"#);
+//! let b = parse_str(r#"ESC "E" 1 "TOTAL 4.00" LF"#);
+//!
+//! let diff = diff_jobs(&a, &b);
+//! assert_eq!(diff.changes[0].to_string(), r#"~ text "TOTAL 4.00": bold"#);
+//! ```
+
+use crate::json_renderer::{JsonRenderer, LayoutElement, ReceiptLayout, TextStyle};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// An element of a layout without its position
+#[derive(Clone, Debug, PartialEq)]
+pub enum Element {
+    Text {
+        text: String,
+        style: TextStyle,
+    },
+    Code {
+        symbology: String,
+        payload: String,
+        hri: Option<String>,
+    },
+    Image {
+        w: u32,
+        h: u32,
+    },
+    Graphics {
+        w: u32,
+        h: u32,
+    },
+    Page {
+        w: u32,
+        h: u32,
+    },
+    Cut {
+        partial: bool,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added(Element),
+    Removed(Element),
+    /// The same kind of element with the fields that differ
+    Changed {
+        before: Element,
+        after: Element,
+        fields: Vec<&'static str>,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SemanticDiff {
+    pub changes: Vec<Change>,
+}
+
+impl SemanticDiff {
+    pub fn is_same(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Element::Text { text, .. } => write!(f, "text {:?}", text),
+            Element::Code {
+                symbology, payload, ..
+            } => write!(f, "{} {:?}", symbology, payload),
+            Element::Image { w, h } => write!(f, "image {}x{}", w, h),
+            Element::Graphics { w, h } => write!(f, "graphics {}x{}", w, h),
+            Element::Page { w, h } => write!(f, "page {}x{}", w, h),
+            Element::Cut { partial: true } => write!(f, "partial cut"),
+            Element::Cut { partial: false } => write!(f, "cut"),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added(element) => write!(f, "+ {}", element),
+            Change::Removed(element) => write!(f, "- {}", element),
+            Change::Changed { before, fields, .. } => {
+                write!(f, "~ {}: {}", before, fields.join(", "))
+            }
+        }
+    }
+}
+
+/// Renders both jobs to layouts and compares all of their receipts
+pub fn diff_jobs(a: &[u8], b: &[u8]) -> SemanticDiff {
+    let a = JsonRenderer::render(a, None).output;
+    let b = JsonRenderer::render(b, None).output;
+    diff_layouts(&a, &b)
+}
+
+/// Compares the receipts of two jobs
+pub fn diff_layouts(a: &[ReceiptLayout], b: &[ReceiptLayout]) -> SemanticDiff {
+    let a = elements(a);
+    let b = elements(b);
+    let mut changes = vec![];
+    let mut removed = vec![];
+    let mut added = vec![];
+
+    for edit in edits(&a, &b) {
+        match edit {
+            Edit::Same => {
+                pair_changes(&mut removed, &mut added, &mut changes);
+            }
+            Edit::Removed(element) => removed.push(element.clone()),
+            Edit::Added(element) => added.push(element.clone()),
+        }
+    }
+    pair_changes(&mut removed, &mut added, &mut changes);
+
+    SemanticDiff { changes }
+}
+
+/// The elements of all receipts in reading order
+fn elements(layouts: &[ReceiptLayout]) -> Vec<Element> {
+    let mut elements = vec![];
+
+    for layout in layouts {
+        //Page mode elements are relative to their page, so only the
+        //elements between pages and cuts are sorted together
+        let mut section: Vec<&LayoutElement> = vec![];
+
+        for element in &layout.elements {
+            let ends_section = matches!(
+                element,
+                LayoutElement::Page { .. } | LayoutElement::Cut { .. }
+            );
+            section.push(element);
+
+            if ends_section {
+                sort_section(&mut section, &mut elements);
+            }
+        }
+        sort_section(&mut section, &mut elements);
+    }
+
+    elements
+}
+
+fn sort_section(section: &mut Vec<&LayoutElement>, elements: &mut Vec<Element>) {
+    let separator = match section.last() {
+        Some(LayoutElement::Page { .. } | LayoutElement::Cut { .. }) => section.pop(),
+        _ => None,
+    };
+
+    section.sort_by_key(|element| position(element));
+    elements.extend(section.drain(..).map(element));
+    elements.extend(separator.map(element));
+}
+
+fn position(element: &LayoutElement) -> (u32, u32) {
+    match element {
+        LayoutElement::Text { bounds, .. }
+        | LayoutElement::Image { bounds, .. }
+        | LayoutElement::Graphics { bounds, .. }
+        | LayoutElement::Code { bounds, .. }
+        | LayoutElement::Page { bounds } => (bounds.y, bounds.x),
+        LayoutElement::Cut { y, .. } => (*y, 0),
+    }
+}
+
+fn element(element: &LayoutElement) -> Element {
+    match element {
+        LayoutElement::Text { text, style, .. } => Element::Text {
+            text: text.clone(),
+            style: style.clone(),
+        },
+        LayoutElement::Code {
+            symbology,
+            payload,
+            hri,
+            ..
+        } => Element::Code {
+            symbology: symbology.clone(),
+            payload: payload.clone(),
+            hri: hri.clone(),
+        },
+        LayoutElement::Image { bounds, .. } => Element::Image {
+            w: bounds.w,
+            h: bounds.h,
+        },
+        LayoutElement::Graphics { bounds, .. } => Element::Graphics {
+            w: bounds.w,
+            h: bounds.h,
+        },
+        LayoutElement::Page { bounds } => Element::Page {
+            w: bounds.w,
+            h: bounds.h,
+        },
+        LayoutElement::Cut { partial, .. } => Element::Cut { partial: *partial },
+    }
+}
+
+enum Edit<'a> {
+    Same,
+    Removed(&'a Element),
+    Added(&'a Element),
+}
+
+/// The shortest edit from a to b by the longest common subsequence
+fn edits<'a>(a: &'a [Element], b: &'a [Element]) -> Vec<Edit<'a>> {
+    let mut lengths = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = match a[i] == b[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let mut edits = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            edits.push(Edit::Same);
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            edits.push(Edit::Removed(&a[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Added(&b[j]));
+            j += 1;
+        }
+    }
+
+    edits
+}
+
+/// Removed and added elements of the same kind at the same place
+/// are changes of one element
+fn pair_changes(removed: &mut Vec<Element>, added: &mut Vec<Element>, changes: &mut Vec<Change>) {
+    let mut added = added.drain(..).collect::<VecDeque<_>>();
+
+    for before in removed.drain(..) {
+        let paired = added
+            .iter()
+            .enumerate()
+            .find_map(|(index, after)| Some((index, changed_fields(&before, after)?)));
+
+        match paired {
+            Some((index, fields)) => {
+                //Added elements before the pair stay in order
+                changes.extend(added.drain(..index).map(Change::Added));
+                changes.push(Change::Changed {
+                    before,
+                    after: added.pop_front().unwrap(),
+                    fields,
+                });
+            }
+            None => changes.push(Change::Removed(before)),
+        }
+    }
+
+    changes.extend(added.drain(..).map(Change::Added));
+}
+
+/// The fields that differ, none for different kinds of elements
+fn changed_fields(before: &Element, after: &Element) -> Option<Vec<&'static str>> {
+    let mut fields = vec![];
+    let mut compare = |name, changed: bool| {
+        if changed {
+            fields.push(name);
+        }
+    };
+
+    match (before, after) {
+        (Element::Text { text, style }, Element::Text { text: t, style: s }) => {
+            compare("text", text != t);
+            compare("font", style.font != s.font);
+            compare("bold", style.bold != s.bold);
+            compare("italic", style.italic != s.italic);
+            compare("underline", style.underline != s.underline);
+            compare("strikethrough", style.strikethrough != s.strikethrough);
+            compare("inverted", style.inverted != s.inverted);
+            compare("upside down", style.upside_down != s.upside_down);
+            compare("width", style.width_mult != s.width_mult);
+            compare("height", style.height_mult != s.height_mult);
+            compare("justify", style.justify != s.justify);
+            compare("color", style.color != s.color);
+        }
+        (
+            Element::Code {
+                symbology,
+                payload,
+                hri,
+            },
+            Element::Code {
+                symbology: s,
+                payload: p,
+                hri: h,
+            },
+        ) => {
+            compare("symbology", symbology != s);
+            compare("payload", payload != p);
+            compare("hri", hri != h);
+        }
+        (Element::Image { w, h }, Element::Image { w: w2, h: h2 })
+        | (Element::Graphics { w, h }, Element::Graphics { w: w2, h: h2 })
+        | (Element::Page { w, h }, Element::Page { w: w2, h: h2 }) => {
+            compare("size", w != w2 || h != h2);
+        }
+        (Element::Cut { partial }, Element::Cut { partial: p }) => {
+            compare("partial", partial != p);
+        }
+        _ => return None,
+    }
+
+    Some(fields)
+}
@@ -0,0 +1,58 @@
+//! Signature Detection
+//!
+//! Payment terminals print the signature that was captured on
+//! their pad as a raster image at the end of the receipt. These
+//! images have a recognizable shape, wide and short with a thin
+//! line of ink, which sets them apart from logos and barcodes.
+//!
+//! The renderer reports inline images that look like signatures
+//! and end in the last part of their receipt in
+//! `RenderOutput::signatures`. This is a heuristic, a logo with
+//! the same proportions will be reported too.
+
+use thermal_parser::graphics::Image;
+
+//Width has to be at least this many times the height
+const MIN_ASPECT: u32 = 3;
+const MIN_WIDTH: u32 = 128;
+//Thinner images are rules and separators
+const MIN_HEIGHT: u32 = 16;
+//About 25mm at 203 dpi
+const MAX_HEIGHT: u32 = 200;
+//Parts of the pixels that have ink
+const MIN_INK: f32 = 0.005;
+const MAX_INK: f32 = 0.25;
+//Signatures end in this last part of the receipt
+const END_OF_RECEIPT: f32 = 1.0 / 3.0;
+
+/// An image that looks like a captured signature
+#[derive(Debug, Clone)]
+pub struct Signature {
+    /// The image with its position on the receipt
+    pub image: Image,
+    /// Byte offset of the command that printed the image
+    pub offset: usize,
+    /// Index of the output (job) that contained the signature
+    pub output: usize,
+}
+
+/// The image is wide and short with sparse ink
+pub fn looks_like_signature(image: &Image) -> bool {
+    if image.w < MIN_WIDTH
+        || image.h < MIN_HEIGHT
+        || image.h > MAX_HEIGHT
+        || image.w < image.h * MIN_ASPECT
+    {
+        return false;
+    }
+
+    let ink = image.pixels.iter().filter(|pixel| pixel.a > 0).count();
+    let ink = ink as f32 / image.pixels.len().max(1) as f32;
+
+    (MIN_INK..=MAX_INK).contains(&ink)
+}
+
+/// The bottom of an image is in the last part of a receipt of this height
+pub fn near_end(bottom: u32, receipt_height: u32) -> bool {
+    bottom as f32 >= receipt_height as f32 * (1.0 - END_OF_RECEIPT)
+}
@@ -0,0 +1,148 @@
+//! Snapshot Testing
+//!
+//! Renders a job to an image and compares it against a stored
+//! golden png. Pixels are compared by their brightness so that
+//! tiny color differences don't fail a test, and a test only
+//! fails when more than the allowed share of pixels differ.
+//!
+//! When a comparison fails a diff image is written next to the
+//! golden file (`name.diff.png`). Differing pixels are red, the
+//! rest of the receipt is drawn faded so the changes stand out.
+//!
+//! A missing golden file is never created silently, the render
+//! is written as `name.new.png` to be reviewed and renamed. Set
+//! `THERMAL_UPDATE_SNAPSHOTS=1` to write golden files instead.
+//!
+//! ```no_run
+//! use thermal_renderer::snapshot::assert_snapshot;
+//!
+//! let bytes = thermal_parser::thermal_file::parse_str(r#""Hello" LF"#);
+//! assert_snapshot(&bytes, "tests/golden/hello.png");
+//! ```
+
+use crate::image_diff::diff_images;
+use crate::image_renderer::{ImageRenderer, ReceiptImage};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub struct Snapshot {
+    /// Brightness difference (0 - 255) under which two pixels are the same
+    pub tolerance: u8,
+    /// Share of pixels (0.0 - 1.0) that may differ
+    pub threshold: f32,
+    /// Write the render as the golden file instead of comparing
+    pub update: bool,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// There is no golden file, the render was written to the path
+    Missing(PathBuf),
+    /// The render differs from the golden file, the diff image was
+    /// written to the path
+    Different {
+        ratio: f32,
+        expected: (u32, u32),
+        actual: (u32, u32),
+        diff: PathBuf,
+    },
+    /// Nothing was rendered
+    Empty,
+    Io(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Missing(path) => write!(
+                f,
+                "Golden image is missing, the render was written to {}",
+                path.display()
+            ),
+            SnapshotError::Different {
+                ratio,
+                expected,
+                actual,
+                diff,
+            } => write!(
+                f,
+                "{:.2}% of pixels differ (expected {}x{} got {}x{}), see {}",
+                ratio * 100.0,
+                expected.0,
+                expected.1,
+                actual.0,
+                actual.1,
+                diff.display()
+            ),
+            SnapshotError::Empty => write!(f, "Nothing was rendered"),
+            SnapshotError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            tolerance: 16,
+            threshold: 0.001,
+            update: std::env::var("THERMAL_UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1"),
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders the bytes and compares the first output to the golden png
+    pub fn check(&self, bytes: &[u8], golden: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let renders = ImageRenderer::render(bytes, None);
+        let image = renders.output.first().ok_or(SnapshotError::Empty)?;
+        self.compare(image, golden)
+    }
+
+    /// Compares a rendered image to the golden png
+    pub fn compare(
+        &self,
+        image: &ReceiptImage,
+        golden: impl AsRef<Path>,
+    ) -> Result<(), SnapshotError> {
+        let golden = golden.as_ref();
+
+        if self.update {
+            return image.save_png(golden).map_err(SnapshotError::Io);
+        }
+
+        if !golden.exists() {
+            let new_path = golden.with_extension("new.png");
+            image.save_png(&new_path).map_err(SnapshotError::Io)?;
+            return Err(SnapshotError::Missing(new_path));
+        }
+
+        let expected = ReceiptImage::load_png(golden).map_err(SnapshotError::Io)?;
+        let diff = diff_images(&expected, image, self.tolerance);
+        let ratio = diff.ratio();
+
+        if ratio <= self.threshold {
+            return Ok(());
+        }
+
+        let diff_path = golden.with_extension("diff.png");
+        diff.image.save_png(&diff_path).map_err(SnapshotError::Io)?;
+
+        Err(SnapshotError::Different {
+            ratio,
+            expected: (expected.width, expected.height),
+            actual: (image.width, image.height),
+            diff: diff_path,
+        })
+    }
+}
+
+/// Renders the bytes and panics when the render doesn't match the golden png
+pub fn assert_snapshot(bytes: &[u8], golden: impl AsRef<Path>) {
+    if let Err(error) = Snapshot::default().check(bytes, golden.as_ref()) {
+        panic!("Snapshot {} failed: {}", golden.as_ref().display(), error);
+    }
+}
@@ -0,0 +1,266 @@
+//! Stream Rendering
+//!
+//! Renders a job while its bytes are still arriving, for example
+//! from a network connection. `StreamRenderer` couples a parser
+//! with a renderer, every chunk of bytes returns the outputs
+//! (jobs), errors and events that were completed by it.
+//!
+//! `render_stream` drives a `StreamRenderer` from an `AsyncRead`
+//! and yields the results one at a time through `RenderStream`,
+//! so a connection doesn't need a thread of its own. There is no
+//! dependency on an async runtime, the traits mirror the shape of
+//! `futures::io::AsyncRead` and `futures::Stream`. With the
+//! `futures-io` feature every `futures::io::AsyncRead` is an
+//! `AsyncRead`, tokio readers get there with `tokio_util::compat`.
+//!
+//! Renderers share data with `Rc` so streams are not `Send`, run
+//! them on a local task set (i.e. `tokio::task::LocalSet`).
+//!
+//! ```no_run
+//! use thermal_renderer::image_renderer::{ImageRenderer, ReceiptImage};
+//! use thermal_renderer::renderer::{DebugProfile, OutputRenderer, Renderer};
+//! use thermal_renderer::stream::{render_stream, StreamEvent};
+//!
+//! async fn print(connection: &[u8]) {
+//!     let mut image_renderer: Box<dyn OutputRenderer<ReceiptImage>> =
+//!         Box::new(ImageRenderer::new());
+//!     let renderer = Renderer::new(&mut image_renderer, DebugProfile::default());
+//!     let mut stream = render_stream(connection, renderer);
+//!
+//!     while let Some(event) = stream.next().await {
+//!         if let StreamEvent::Output(image) = event {
+//!             println!("Printed {}x{}", image.width, image.height);
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::profiling::{RenderPhase, RenderTimings};
+use crate::redaction::Redaction;
+use crate::renderer::{
+    DeviceEvent, DisplayOutput, JobMetrics, RenderError, RenderErrorKind, RenderOutput, Renderer,
+};
+use crate::signature::Signature;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use thermal_parser::parser::Parser;
+
+const READ_SIZE: usize = 4096;
+
+/// Reads bytes without blocking, the same as `futures::io::AsyncRead`
+pub trait AsyncRead {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: futures_io::AsyncRead + ?Sized> AsyncRead for T {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        futures_io::AsyncRead::poll_read(self, cx, buf)
+    }
+}
+
+//futures-io has its own impl for byte slices
+#[cfg(not(feature = "futures-io"))]
+impl AsyncRead for &[u8] {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let len = buf.len().min(self.len());
+        let (read, rest) = self.split_at(len);
+        buf[..len].copy_from_slice(read);
+        *self = rest;
+        Poll::Ready(Ok(len))
+    }
+}
+
+/// A part of the render, each output is followed by its metrics
+#[derive(Debug)]
+pub enum StreamEvent<Output> {
+    /// A finished job
+    Output(Output),
+    Metrics(JobMetrics),
+    Error(RenderError),
+    Redaction(Redaction),
+    Event(DeviceEvent),
+    Signature(Signature),
+    /// What a batch of bytes sent to the customer display
+    Display(DisplayOutput),
+    /// Time spent on a batch of bytes, only with profiling
+    Timings(RenderTimings),
+}
+
+pub struct StreamRenderer<'a, Output> {
+    parser: Parser,
+    renderer: Renderer<'a, Output>,
+    outputs: usize,
+}
+
+impl<'a, Output> StreamRenderer<'a, Output> {
+    pub fn new(parser: Parser, renderer: Renderer<'a, Output>) -> Self {
+        Self {
+            parser,
+            renderer,
+            outputs: 0,
+        }
+    }
+
+    /// Renders the next part of the stream. Output indexes of events,
+    /// redactions and signatures count all outputs of the stream so far
+    pub fn push(&mut self, bytes: &[u8]) -> RenderOutput<Output> {
+        let parser = &mut self.parser;
+        let commands = self
+            .renderer
+            .profiled(RenderPhase::Parse, || parser.parse_chunk(bytes));
+        let output = self.renderer.render_commands(commands);
+        self.offset_outputs(output)
+    }
+
+    /// Ends the stream, returns the rest of the render
+    pub fn finish(&mut self) -> RenderOutput<Output> {
+        let parser = &mut self.parser;
+        let commands = self
+            .renderer
+            .profiled(RenderPhase::Parse, || parser.finish());
+        let output = self.renderer.render_commands(commands);
+        let output = self.offset_outputs(output);
+        self.outputs = 0;
+        output
+    }
+
+    fn offset_outputs(&mut self, mut output: RenderOutput<Output>) -> RenderOutput<Output> {
+        for event in &mut output.events {
+            event.output += self.outputs;
+        }
+        for redaction in &mut output.redactions {
+            redaction.output += self.outputs;
+        }
+        for signature in &mut output.signatures {
+            signature.output += self.outputs;
+        }
+        self.outputs += output.output.len();
+        output
+    }
+}
+
+/// Yields the render of an `AsyncRead` as it is read
+pub struct RenderStream<'a, R, Output> {
+    reader: R,
+    renderer: StreamRenderer<'a, Output>,
+    pending: VecDeque<StreamEvent<Output>>,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+/// Renders ESC/POS bytes from the reader
+pub fn render_stream<R: AsyncRead + Unpin, Output>(
+    reader: R,
+    renderer: Renderer<'_, Output>,
+) -> RenderStream<'_, R, Output> {
+    let parser = thermal_parser::new_esc_pos_parser();
+    RenderStream::new(reader, StreamRenderer::new(parser, renderer))
+}
+
+impl<'a, R: AsyncRead + Unpin, Output> RenderStream<'a, R, Output> {
+    pub fn new(reader: R, renderer: StreamRenderer<'a, Output>) -> Self {
+        Self {
+            reader,
+            renderer,
+            pending: VecDeque::new(),
+            buffer: vec![0; READ_SIZE],
+            done: false,
+        }
+    }
+
+    /// The next event, `None` once the reader ended and everything
+    /// was rendered. Read errors end the stream with an error event
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<StreamEvent<Output>>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            let read = Pin::new(&mut self.reader).poll_read(cx, &mut self.buffer);
+            let output = match read {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) => {
+                    self.done = true;
+                    self.renderer.finish()
+                }
+                Poll::Ready(Ok(len)) => self.renderer.push(&self.buffer[..len]),
+                Poll::Ready(Err(error)) => {
+                    self.done = true;
+                    let mut output = self.renderer.finish();
+                    output.errors.push(RenderError::new(
+                        RenderErrorKind::ReadError,
+                        error.to_string(),
+                    ));
+                    output
+                }
+            };
+
+            self.queue(output);
+        }
+    }
+
+    /// Waits for the next event
+    pub async fn next(&mut self) -> Option<StreamEvent<Output>> {
+        poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    fn queue(&mut self, output: RenderOutput<Output>) {
+        queue_events(output, &mut self.pending);
+    }
+}
+
+//Splits a render into the events of a stream
+pub(crate) fn queue_events<Output>(
+    output: RenderOutput<Output>,
+    pending: &mut VecDeque<StreamEvent<Output>>,
+) {
+    let RenderOutput {
+        output,
+        errors,
+        metrics,
+        redactions,
+        events,
+        signatures,
+        display,
+        timings,
+    } = output;
+
+    pending.extend(errors.into_iter().map(StreamEvent::Error));
+    pending.extend(redactions.into_iter().map(StreamEvent::Redaction));
+    pending.extend(events.into_iter().map(StreamEvent::Event));
+    pending.extend(signatures.into_iter().map(StreamEvent::Signature));
+    if !display.bytes.is_empty() {
+        pending.push_back(StreamEvent::Display(display));
+    }
+
+    //Every output is followed by its metrics
+    let mut metrics = metrics.into_iter();
+    for output in output {
+        pending.push_back(StreamEvent::Output(output));
+        if let Some(metrics) = metrics.next() {
+            pending.push_back(StreamEvent::Metrics(metrics));
+        }
+    }
+    if let Some(timings) = timings {
+        pending.push_back(StreamEvent::Timings(timings));
+    }
+}
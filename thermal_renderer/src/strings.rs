@@ -0,0 +1,94 @@
+//! Localized Strings
+//!
+//! Text that the renderer adds to the output itself, the labels of
+//! barcodes and 2d codes (the accessible labels of the html), the
+//! marker of unknown commands and the labels of the debug output,
+//! can be replaced with a string table.
+//!
+//! The keys are the english text, barcodes and 2d codes use the name
+//! of their symbology (`CODE39`, `QR Code`). `{}` in a key is replaced with a value,
+//! the translation places the values with `{}` in the same order.
+//! Text without a translation stays english.
+//!
+//! ```
+//! use thermal_renderer::strings::Strings;
+//!
+//! let strings = Strings::parse(
+//!     "# Spanish
+//!     CODE39 = Código 39
+//!     QR Code = Código QR",
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(strings.get("QR Code"), "Código QR");
+//! assert_eq!(strings.get("Aztec"), "Aztec");
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Inverted marker printed in place of an unknown command, with its bytes
+pub const UNKNOWN_COMMAND: &str = "[?{}]";
+pub const BEGIN_RENDER: &str = "Begin Render";
+pub const END_RENDER: &str = "End Render";
+pub const RENDER_BARCODE: &str = "Render Barcode";
+pub const RENDER_IMAGE: &str = "Render Image";
+/// Debug line of laid out text, with the spans and the x offset
+pub const RENDER_TEXT: &str = "Render Text {} at x offset {}";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Strings {
+    table: HashMap<String, String>,
+}
+
+impl Strings {
+    /// A table without translations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a table with one `key = translation` per line, empty
+    /// lines and lines starting with # are skipped
+    pub fn parse(table: &str) -> Result<Self, String> {
+        let mut strings = Self::new();
+
+        for (index, line) in table.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once(" = ") {
+                Some((key, text)) => strings.insert(key.trim(), text.trim()),
+                None => return Err(format!("Line {} has no ` = `: {}", index + 1, line)),
+            }
+        }
+
+        Ok(strings)
+    }
+
+    pub fn insert(&mut self, key: &str, text: &str) {
+        self.table.insert(key.to_string(), text.to_string());
+    }
+
+    /// The translation of the key, or the key without a translation
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.table.get(key).map_or(key, |text| text.as_str())
+    }
+
+    /// The translation of the key with every `{}` replaced by the next value
+    pub fn format(&self, key: &str, values: &[&dyn Display]) -> String {
+        let mut values = values.iter();
+        let mut parts = self.get(key).split("{}");
+        let mut text = parts.next().unwrap_or_default().to_string();
+
+        for part in parts {
+            if let Some(value) = values.next() {
+                text.push_str(&value.to_string());
+            }
+            text.push_str(part);
+        }
+
+        text
+    }
+}
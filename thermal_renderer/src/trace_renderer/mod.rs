@@ -0,0 +1,298 @@
+//! Trace Renderer
+//!
+//! The trace renderer tells the story of a job instead of drawing
+//! it. Every command is listed with its byte offset, its bytes and
+//! the debug line the renderer logs for it, followed by what it
+//! printed: lines of text, images, codes, pages and cuts.
+//!
+//! Text is laid out when a command that isn't text arrives, so lines
+//! of text follow that command rather than the text commands.
+//! Real-time commands that were executed from the data of another
+//! command are marked as embedded.
+//!
+//! The trace is markdown, for tickets and chats, or plain text with
+//! aligned columns for a terminal.
+//!
+//! ```
+//! use thermal_renderer::trace_renderer::{TraceFormat, TraceRenderer};
+//!
+//! let traces = TraceRenderer::render(b"\x1b@TOTAL 4.00\n", TraceFormat::Text, None);
+//! let trace = &traces.output[0].content;
+//!
+//! //The line is printed at the end of the job, after the line feed
+//! assert!(trace.contains("000C  0A"));
+//! assert!(trace.ends_with("Line Feed [LF]\n                                | TOTAL 4.00\n"));
+//! ```
+
+use crate::renderer::{DebugProfile, OutputRenderer, RenderOutput, Renderer};
+use std::mem;
+use thermal_parser::command::{Command, DeviceCommand};
+use thermal_parser::context::{Context, TextJustify};
+use thermal_parser::graphics::{Image, VectorGraphic};
+use thermal_parser::text::TextSpan;
+
+/// Bytes of a command that are shown by default
+const MAX_BYTES: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TraceFormat {
+    /// A list of the commands with what they printed in code blocks
+    #[default]
+    Markdown,
+    /// Columns of offsets, bytes and debug lines, what was printed
+    /// is indented under the command
+    Text,
+}
+
+/// ReceiptTrace is the main output for the trace renderer
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceiptTrace {
+    pub content: String,
+}
+
+//A line of the trace
+#[derive(Clone, Debug)]
+enum Entry {
+    Command {
+        offset: usize,
+        bytes: Vec<u8>,
+        debug: String,
+        embedded: bool,
+    },
+    Printed(String),
+}
+
+pub struct TraceRenderer {
+    pub format: TraceFormat,
+    /// Bytes of a command that are shown, longer commands end with ..
+    pub max_bytes: usize,
+    pub debug_profile: DebugProfile,
+    entries: Vec<Entry>,
+    receipts: usize,
+}
+
+impl Default for TraceRenderer {
+    fn default() -> Self {
+        Self {
+            format: TraceFormat::default(),
+            max_bytes: MAX_BYTES,
+            debug_profile: DebugProfile::default(),
+            entries: vec![],
+            receipts: 0,
+        }
+    }
+}
+
+impl TraceRenderer {
+    pub fn new(format: TraceFormat) -> Self {
+        Self {
+            format,
+            ..Self::default()
+        }
+    }
+
+    /// This is the normal way to trace bytes
+    pub fn render(
+        bytes: &[u8],
+        format: TraceFormat,
+        debug_profile: Option<DebugProfile>,
+    ) -> RenderOutput<ReceiptTrace> {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(TraceRenderer::new(format));
+        let mut renderer = Renderer::new(&mut child_renderer, debug_profile.unwrap_or_default());
+        renderer.render(bytes)
+    }
+
+    fn printed(&mut self, line: String) {
+        self.entries.push(Entry::Printed(line));
+    }
+
+    fn hex(&self, bytes: &[u8]) -> String {
+        let mut hex: Vec<String> = bytes
+            .iter()
+            .take(self.max_bytes)
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        if bytes.len() > self.max_bytes {
+            hex.push("..".to_string());
+        }
+        hex.join(" ")
+    }
+
+    fn to_markdown(&self, entries: &[Entry]) -> String {
+        let mut content = format!("## Receipt {}\n\n", self.receipts);
+        let mut printing = false;
+
+        for entry in entries {
+            match entry {
+                Entry::Command {
+                    offset,
+                    bytes,
+                    debug,
+                    embedded,
+                } => {
+                    if printing {
+                        content.push_str("  ```\n");
+                        printing = false;
+                    }
+                    content.push_str(&format!(
+                        "- `{:04X}` `{}` {}{}\n",
+                        offset,
+                        self.hex(bytes),
+                        escape_markdown(debug),
+                        if *embedded { " *(embedded)*" } else { "" }
+                    ));
+                }
+                Entry::Printed(line) => {
+                    if !printing {
+                        content.push_str("  ```\n");
+                        printing = true;
+                    }
+                    content.push_str(&format!("  {}\n", line));
+                }
+            }
+        }
+        if printing {
+            content.push_str("  ```\n");
+        }
+        content
+    }
+
+    fn to_text(&self, entries: &[Entry]) -> String {
+        let mut content = format!("Receipt {}\n", self.receipts);
+        //Room for the bytes, the .. of longer commands included
+        let width = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Command { bytes, .. } => Some(self.hex(bytes).len()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        for entry in entries {
+            match entry {
+                Entry::Command {
+                    offset,
+                    bytes,
+                    debug,
+                    embedded,
+                } => content.push_str(&format!(
+                    "{:04X}  {:width$}  {}{}\n",
+                    offset,
+                    self.hex(bytes),
+                    debug,
+                    if *embedded { " (embedded)" } else { "" },
+                    width = width
+                )),
+                Entry::Printed(line) => {
+                    content.push_str(&format!("{:indent$}| {}\n", "", line, indent = width + 6))
+                }
+            }
+        }
+        content
+    }
+}
+
+impl OutputRenderer<ReceiptTrace> for TraceRenderer {
+    fn set_debug_profile(&mut self, profile: DebugProfile) {
+        self.debug_profile = profile;
+    }
+
+    //The begin and end of parsing have no bytes and are left out
+    fn command_debug(&mut self, command: &Command, debug: &str) {
+        let bytes = match command.embedded {
+            true => [&command.commands[..], &command.data[..]].concat(),
+            false => command.to_bytes(),
+        };
+        if bytes.is_empty() {
+            return;
+        }
+
+        self.entries.push(Entry::Command {
+            offset: command.offset,
+            bytes,
+            debug: debug.to_string(),
+            embedded: command.embedded,
+        });
+    }
+
+    fn page_begin(&mut self, _context: &mut Context) {
+        self.printed("[Page mode]".to_string());
+    }
+
+    fn render_page(&mut self, _context: &mut Context) {
+        self.printed("[Page printed]".to_string());
+    }
+
+    fn render_graphics(&mut self, _context: &mut Context, graphics: &Vec<VectorGraphic>) {
+        self.printed(format!("[Graphics {}]", graphics.len()));
+    }
+
+    fn render_code(
+        &mut self,
+        _context: &mut Context,
+        _graphics: &Vec<VectorGraphic>,
+        symbology: &str,
+        _label: &str,
+        payload: &str,
+        _hri: Option<&str>,
+    ) {
+        self.printed(format!("[{} {:?}]", symbology, payload));
+    }
+
+    fn render_image(&mut self, _context: &mut Context, image: &Image) {
+        self.printed(format!(
+            "[Image {}x{} at {},{}]",
+            image.w, image.h, image.x, image.y
+        ));
+    }
+
+    fn render_text(
+        &mut self,
+        _context: &mut Context,
+        spans: &Vec<TextSpan>,
+        _x_offset: u32,
+        _max_height: u32,
+        _text_justify: TextJustify,
+    ) {
+        let line: String = spans.iter().map(|span| span.text.as_str()).collect();
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        //Blank lines only feed paper
+        if !line.chars().all(|c| c.is_whitespace() || c.is_control()) {
+            self.printed(line.to_string());
+        }
+    }
+
+    fn device_command(&mut self, _context: &mut Context, command: &DeviceCommand) {
+        if matches!(command, DeviceCommand::FullCut | DeviceCommand::PartialCut) {
+            self.printed(format!("[{}]", command.as_string()));
+        }
+    }
+
+    //Jobs after a form feed don't begin, receipts are counted as they end
+    fn end_render(&mut self, _context: &mut Context) -> ReceiptTrace {
+        self.receipts += 1;
+        let entries = mem::take(&mut self.entries);
+        let content = match self.format {
+            TraceFormat::Markdown => self.to_markdown(&entries),
+            TraceFormat::Text => self.to_text(&entries),
+        };
+        ReceiptTrace { content }
+    }
+}
+
+//Debug lines have text and data in them, i.e. the * of code 39
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        if matches!(
+            char,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
@@ -1,12 +1,54 @@
 use png::BitDepth;
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::mpsc::sync_channel;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use thermal_parser::command::{Command, CommandType, DeviceCommand};
+use thermal_parser::context::{Context, FontMetrics, TextJustify};
+use thermal_parser::graphics::{
+    Code2D, Image, ImageFlow, ImageRef, ImageRefStorage, VectorGraphic, RGBA,
+};
+use thermal_parser::profile::{Compositing, PrinterId, PrinterProfile};
+use thermal_parser::template::encode_template;
+use thermal_parser::text::TextSpan;
 use thermal_parser::thermal_file::parse_str;
-use thermal_renderer::html_renderer::HtmlRenderer;
-// use thermal_renderer::html_renderer::HtmlRenderer;
-use thermal_renderer::image_renderer::ImageRenderer;
-use thermal_renderer::renderer::DebugProfile;
+use thermal_parser::{parse_esc_p, parse_star, parse_star_raster, parse_tspl};
+use thermal_renderer::barcode_renderer::BarcodeRenderer;
+use thermal_renderer::event_renderer::{EventRenderer, LayoutEvent};
+use thermal_renderer::font_subset::subset_font;
+use thermal_renderer::html_renderer::{HtmlLayout, HtmlPatch, HtmlRenderer, ReceiptHtml};
+use thermal_renderer::image_diff::diff_renders;
+use thermal_renderer::image_extractor::{ImageExtractor, ImageOrigin};
+use thermal_renderer::image_renderer::canvas::GrayCanvas;
+use thermal_renderer::image_renderer::color::{ColorProfile, PaperTint};
+use thermal_renderer::image_renderer::watermark::Watermark;
+use thermal_renderer::image_renderer::wear::Wear;
+use thermal_renderer::image_renderer::{ImageRenderer, ReceiptImage};
+use thermal_renderer::job_splitter::{JobSplitter, SplitOnMarker};
+use thermal_renderer::json_renderer::{
+    Bounds, JsonRenderer, LayoutElement, ReceiptLayout, TextMetrics, TextStyle,
+};
+use thermal_renderer::layout::layout_text;
+use thermal_renderer::length_renderer::LengthRenderer;
+use thermal_renderer::line_display::LineDisplay;
+use thermal_renderer::pacing::{Link, PacedRenderer};
+use thermal_renderer::pool::BufferPool;
+use thermal_renderer::redaction::Redactor;
+use thermal_renderer::renderer::{
+    DebugProfile, DeviceEventKind, DisplayOutput, OutputRenderer, RenderConfig, RenderEvent,
+    RenderOutput, Renderer, UnknownCommands,
+};
+use thermal_renderer::semantic_diff::{diff_jobs, Change, Element};
+use thermal_renderer::signature::looks_like_signature;
+use thermal_renderer::snapshot::{assert_snapshot, Snapshot, SnapshotError};
+use thermal_renderer::stream::{render_stream, AsyncRead, StreamEvent, StreamRenderer};
+use thermal_renderer::strings::Strings;
+use thermal_renderer::trace_renderer::{TraceFormat, TraceRenderer};
+use thermal_renderer::{render_png, HtmlOptions};
 
 #[test]
 fn typography() {
@@ -78,111 +120,3420 @@ fn discount() {
     test_sample("discount", "thermal")
 }
 
-fn test_sample(name: &str, ext: &str) {
+#[test]
+fn star_receipt() {
+    let bytes = sample_bytes("star_receipt.thermal");
+
+    let mut json_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+    let renders = renderer.render_commands(parse_star(&bytes));
+
+    assert_eq!(renders.output.len(), 1);
+    assert!(
+        renders.errors.is_empty(),
+        "There were errors when rendering star commands: {:?}",
+        renders.errors
+    );
+
+    let layout = &renders.output[0];
+    let text = |wanted: &str| {
+        layout.elements.iter().find_map(|element| match element {
+            LayoutElement::Text { text, style, .. } if text == wanted => Some(style.clone()),
+            _ => None,
+        })
+    };
+    let title = text("STAR STORE").expect("No title");
+    assert_eq!((title.width_mult, title.height_mult), (2, 2));
+    assert_eq!(title.justify, "center");
+    assert!(text("Item").unwrap().bold);
+    assert!(text(" TOTAL                  6.25 ").unwrap().inverted);
+
+    //The 16x4 raster logo, then the centered code39 with its hri below
+    let logo = layout
+        .elements
+        .iter()
+        .find_map(|element| match element {
+            LayoutElement::Image { bounds, .. } => Some(bounds.clone()),
+            _ => None,
+        })
+        .expect("No raster image");
+    assert_eq!((logo.w, logo.h), (16, 4));
+    assert!(layout.elements.iter().any(|element| matches!(
+        element,
+        LayoutElement::Code { symbology, payload, hri, .. }
+            if symbology == "CODE39" && payload == "00014" && hri.as_deref() == Some("00014")
+    )));
+
+    let mut image_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
+    let mut renderer = Renderer::new(&mut image_renderer, DebugProfile::default());
+    let image = renderer
+        .render_commands(parse_star(&bytes))
+        .output
+        .remove(0);
+    //As wide as 3.2 inch paper at 203 dpi
+    assert_eq!((image.width, image.height), (649, layout.height));
+
+    //Only the logo is printed on its rows, the frame is 40 dots
+    let ink = (logo.y..logo.y + logo.h)
+        .flat_map(|y| (0..image.width).map(move |x| (y * image.width + x) as usize * 3))
+        .filter(|i| image.bytes[*i] < 128)
+        .count();
+    assert_eq!(ink, 40);
+}
+
+#[test]
+fn esc_p_kitchen() {
+    let bytes = sample_bytes("esc_p_kitchen.thermal");
+
+    let mut json_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+    let renders = renderer.render_commands(parse_esc_p(&bytes));
+
+    assert_eq!(renders.output.len(), 1);
+    assert!(
+        renders.errors.is_empty(),
+        "There were errors when rendering esc/p commands: {:?}",
+        renders.errors
+    );
+
+    //The modifier is printed with the red of the ribbon
+    let red = Context::new().graphics.render_colors.color_2;
+    let layout = &renders.output[0];
+    let modifier = layout
+        .elements
+        .iter()
+        .find_map(|element| match element {
+            LayoutElement::Text {
+                text,
+                style,
+                bounds,
+                ..
+            } if text == "   NO ONIONS" => Some((style.clone(), bounds.clone())),
+            _ => None,
+        })
+        .expect("No modifier");
+    assert_eq!(
+        modifier.0.color,
+        format!("#{:02X}{:02X}{:02X}{:02X}", red.r, red.g, red.b, red.a)
+    );
+
+    //64 single density columns and 96 double density columns are as
+    //wide, the 9 pin image has one more dot in each column
+    let images: Vec<(u32, u32)> = layout
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            LayoutElement::Image { bounds, .. } => Some((bounds.w, bounds.h)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(images, vec![(192, 24), (192, 27)]);
+
+    let mut image_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
+    let mut renderer = Renderer::new(&mut image_renderer, DebugProfile::default());
+    let image = renderer
+        .render_commands(parse_esc_p(&bytes))
+        .output
+        .remove(0);
+
+    //Red ink is only on the rows of the modifier
+    let red_rows: Vec<u32> = (0..image.height)
+        .filter(|y| {
+            (0..image.width).any(|x| {
+                let i = (y * image.width + x) as usize * 3;
+                image.bytes[i] > 128 && image.bytes[i + 1] < 100
+            })
+        })
+        .collect();
+    assert!(!red_rows.is_empty());
+    let rows = modifier.1.y..modifier.1.y + modifier.1.h;
+    assert!(red_rows.iter().all(|y| rows.contains(y)), "{:?}", red_rows);
+}
+
+#[test]
+fn star_raster() {
+    let render = |bytes: &[u8]| {
+        let mut image_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
+        let mut renderer = Renderer::new(&mut image_renderer, DebugProfile::default());
+        renderer.render_commands(parse_star_raster(bytes))
+    };
+
+    let mut renders = render(&sample_bytes("star_raster.thermal"));
+    assert_eq!(renders.output.len(), 1);
+    assert!(
+        renders.errors.is_empty(),
+        "There were errors when rendering star raster commands: {:?}",
+        renders.errors
+    );
+    let image = renders.output.remove(0);
+
+    //The same job without the raster lines and the feed between them
+    let blank = render(&parse_str(
+        r#"ESC "@" ESC "*rR" ESC "*rA" ESC FF NUL ESC FF 4 ESC "*rB""#,
+    ))
+    .output
+    .remove(0);
+    assert_eq!(image.height, blank.height + 48 + 24);
+
+    //Every transferred line has 36 bytes of ink, checkered or solid
+    let ink_rows: Vec<usize> = (0..image.height)
+        .map(|y| {
+            (0..image.width)
+                .filter(|x| image.bytes[(y * image.width + x) as usize * 3] < 128)
+                .count()
+        })
+        .filter(|ink| *ink > 0)
+        .collect();
+    assert_eq!(ink_rows, vec![288; 48]);
+}
+
+#[test]
+fn tspl_labels() {
+    let bytes = sample_bytes("tspl_labels.bin");
+    let commands = parse_tspl(&bytes);
+
+    let mut image_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
+    let mut renderer = Renderer::new(&mut image_renderer, DebugProfile::default());
+    let renders = renderer.render_commands(commands);
+
+    //One image per printed label, sized by SIZE 2,1.25 at 203 dpi
+    assert_eq!(renders.output.len(), 2);
+    assert!(
+        renders.errors.is_empty(),
+        "There were errors when rendering tspl commands: {:?}",
+        renders.errors
+    );
+
+    for label in &renders.output {
+        assert_eq!(label.width, 406);
+        assert_eq!(label.height, 253);
+    }
+}
+
+#[test]
+fn html_semantic_layout() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+    let renders = HtmlRenderer::render_with_layout(&bytes, None, HtmlLayout::Semantic);
+
+    assert!(
+        renders.errors.is_empty(),
+        "There were errors when rendering semantic html: {:?}",
+        renders.errors
+    );
+
+    let html = &renders.output.first().expect("No html generated").content;
+
+    assert!(html.contains("<div class='line center'>"));
+    assert!(html.contains("<svg"));
+    assert!(!html.contains("position: absolute"));
+}
+
+#[test]
+fn html_self_contained() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+    let renders = HtmlRenderer::render_self_contained(&bytes, None, HtmlLayout::Positioned);
+
+    let html = &renders.output.first().expect("No html generated").content;
+
+    //Only the regular face is used
+    assert_eq!(html.matches("@font-face").count(), 1);
+    assert!(html.contains("data:image/png;base64"));
+    assert!(!html.contains("<link"));
+    assert!(!html.contains("{{"));
+}
+
+#[test]
+fn html_font_subset() {
+    let bytes = parse_str(r#"ESC "@" "PLAIN" LF ESC "E" 1 "BOLD" LF"#);
+
+    let render = |subset: bool| {
+        let mut html_renderer = HtmlRenderer::new();
+        html_renderer.embed_font = true;
+        html_renderer.subset_font = subset;
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(html_renderer);
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.render(&bytes).output.remove(0).content
+    };
+
+    let whole = render(false);
+    let subset = render(true);
+    assert_eq!(whole.matches("@font-face").count(), 4);
+    assert_eq!(subset.matches("@font-face").count(), 2);
+    assert!(subset.len() * 10 < whole.len());
+
+    //Used glyphs are the same, unused chars are not in the font anymore
+    let font = include_bytes!("../resources/fonts/JetBrainsMonoNL-Bold.ttf");
+    let chars: BTreeSet<char> = "BOLD".chars().collect();
+    let subset_bytes = subset_font(font, &chars).unwrap();
+
+    let settings = fontdue::FontSettings::default();
+    let original = fontdue::Font::from_bytes(font.as_slice(), settings).unwrap();
+    let subset = fontdue::Font::from_bytes(subset_bytes, settings).unwrap();
+
+    assert_eq!(original.rasterize('B', 24.0), subset.rasterize('B', 24.0));
+    assert_ne!(original.lookup_glyph_index('Z'), 0);
+    assert_eq!(subset.lookup_glyph_index('Z'), 0);
+}
+
+#[test]
+fn html_barcode_svg() {
+    let bytes = sample_bytes("barcodes.thermal");
+    let renders = HtmlRenderer::render(&bytes, None);
+
+    let html = &renders.output.first().expect("No html generated").content;
+
+    assert!(html.contains("data-symbology='QR Code' data-payload='https://google.com'"));
+    assert!(html.contains("<title>QR Code: https://google.com</title>"));
+    assert!(html.contains("data-symbology='CODE39' data-payload='*00014*'"));
+}
+
+#[test]
+fn barcode_symbology() {
+    let bytes = parse_str(r#"ESC "@" GS "k" 73 8 "{BA-1234" GS "k" 67 12 "490123456789""#);
+
+    let renders = JsonRenderer::render(&bytes, None);
+    assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+    let symbologies: Vec<&str> = renders.output[0]
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Code { symbology, .. } => Some(symbology.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(symbologies, ["CODE128", "EAN13"]);
+
+    let json = renders.output[0].to_json();
+    assert!(json.contains("\"symbology\":\"CODE128\""));
+    assert!(json.contains("\"symbology\":\"EAN13\""));
+}
+
+#[test]
+fn html_theming_hooks() {
+    let renders = HtmlRenderer::render(&parse_str("\"Theme\" LF"), None);
+    let html = &renders.output.first().expect("No html generated").content;
+
+    assert!(html.contains("--thermal-paper-color: #FFFFFFFF;"));
+    assert!(html.contains("--thermal-font:"));
+    assert!(html.contains("class=\"thermal-receipt\""));
+    assert!(html.contains("background: var(--thermal-paper-color);"));
+}
+
+#[test]
+fn html_character_advances() {
+    let bytes = parse_str(r#"ESC "@" "A" ESC "M" 1 "B" ESC "M" 0 GS "!" 0x10 "C" LF"#);
+    let renders = HtmlRenderer::render(&bytes, None);
+    let html = &renders.output.first().expect("No html generated").content;
+
+    //Characters move as far as in the layout no matter the browser font,
+    //double width text is scaled after the spacing
+    assert_eq!(html.matches("letter-spacing: calc(12px - 1ch)").count(), 2);
+    assert_eq!(html.matches("letter-spacing: calc(9px - 1ch)").count(), 1);
+}
+
+#[test]
+fn html_patches() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+    let expected = HtmlRenderer::render(&bytes, None);
+    let expected = &expected.output.first().expect("No html generated").content;
+
+    let (html_renderer, patches) = HtmlRenderer::new_with_patches(HtmlLayout::Positioned);
+    let mut html_renderer: Box<dyn OutputRenderer<ReceiptHtml>> = Box::new(html_renderer);
+    let renderer = Renderer::new(&mut html_renderer, DebugProfile::default());
+    let mut stream = StreamRenderer::new(thermal_parser::new_esc_pos_parser(), renderer);
+
+    //Every chunk sends the rows it finished
+    let mut chunk_patches = vec![];
+    for chunk in bytes.chunks(64) {
+        stream.push(chunk);
+        chunk_patches.push(patches.try_iter().collect::<Vec<_>>());
+    }
+    stream.finish();
+    chunk_patches.push(patches.try_iter().collect());
+
+    assert!(chunk_patches.iter().filter(|p| !p.is_empty()).count() > 2);
+
+    let patches: Vec<HtmlPatch> = chunk_patches.into_iter().flatten().collect();
+    let Some(HtmlPatch::Begin { html: document }) = patches.first() else {
+        panic!("The first patch should begin the document");
+    };
+    let Some(HtmlPatch::End { style }) = patches.last() else {
+        panic!("The last patch should end the document");
+    };
+    assert!(patches
+        .last()
+        .unwrap()
+        .to_json()
+        .starts_with("{\"type\":\"end\","));
+
+    //Applying the patches gives the document of the normal render
+    let content: String = patches
+        .iter()
+        .filter_map(|patch| match patch {
+            HtmlPatch::Append { html } => Some(html.as_str()),
+            _ => None,
+        })
+        .collect();
+    let style_start = document.find("class=\"thermal-receipt\" style=\"").unwrap() + 31;
+    let style_end = style_start + document[style_start..].find('"').unwrap();
+    let patched = format!(
+        "{}{}{}",
+        &document[..style_start],
+        style,
+        &document[style_end..]
+    )
+    .replace(
+        "<article class=\"thermal-content\"></article>",
+        &format!("<article class=\"thermal-content\">{}</article>", content),
+    );
+
+    assert_eq!(&patched, expected);
+}
+
+#[test]
+fn text_layout() {
+    let mut context = Context::new();
+    let width = context.get_width();
+    let start_y = context.get_y();
+
+    //60 characters wrap after the 8th word on a 609 dot line
+    let words = "HELLO ".repeat(10);
+    let lines = layout_text(&[TextSpan::new(words, &context)], &mut context);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].spans.len(), 8);
+    assert_eq!(lines[0].height, 24);
+    let second = lines[1].spans[0].dimensions.as_ref().unwrap();
+    assert_eq!(second.y, start_y + 24);
+
+    //The line stays open, the next layout continues it
+    context.text.justify = TextJustify::Right;
+    let spans = [
+        TextSpan::new("END".to_string(), &context),
+        TextSpan::new("\n".to_string(), &context),
+    ];
+    let lines = layout_text(&spans, &mut context);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].spans[0].dimensions.as_ref().unwrap().x, 12 * 12);
+
+    let lines = layout_text(
+        &[TextSpan::new("TOTAL".to_string(), &context)],
+        &mut context,
+    );
+    assert_eq!(lines[0].x_offset, width - 5 * 12);
+    assert_eq!(context.get_y(), start_y + 48);
+}
+
+#[test]
+fn json_layout() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+    let renders = JsonRenderer::render(&bytes, None);
+
+    assert!(
+        renders.errors.is_empty(),
+        "There were errors when rendering json: {:?}",
+        renders.errors
+    );
+
+    let layout = renders.output.first().expect("No layout generated");
+
+    assert!(layout
+        .elements
+        .iter()
+        .any(|e| matches!(e, LayoutElement::Text { text, .. } if text == "Thank you")));
+    assert!(layout
+        .elements
+        .iter()
+        .any(|e| matches!(e, LayoutElement::Code { payload, .. } if payload == "*00014*")));
+    assert!(matches!(
+        layout.elements.last(),
+        Some(LayoutElement::Cut { partial: true, .. })
+    ));
+
+    let json = layout.to_json();
+    assert!(json.starts_with("{\"width\":"));
+    assert!(json.contains("\"text\":\"<< Bonus points : 14 >>\""));
+}
+
+#[test]
+fn job_metrics() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+    let profile = PrinterProfile {
+        feed_speed: 100.0,
+        ..PrinterProfile::default()
+    };
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer =
+        Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), &profile);
+    let renders = renderer.render(&bytes);
+
+    assert_eq!(renders.metrics.len(), renders.output.len());
+
+    let metrics = renders.metrics.first().expect("No metrics generated");
+    let layout = renders.output.first().unwrap();
+
+    assert_eq!(metrics.cuts, 1);
+    assert_eq!(metrics.graphics, 2);
+    assert_eq!(metrics.lines, 10);
+    assert_eq!(metrics.paper_length_mm, profile.dots_to_mm(layout.height));
+    assert_eq!(metrics.print_time_secs, metrics.paper_length_mm / 100.0);
+}
+
+#[test]
+fn length_matches_image() {
+    for name in [
+        "receipt_with_barcode",
+        "barcodes",
+        "page_mode",
+        "gs_images_raster",
+        "typography",
+    ] {
+        let bytes = sample_bytes(&format!("{}.thermal", name));
+
+        let lengths = LengthRenderer::render(&bytes, None);
+        let images = ImageRenderer::render(&bytes, None);
+
+        assert_eq!(lengths.output.len(), images.output.len(), "{}", name);
+
+        for (length, image) in lengths.output.iter().zip(images.output.iter()) {
+            assert_eq!(length.width, image.width, "{} width", name);
+            assert_eq!(length.height, image.height, "{} height", name);
+        }
+    }
+}
+
+#[test]
+fn command_middleware() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+    let mut dropped = 0;
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+
+    //Replace text
+    renderer.add_middleware(Box::new(|mut command: Command, _context: &Context| {
+        if command.kind == CommandType::Text && command.data == b"Thank you" {
+            command.data = b"Thanks".to_vec();
+        }
+        vec![command]
+    }));
+
+    //Drop cuts
+    renderer.add_middleware(Box::new(|command: Command, context: &Context| {
+        let device_commands = command.handler.get_device_command(&command, context);
+        let is_cut = device_commands.is_some_and(|device_commands| {
+            device_commands
+                .iter()
+                .any(|c| matches!(c, DeviceCommand::FullCut | DeviceCommand::PartialCut))
+        });
+
+        if is_cut {
+            dropped += 1;
+            return vec![];
+        }
+        vec![command]
+    }));
+
+    let renders = renderer.render(&bytes);
+    drop(renderer);
+
+    let layout = renders.output.first().expect("No layout generated");
+
+    assert_eq!(dropped, 1);
+    assert_eq!(renders.metrics.first().unwrap().cuts, 0);
+    assert!(layout
+        .elements
+        .iter()
+        .any(|e| matches!(e, LayoutElement::Text { text, .. } if text == "Thanks")));
+    assert!(!layout
+        .elements
+        .iter()
+        .any(|e| matches!(e, LayoutElement::Cut { .. })));
+}
+
+#[test]
+fn text_redaction() {
+    let bytes = parse_str(
+        r#"ESC "@" "CARD 4111 1111 " ESC "E" 1 "1111 1111" ESC "E" 0 LF "TEL (555) 123-4567" LF "LOYALTY LOY-99812" LF "TOTAL 7.70" LF"#,
+    );
+
+    let mut redactor = Redactor::with_common_patterns();
+    redactor.add_pattern("loyalty id", r"LOY-\d+").unwrap();
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    renderer.set_redactor(redactor);
+    let renders = renderer.render(&bytes);
+
+    let layout = renders.output.first().expect("No layout generated");
+    let text: String = layout
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        text,
+        "CARD **** **** **** ****TEL (***) ***-****LOYALTY ***-*****TOTAL 7.70"
+    );
+
+    let patterns: Vec<&str> = renders
+        .redactions
+        .iter()
+        .map(|r| r.pattern.as_str())
+        .collect();
+    assert_eq!(patterns, vec!["card number", "phone number", "loyalty id"]);
+    assert_eq!(renders.redactions[0].masked, "**** **** **** ****");
+}
+
+#[test]
+fn logo_substitution() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+
+    let black = RGBA {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+
+    let logo = Image {
+        pixels: vec![black; 100 * 20],
+        x: 0,
+        y: 0,
+        w: 100,
+        h: 20,
+        flow: ImageFlow::Block,
+        upside_down: false,
+    };
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    renderer.substitute_graphic(
+        ImageRef {
+            kc1: b'A',
+            kc2: b'1',
+            storage: ImageRefStorage::Ram,
+        },
+        logo,
+    );
+    let renders = renderer.render(&bytes);
+
+    let layout = renders.output.first().expect("No layout generated");
+    let images: Vec<(u32, u32)> = layout
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Image { bounds, .. } => Some((bounds.w, bounds.h)),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(images, vec![(100, 20)]);
+}
+
+#[test]
+fn print_density() {
+    let darkest = |bytes: &[u8]| -> u8 {
+        let renders = ImageRenderer::render_with_density(bytes, None);
+        let image = renders.output.first().expect("No image generated");
+        *image.bytes.iter().min().unwrap()
+    };
+
+    let standard = darkest(&parse_str(r#"ESC "@" "DENSITY" LF"#));
+    let light = darkest(&parse_str(r#"ESC "@" GS "(K" 2 0 49 250 "DENSITY" LF"#));
+
+    assert_eq!(standard, 0);
+    assert!(
+        light >= 70,
+        "light density should fade the ink, darkest {}",
+        light
+    );
+}
+
+#[test]
+fn gray_canvas() {
+    let bytes = parse_str(
+        r#"ESC "@" ESC "E" 1 "GRAY" LF ESC "L" ESC "T" 1 "PAGE" FF GS "B" 1 "INVERTED" LF"#,
+    );
+
+    let render = |renderer: ImageRenderer| {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(renderer);
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.render(&bytes).output.remove(0)
+    };
+
+    let rgba = render(ImageRenderer::new());
+    let gray = render(ImageRenderer::with_canvas(|| {
+        Box::new(GrayCanvas::default())
+    }));
+
+    //Black ink on white paper has no colors to lose
+    assert_eq!((gray.width, gray.height), (rgba.width, rgba.height));
+    assert!(gray.bytes == rgba.bytes);
+}
+
+#[test]
+fn device_events() {
+    let bytes = parse_str(
+        r#"ESC "@" ESC "c" 5 1 ESC "c" 3 12 ESC "c" 4 2 "PAID" LF ESC "p" 0 25 250 ESC "=" 1 DLE 4 1 DLE 4 9 GS "V" 0"#,
+    );
+
+    let renders = JsonRenderer::render(&bytes, None);
+    let kinds: Vec<DeviceEventKind> = renders.events.iter().map(|e| e.kind.clone()).collect();
+
+    assert_eq!(
+        kinds,
+        vec![
+            DeviceEventKind::PanelButtons { enabled: false },
+            DeviceEventKind::PaperEndSensors { sensors: 12 },
+            DeviceEventKind::PrintStopSensors { sensors: 2 },
+            DeviceEventKind::DrawerPulse,
+            DeviceEventKind::PeripheralDevice {
+                printer_enabled: true,
+                display_enabled: false
+            },
+            DeviceEventKind::StatusRequest {
+                status_type: 1,
+                response: Some(0x12)
+            },
+            DeviceEventKind::StatusRequest {
+                status_type: 9,
+                response: None
+            },
+        ]
+    );
+    assert!(renders.events.iter().all(|e| e.output == 0));
+}
+
+#[test]
+fn printer_id_requests() {
+    let bytes = parse_str(r#"ESC "@" GS "I" 67 GS "I" 65 GS "I" 1 GS "I" 99 "ID" LF"#);
+
+    let profile = PrinterProfile {
+        printer_id: PrinterId {
+            model_id: 0x75,
+            model: "TM-T88VI".to_string(),
+            firmware_version: "30.01 ESC/POS".to_string(),
+            ..PrinterId::default()
+        },
+        ..PrinterProfile::default()
+    };
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer =
+        Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), &profile);
+    let renders = renderer.render(&bytes);
+    let kinds: Vec<DeviceEventKind> = renders.events.iter().map(|e| e.kind.clone()).collect();
+
+    assert_eq!(
+        kinds,
+        vec![
+            DeviceEventKind::PrinterIdRequest {
+                id_type: 67,
+                response: Some(b"_TM-T88VI\0".to_vec()),
+            },
+            DeviceEventKind::PrinterIdRequest {
+                id_type: 65,
+                response: Some(b"_30.01 ESC/POS\0".to_vec()),
+            },
+            DeviceEventKind::PrinterIdRequest {
+                id_type: 1,
+                response: Some(vec![0x75]),
+            },
+            DeviceEventKind::PrinterIdRequest {
+                id_type: 99,
+                response: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn unknown_command_handling() {
+    let bytes = parse_str(r#"ESC "@" "BEFORE" ESC "~" 1 LF "AFTER" LF "NEXT" LF"#);
+
+    let render = |unknown_commands: UnknownCommands| {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.set_unknown_commands(unknown_commands);
+        let renders = renderer.render(&bytes);
+        let text: Vec<String> = renders.output[0]
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                LayoutElement::Text { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        (renders.errors.len(), renders.output.len(), text.join(" "))
+    };
+
+    let (errors, _, text) = render(UnknownCommands::Skip);
+    assert_eq!(errors, 0);
+    assert!(text.contains("BEFORE") && text.contains("AFTER"));
+
+    let (errors, _, report_text) = render(UnknownCommands::Report);
+    assert_eq!(errors, 1);
+    assert_eq!(report_text, text);
+
+    //The error has the offset, the bytes before and the unknown bytes
+    let renders = JsonRenderer::render(&bytes, None);
+    let error = format!("{:?}", renders.errors[0]);
+    assert!(
+        error.ends_with("at byte 8 (0x8): 1B 40 42 45 46 4F 52 45 [1B 7E 01]"),
+        "{}",
+        error
+    );
+
+    //The marker is between the text before and after the command
+    let (errors, _, text) = render(UnknownCommands::Placeholder);
+    assert_eq!(errors, 1);
+    let marker = text.find("[?").expect("No placeholder rendered");
+    assert!(text.find("BEFORE").unwrap() < marker);
+    assert!(marker < text.find("AFTER").unwrap());
+
+    let (errors, outputs, text) = render(UnknownCommands::Abort);
+    assert_eq!(errors, 1);
+    assert_eq!(outputs, 1);
+    assert!(text.contains("BEFORE"));
+    assert!(!text.contains("AFTER") && !text.contains("NEXT"));
+}
+
+#[test]
+fn label_paper() {
+    let bytes = parse_str(r#"ESC "@" GS "(F" 4 0 1 0 16 0 "LABEL 1" LF FF "LABEL 2" LF GS FF"#);
+
+    let profile = PrinterProfile {
+        label_length_mm: Some(50.0),
+        ..PrinterProfile::default()
+    };
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(LengthRenderer::new());
+    let mut renderer =
+        Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), &profile);
+    let renders = renderer.render(&bytes);
+
+    let heights: Vec<u32> = renders.output.iter().map(|l| l.height).collect();
+    assert_eq!(heights, vec![400, 400]);
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer =
+        Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), &profile);
+    let renders = renderer.render(&bytes);
+
+    let first_y: Vec<u32> = renders
+        .output
+        .iter()
+        .filter_map(|layout| match layout.elements.first() {
+            Some(LayoutElement::Text { bounds, .. }) => Some(bounds.y),
+            _ => None,
+        })
+        .collect();
+
+    //The start adjustment applies from the second label on
+    assert_eq!(first_y, vec![0, 16]);
+}
+
+#[test]
+fn paper_layout() {
+    //72mm linerless paper with 2mm on the left, cut to 50mm labels
+    let bytes = parse_str(
+        r#"GS "(P" 7 0 48 64 2 16 0 0 2 GS "(P" 3 0 49 144 1 ESC "@" "LABEL" LF GS "V" 66 0"#,
+    );
+
+    let renders = ImageRenderer::render(&bytes, None);
+    let image = &renders.output[0];
+    assert_eq!(renders.output.len(), 1);
+    assert_eq!((image.width, image.height), (576, 400));
+    assert!((renders.metrics[0].paper_length_mm - 50.0).abs() < 0.1);
+
+    //Nothing is printed left of the origin
+    let first_ink = (0..image.height as usize)
+        .flat_map(|y| (0..image.width as usize).map(move |x| (x, y)))
+        .filter(|(x, y)| image.bytes[(y * image.width as usize + x) * 3] < 128)
+        .map(|(x, _)| x)
+        .min();
+    assert!(first_ink.is_some_and(|x| x >= 16), "{:?}", first_ink);
+
+    //Labels start printing at the top of the label
+    let layout = JsonRenderer::render(&bytes, None);
+    match layout.output[0].elements.first() {
+        Some(LayoutElement::Text { bounds, .. }) => assert_eq!(bounds.y, 0),
+        _ => panic!("Expected the label text"),
+    }
+
+    //A layout change after printing starts a new label
+    let bytes = parse_str(r#""ROLL" LF GS "(P" 3 0 49 144 1 "LABEL" LF"#);
+    let renders = LengthRenderer::render(&bytes, None);
+    assert_eq!(renders.output.len(), 2);
+    assert_eq!(renders.output[1].height, 400);
+}
+
+#[test]
+fn page_mode_cancel() {
+    let canceled = parse_str(
+        r#"ESC "@" ESC "L" ESC "W" 0 0 0 0 128 1 200 0 "GHOST PAGE" LF CAN "REAL PAGE" LF FF"#,
+    );
+    //The print position is kept, only the data is deleted
+    let expected = parse_str(r#"ESC "@" ESC "L" ESC "W" 0 0 0 0 128 1 200 0 LF "REAL PAGE" LF FF"#);
+
+    let layout = JsonRenderer::render(&canceled, None);
+    let text: Vec<String> = layout.output[0]
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Text { text, .. } => Some(text.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(text.join(""), "REAL PAGE");
+
+    let canceled_image = ImageRenderer::render(&canceled, None);
+    let expected_image = ImageRenderer::render(&expected, None);
+    assert!(canceled_image.output[0].bytes == expected_image.output[0].bytes);
+}
+
+#[test]
+fn initialize_mid_stream() {
+    let texts = |bytes: &[u8]| -> Vec<(String, u32)> {
+        let layout = JsonRenderer::render(bytes, None);
+        layout.output[0]
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                LayoutElement::Text { text, bounds, .. } => Some((text.clone(), bounds.y)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    //The next receipt continues below the previous one
+    let receipts = texts(&parse_str(r#"ESC "@" "AAA" LF ESC "@" "BBB" LF"#));
+    assert_eq!(receipts.len(), 2);
+    assert!(receipts[1].1 > receipts[0].1);
+
+    //Text without a newline and page mode data are never printed
+    let discarded = texts(&parse_str(
+        r#"ESC "@" "LOST" ESC "@" ESC "L" "GHOST PAGE" LF ESC "@" "KEPT" LF"#,
+    ));
+    let text: Vec<&str> = discarded.iter().map(|(t, _)| t.as_str()).collect();
+    assert_eq!(text.join(""), "KEPT");
+
+    let image = ImageRenderer::render(
+        &parse_str(r#"ESC "@" ESC "L" "GHOST PAGE" LF ESC "@""#),
+        None,
+    );
+    assert!(image.output[0].bytes.chunks(3).all(|rgb| rgb[0] >= 128));
+}
+
+#[test]
+fn page_mode_compositing() {
+    let block = r#"ESC "@" ESC "L" ESC "W" 0 0 0 0 200 0 100 0 GS "B" 1 "      " GS "B" 0 "#;
+    let block_only = parse_str(&format!("{} FF", block));
+    let overlapped = parse_str(&format!(r#"{} ESC "W" 0 0 0 0 200 0 100 0 "AB" FF"#, block));
+
+    let dark_pixels = |bytes: &[u8], compositing: Compositing| -> usize {
+        let profile = PrinterProfile {
+            page_compositing: compositing,
+            ..PrinterProfile::default()
+        };
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
+        let mut renderer =
+            Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), &profile);
+        let renders = renderer.render(bytes);
+        let image = renders.output.first().expect("No image generated");
+        image.bytes.chunks(3).filter(|rgb| rgb[0] < 128).count()
+    };
+
+    let block_pixels = dark_pixels(&block_only, Compositing::Or);
+
+    //Black dots stay black when or-ing, overwriting blanks part of the block
+    assert_eq!(dark_pixels(&overlapped, Compositing::Or), block_pixels);
+    assert!(dark_pixels(&overlapped, Compositing::Overwrite) < block_pixels);
+}
+
+#[test]
+fn page_mode_debug_overlay() {
+    let bytes = parse_str(r#"ESC "@" ESC "L" ESC "T" 3 ESC "W" 0 0 0 0 200 0 144 0 "OVERLAY" FF"#);
+
+    let arrow_pixels = |debug_profile: DebugProfile| -> usize {
+        let renders = ImageRenderer::render(&bytes, Some(debug_profile));
+        let image = renders.output.first().expect("No image generated");
+        image
+            .bytes
+            .chunks(3)
+            .filter(|rgb| rgb == &[216, 30, 30])
+            .count()
+    };
+
+    let page_debug = DebugProfile {
+        page: true,
+        ..DebugProfile::default()
+    };
+
+    assert_eq!(arrow_pixels(DebugProfile::default()), 0);
+    assert!(arrow_pixels(page_debug) > 0);
+}
+
+#[test]
+fn image_debug_dumps() {
+    let dir = std::env::temp_dir().join("thermal_image_dumps");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let bytes = parse_str(
+        r#"ESC "@" GS "v0" 0 1 0 8 0 0xFF 0x81 0x81 0x81 0x81 0x81 0x81 0xFF ESC "L" ESC "T" 3 ESC "W" 0 0 0 0 200 0 144 0 "PAGE" FF"#,
+    );
+
+    let debug_profile = DebugProfile {
+        image: true,
+        image_dir: Some(dir.clone()),
+        ..DebugProfile::default()
+    };
+    let renders = ImageRenderer::render(&bytes, Some(debug_profile));
+    assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+
+    let mut files: Vec<String> = std::fs::read_dir(&dir)
+        .expect("No dump directory")
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+
+    //The image command comes before the command that prints the page
+    assert_eq!(files.len(), 3);
+    assert!(files[0].ends_with("_image.png"));
+    assert!(files[1].ends_with("_page.png"));
+    assert!(files[2].ends_with("_page_rotated.png"));
+    assert_eq!(files[1][..5], files[2][..5]);
+
+    let image =
+        thermal_renderer::image_renderer::ReceiptImage::load_png(&dir.join(&files[0])).unwrap();
+    assert_eq!((image.width, image.height), (8, 8));
+
+    //Nothing is written without image debugging
+    let _ = std::fs::remove_dir_all(&dir);
+    ImageRenderer::render(&bytes, None);
+    assert!(!dir.exists());
+}
+
+#[test]
+fn page_mode_repeat_print() {
+    let bytes = parse_str(
+        r#"ESC "@" ESC "L" ESC "W" 0 0 0 0 128 1 100 0 "TICKET" ESC FF ESC FF "VOID" FF"#,
+    );
+
+    let renders = JsonRenderer::render(&bytes, None);
+    let layout = renders.output.first().expect("No layout generated");
+
+    let pages: Vec<u32> = layout
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Page { bounds } => Some(bounds.y),
+            _ => None,
+        })
+        .collect();
+
+    //Every print advances the paper by the page height
+    assert_eq!(pages, vec![108, 208, 308]);
+
+    assert_eq!(layout.height, 408);
+
+    //The buffer is kept between prints, every copy has the ticket on it
+    let renders = ImageRenderer::render(&bytes, None);
+    let image = renders.output.first().expect("No image generated");
+    let row_len = image.width as usize * 3;
+
+    for page_y in pages {
+        let start = page_y as usize * row_len;
+        let page = &image.bytes[start..start + 100 * row_len];
+        assert!(page.iter().any(|b| *b < 128), "Empty copy at {}", page_y);
+    }
+}
+
+#[test]
+fn character_size() {
+    let text_bounds = |bytes: &[u8]| -> (u32, Vec<(String, Bounds)>) {
+        let renders = JsonRenderer::render(bytes, None);
+        let layout = renders.output.first().expect("No layout generated");
+        let text = layout
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                LayoutElement::Text { bounds, text, .. } => Some((text.clone(), bounds.clone())),
+                _ => None,
+            })
+            .collect();
+        (layout.width, text)
+    };
+
+    //Every width and height multiplier from 1 to 8
+    for w in 0..8u8 {
+        for h in 0..8u8 {
+            let n = (w << 4) | h;
+            let (_, text) = text_bounds(&parse_str(&format!(r#"ESC "@" GS "!" {} "AB" LF"#, n)));
+            assert_eq!(text[0].1.w, 2 * 12 * (w as u32 + 1), "GS ! {:#04x}", n);
+            assert_eq!(text[0].1.h, 24 * (h as u32 + 1), "GS ! {:#04x}", n);
+        }
+    }
+
+    //Mixed sizes share the bottom of the line, the next line is below the tallest
+    let (_, text) = text_bounds(&parse_str(
+        r#"ESC "@" "TOTAL" GS "!" 0x22 " $12.50" GS "!" 0 " X" LF "NEXT" LF"#,
+    ));
+    let bottom = text[0].1.y + text[0].1.h;
+    assert!(text[..3].iter().all(|(_, b)| b.y + b.h == bottom));
+    assert_eq!(text[1].1.h, 72);
+    assert_eq!(text[3].1.y, bottom);
+
+    //Wide words are broken at the scaled width and right justified to the edge
+    let (width, text) = text_bounds(&parse_str(
+        r#"ESC "@" ESC "a" 2 GS "!" 0x20 "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789abcdefghijklmnopqrstuvwxyz" LF"#,
+    ));
+    let joined: String = text.iter().map(|(t, _)| t.as_str()).collect();
+    assert_eq!(joined.len(), 62);
+    assert!(text.len() > 3);
+    let right = text[0].1.x + text[0].1.w;
+    assert!(right <= width);
+    assert!(text.iter().all(|(_, b)| b.x + b.w == right));
+
+    //A space after a total that fills the line doesn't push it to the next line
+    let (_, text) = text_bounds(&parse_str(
+        r#"ESC "@" GS "!" 0x20 "TOTAL     $12.50 " LF "NEXT" LF"#,
+    ));
+    assert_eq!(text[0].0, "TOTAL     $12.50");
+    assert_eq!(text[1].0, "NEXT");
+    assert_eq!(text[1].1.y, text[0].1.y + text[0].1.h);
+}
+
+#[test]
+fn reverse_video() {
+    //A single height span next to a double height one, then a blank
+    let bytes = parse_str(r#"ESC "@" GS "B" 1 "AB" GS "!" 0x01 "CD" GS "!" 0 " E" GS "B" 0 LF"#);
+
+    let renders = JsonRenderer::render(&bytes, None);
+    let layout = renders.output.first().expect("No layout generated");
+    let bounds: Vec<Bounds> = layout
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Text { bounds, .. } => Some(bounds.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let renders = ImageRenderer::render(&bytes, None);
+    let image = renders.output.first().expect("No image generated");
+    let dark = |x: u32, y: u32| image.bytes[((y * image.width + x) * 3) as usize] < 128;
+
+    //The image adds the paper margin, the block starts at the first dark dot
+    let bottom = bounds[1].y + bounds[1].h;
+    let left = (0..image.width).find(|x| dark(*x, bottom - 1)).unwrap();
+
+    //The cells of the short spans are filled up to the top of the line
+    //and the blank is filled for the whole line height
+    let top = bounds[1].y;
+    assert_eq!(bounds[0].y + bounds[0].h, bottom);
+    for y in top..top + 8 {
+        assert!((left..left + bounds[0].w).all(|x| dark(x, y)));
+        assert!((left + bounds[2].x..left + bounds[2].x + bounds[2].w).all(|x| dark(x, y)));
+    }
+    for y in top..bottom {
+        assert!((left + bounds[2].x..left + bounds[2].x + 12).all(|x| dark(x, y)));
+    }
+}
+
+#[test]
+fn underline_thickness() {
+    //Blanks are only inked by the line, each run is a line and its thickness
+    let lines = |setup: &str| -> Vec<usize> {
+        let bytes = parse_str(&format!(r#"ESC "@" {} "    " LF"#, setup));
+        let renders = ImageRenderer::render(&bytes, None);
+        let image = renders.output.first().expect("No image generated");
+        let rows = image
+            .bytes
+            .chunks((image.width * 3) as usize)
+            .map(|row| row.chunks(3).any(|rgb| rgb[0] < 128));
+
+        let mut runs = vec![];
+        let mut run = 0;
+        for dark in rows.chain([false]) {
+            if dark {
+                run += 1;
+            } else if run > 0 {
+                runs.push(run);
+                run = 0;
+            }
+        }
+        runs
+    };
+
+    let single = lines(r#"ESC "-" 1"#);
+    assert_eq!(single.len(), 1);
+    assert_eq!(lines(r#"GS "!" 0x01 ESC "-" 1"#), vec![single[0] * 2]);
+    assert_eq!(lines(r#"ESC "-" 2"#), vec![single[0]; 2]);
+    assert_eq!(lines(r#"GS "!" 0x11 ESC "-" 2"#), vec![single[0] * 2; 2]);
+    assert_eq!(lines(r#"ESC "G" 1"#), vec![single[0]; 2]);
+    assert_eq!(lines(r#"GS "!" 0x01 ESC "G" 1"#), vec![single[0] * 2; 2]);
+
+    let html = |setup: &str| -> String {
+        let bytes = parse_str(&format!(r#"ESC "@" {} "TOTAL" LF"#, setup));
+        let renders = HtmlRenderer::render(&bytes, None);
+        renders
+            .output
+            .first()
+            .expect("No html generated")
+            .content
+            .clone()
+    };
+
+    assert!(html(r#"ESC "-" 1"#).contains("class='u'"));
+    assert!(html(r#"ESC "-" 2"#).contains("class='u ud'"));
+}
+
+#[test]
+fn motion_units() {
+    //1/101 inch motion units are 2.01 dots on a 203 dpi printer
+    let bytes = parse_str(
+        r#"ESC "@" GS "P" 101 101 "A" LF ESC "$" 50 0 "B" LF ESC "$" 50 0 ESC "\\" 10 0 "C" LF ESC "$" 5 0 ESC "\\" 236 255 "D" LF"#,
+    );
+
+    let renders = JsonRenderer::render(&bytes, None);
+    let layout = renders.output.first().expect("No layout generated");
+
+    let x: Vec<(String, u32)> = layout
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Text { bounds, text, .. } => Some((text.clone(), bounds.x)),
+            _ => None,
+        })
+        .collect();
+
+    let start = x[0].1;
+    assert_eq!(x[1], ("B".to_string(), start + 100));
+    //60 units are 120.6 dots, the fraction of the absolute move is kept
+    assert_eq!(x[2], ("C".to_string(), start + 121));
+
+    //Moving before the start of the line stops at the start
+    assert_eq!(x[3], ("D".to_string(), start));
+}
+
+#[test]
+fn motion_unit_carry() {
+    let text_x = |bytes: &[u8], profile: &PrinterProfile| -> Vec<u32> {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer =
+            Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), profile);
+        let renders = renderer.render(bytes);
+        let layout = renders.output.first().expect("No layout generated");
+
+        layout
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                LayoutElement::Text { bounds, .. } => Some(bounds.x),
+                _ => None,
+            })
+            .collect()
+    };
+
+    //1/135 inch is about 1.5 dots, ten moves of one unit are 15 dots and not 20
+    let moves = r#"ESC "\\" 1 0 "#.repeat(10);
+    let bytes = parse_str(&format!(
+        r#"ESC "@" GS "P" 135 135 "A" LF {} "B" LF"#,
+        moves
+    ));
+    let x = text_x(&bytes, &PrinterProfile::default());
+    assert_eq!(x[1], x[0] + 15);
+
+    //GS P 0 0 restores the motion units of the profile
+    let profile = PrinterProfile {
+        motion_units: Some((101, 101)),
+        ..PrinterProfile::default()
+    };
+    let bytes = parse_str(r#"ESC "@" GS "P" 203 203 GS "P" 0 0 "A" LF ESC "$" 50 0 "B" LF"#);
+    let x = text_x(&bytes, &profile);
+    assert_eq!(x[1], x[0] + 100);
+}
+
+#[test]
+fn fractional_feeds() {
+    let text_y = |src: &str| -> Vec<u32> {
+        let renders = JsonRenderer::render(&parse_str(src), None);
+        let layout = renders.output.first().expect("No layout generated");
+
+        layout
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                LayoutElement::Text { bounds, .. } => Some(bounds.y),
+                _ => None,
+            })
+            .collect()
+    };
+    let line = text_y(r#"ESC "@" "A" LF "B" LF"#);
+    let line = line[1] - line[0];
+
+    //180 feeds of 1/180 inch are one inch of paper, 203 dots and not 180
+    let feeds = r#"ESC "J" 1 "#.repeat(180);
+    let y = text_y(&format!(r#"ESC "@" GS "P" 0 180 "A" LF {} "B" LF"#, feeds));
+    assert_eq!(y[1] - y[0] - line, 203);
+
+    //Rules 2/240 inch apart are 1.69 dots apart, each rule lands on the
+    //nearest dot of where it is on paper instead of drifting 2 dots a rule
+    let rules = r#""-" ESC "J" 2 "#.repeat(40);
+    let y = text_y(&format!(r#"ESC "@" GS "P" 0 240 {} LF"#, rules));
+    for (index, rule) in y.iter().enumerate() {
+        let exact = index as f32 * 2.0 * 203.0 / 240.0;
+        assert_eq!(*rule - y[0], exact.round() as u32);
+    }
+
+    //Feeds of whole dots are unchanged
+    let y = text_y(r#"ESC "@" "A" LF ESC "J" 30 "B" LF"#);
+    assert_eq!(y[1] - y[0] - line, 30);
+}
+
+#[test]
+fn line_spacing() {
+    let profile = PrinterProfile {
+        line_spacing: 30,
+        ..PrinterProfile::default()
+    };
+
+    let text_y = |src: &str| -> Vec<u32> {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer =
+            Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), &profile);
+        let renders = renderer.render(&parse_str(src));
+        let layout = renders.output.first().expect("No layout generated");
+
+        layout
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                LayoutElement::Text { bounds, .. } => Some(bounds.y + bounds.h),
+                _ => None,
+            })
+            .collect()
+    };
+
+    //ESC 3 is in motion units, 30 units of 1/101 inch are 60 dots,
+    //changing the units afterwards keeps the spacing
+    let y = text_y(
+        r#"ESC "@" GS "P" 0 101 ESC "3" 30 GS "P" 0 203 "A" LF "B" LF ESC "2" "C" LF "D" LF"#,
+    );
+    assert_eq!(y[1] - y[0], 60);
+    assert_eq!(y[2] - y[1], 60);
+    assert_eq!(y[3] - y[2], 30);
+
+    //Spacing smaller than the text moves by the tallest character on the
+    //line, the y values are the bottoms of the text
+    let y = text_y(r#"ESC "@" ESC "3" 10 "A" LF "B" GS "!" 1 "C" LF GS "!" 0 "D" LF LF "E" LF"#);
+    assert_eq!(y[1] - y[0], 48);
+    assert_eq!(y[1], y[2]);
+    assert_eq!(y[3] - y[2], 24);
+    assert_eq!(y[4] - y[3], 10 + 24);
+}
+
+#[test]
+fn profile_font_metrics() {
+    let text_bounds = |profile: &PrinterProfile| -> Vec<(String, Bounds)> {
+        let bytes = parse_str(&format!(
+            r#"ESC "@" ESC "M" 1 "{}" LF"#,
+            "0123456789".repeat(8)
+        ));
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer =
+            Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), profile);
+        let renders = renderer.render(&bytes);
+        let layout = renders.output.first().expect("No layout generated");
+
+        layout
+            .elements
+            .iter()
+            .filter_map(|e| match e {
+                LayoutElement::Text { bounds, text, .. } => Some((text.clone(), bounds.clone())),
+                _ => None,
+            })
+            .collect()
+    };
+
+    //609 dots fit 67 characters of the default 9x17 Font B
+    let text = text_bounds(&PrinterProfile::default());
+    assert_eq!(text[0].0.len(), 67);
+    assert_eq!(text[0].1.h, 17);
+
+    let profile = PrinterProfile {
+        fonts: FontMetrics {
+            b: (10, 24),
+            ..FontMetrics::default()
+        },
+        ..PrinterProfile::default()
+    };
+    let text = text_bounds(&profile);
+    assert_eq!(text[0].0.len(), 60);
+    assert_eq!(text[0].1.h, 24);
+    assert_eq!(text[1].1.y, text[0].1.y + 24);
+}
+
+#[test]
+fn profile_code_table() {
+    let profile = PrinterProfile {
+        code_table: 19,
+        line_spacing: 30,
+        ..PrinterProfile::default()
+    };
+
+    //The job never selects a code table or line spacing
+    let bytes = parse_str(r#"ESC "@" "5" 0xD5 LF "5" 0xD5 LF"#);
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer =
+        Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), &profile);
+    let renders = renderer.render(&bytes);
+    let layout = renders.output.first().expect("No layout generated");
+
+    let lines: Vec<(String, u32)> = layout
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Text { bounds, text, .. } => Some((text.clone(), bounds.y)),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(lines[0].0, "5€");
+    assert_eq!(lines[1].0, "5€");
+    assert_eq!(lines[1].1 - lines[0].1, 30);
+}
+
+#[test]
+fn inline_image_baseline() {
+    let layout = |text: &str| -> Vec<LayoutElement> {
+        let renders = JsonRenderer::render(&parse_str(text), None);
+        renders
+            .output
+            .first()
+            .expect("No layout generated")
+            .elements
+            .clone()
+    };
+
+    let bounds = |element: &LayoutElement| -> (u32, u32) {
+        match element {
+            LayoutElement::Text { bounds, .. } | LayoutElement::Image { bounds, .. } => {
+                (bounds.y, bounds.h)
+            }
+            _ => panic!("Unexpected element"),
+        }
+    };
+
+    //A 24 dot image with a smaller line spacing doesn't overlap the next line
+    let elements =
+        layout(r#"ESC "@" ESC "3" 10 ESC "*" 33 2 0 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF LF "A" LF"#);
+    let (image_y, image_h) = bounds(&elements[0]);
+    let (text_y, _) = bounds(&elements[1]);
+    assert_eq!(image_h, 24);
+    assert_eq!(text_y, image_y + image_h);
+
+    //An image after double height text sits on the text baseline
+    let elements = layout(
+        r#"ESC "@" GS "!" 1 "A" ESC "*" 33 2 0 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF "B" LF "C" LF"#,
+    );
+    let (text_y, text_h) = bounds(&elements[0]);
+    let (image_y, image_h) = bounds(&elements[1]);
+    let (after_y, after_h) = bounds(&elements[2]);
+    assert_eq!(image_y + image_h, text_y + text_h);
+    assert_eq!(after_y + after_h, text_y + text_h);
+
+    //The next line starts below the tallest part of the line
+    let (next_y, _) = bounds(&elements[3]);
+    assert!(next_y >= text_y + text_h);
+}
+
+#[test]
+fn text_metrics() {
+    let renders = JsonRenderer::render(
+        &parse_str(r#"ESC "@" "small " GS "!" 0x11 "TALL" GS "!" 0 LF "next" LF"#),
+        None,
+    );
+    let layout = &renders.output[0];
+
+    let texts: Vec<(&str, &Bounds, &TextMetrics)> = layout
+        .elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Text {
+                text,
+                bounds,
+                metrics,
+                ..
+            } => Some((text.as_str(), bounds, metrics)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(texts.len(), 3);
+
+    for (_, bounds, metrics) in &texts {
+        assert_eq!(metrics.baseline, bounds.y + metrics.ascent);
+        assert_eq!(metrics.ascent + metrics.descent, bounds.h);
+    }
+    let (_, small, small_metrics) = texts[0];
+    let (_, tall, tall_metrics) = texts[1];
+    assert_eq!(small_metrics.ascent, 15);
+    assert_eq!(tall_metrics.ascent, 30);
+
+    //The first line is around both runs and has the baseline of the tall one
+    assert_eq!(layout.lines.len(), 2);
+    let line = &layout.lines[0];
+    assert_eq!(line.bounds.x, small.x);
+    assert_eq!(line.bounds.y, tall.y);
+    assert_eq!(line.bounds.w, tall.x + tall.w - small.x);
+    assert_eq!(line.bounds.h, tall.h);
+    assert_eq!(line.baseline, tall_metrics.baseline);
+    assert_eq!(&layout.lines[1].bounds, texts[2].1);
+
+    let json = layout.to_json();
+    assert!(json.contains("\"baseline\":"));
+    assert!(json.contains("\"lines\":[{\"x\":"));
+}
+
+#[test]
+fn page_mode_image_justification() {
+    let image_x = |justify: u8| -> u32 {
+        let text = format!(
+            r#"ESC "@" ESC "L" ESC "W" 100 0 0 0 200 0 100 0 ESC "a" {} GS "v0" 0 8 0 1 0 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF FF"#,
+            justify
+        );
+        let renders = JsonRenderer::render(&parse_str(&text), None);
+        let layout = renders.output.first().expect("No layout generated");
+
+        layout
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                LayoutElement::Image { bounds, .. } => Some(bounds.x),
+                _ => None,
+            })
+            .expect("No image in layout")
+    };
+
+    //The 64 dot wide image is justified within the 200 dot wide print area
+    assert_eq!(image_x(0), 100);
+    assert_eq!(image_x(1), 168);
+    assert_eq!(image_x(2), 236);
+}
+
+#[test]
+fn bit_image_modes() {
+    let render = |mode: u8, columns: &str| {
+        let text = format!(r#"ESC "@" ESC "*" {} 2 0 {} LF"#, mode, columns);
+        let layout = JsonRenderer::render(&parse_str(&text), None);
+        let image = ImageRenderer::render(&parse_str(&text), None);
+        (layout, image)
+    };
+
+    let image_bounds = |layout: &RenderOutput<ReceiptLayout>| {
+        layout
+            .output
+            .first()
+            .expect("No layout generated")
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                LayoutElement::Image { bounds, .. } => Some(bounds.clone()),
+                _ => None,
+            })
+            .expect("No image in layout")
+    };
+
+    //8 dot modes are three dots per dot high, single density is two dots per dot wide
+    for (mode, columns, w, h) in [
+        (0, "0x80 0x01", 4, 24),
+        (1, "0x80 0x01", 2, 24),
+        (32, "0x80 0 0 0 0 0x01", 4, 24),
+        (33, "0x80 0 0 0 0 0x01", 2, 24),
+    ] {
+        let (layout, image) = render(mode, columns);
+        let bounds = image_bounds(&layout);
+        assert_eq!((bounds.w, bounds.h), (w, h), "Mode {}", mode);
+
+        //The first column has its top dot set, the second column its bottom dot
+        let image = image.output.first().expect("No image generated");
+        let margin = (image.width - layout.output[0].width) / 2;
+        let dark = |x: u32, y: u32| {
+            let i = ((bounds.y + y) * image.width + margin + bounds.x + x) as usize * 3;
+            image.bytes[i] < 128
+        };
+        assert!(dark(0, 0), "Mode {}", mode);
+        assert!(!dark(0, h - 1), "Mode {}", mode);
+        assert!(dark(w - 1, h - 1), "Mode {}", mode);
+        assert!(!dark(w - 1, 0), "Mode {}", mode);
+    }
+}
+
+#[test]
+fn raster_bit_image_scaling() {
+    let size = |mode: &str| -> (u32, u32) {
+        let text = format!(r#"ESC "@" GS "v0" {} 1 0 2 0 0xFF 0xFF"#, mode);
+        let renders = JsonRenderer::render(&parse_str(&text), None);
+        let layout = renders.output.first().expect("No layout generated");
+
+        layout
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                LayoutElement::Image { bounds, .. } => Some((bounds.w, bounds.h)),
+                _ => None,
+            })
+            .expect("No image in layout")
+    };
+
+    //Both the binary and the ascii digit forms of m select the same scaling
+    for (normal, double_width, double_height, quadruple) in
+        [("0", "1", "2", "3"), ("48", "49", "50", "51")]
+    {
+        assert_eq!(size(normal), (8, 2));
+        assert_eq!(size(double_width), (16, 2));
+        assert_eq!(size(double_height), (8, 4));
+        assert_eq!(size(quadruple), (16, 4));
+    }
+}
+
+#[test]
+fn image_density() {
+    let size = |text: &str, profile: &PrinterProfile| -> (u32, u32) {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer =
+            Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), profile);
+        let renders = renderer.render(&parse_str(text));
+        let layout = renders.output.first().expect("No layout generated");
+
+        layout
+            .elements
+            .iter()
+            .find_map(|e| match e {
+                LayoutElement::Image { bounds, .. } => Some((bounds.w, bounds.h)),
+                _ => None,
+            })
+            .expect("No image in layout")
+    };
+    let raster = |mode: u8| format!(r#"ESC "@" GS "v0" {} 1 0 2 0 0xFF 0xFF"#, mode);
+    let bit_image = |mode: u8| format!(r#"ESC "@" ESC "*" {} 2 0 0xFF 0xFF LF"#, mode);
+
+    //A 360 dpi head that prints images at 180 dpi
+    let profile = PrinterProfile {
+        dots_per_inch: 360,
+        image_density: Some((180, 180)),
+        ..PrinterProfile::default()
+    };
+    assert_eq!(size(&raster(0), &profile), (16, 4));
+    assert_eq!(size(&raster(49), &profile), (32, 4));
+    assert_eq!(size(&raster(3), &profile), (32, 8));
+    //Single density 8 dot images are 90 x 60 dpi
+    assert_eq!(size(&bit_image(0), &profile), (8, 48));
+    assert_eq!(size(&bit_image(1), &profile), (4, 48));
+
+    //Image dots that aren't square keep their aspect on paper
+    let profile = PrinterProfile {
+        dots_per_inch: 360,
+        image_density: Some((180, 120)),
+        ..PrinterProfile::default()
+    };
+    assert_eq!(size(&raster(0), &profile), (16, 6));
+    assert_eq!(size(&raster(51), &profile), (32, 12));
+
+    //Without an image density every image dot is a printer dot
+    assert_eq!(size(&raster(3), &PrinterProfile::default()), (16, 4));
+}
+
+#[test]
+fn image_data_mismatch() {
+    let render = |text: &str| {
+        let renders = JsonRenderer::render(&parse_str(text), None);
+        let layout = renders.output.first().expect("No layout generated");
+        let size = layout.elements.iter().find_map(|e| match e {
+            LayoutElement::Image { bounds, .. } => Some((bounds.w, bounds.h)),
+            _ => None,
+        });
+        (size, format!("{:?}", renders.errors))
+    };
+
+    //A raster image that is declared 4 rows high but only has 2 rows keeps the 2 rows
+    let (size, errors) = render(r#"ESC "@" GS "v0" 0 1 0 4 0 0xFF 0xFF"#);
+    assert_eq!(size, Some((8, 2)));
+    assert!(errors.contains("ImageDataError"), "{}", errors);
+    assert!(errors.contains("expected 4 bytes got 2"), "{}", errors);
+
+    //A column image keeps the complete columns
+    let (size, errors) = render(r#"ESC "@" ESC "*" 33 2 0 0xFF 0xFF 0xFF 0xFF"#);
+    assert_eq!(size, Some((1, 24)));
+    assert!(errors.contains("expected 6 bytes got 4"), "{}", errors);
+
+    //Without a single complete row there is nothing to render
+    let (size, errors) = render(r#"ESC "@" ESC "*" 33 2 0 0xFF"#);
+    assert_eq!(size, None);
+    assert!(errors.contains("GraphicsError"), "{}", errors);
+}
+
+/// The bytes of a file in sample_files/in, .thermal files are parsed
+/// from their text form
+fn sample_bytes(file: &str) -> Vec<u8> {
     let sample_file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("..")
         .join("sample_files")
         .join("in")
+        .join(file);
+
+    if file.ends_with(".thermal") {
+        parse_str(&std::fs::read_to_string(sample_file).unwrap())
+    } else {
+        std::fs::read(sample_file).unwrap()
+    }
+}
+
+fn test_sample(name: &str, ext: &str) {
+    let img_out = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("sample_files")
+        .join("out")
+        .join("img")
+        .join(format!("{}.{}", name, ext));
+
+    let html_out = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("sample_files")
+        .join("out")
+        .join("html")
         .join(format!("{}.{}", name, ext));
 
-    let img_out = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("..")
-        .join("sample_files")
-        .join("out")
-        .join("img")
-        .join(format!("{}.{}", name, ext));
+    let bytes = sample_bytes(&format!("{}.{}", name, ext));
+
+    let debug_profile = DebugProfile {
+        text: false,     //Should debug lines be rendered on text?
+        image: false,    //Should debug borders be rendered around images?
+        page: false,     //Should debug borders be rendered around page images?
+        info: true,      //Should render info be output to the console?
+        image_dir: None, //Where should debug images be written?
+    };
+
+    render_image(
+        &bytes,
+        format!("{}.png", img_out.to_str().unwrap().to_string()),
+        name.to_string(),
+        debug_profile.clone(),
+    );
+    render_html(
+        &bytes,
+        format!("{}.html", html_out.to_str().unwrap().to_string()),
+        name.to_string(),
+        debug_profile,
+    );
+}
+
+fn render_html(bytes: &[u8], out_path: String, name: String, debug_profile: DebugProfile) {
+    let renders = HtmlRenderer::render(bytes, Some(debug_profile));
+
+    if let Some(render) = renders.output.first() {
+        let path = Path::new(&out_path);
+        let mut file = File::create(path).unwrap();
+        file.write_all(render.content.as_bytes())
+            .expect("Can't write output html");
+    } else {
+        assert!(false, "No image generated from renderer.");
+    }
+
+    let errors = renders.errors;
+
+    if errors.len() > 0 {
+        println!("Errors found for test file {}:", name);
+        for error in errors {
+            println!("{:?}", error);
+        }
+        assert!(false, "There were errors when rendering html.");
+    }
+}
+
+fn render_image(bytes: &[u8], out_path: String, name: String, debug_profile: DebugProfile) {
+    let renders = ImageRenderer::render(bytes, Some(debug_profile));
+
+    if let Some(render) = renders.output.first() {
+        save_image(&render.bytes, render.width, render.height, out_path);
+    } else {
+        assert!(false, "No image generated from renderer.");
+    }
+
+    let errors = renders.errors;
+
+    if errors.len() > 0 {
+        println!("Errors found for test file {}:", name);
+        for error in errors {
+            println!("{:?}", error);
+        }
+        assert!(false, "There were errors when rendering an image.");
+    }
+}
+
+#[test]
+fn barcode_renderer_modules() {
+    let code = Code2D {
+        symbology: "QR".to_string(),
+        payload: "".to_string(),
+        points: vec![1, 0, 1, 1],
+        width: 2,
+        point_width: 3,
+        point_height: 3,
+    };
+
+    let matrix = code.module_matrix();
+    assert_eq!((matrix.width, matrix.height), (2, 2));
+    assert!(matrix.is_dark(0, 0) && !matrix.is_dark(1, 0) && matrix.is_dark(1, 1));
+    assert_eq!(code.size(), (6, 6));
+
+    //Modules are offset by the origin, columns past the max width are dropped
+    assert_eq!(code.rectangles(10, 20, 100).len(), 3);
+    assert_eq!(code.rectangles(10, 20, 5).len(), 2);
+
+    //Runs of dark modules in a row are one rectangle
+    let mut runs = vec![];
+    code.runs_into(10, 20, 100, &mut runs);
+    let runs: Vec<(u32, u32, u32, u32)> = runs
+        .iter()
+        .map(|VectorGraphic::Rectangle(r)| (r.x, r.y, r.w, r.h))
+        .collect();
+    assert_eq!(runs, vec![(10, 20, 3, 3), (10, 23, 6, 3)]);
+
+    //The renderer draws the same rectangles
+    let bytes = parse_str(r#"GS "(k" 3 0 49 67 4 GS "(k" 5 0 49 80 48 "AB" GS "(k" 3 0 49 81 48"#);
+    let layout = JsonRenderer::render(&bytes, None);
+    let bounds = layout.output[0]
+        .elements
+        .iter()
+        .find_map(|e| match e {
+            LayoutElement::Code { bounds, .. } => Some(bounds.clone()),
+            _ => None,
+        })
+        .expect("No code");
+    assert_eq!((bounds.w, bounds.h), (21 * 4, 21 * 4));
+}
+
+#[test]
+fn profile_unsupported_commands() {
+    let mut bytes =
+        parse_str(r#"GS "(k" 3 0 53 67 4 GS "(k" 5 0 53 80 48 "AB" GS "(k" 3 0 53 81 48"#);
+    bytes.extend(parse_str(
+        r#"GS "(k" 5 0 49 80 48 "AB" GS "(k" 3 0 49 81 48 LF"#,
+    ));
+    let codes = |profile: &PrinterProfile| {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer =
+            Renderer::new_with_profile(&mut child_renderer, DebugProfile::default(), profile);
+        let renders = renderer.render(&bytes);
+        let codes = renders.output[0]
+            .elements
+            .iter()
+            .filter(|e| matches!(e, LayoutElement::Code { .. }))
+            .count();
+        (codes, format!("{:?}", renders.errors))
+    };
+
+    //Aztec codes (GS ( k 53) are not available on this printer, QR codes are
+    let profile = PrinterProfile {
+        name: "TM-T20".to_string(),
+        unsupported_commands: vec![vec![0x1D, b'(', b'k', 53]],
+        ..PrinterProfile::default()
+    };
+
+    let (count, errors) = codes(&profile);
+    assert!(errors.contains("UnsupportedCommand"), "{}", errors);
+    assert!(errors.contains("not supported by TM-T20"), "{}", errors);
+    assert_eq!(errors.matches("UnsupportedCommand").count(), 3);
+    assert_eq!(count, 1);
+
+    let (_, errors) = codes(&PrinterProfile::default());
+    assert!(!errors.contains("UnsupportedCommand"), "{}", errors);
+}
+
+#[test]
+fn golden_snapshot() {
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join("snapshot.png");
+
+    let bytes = parse_str(r#"ESC "@" ESC "a" 1 "SNAPSHOT" LF GS "H" 2 GS "k" 4 "*123*" 0 LF"#);
+    assert_snapshot(&bytes, &golden);
+
+    //A different job fails and leaves a diff image
+    let changed = parse_str(r#"ESC "@" ESC "a" 1 "SNAPSH0T" LF GS "H" 2 GS "k" 4 "*124*" 0 LF"#);
+    let snapshot = Snapshot {
+        update: false,
+        ..Snapshot::default()
+    };
+
+    match snapshot.check(&changed, &golden) {
+        Err(SnapshotError::Different {
+            ratio,
+            expected,
+            actual,
+            diff,
+        }) => {
+            assert!(ratio > 0.0 && ratio < 0.1);
+            assert_eq!(expected, actual);
+            assert!(diff.exists());
+            std::fs::remove_file(diff).unwrap();
+        }
+        other => panic!("Expected a difference, got {:?}", other),
+    }
+
+    //Within the threshold the same job passes
+    let lenient = Snapshot {
+        threshold: 1.0,
+        update: false,
+        ..Snapshot::default()
+    };
+    assert!(lenient.check(&changed, &golden).is_ok());
+}
+
+#[test]
+fn visual_diff() {
+    let a = parse_str(r#"ESC "@" ESC "a" 1 "TOTAL 10.00" LF "THANK YOU" LF LF "FOOTER" LF"#);
+    let b = parse_str(r#"ESC "@" ESC "a" 1 "TOTAL 12.00" LF "THANK YOU" LF LF "F00TER" LF"#);
+
+    let (diff, composite) = diff_renders(&a, &b, 16).expect("Nothing rendered");
+    assert!(!diff.is_same());
+    assert!(diff.ratio() < 0.01);
+
+    //One region per changed line, the unchanged line in between splits them
+    assert_eq!(diff.regions.len(), 2);
+    assert!(diff.regions[0].y + diff.regions[0].h < diff.regions[1].y);
+
+    //Both renders and the diff are side by side
+    assert_eq!(composite.width, diff.image.width * 3 + 16);
+    assert_eq!(composite.height, diff.image.height);
+
+    let (same, _) = diff_renders(&a, &a, 0).expect("Nothing rendered");
+    assert!(same.is_same() && same.regions.is_empty());
+}
+
+#[test]
+fn receipt_template() {
+    let template = r#"{
+        "columns": 48,
+        "lines": [
+            {"text": "CAFÉ ROMA", "align": "center", "bold": true, "width": 2, "height": 2},
+            {"text": "Coffee", "right": "3.50"},
+            {"barcode": "CODE128", "data": "A-1234", "hri": true},
+            {"qr": "https://example.com", "size": 4},
+            {"image": [".######.", ".#....#.", ".######."]},
+            {"feed": 2},
+            {"cut": "partial"}
+        ]
+    }"#;
+    let bytes = encode_template(template).unwrap();
+
+    let renders = JsonRenderer::render(&bytes, None);
+    assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+    let elements: Vec<String> = renders.output[0]
+        .elements
+        .iter()
+        .map(|e| match e {
+            LayoutElement::Text { text, style, .. } => {
+                format!("{} {} {}", text, style.bold, style.width_mult)
+            }
+            LayoutElement::Code {
+                symbology, payload, ..
+            } => format!("{} {}", symbology, payload),
+            LayoutElement::Image { bounds, .. } => format!("image {}x{}", bounds.w, bounds.h),
+            LayoutElement::Cut { partial, .. } => format!("cut {}", partial),
+            _ => "other".to_string(),
+        })
+        .collect();
+    assert_eq!(
+        elements,
+        vec![
+            "CAFÉ ROMA true 2",
+            "Coffee                                      3.50 false 1",
+            "CODE128 A-1234",
+            "A-1234 false 1",
+            "QR Code https://example.com",
+            "image 8x3",
+            "cut true",
+        ]
+    );
+
+    let error = encode_template(r#"[{"text": "A"}, {"barcode": "PDF"}]"#).unwrap_err();
+    assert_eq!(error, "Line 2: Unknown barcode PDF");
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn image_crate_pictures() {
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use thermal_parser::utils::dither::{raster_bit_image_from, Dithering};
+
+    //A black bar with a transparent half, transparent is paper
+    let picture = RgbaImage::from_fn(32, 8, |x, _| match x < 16 {
+        true => Rgba([0, 0, 0, 255]),
+        false => Rgba([0, 0, 0, 0]),
+    });
+    let mut bytes = parse_str(r#"ESC "@""#);
+    bytes.extend(raster_bit_image_from(
+        &DynamicImage::ImageRgba8(picture),
+        Dithering::default(),
+    ));
+
+    let renders = ImageRenderer::render(&bytes, None);
+    let images = renders.to_rgba_images();
+    assert_eq!(images.len(), 1);
+    assert_eq!(
+        images[0].dimensions(),
+        (renders.output[0].width, renders.output[0].height)
+    );
+
+    let ink: Vec<u32> = images[0]
+        .enumerate_pixels()
+        .filter(|(_, _, p)| p.0 == [0, 0, 0, 255])
+        .map(|(x, _, _)| x)
+        .collect();
+    assert_eq!(ink.len(), 16 * 8);
+    assert_eq!(ink.iter().max().unwrap() - ink.iter().min().unwrap(), 15);
+}
+
+#[test]
+fn semantic_diff() {
+    let a = parse_str(
+        r#"ESC "@" ESC "a" 1 ESC "E" 1 "STORE" LF ESC "E" 0 ESC "a" 0 "TOTAL 10.00" LF
+        GS "k" 4 "*123*" 0 "THANK YOU" LF GS "V" 1"#,
+    );
+
+    //Styles set in another order, extra spacing and redundant commands print the same
+    let reordered = parse_str(
+        r#"ESC "@" ESC "E" 1 ESC "a" 1 "STORE" LF ESC "a" 0 ESC "E" 0 ESC "E" 0 ESC "3" 40
+        "TOTAL 10.00" LF GS "k" 4 "*123*" 0 "THANK YOU" LF GS "V" 1"#,
+    );
+    assert!(diff_jobs(&a, &reordered).is_same());
+
+    let b = parse_str(
+        r#"ESC "@" ESC "a" 1 "STORE" LF ESC "a" 0 "TOTAL 12.00" LF
+        GS "k" 4 "*124*" 0 "THANK YOU" LF "COME AGAIN" LF GS "V" 0"#,
+    );
+    let diff = diff_jobs(&a, &b);
+    let changes: Vec<String> = diff.changes.iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        changes,
+        [
+            r#"~ text "STORE": bold"#,
+            r#"~ text "TOTAL 10.00": text"#,
+            r#"~ CODE39 "*123*": payload"#,
+            r#"+ text "COME AGAIN""#,
+            "~ partial cut: partial",
+        ]
+    );
+    assert!(matches!(
+        &diff.changes[1],
+        Change::Changed { after: Element::Text { text, .. }, .. } if text == "TOTAL 12.00"
+    ));
+}
+
+#[test]
+fn arbitrary_bytes_never_panic() {
+    let render_all = |bytes: &[u8]| {
+        ImageRenderer::render(bytes, None);
+        HtmlRenderer::render(bytes, None);
+        JsonRenderer::render(bytes, None);
+    };
+
+    //Inputs that used to panic
+    render_all(&[0x1B, 0x65, 0x06, 0x31, 0x31]); //Reverse feed past the top
+    render_all(&[0x1B, 0x2A, 0x00, 0x00, 0x00, 0x0A]); //Image without width
+    render_all(&[
+        0x1B, 0x40, 0x1B, 0x4C, 0x1D, 0x50, 0xCB, 0xCB, 0x1B, 0x57, 0x76, 0x32, 0x00, 0x00, 0x00,
+        0xF4, 0x01, 0xEE,
+    ]); //Page mode print area far larger than the paper
+    render_all(b"\x1Dk\x43\x0D59012341234\xC3\xA9"); //EAN13 cut inside a character
+    render_all(b"\x1Dk\x43\x0D59012341234\xFF");
+
+    let mut json_renderer: Box<dyn OutputRenderer<ReceiptLayout>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+    renderer.render_commands(parse_tspl(
+        b"SIZE 2,1\r\nBARCODE 10,10,\"EAN13\",50,1,0,2,2,\"59012341234\xFF\"\r\nPRINT 1\r\n",
+    ));
+
+    //Pseudo random bytes that are mostly command bytes and parameters,
+    //the fuzz targets in /fuzz cover far more than this
+    let mut seed = 0x2545F4914F6CDD1Du64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    for _ in 0..100 {
+        let len = next() % 48 + 1;
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| match next() % 4 {
+                0 => [0x1B, 0x1D, 0x1C, 0x10, 0x0A][next() as usize % 5],
+                1 => (next() % 8) as u8,
+                2 => b"(kLv*!@aEt$8PJdVX0123"[next() as usize % 21],
+                _ => next() as u8,
+            })
+            .collect();
+        JsonRenderer::render(&bytes, None);
+    }
+
+    //Jobs cut off at any byte
+    let receipt = sample_bytes("test_receipt_4.bin");
+    for len in 0..receipt.len() {
+        JsonRenderer::render(&receipt[..len], None);
+    }
+}
+
+#[test]
+fn render_stream_chunks() {
+    //Returns one chunk per read and is pending before every read
+    struct SlowReader {
+        chunks: Vec<Vec<u8>>,
+        ready: bool,
+    }
+
+    impl AsyncRead for SlowReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+
+            if self.chunks.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            let chunk = self.chunks.remove(0);
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Poll::Ready(Ok(chunk.len()))
+        }
+    }
+
+    let bytes = parse_str(r#"ESC "@" "FIRST" LF FF ESC "@" "SECOND JOB" LF ESC "p" 0 25 250 FF"#);
+    let expected = JsonRenderer::render(&bytes, None);
+
+    let mut json_renderer: Box<dyn OutputRenderer<ReceiptLayout>> = Box::new(JsonRenderer::new());
+    let renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+    let reader = SlowReader {
+        chunks: bytes.chunks(5).map(|c| c.to_vec()).collect(),
+        ready: false,
+    };
+    let mut stream = render_stream(reader, renderer);
+
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    let mut outputs = vec![];
+    let mut events = vec![];
+    let mut pending = 0;
+    loop {
+        match stream.poll_next(&mut cx) {
+            Poll::Pending => pending += 1,
+            Poll::Ready(Some(StreamEvent::Output(layout))) => outputs.push(layout.to_json()),
+            Poll::Ready(Some(StreamEvent::Event(event))) => events.push(event),
+            Poll::Ready(Some(_)) => {}
+            Poll::Ready(None) => break,
+        }
+    }
+
+    assert!(pending > 1);
+    assert!(outputs.len() > 1);
+    let expected_outputs: Vec<String> = expected.output.iter().map(|o| o.to_json()).collect();
+    assert_eq!(outputs, expected_outputs);
+
+    //Events point at the output of the whole stream, not of the chunk
+    assert_eq!(events, expected.events);
+    assert!(events[0].output > 0);
+}
+
+#[cfg(feature = "futures-io")]
+#[test]
+fn render_stream_futures_reader() {
+    //Only implements the futures trait
+    struct FuturesReader(std::io::Cursor<Vec<u8>>);
+
+    impl futures_io::AsyncRead for FuturesReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            Poll::Ready(std::io::Read::read(&mut self.0, buf))
+        }
+    }
+
+    let bytes = parse_str(r#"ESC "@" "FUTURES" LF FF"#);
+    let expected = JsonRenderer::render(&bytes, None);
+
+    let mut json_renderer: Box<dyn OutputRenderer<ReceiptLayout>> = Box::new(JsonRenderer::new());
+    let renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+    let mut stream = render_stream(FuturesReader(std::io::Cursor::new(bytes)), renderer);
+
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    let mut outputs = vec![];
+    while let Poll::Ready(Some(event)) = stream.poll_next(&mut cx) {
+        if let StreamEvent::Output(layout) = event {
+            outputs.push(layout.to_json());
+        }
+    }
+
+    let expected_outputs: Vec<String> = expected.output.iter().map(|o| o.to_json()).collect();
+    assert_eq!(outputs, expected_outputs);
+}
+
+#[test]
+fn event_renderer_channel() {
+    let bytes = sample_bytes("receipt_with_barcode.thermal");
+    let expected = JsonRenderer::render(&bytes, None);
+    let layout = expected.output.first().expect("No layout generated");
+
+    //A bound of 1 makes the renderer wait for the receiver
+    let (sender, receiver) = sync_channel(1);
+    let render_bytes = bytes.clone();
+    let handle = std::thread::spawn(move || EventRenderer::render(&render_bytes, sender, None));
+    let events: Vec<LayoutEvent> = receiver.iter().collect();
+    let renders = handle.join().unwrap();
+
+    assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+    assert_eq!(renders.output.len(), expected.output.len());
+    assert_eq!(renders.output[0].events, events.len());
+    assert_eq!(events.first(), Some(&LayoutEvent::RenderStarted));
+    assert_eq!(
+        events.last(),
+        Some(&LayoutEvent::RenderEnded {
+            width: layout.width,
+            height: layout.height
+        })
+    );
+
+    let elements: Vec<LayoutElement> = events
+        .into_iter()
+        .filter_map(|event| match event {
+            LayoutEvent::Element(element) => Some(element),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(elements, layout.elements);
+
+    //A dropped receiver doesn't stop the render
+    let (sender, receiver) = EventRenderer::channel();
+    drop(receiver);
+    let renders = EventRenderer::render(&bytes, sender, None);
+    assert_eq!(renders.output.len(), 1);
+    assert_eq!(renders.output[0].events, 0);
+    assert!(!renders.errors.is_empty());
+}
+
+#[test]
+fn minimal_output_renderer() {
+    //Only the events and the output, every other method has a default
+    #[derive(Default)]
+    struct TextOnly {
+        cuts: usize,
+        lines: Vec<String>,
+    }
+
+    impl OutputRenderer<(usize, Vec<String>)> for TextOnly {
+        fn render_event(&mut self, event: RenderEvent) {
+            match event {
+                RenderEvent::Device(DeviceCommand::FullCut) => self.cuts += 1,
+                RenderEvent::Text { spans, .. } => self
+                    .lines
+                    .push(spans.iter().map(|s| s.text.as_str()).collect()),
+                _ => {}
+            }
+        }
+
+        fn end_render(&mut self, _context: &mut Context) -> (usize, Vec<String>) {
+            (
+                std::mem::take(&mut self.cuts),
+                std::mem::take(&mut self.lines),
+            )
+        }
+    }
+
+    let bytes = parse_str(r#"ESC "@" "HELLO " ESC "E" 1 "BOLD" LF "WORLD" LF GS "V" 0"#);
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(TextOnly::default());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    let renders = renderer.render(&bytes);
+
+    assert_eq!(
+        renders.output,
+        vec![(1, vec!["HELLO BOLD".to_string(), "WORLD".to_string()])]
+    );
+}
+
+#[test]
+fn convenience_functions() {
+    let bytes = parse_str(r#"ESC "@" "HELLO" LF GS "V" 0"#);
+    let profile = PrinterProfile::default();
+
+    let png = render_png(&bytes, &profile).expect("No png generated");
+    let reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let renders = ImageRenderer::render(&bytes, None);
+    let image = renders.output.first().expect("No image generated");
+    assert_eq!(reader.info().width, image.width);
+    assert_eq!(reader.info().height, image.height);
+
+    let html =
+        thermal_renderer::render_html(&bytes, &HtmlOptions::default()).expect("No html generated");
+    assert!(html.contains("HELLO"));
+
+    let options = HtmlOptions {
+        layout: HtmlLayout::Semantic,
+        self_contained: true,
+        ..HtmlOptions::default()
+    };
+    let html = thermal_renderer::render_html(&bytes, &options).expect("No html generated");
+    assert!(html.contains("@font-face"));
+    assert!(html.contains("<div class='line left'>HELLO</div>"));
+}
+
+#[test]
+fn render_determinism() {
+    //Hashes of renders that must not change between runs, recorded on
+    //x86_64 Linux. THERMAL_UPDATE_SNAPSHOTS=1 writes new hashes after a
+    //deliberate change
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join("determinism.txt");
+
+    let fnv = |bytes: &[u8]| -> u64 {
+        bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    };
+
+    let mut hashes = String::new();
+
+    for name in [
+        "typography",
+        "gs_images_raster",
+        "receipt_with_barcode",
+        "page_mode",
+    ] {
+        let bytes = sample_bytes(&format!("{}.thermal", name));
+
+        let images: Vec<u64> = (0..2)
+            .map(|_| {
+                let renders = ImageRenderer::render(&bytes, None);
+                let image = renders.output.first().expect("No image generated");
+                fnv(&image.bytes) ^ ((image.width as u64) << 32 | image.height as u64)
+            })
+            .collect();
+        let htmls: Vec<u64> = (0..2)
+            .map(|_| {
+                let renders = HtmlRenderer::render(&bytes, None);
+                fnv(renders
+                    .output
+                    .first()
+                    .expect("No html generated")
+                    .content
+                    .as_bytes())
+            })
+            .collect();
+
+        //Rendering twice in the same process gives the same output
+        assert_eq!(
+            images[0], images[1],
+            "{} image differs between renders",
+            name
+        );
+        assert_eq!(htmls[0], htmls[1], "{} html differs between renders", name);
+
+        hashes.push_str(&format!("{} {:016x} {:016x}\n", name, images[0], htmls[0]));
+    }
+
+    if std::env::var("THERMAL_UPDATE_SNAPSHOTS").is_ok() {
+        std::fs::write(&golden, &hashes).unwrap();
+    }
+
+    let expected = std::fs::read_to_string(&golden).unwrap_or_default();
+    assert_eq!(expected, hashes, "Renders differ from {}", golden.display());
+}
+
+fn save_image(bytes: &Vec<u8>, width: u32, height: u32, out_path: String) {
+    if bytes.len() == 0 || width == 0 || height == 0 {
+        assert!(false, "No image generated from render.");
+        return;
+    }
+
+    let path = Path::new(&out_path);
+    let file = File::create(path).unwrap();
+    let ref mut writer = BufWriter::new(file);
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(BitDepth::Eight);
+
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(bytes).unwrap(); // Save
+}
 
-    let html_out = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("..")
-        .join("sample_files")
-        .join("out")
-        .join("html")
-        .join(format!("{}.{}", name, ext));
+#[test]
+fn barcode_hri_both() {
+    //Character styles don't apply to the hri, it uses the barcode font
+    let bytes = parse_str(
+        r#"ESC "@" ESC "!" 56 GS "f" 1 GS "H" 3 GS "h" 50 GS "w" 2 GS "k" 4 "*123*" 0 "END" LF"#,
+    );
 
-    let bytes = if ext == "thermal" {
-        let text = std::fs::read_to_string(sample_file.to_str().unwrap()).unwrap();
-        parse_str(&text)
-    } else {
-        std::fs::read(sample_file.to_str().unwrap()).unwrap()
+    let layout = JsonRenderer::render(&bytes, None);
+    let elements = &layout.output[0].elements;
+
+    let hri: Vec<(&Bounds, &TextStyle)> = elements
+        .iter()
+        .filter_map(|e| match e {
+            LayoutElement::Text {
+                bounds,
+                text,
+                style,
+                ..
+            } if text == "*123*" => Some((bounds, style)),
+            _ => None,
+        })
+        .collect();
+    let (code, code_hri) = elements
+        .iter()
+        .find_map(|e| match e {
+            LayoutElement::Code { bounds, hri, .. } => Some((bounds, hri)),
+            _ => None,
+        })
+        .expect("No barcode");
+
+    assert_eq!(code_hri.as_deref(), Some("*123*"));
+    assert_eq!(hri.len(), 2);
+
+    for (bounds, style) in &hri {
+        assert_eq!(style.font, "B");
+        assert_eq!((style.width_mult, style.height_mult), (1, 1));
+        assert!(!style.bold && !style.underline);
+        assert_eq!(bounds.h, 17);
+    }
+
+    //Text above, 8 dots, bars, 8 dots, text below
+    assert_eq!(code.y, hri[0].0.y + 17 + 8);
+    assert_eq!(hri[1].0.y, code.y + 50 + 8);
+}
+
+#[test]
+fn buffer_pool() {
+    let mut pool: BufferPool<u32> = BufferPool::new();
+    assert!(pool.take().is_empty());
+
+    //Buffers come back empty, the largest first
+    let mut small = pool.take();
+    small.extend([1, 2]);
+    let mut large = Vec::with_capacity(64);
+    large.extend([1, 2, 3]);
+    pool.give(small);
+    pool.give(large);
+
+    let taken = pool.take();
+    assert!(taken.is_empty());
+    assert!(taken.capacity() >= 64);
+    assert!(pool.retained_bytes() >= 2 * size_of::<u32>());
+
+    pool.clear();
+    assert_eq!(pool.retained_bytes(), 0);
+
+    //A reused buffer gets the same rectangles as a new one
+    let code = Code2D {
+        symbology: "QR Code".to_string(),
+        payload: "HI".to_string(),
+        points: vec![1, 0, 1, 1, 0, 1, 0, 1, 1],
+        width: 3,
+        point_width: 4,
+        point_height: 4,
     };
+    let mut pool = BufferPool::new();
+    for _ in 0..2 {
+        let mut graphics = pool.take();
+        code.rectangles_into(10, 20, 100, &mut graphics);
+        assert_eq!(
+            format!("{:?}", graphics),
+            format!("{:?}", code.rectangles(10, 20, 100))
+        );
+        pool.give(graphics);
+    }
+}
 
-    let debug_profile = DebugProfile {
-        text: false,  //Should debug lines be rendered on text?
-        image: false, //Should debug borders be rendered around images?
-        page: false,  //Should debug borders be rendered around page images?
-        info: true,   //Should render info be output to the console?
+#[test]
+fn buffer_graphics() {
+    test_sample("buffer_graphics", "thermal");
+
+    #[derive(Default)]
+    struct Images(Vec<Image>);
+
+    impl OutputRenderer<Vec<Image>> for Images {
+        fn render_event(&mut self, event: RenderEvent) {
+            if let RenderEvent::Image(image) = event {
+                self.0.push(image.clone());
+            }
+        }
+
+        fn end_render(&mut self, _context: &mut Context) -> Vec<Image> {
+            std::mem::take(&mut self.0)
+        }
+    }
+
+    let bytes = sample_bytes("buffer_graphics.thermal");
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(Images::default());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    let renders = renderer.render(&bytes);
+    assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+
+    //The empty buffer and the buffer cleared by ESC @ print nothing
+    let images = &renders.output[0];
+    assert_eq!(images.len(), 2);
+
+    let colors = Context::new().graphics.render_colors;
+    let planes = &images[0];
+    assert_eq!((planes.w, planes.h), (32, 8));
+    let rgba = |c: &RGBA| (c.r, c.g, c.b, c.a);
+    let pixel = |x: u32, y: u32| rgba(&planes.pixels[(y * planes.w + x) as usize]);
+
+    //Color 2 is on top of color 1, the first color 2 plane was replaced
+    assert_eq!(pixel(0, 0), rgba(&colors.color_2));
+    assert_eq!(pixel(16, 0), rgba(&colors.color_1));
+    assert_eq!(pixel(20, 0).3, 0);
+    assert_eq!(pixel(4, 1), rgba(&colors.color_2));
+
+    //Column format, stretched twice as wide
+    assert_eq!((images[1].w, images[1].h), (32, 16));
+}
+
+#[test]
+fn extract_images() {
+    //Inline raster, a download graphic printed by its key codes and the print buffer
+    let bytes = parse_str(
+        r#"
+        ESC "@" "TOTAL" LF
+        GS "v0" 0 1 0 2 0 0xFF 0x81
+        GS "(L" 13 0 48 83 48 "A1" 1 8 0 2 0 49 0xF0 0x0F
+        GS "(L" 6 0 48 85 "A1" 1 1
+        GS "(L" 11 0 48 112 48 1 1 49 8 0 1 0 0xAA
+        GS "(L" 2 0 48 50
+        "#,
+    );
+
+    let renders = ImageExtractor::extract(&bytes, None);
+    assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+    let images = &renders.output[0];
+    assert_eq!(images.len(), 3);
+
+    let ram = ImageRef {
+        kc1: b'A',
+        kc2: b'1',
+        storage: ImageRefStorage::Ram,
     };
+    let origins: Vec<&ImageOrigin> = images.iter().map(|i| &i.origin).collect();
+    assert_eq!(
+        origins,
+        vec![
+            &ImageOrigin::Inline,
+            &ImageOrigin::Stored(ram),
+            &ImageOrigin::Buffer
+        ]
+    );
 
-    render_image(
-        &bytes,
-        format!("{}.png", img_out.to_str().unwrap().to_string()),
-        name.to_string(),
-        debug_profile,
+    //Images are below each other where the image renderer prints them
+    assert!(images[0].y > 0);
+    assert_eq!(images[1].y, images[0].y + images[0].h);
+    assert_eq!((images[1].w, images[1].h), (8, 2));
+    assert!(images.iter().all(|i| !i.page_mode));
+
+    //The offset is the command that printed the image
+    assert_eq!(bytes[images[0].offset..][..3], [0x1D, b'v', b'0']);
+    assert_eq!(bytes[images[1].offset..][..5], [0x1D, b'(', b'L', 6, 0]);
+
+    //The png has the size and dots of the image
+    let decoder = png::Decoder::new(images[1].png.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height), (8, 2));
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    let alpha: Vec<u8> = pixels.chunks(4).map(|p| p[3]).collect();
+    assert_eq!(alpha[..8], [255, 255, 255, 255, 0, 0, 0, 0]);
+}
+
+#[test]
+fn signature_detection() {
+    //256 x 48 dots, a wavy line of ink or solid ink
+    let raster = |wavy: bool| -> Vec<u8> {
+        let mut bytes = vec![0x1D, b'v', b'0', 0, 32, 0, 48, 0];
+        let mut rows = vec![0u8; 32 * 48];
+        for x in 0..256usize {
+            let y = (24.0 + (x as f32 / 20.0).sin() * 16.0) as usize;
+            for y in [y, y + 1] {
+                rows[y * 32 + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        if !wavy {
+            rows.fill(0xFF);
+        }
+        bytes.extend(rows);
+        bytes
+    };
+    let text = |text: &str| text.as_bytes().to_vec();
+    //Cut and end the job with a form feed
+    let cut = vec![0x1D, b'V', 65, 0, 0x0C];
+
+    //Signature at the end of the first receipt, a solid bar at the end of
+    //the second and a signature shaped image at the top of a long third
+    let mut bytes = vec![0x1B, b'@'];
+    bytes.extend(text("SALE\nTOTAL 12.00\n"));
+    bytes.extend(raster(true));
+    bytes.extend(text("X__________\n"));
+    bytes.extend(&cut);
+    bytes.extend(text("SALE\n"));
+    bytes.extend(raster(false));
+    bytes.extend(&cut);
+    bytes.extend(raster(true));
+    bytes.extend(text(&"ITEM 1.00\n".repeat(40)));
+
+    let renders = ImageExtractor::extract(&bytes, None);
+    assert_eq!(renders.output.len(), 3);
+    let flags: Vec<Vec<bool>> = renders
+        .output
+        .iter()
+        .map(|images| images.iter().map(|i| i.signature).collect())
+        .collect();
+    assert_eq!(flags, vec![vec![true], vec![false], vec![false]]);
+
+    //The renderer reports the same signatures for every output renderer
+    let renders = ImageRenderer::render(&bytes, None);
+    assert_eq!(renders.signatures.len(), 1);
+    let signature = &renders.signatures[0];
+    assert_eq!(signature.output, 0);
+    assert_eq!(bytes[signature.offset..][..3], [0x1D, b'v', b'0']);
+    assert_eq!((signature.image.w, signature.image.h), (256, 48));
+    assert!(signature.image.y > 0);
+
+    //Streams count the outputs of the whole stream
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
+    let renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    let mut stream = StreamRenderer::new(thermal_parser::new_esc_pos_parser(), renderer);
+    let split = bytes.len() / 2;
+    let mut signatures = stream.push(&bytes[..split]).signatures;
+    signatures.extend(stream.push(&bytes[split..]).signatures);
+    signatures.extend(stream.finish().signatures);
+    assert_eq!(signatures.len(), 1);
+    assert_eq!(signatures[0].output, 0);
+
+    let black = RGBA {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    let image = |w: u32, h: u32, ink: usize| Image {
+        pixels: (0..(w * h) as usize)
+            .map(|i| if i < ink { black } else { RGBA::blank() })
+            .collect(),
+        x: 0,
+        y: 0,
+        w,
+        h,
+        flow: ImageFlow::Block,
+        upside_down: false,
+    };
+    assert!(looks_like_signature(&image(300, 60, 1000)));
+    assert!(!looks_like_signature(&image(300, 60, 0)));
+    assert!(!looks_like_signature(&image(120, 120, 1000)));
+    assert!(!looks_like_signature(&image(576, 4, 1000)));
+}
+
+#[test]
+fn localized_strings() {
+    let strings = Strings::parse(
+        "# Spanish
+        CODE39 = Código 39
+        QR Code = Código QR
+        [?{}] = [¿{}?]",
+    )
+    .unwrap();
+    assert!(Strings::parse("Barcode: Código de barras").is_err());
+    assert_eq!(
+        strings.format("[?{}]", &[&"1B 7E"]),
+        "[¿1B 7E?]".to_string()
     );
-    render_html(
-        &bytes,
-        format!("{}.html", html_out.to_str().unwrap().to_string()),
-        name.to_string(),
-        debug_profile,
+
+    let bytes = parse_str(
+        r#"GS "(k" 5 0 49 80 48 "AB" GS "(k" 3 0 49 81 48 GS "k" 4 "*123*" 0 "BEFORE" ESC 0x7E 1 LF "AFTER" LF"#,
     );
+    let options = HtmlOptions {
+        layout: HtmlLayout::Semantic,
+        strings: strings.clone(),
+        ..HtmlOptions::default()
+    };
+    let html = thermal_renderer::render_html(&bytes, &options).expect("No html generated");
+    assert!(html.contains("aria-label='Código QR: AB'"), "{}", html);
+    assert!(html.contains("aria-label='Código 39: *123*'"));
+    //Data attributes are for scripts and are never translated
+    assert!(html.contains("data-symbology='QR Code'"));
+    assert!(html.contains("data-symbology='CODE39'"));
+
+    //Without a translation the text stays english
+    let html = thermal_renderer::render_html(&bytes, &HtmlOptions::default()).unwrap();
+    assert!(html.contains("aria-label='QR Code: AB'"));
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    renderer.set_unknown_commands(UnknownCommands::Placeholder);
+    renderer.set_strings(strings);
+    let renders = renderer.render(&bytes);
+    assert!(renders.output[0].elements.iter().any(|element| matches!(
+        element,
+        LayoutElement::Text { text, .. } if text.contains("[¿1B 7E 01?]")
+    )));
+
+    let symbologies: Vec<&str> = renders.output[0]
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            LayoutElement::Code { symbology, .. } => Some(symbology.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(symbologies, vec!["QR Code", "CODE39"]);
+    assert!(renders.output[0]
+        .to_json()
+        .contains("\"symbology\":\"QR Code\""));
 }
 
-fn render_html(bytes: &Vec<u8>, out_path: String, name: String, debug_profile: DebugProfile) {
-    let renders = HtmlRenderer::render(bytes, Some(debug_profile));
+#[test]
+fn watermark_stamp() {
+    let bytes = parse_str(r#"ESC "@" "RECEIPT" LF ESC "d" 20 "TOTAL 4.00" LF ESC "d" 20"#);
 
-    if let Some(render) = renders.output.first() {
-        let path = Path::new(&out_path);
-        let mut file = File::create(path).unwrap();
-        file.write_all(render.content.as_bytes())
-            .expect("Can't write output html");
-    } else {
-        assert!(false, "No image generated from renderer.");
+    let render = |watermark: Option<Watermark>| {
+        let mut image_renderer = ImageRenderer::new();
+        image_renderer.watermark = watermark;
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(image_renderer);
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.render(&bytes).output.remove(0)
+    };
+
+    let original = render(None);
+    let copy = render(Some(Watermark::text("COPY")));
+    assert_eq!((copy.width, copy.height), (original.width, original.height));
+
+    //Stamped pixels are tinted red, the corners are not on the diagonal
+    let changed: Vec<usize> = (0..original.bytes.len() / 3)
+        .filter(|i| original.bytes[i * 3..i * 3 + 3] != copy.bytes[i * 3..i * 3 + 3])
+        .collect();
+    assert!(changed.len() > 1000, "{} pixels changed", changed.len());
+    assert!(changed
+        .iter()
+        .all(|i| copy.bytes[i * 3] >= copy.bytes[i * 3 + 1]));
+    let (w, h) = (original.width as usize, original.height as usize);
+    for corner in [0, w - 1, (h - 1) * w] {
+        assert!(!changed.contains(&corner));
     }
 
-    let errors = renders.errors;
+    let invisible = Watermark {
+        opacity: 0.0,
+        ..Watermark::text("VOID")
+    };
+    assert_eq!(render(Some(invisible)).bytes, original.bytes);
 
-    if errors.len() > 0 {
-        println!("Errors found for test file {}:", name);
-        for error in errors {
-            println!("{:?}", error);
+    let black = RGBA {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    let stamp = Image {
+        pixels: vec![black; 40 * 10],
+        x: 0,
+        y: 0,
+        w: 40,
+        h: 10,
+        flow: ImageFlow::None,
+        upside_down: false,
+    };
+    let stamped = render(Some(Watermark::image(stamp)));
+    let center = (h / 2 * w + w / 2) * 3;
+    assert!(stamped.bytes[center] < original.bytes[center]);
+}
+
+#[test]
+fn box_drawing_lines() {
+    //A code page 437 box, ┌──┐ │  │ └──┘
+    let bytes = parse_str(
+        r#"ESC "@" 0xDA 0xC4 0xC4 0xBF LF 0xB3 "  " 0xB3 LF 0xB3 "  " 0xB3 LF 0xC0 0xC4 0xC4 0xD9 LF"#,
+    );
+
+    let render = |box_drawing: bool| {
+        let mut image_renderer = ImageRenderer::new();
+        image_renderer.box_drawing = box_drawing;
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(image_renderer);
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.render(&bytes).output.remove(0)
+    };
+
+    //The longest run of ink in the column with the most ink, and the ink in that column
+    let left_side = |image: &ReceiptImage| {
+        let (w, h) = (image.width as usize, image.height as usize);
+        let ink = |x: usize, y: usize| image.bytes[(y * w + x) * 3] < 128;
+        let x = (0..w)
+            .max_by_key(|x| (0..h).filter(|y| ink(*x, *y)).count())
+            .unwrap();
+        let mut longest = 0;
+        let mut run = 0;
+        for y in 0..h {
+            run = if ink(x, y) { run + 1 } else { 0 };
+            longest = longest.max(run);
         }
-        assert!(false, "There were errors when rendering html.");
+        (longest, (0..h).filter(|y| ink(x, *y)).count())
+    };
+
+    //Lines are drawn on the pixel grid, there is no anti aliasing
+    let lines = render(true);
+    assert!(lines.bytes.iter().all(|byte| *byte == 0 || *byte == 255));
+
+    //The side of the box is one line from the top corner to the bottom
+    //corner, three lines of 24 dots apart
+    let (longest, ink) = left_side(&lines);
+    assert_eq!(longest, ink);
+    assert!(longest >= 24 * 3);
+
+    //The glyphs of the font are anti aliased
+    let glyphs = render(false);
+    assert!(glyphs.bytes.iter().any(|byte| *byte != 0 && *byte != 255));
+}
+
+#[test]
+fn wear_artifacts() {
+    let bytes =
+        parse_str(r#"ESC "@" GS "!" 0x11 "WORN PRINT HEAD" LF "WORN PRINT HEAD" LF ESC "d" 2"#);
+
+    let render = |wear: Option<Wear>| {
+        let mut image_renderer = ImageRenderer::new();
+        image_renderer.wear = wear;
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(image_renderer);
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.render(&bytes).output.remove(0)
+    };
+
+    let original = render(None);
+    let worn = render(Some(Wear::new(1)));
+    assert_eq!((worn.width, worn.height), (original.width, original.height));
+
+    //Wear only takes ink away
+    let changed: Vec<usize> = (0..original.bytes.len())
+        .filter(|i| worn.bytes[*i] != original.bytes[*i])
+        .collect();
+    assert!(!changed.is_empty());
+    assert!(changed.iter().all(|i| worn.bytes[*i] > original.bytes[*i]));
+
+    //The same seed gives the same artifacts
+    assert_eq!(render(Some(Wear::new(1))).bytes, worn.bytes);
+    assert_ne!(render(Some(Wear::new(2))).bytes, worn.bytes);
+
+    let unworn = Wear {
+        dropped_dots: 0.0,
+        weak_columns: 0.0,
+        ..Wear::new(1)
+    };
+    assert_eq!(render(Some(unworn)).bytes, original.bytes);
+
+    //Every column is weak and prints half of the ink
+    let weak = Wear {
+        dropped_dots: 0.0,
+        weak_columns: 1.0,
+        weak_strength: 0.5,
+        ..Wear::new(1)
+    };
+    let faded = render(Some(weak));
+    let black = original.bytes.iter().position(|byte| *byte == 0).unwrap();
+    assert_eq!(faded.bytes[black], 127);
+}
+
+#[test]
+fn color_management() {
+    let bytes = parse_str(r#"ESC "@" "TINTED PAPER" LF ESC "d" 2"#);
+
+    let render = |tint: Option<PaperTint>, profile: ColorProfile| {
+        let mut image_renderer = ImageRenderer::new();
+        image_renderer.paper_tint = tint;
+        image_renderer.color_profile = profile;
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(image_renderer);
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.render(&bytes).output.remove(0)
+    };
+
+    //White becomes the paper and black the ink
+    let original = render(None, ColorProfile::Untagged);
+    let tint = PaperTint::thermal();
+    let tinted = render(Some(tint.clone()), ColorProfile::Untagged);
+    let black = original.bytes.iter().position(|byte| *byte == 0).unwrap() / 3 * 3;
+    assert_eq!(tinted.bytes[0..3], tint.paper);
+    assert_eq!(tinted.bytes[black..black + 3], tint.ink);
+
+    //The profile is written into the png and read back
+    let path = std::env::temp_dir().join("thermal_color_management.png");
+    for profile in [
+        ColorProfile::Untagged,
+        ColorProfile::Srgb,
+        ColorProfile::Icc(b"not a real profile".repeat(20)),
+    ] {
+        let receipt = render(None, profile.clone());
+        receipt.save_png(&path).unwrap();
+        let loaded = ReceiptImage::load_png(&path).unwrap();
+        assert_eq!(loaded.color_profile, profile);
+        assert_eq!(loaded.bytes, original.bytes);
     }
+
+    let srgb = render(None, ColorProfile::Srgb).to_png().unwrap();
+    assert!(srgb.windows(4).any(|chunk| chunk == b"sRGB"));
+    let untagged = original.to_png().unwrap();
+    assert!(!untagged.windows(4).any(|chunk| chunk == b"sRGB"));
 }
 
-fn render_image(bytes: &Vec<u8>, out_path: String, name: String, debug_profile: DebugProfile) {
-    let renders = ImageRenderer::render(bytes, Some(debug_profile));
+#[test]
+fn feed_before_cut() {
+    let measure = |job: &str| {
+        let renders = LengthRenderer::render(&parse_str(job), None);
+        let length = renders.output.into_iter().next().expect("Nothing rendered");
+        (length.cuts, length.height)
+    };
 
-    if let Some(render) = renders.output.first() {
-        save_image(&render.bytes, render.width, render.height, out_path);
-    } else {
-        assert!(false, "No image generated from renderer.");
+    //The paper is fed to the cutter before the cut, the receipt ends at the cut
+    let (cuts, height) = measure(r#"ESC "@" "TOTAL" LF GS "V" 0"#);
+    assert_eq!(cuts, [height]);
+
+    //GS V 65 and 66 feed n more before cutting
+    let (fed_cuts, fed_height) = measure(r#"ESC "@" "TOTAL" LF GS "V" 65 40"#);
+    assert_eq!(fed_cuts, [cuts[0] + 40]);
+    assert_eq!(fed_height, height + 40);
+
+    //n is in vertical motion units, 40/100 inch is 81 dots at 203 dpi
+    let (unit_cuts, _) = measure(r#"ESC "@" GS "P" 0 100 "TOTAL" LF GS "V" 66 40"#);
+    assert_eq!(unit_cuts, [cuts[0] + 81]);
+}
+
+#[test]
+fn render_config_margins() {
+    let bytes = parse_str(r#"ESC "@" "FIRST" LF "LAST" LF GS "V" 0"#);
+
+    let render = |config: RenderConfig| {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.set_config(config);
+        let layout = renderer.render(&bytes).output.remove(0);
+        let top = match &layout.elements[0] {
+            LayoutElement::Text { bounds, .. } => bounds.y,
+            other => panic!("Expected text, got {:?}", other),
+        };
+        (top, layout.height)
+    };
+
+    //The defaults have the profile margin and feed two lines before
+    //the receipt and before the cut
+    let (top, height) = render(RenderConfig::default());
+    let (tight_top, tight_height) = render(RenderConfig::tight());
+    assert_eq!(tight_top, 0);
+    assert_eq!((top, height), (108, 204));
+    assert_eq!(tight_height, 48);
+
+    let leading = RenderConfig {
+        leading_lines: 2,
+        ..RenderConfig::tight()
+    };
+    assert_eq!(render(leading), (48, 96));
+
+    let margins = RenderConfig {
+        top_margin: Some(10),
+        bottom_margin: 30,
+        ..RenderConfig::tight()
+    };
+    assert_eq!(render(margins), (10, tight_height + 40));
+}
+
+#[test]
+fn peripheral_routing() {
+    let bytes = parse_str(
+        r#"ESC "@" "RECEIPT" LF ESC "=" 2 0x0C "TOTAL 4.00" ESC "=" 3 "BOTH" LF ESC "=" 1 "THANKS" LF GS "V" 0"#,
+    );
+    let render = |route_peripherals| {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.set_config(RenderConfig {
+            route_peripherals,
+            ..RenderConfig::default()
+        });
+        let renders = renderer.render(&bytes);
+        let text: Vec<String> = renders
+            .output
+            .iter()
+            .flat_map(|layout| &layout.elements)
+            .filter_map(|e| match e {
+                LayoutElement::Text { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        (text, renders.display)
+    };
+
+    //Without routing the display text ends up on the receipt
+    let (text, display) = render(false);
+    assert!(text.concat().contains("TOTAL 4.00"));
+    assert_eq!(display, DisplayOutput::default());
+
+    let (text, display) = render(true);
+    assert_eq!(text, vec!["RECEIPT", "BOTH", "THANKS"]);
+    assert_eq!(display.text, "TOTAL 4.00BOTH\n");
+    assert_eq!(display.bytes, b"\x0CTOTAL 4.00BOTH\n");
+}
+
+#[test]
+fn line_display_frames() {
+    let bytes = parse_str(
+        r#"ESC "@" "RECEIPT" LF ESC "=" 2 0x0C "COFFEE" 0x1F "$" 15 2 " 3.50" 0x0C "TOTAL" 0x1F "$" 15 2 " 7.00" ESC "=" 1 "THANKS" LF GS "V" 0"#,
+    );
+
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    renderer.set_config(RenderConfig {
+        route_peripherals: true,
+        ..RenderConfig::default()
+    });
+    let renders = renderer.render(&bytes);
+
+    let frames = LineDisplay::new().render(&renders.display.bytes);
+    let lines: Vec<Vec<String>> = frames.into_iter().map(|frame| frame.lines).collect();
+    assert_eq!(
+        lines,
+        vec![
+            vec!["COFFEE              ", "               3.50 "],
+            vec!["TOTAL               ", "               7.00 "],
+        ]
+    );
+
+    //Lines scroll up in vertical scroll mode
+    let frames = LineDisplay::new().render(b"\x1f\x02ONE\r\nTWO\r\nTHREE");
+    assert_eq!(
+        frames[0].lines,
+        vec!["TWO                 ", "THREE               "]
+    );
+}
+
+#[test]
+fn paper_out_simulation() {
+    let mut job = String::from(r#"ESC "@" 0x10 0x04 4 "#);
+    for line in 0..40 {
+        job.push_str(&format!(r#""LINE {}" LF "#, line));
     }
+    job.push_str(r#"0x10 0x04 1 0x10 0x04 2 0x10 0x04 4 GS "V" 0 FF "NEXT" LF"#);
+    let bytes = parse_str(&job);
 
-    let errors = renders.errors;
+    let config = RenderConfig {
+        paper_limit_mm: Some(50.0),
+        ..RenderConfig::default()
+    };
+    let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+    let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+    renderer.set_config(config);
+    let renders = renderer.render(&bytes);
+    assert!(renderer.is_paper_out());
 
-    if errors.len() > 0 {
-        println!("Errors found for test file {}:", name);
-        for error in errors {
-            println!("{:?}", error);
+    //The job ends at the paper limit and nothing after it is rendered
+    assert_eq!(renders.output.len(), 1);
+    let profile = PrinterProfile::default();
+    let height = renders.output[0].height;
+    assert!(height >= profile.mm_to_dots(50.0));
+    assert!(height < profile.mm_to_dots(50.0) + 40);
+    let texts: Vec<&str> = renders.output[0]
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            LayoutElement::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert!(texts.len() < 40 && !texts.contains(&"NEXT"));
+    assert_eq!(renders.metrics[0].cuts, 0);
+
+    assert_eq!(renders.errors.len(), 1);
+    assert!(format!("{:?}", renders.errors[0]).contains("[PaperOut] Paper ran out after 50mm"));
+
+    //Status requests answer with a paper end once the paper is out
+    let events: Vec<&DeviceEventKind> = renders.events.iter().map(|e| &e.kind).collect();
+    let status = |status_type: u8, response: u8| DeviceEventKind::StatusRequest {
+        status_type,
+        response: Some(response),
+    };
+    assert_eq!(
+        events,
+        [
+            &status(4, 0x12),
+            &DeviceEventKind::PaperOut,
+            &status(1, 0x1A),
+            &status(2, 0x32),
+            &status(4, 0x7E),
+        ]
+    );
+
+    //Without a limit the whole job is rendered
+    let renders = JsonRenderer::render(&bytes, None);
+    assert_eq!(renders.output.len(), 2);
+    assert!(renders.errors.is_empty());
+}
+
+#[test]
+fn job_splitting() {
+    let render = |splitter: JobSplitter, src: &str| {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.add_middleware(Box::new(splitter));
+        let renders = renderer.render(&parse_str(src));
+        assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+        renders
+            .output
+            .iter()
+            .map(|layout| {
+                layout
+                    .elements
+                    .iter()
+                    .filter_map(|e| match e {
+                        LayoutElement::Text { text, .. } => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect::<Vec<String>>()
+    };
+
+    //A long feed and a pulse end the receipt, the next one starts with the next text
+    let job = r#"ESC "@" "ONE" LF ESC "d" 6 ESC "p" 0 25 250 LF "TWO" LF"#;
+    assert_eq!(render(JobSplitter::cutterless(), job), ["ONE", "TWO"]);
+    let job = r#"ESC "@" "ONE" LF ESC "d" 1 ESC "p" 0 25 250 "TWO" LF"#;
+    assert_eq!(render(JobSplitter::cutterless(), job), ["ONE TWO"]);
+
+    //Initializing starts a new job once something was printed
+    let job = r#"ESC "@" ESC "@" "ONE" LF ESC "@" "TWO" LF ESC "@" "THREE" LF"#;
+    assert_eq!(
+        render(JobSplitter::cutterless(), job),
+        ["ONE", "TWO", "THREE"]
+    );
+    assert_eq!(render(JobSplitter::new(), job), ["ONE TWO THREE"]);
+
+    //Form feeds still end jobs and the splitter doesn't split them again
+    let job = r#"ESC "@" "ONE" LF FF ESC "@" "TWO" LF"#;
+    assert_eq!(render(JobSplitter::cutterless(), job), ["ONE", "TWO"]);
+
+    //A marker that was written into the capture, and a rule of our own
+    let job = r#""ONE" LF ESC "=" 1 "TWO" LF "END" LF "THREE" LF"#;
+    let splitter = JobSplitter::new()
+        .with_rule(SplitOnMarker::new(&[0x1B, b'=', 1]))
+        .with_rule(|command: &Command, context: &Context| {
+            command
+                .handler
+                .get_text(command, context)
+                .is_some_and(|span| span.text == "END")
+        });
+    assert_eq!(render(splitter, job), ["ONE", "TWO END", "THREE"]);
+}
+
+#[test]
+fn paper_sensor_selection() {
+    //A roll paper status request after every line
+    let render = |setup: &str| {
+        let mut job = format!(r#"ESC "@" {} "#, setup);
+        for line in 0..40 {
+            job.push_str(&format!(r#""LINE {}" LF 0x10 0x04 4 "#, line));
         }
-        assert!(false, "There were errors when rendering an image.");
-    }
+        job.push_str(r#"0x10 0x04 1 0x10 0x04 2"#);
+
+        let config = RenderConfig {
+            paper_limit_mm: Some(50.0),
+            paper_near_end_mm: Some(20.0),
+            ..RenderConfig::default()
+        };
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        renderer.set_config(config);
+        renderer.render(&parse_str(&job))
+    };
+    let responses = |renders: &RenderOutput<ReceiptLayout>, kind: u8| -> Vec<u8> {
+        renders
+            .events
+            .iter()
+            .filter_map(|e| match e.kind {
+                DeviceEventKind::StatusRequest {
+                    status_type,
+                    response,
+                } if status_type == kind => response,
+                _ => None,
+            })
+            .collect()
+    };
+    let profile = PrinterProfile::default();
+
+    //The near end is reported before the paper runs out
+    let renders = render("");
+    let kinds: Vec<&DeviceEventKind> = renders
+        .events
+        .iter()
+        .map(|e| &e.kind)
+        .filter(|k| !matches!(k, DeviceEventKind::StatusRequest { .. }))
+        .collect();
+    assert_eq!(
+        kinds,
+        [&DeviceEventKind::PaperNearEnd, &DeviceEventKind::PaperOut]
+    );
+    let mut roll_paper = responses(&renders, 4);
+    roll_paper.dedup();
+    assert_eq!(roll_paper, [0x12, 0x1E, 0x7E]);
+    assert!(renders.output[0].height >= profile.mm_to_dots(50.0));
+
+    //ESC c 4 stops printing at the near end, the paper isn't out
+    let renders = render(r#"ESC "c" 4 1"#);
+    assert!(renders.output[0].height < profile.mm_to_dots(31.0));
+    assert!(format!("{:?}", renders.errors[0])
+        .contains("[PaperOut] Printing stopped at the paper near end after 30mm"));
+    assert!(!renders
+        .events
+        .iter()
+        .any(|e| e.kind == DeviceEventKind::PaperOut));
+    assert_eq!(responses(&renders, 1), [0x1A]);
+    assert_eq!(responses(&renders, 2), [0x32]);
+    assert_eq!(responses(&renders, 4).last(), Some(&0x1E));
+
+    //ESC c 3 without the near end sensor doesn't report it
+    let renders = render(r#"ESC "c" 3 12 ESC "c" 4 1"#);
+    assert!(responses(&renders, 4).iter().all(|status| *status == 0x12));
+    let renders = render(r#"ESC "c" 3 0"#);
+    assert!(responses(&renders, 4).iter().all(|status| *status == 0x12));
+    assert_eq!(responses(&renders, 1), [0x1A]);
+
+    //ESC @ selects the default sensors again
+    let renders = render(r#"ESC "c" 4 1 ESC "c" 3 0 ESC "@""#);
+    assert!(renders.output[0].height >= profile.mm_to_dots(50.0));
+    assert_eq!(responses(&renders, 4).last(), Some(&0x7E));
 }
 
-fn save_image(bytes: &Vec<u8>, width: u32, height: u32, out_path: String) {
-    if bytes.len() == 0 || width == 0 || height == 0 {
-        assert!(false, "No image generated from render.");
-        return;
+#[test]
+fn render_timings() {
+    let bytes = parse_str(
+        r#"ESC "@" "TOTAL 4.00" LF GS "k" 4 "*123*" 0 GS "(k" 4 0 49 65 50 0 GS "(k" 5 0 49 80 48 "ABC" GS "(k" 3 0 49 81 48 GS "v0" 0 1 0 2 0 0xF0 0x0F LF"#,
+    );
+    let mut image_renderer: Box<dyn OutputRenderer<_>> = Box::new(ImageRenderer::new());
+
+    //Nothing is measured by default
+    let mut renderer = Renderer::new(&mut image_renderer, DebugProfile::default());
+    assert!(renderer.render(&bytes).timings.is_none());
+
+    renderer.set_profiling(true);
+    let timings = renderer.render(&bytes).timings.unwrap();
+    for phase in [
+        timings.parse,
+        timings.layout,
+        timings.barcode_encode,
+        timings.image_decode,
+        timings.rasterize,
+    ] {
+        assert!(phase > Duration::ZERO, "{:?}", timings);
     }
+    let phases = timings.parse
+        + timings.layout
+        + timings.barcode_encode
+        + timings.image_decode
+        + timings.rasterize;
+    assert!(phases <= timings.total, "{:?}", timings);
 
-    let path = Path::new(&out_path);
-    let file = File::create(path).unwrap();
-    let ref mut writer = BufWriter::new(file);
-    let mut encoder = png::Encoder::new(writer, width, height);
-    encoder.set_color(png::ColorType::Rgb);
-    encoder.set_depth(BitDepth::Eight);
+    //Every render starts from zero
+    let timings = renderer
+        .render(&parse_str(r#""TOTAL" LF"#))
+        .timings
+        .unwrap();
+    assert_eq!(timings.barcode_encode, Duration::ZERO);
+    assert_eq!(timings.image_decode, Duration::ZERO);
 
-    let mut writer = encoder.write_header().unwrap();
-    writer.write_image_data(bytes).unwrap(); // Save
+    //Streams time the parsing of every chunk
+    drop(renderer);
+    let mut renderer = Renderer::new(&mut image_renderer, DebugProfile::default());
+    renderer.set_profiling(true);
+    let mut stream = StreamRenderer::new(thermal_parser::new_esc_pos_parser(), renderer);
+    let timings = stream.push(&bytes).timings.unwrap();
+    assert!(timings.parse > Duration::ZERO);
+    assert!(stream.finish().timings.is_some());
+}
+
+#[test]
+fn paced_rendering() {
+    assert_eq!(
+        Link::serial(9600).transfer_time(960),
+        Duration::from_secs(1)
+    );
+    assert_eq!(Link::usb().transfer_time(64), Duration::from_millis(1));
+
+    //A status request after 200 bytes of text and a job that ends after 400
+    let mut job = r#"ESC "@" "#.to_string();
+    job.push_str(&r#""0123456789" "#.repeat(20));
+    job.push_str(r#"LF 0x10 0x04 1 "#);
+    job.push_str(&r#""0123456789" "#.repeat(20));
+    job.push_str("LF");
+    let bytes = parse_str(&job);
+
+    let render = |link: Link, realtime: bool| {
+        let mut json_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let renderer = Renderer::new(&mut json_renderer, DebugProfile::default());
+        let stream = StreamRenderer::new(thermal_parser::new_esc_pos_parser(), renderer);
+        let mut paced = PacedRenderer::new(stream, link);
+        paced.realtime = realtime;
+        paced
+            .render(&bytes)
+            .into_iter()
+            .filter_map(|timed| match timed.event {
+                StreamEvent::Event(_) => Some(("status", timed.at)),
+                StreamEvent::Output(_) => Some(("output", timed.at)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    //The status request arrives with the packet of its last byte
+    let link = Link::serial(9600);
+    let status_end: usize = 2 + 200 + 1 + 3;
+    assert_eq!(
+        render(link.clone(), false),
+        [
+            ("status", link.transfer_time(status_end.div_ceil(16) * 16)),
+            ("output", link.transfer_time(bytes.len())),
+        ]
+    );
+
+    //In real time the render takes as long as the link
+    let link = Link::network(20_000);
+    let started = Instant::now();
+    let events = render(link.clone(), true);
+    assert!(started.elapsed() >= link.transfer_time(bytes.len()));
+    assert_eq!(events[1], ("output", link.transfer_time(bytes.len())));
+}
+
+#[test]
+fn realtime_dle_dc4() {
+    let render = |job: &str| {
+        let mut child_renderer: Box<dyn OutputRenderer<_>> = Box::new(JsonRenderer::new());
+        let mut renderer = Renderer::new(&mut child_renderer, DebugProfile::default());
+        let renders = renderer.render(&parse_str(job));
+        assert!(renders.errors.is_empty(), "{:?}", renders.errors);
+
+        let texts: Vec<String> = renders
+            .output
+            .iter()
+            .map(|layout| {
+                layout
+                    .elements
+                    .iter()
+                    .filter_map(|e| match e {
+                        LayoutElement::Text { text, .. } => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect();
+        let events: Vec<DeviceEventKind> = renders.events.into_iter().map(|e| e.kind).collect();
+        (texts, events)
+    };
+
+    //Pulses on pin 2 or 5 for 100 to 800ms, other values are ignored
+    let (_, events) =
+        render(r#"ESC "@" DLE 0x14 1 0 2 DLE 0x14 1 1 8 DLE 0x14 1 2 1 DLE 0x14 1 0 9"#);
+    assert_eq!(
+        events,
+        [DeviceEventKind::DrawerPulse, DeviceEventKind::DrawerPulse]
+    );
+
+    //Real-time pulses inside image data are executed too
+    let (_, events) = render(r#"ESC "@" GS "v0" 0 5 0 1 0 0x10 0x14 1 0 1 LF"#);
+    assert_eq!(events, [DeviceEventKind::DrawerPulse]);
+
+    //Clearing the buffers drops the line that was not printed
+    let (texts, events) =
+        render(r#"ESC "@" "KEEP" LF "DROP" DLE 0x14 8 1 3 20 1 6 2 8 "AFTER" LF"#);
+    assert_eq!(texts, ["KEEP AFTER"]);
+    assert_eq!(
+        events,
+        [DeviceEventKind::BufferClear {
+            response: vec![0x37, 0x25, 0x00]
+        }]
+    );
+
+    //Nothing is printed after the power-off sequence
+    let (texts, events) =
+        render(r#"ESC "@" "ONE" LF "TWO" DLE 0x14 2 1 8 "THREE" LF FF "FOUR" LF"#);
+    assert_eq!(texts, ["ONE"]);
+    assert_eq!(
+        events,
+        [DeviceEventKind::PowerOff {
+            response: vec![0x3B, 0x30, 0x00]
+        }]
+    );
+}
+
+#[test]
+fn trace_rendering() {
+    let bytes =
+        parse_str(r#"ESC "@" "*SALE*" LF GS "v0" 0 2 0 1 0 0x10 0x04 FF "TWO" LF GS "V" 1"#);
+
+    let traces = TraceRenderer::render(&bytes, TraceFormat::Markdown, None);
+    assert!(traces.errors.is_empty(), "{:?}", traces.errors);
+    assert_eq!(traces.output.len(), 2);
+    assert_eq!(
+        traces.output[0].content,
+        [
+            "## Receipt 1",
+            "",
+            "- `0000` `1B 40` Initialize \\[ESC @\\]",
+            r#"- `0002` `2A 53 41 4C 45 2A` "\*SALE\*""#,
+            "- `0008` `0A` Line Feed \\[LF\\]",
+            "- `0009` `1D 76 30 00 02 00 01 00 ..` Raster Bit Image \\[10, 04\\] \\[GS v 0\\]",
+            "  ```",
+            "  *SALE*",
+            "  [Image 16x1 at 0,132]",
+            "  ```",
+            "- `0013` `0C` Form Feed \\[FF\\]",
+            "",
+        ]
+        .join("\n")
+    );
+
+    //The cut follows the command that made it
+    let traces = TraceRenderer::render(&bytes, TraceFormat::Text, None);
+    let trace = &traces.output[1].content;
+    assert!(trace.starts_with("Receipt 2\n0014  54 57 4F"), "{}", trace);
+    assert!(trace.contains("Feed and Cut [01] [GS V]\n"), "{}", trace);
+    assert!(
+        trace.ends_with("| TWO\n              | [Partial Cut]\n"),
+        "{}",
+        trace
+    );
+
+    //Real-time commands in the data of another command run first
+    let bytes = parse_str(r#"ESC "@" GS "v0" 0 3 0 1 0 0x10 0x04 1"#);
+    let traces = TraceRenderer::render(&bytes, TraceFormat::Text, None);
+    let trace = &traces.output[0].content;
+    assert!(
+        trace.starts_with("Receipt 1\n0000  1B 40                       Initialize [ESC @]\n000A  10 04 01                    Transmit Real-Time Status [01] [DLE EOT] (embedded)\n0002"),
+        "{}",
+        trace
+    );
 }